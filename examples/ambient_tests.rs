@@ -16,10 +16,10 @@ fn run_test(test: &TestCase) -> Result<(), String> {
     println!("Description: {}", test.description);
     println!("Input: {}", test.input);
 
-    mettail_runtime::clear_var_cache();
+    let vars = mettail_runtime::VarContext::new();
     let parser = ambient::ProcParser::new();
     let input_term = parser
-        .parse(test.input)
+        .parse(&vars, test.input)
         .map_err(|e| format!("Parse error: {:?}", e))?;
 
     // Normalize to flatten any nested collections
@@ -84,7 +84,7 @@ fn run_test(test: &TestCase) -> Result<(), String> {
     // Check expected output if provided
     if let Some(expected_str) = test.expected_output {
         let expected = parser
-            .parse(expected_str)
+            .parse(&vars, expected_str)
             .map_err(|e| format!("Parse error in expected: {:?}", e))?;
 
         // Check if expected output is in the rewrite relation
@@ -5,10 +5,11 @@
 
 fn test_equation(name: &str, lhs_str: &str, rhs_str: &str) {
     let parser = ambient::ProcParser::new();
+    let vars = mettail_runtime::VarContext::new();
 
-    let lhs = parser.parse(lhs_str)
+    let lhs = parser.parse(&vars, lhs_str)
         .expect(&format!("Failed to parse LHS: {}", lhs_str));
-    let rhs = parser.parse(rhs_str)
+    let rhs = parser.parse(&vars, rhs_str)
         .expect(&format!("Failed to parse RHS: {}", rhs_str));
 
     println!("  LHS: {}", lhs_str);
@@ -36,9 +36,9 @@ fn demonstrate_rewrite(input: &str) {
     println!("  Input:  {}", input);
 
     // Parse the term
-    mettail_runtime::clear_var_cache();
+    let vars = mettail_runtime::VarContext::new();
     let parser = rhocalc::ProcParser::new();
-    let term = parser.parse(input).unwrap_or_else(|e| {
+    let term = parser.parse(&vars, input).unwrap_or_else(|e| {
         eprintln!("  Parse error: {:?}", e);
         std::process::exit(1);
     });
@@ -16,10 +16,10 @@ fn run_test(test: &TestCase) -> Result<(), String> {
     println!("Description: {}", test.description);
     println!("Input: {}", test.input);
 
-    mettail_runtime::clear_var_cache();
+    let vars = mettail_runtime::VarContext::new();
     let parser = rhocalc::ProcParser::new();
     let input_term = parser
-        .parse(test.input)
+        .parse(&vars, test.input)
         .map_err(|e| format!("Parse error: {:?}", e))?;
 
     // Normalize to flatten any nested collections
@@ -31,10 +31,6 @@ fn run_test(test: &TestCase) -> Result<(), String> {
         include_source!(rhocalc_source);
         proc(p) <-- for p in [input_term.clone()];
 
-        relation redex_eq(Proc);
-        redex_eq(q.clone()) <-- eq_proc(input_term.clone(), q);
-        proc(q) <-- redex_eq(q);
-
         relation path(Proc, Proc);
         path(p1, p2) <-- rw_proc(p1,p2);
         path(p1, p3) <-- path(p1,p2), path(p2,p3);
@@ -92,7 +88,7 @@ fn run_test(test: &TestCase) -> Result<(), String> {
     // Check expected output if provided
     if let Some(expected_str) = test.expected_output {
         let expected = parser
-            .parse(expected_str)
+            .parse(&vars, expected_str)
             .map_err(|e| format!("Parse error in expected: {:?}", e))?
             .normalize();
 
@@ -132,7 +128,7 @@ fn main() {
 
         TestCase {
             name: "basic_communication",
-            input: "{for(x -> y) {y!(0)}, x!({})}",
+            input: "{for(x -> y) {y!(0)} | x!({})}",
             expected_output: Some("{{@({})!(0)}}"),
             should_normalize: true,
             min_rewrites: 1,
@@ -141,7 +137,7 @@ fn main() {
 
         TestCase {
             name: "communication_with_data",
-            input: "{for(chan -> x) {x!(result)}, chan!(data)}",
+            input: "{for(chan -> x) {x!(result)} | chan!(data)}",
             expected_output: Some("{{@(data)!(result)}}"),
             should_normalize: true,
             min_rewrites: 1,
@@ -150,7 +146,7 @@ fn main() {
 
         TestCase {
             name: "zero_communication",
-            input: "{for(c -> x) {0}, c!(p)}",
+            input: "{for(c -> x) {0} | c!(p)}",
             expected_output: Some("{0}"),
             should_normalize: true,
             min_rewrites: 1,
@@ -203,8 +199,8 @@ fn main() {
 
         TestCase {
             name: "pdrop_in_parallel",
-            input: "{*(@(p)), q}",
-            expected_output: Some("{p, q}"),
+            input: "{*(@(p)) | q}",
+            expected_output: Some("{p | q}"),
             should_normalize: true,
             min_rewrites: 1,
             description: "PDrop in parallel context",
@@ -212,8 +208,8 @@ fn main() {
 
         TestCase {
             name: "pdrop_nested_quote",
-            input: "{*(@({a, b}))}",
-            expected_output: Some("{{a, b}}"),
+            input: "{*(@({a | b}))}",
+            expected_output: Some("{{a | b}}"),
             should_normalize: true,
             min_rewrites: 1,
             description: "PDrop with nested process in quote",
@@ -221,8 +217,8 @@ fn main() {
 
         TestCase {
             name: "multiple_pdrops",
-            input: "{*(@(p)), *(@(q))}",
-            expected_output: Some("{p, q}"),
+            input: "{*(@(p)) | *(@(q))}",
+            expected_output: Some("{p | q}"),
             should_normalize: true,
             min_rewrites: 2,
             description: "Multiple PDrop rewrites in parallel",
@@ -234,7 +230,7 @@ fn main() {
 
         TestCase {
             name: "send_quoted_process",
-            input: "{for(c -> x) {*(x)}, c!(result)}",
+            input: "{for(c -> x) {*(x)} | c!(result)}",
             expected_output: Some("{result}"),
             should_normalize: true,
             min_rewrites: 2, // Communication, then PDrop
@@ -243,8 +239,8 @@ fn main() {
 
         TestCase {
             name: "send_and_drop_complex",
-            input: "{for(c -> x) {{*(x), observer}}, c!(p)}",
-            expected_output: Some("{p, observer}"),
+            input: "{for(c -> x) {{*(x) | observer}} | c!(p)}",
+            expected_output: Some("{p | observer}"),
             should_normalize: true,
             min_rewrites: 2,
             description: "Communication followed by drop with other processes",
@@ -252,8 +248,8 @@ fn main() {
 
         TestCase {
             name: "nested_communication_drop",
-            input: "{for(c1 -> x) {{for(c2 -> y) {{*(x), *(y)}}, c2!(q)}}, c1!(p)}",
-            expected_output: Some("{p, q}"),
+            input: "{for(c1 -> x) {{for(c2 -> y) {{*(x) | *(y)}} | c2!(q)}} | c1!(p)}",
+            expected_output: Some("{p | q}"),
             should_normalize: true,
             min_rewrites: 3, // Two communications, two drops
             description: "Nested communication with multiple drops",
@@ -261,7 +257,7 @@ fn main() {
 
         TestCase {
             name: "quote_drop_roundtrip",
-            input: "{for(c -> name) {*(@(*(name)))}, c!(x)}",
+            input: "{for(c -> name) {*(@(*(name)))} | c!(x)}",
             expected_output: Some("{x}"),
             should_normalize: true,
             min_rewrites: 2, // Communication, then drop
@@ -274,7 +270,7 @@ fn main() {
 
         TestCase {
             name: "parallel_sends",
-            input: "{for(c -> x) {x!(result)}, c!(a), c!(b)}",
+            input: "{for(c -> x) {x!(result)} | c!(a) | c!(b)}",
             expected_output: None, // Multiple possible outcomes
             should_normalize: false,
             min_rewrites: 2, // Two different communications possible
@@ -283,7 +279,7 @@ fn main() {
 
         TestCase {
             name: "parallel_receives",
-            input: "{for(c -> x) {x!(p)}, for(c -> y) {y!(q)}, c!(data)}",
+            input: "{for(c -> x) {x!(p)} | for(c -> y) {y!(q)} | c!(data)}",
             expected_output: None, // Multiple receivers
             should_normalize: false,
             min_rewrites: 2,
@@ -292,8 +288,8 @@ fn main() {
 
         TestCase {
             name: "different_channels",
-            input: "{for(c1 -> x) {x!(p)}, for(c2 -> y) {y!(q)}, c1!(a), c2!(b)}",
-            expected_output: Some("{{@(a)!(p), @(b)!(q)}}"),
+            input: "{for(c1 -> x) {x!(p)} | for(c2 -> y) {y!(q)} | c1!(a) | c2!(b)}",
+            expected_output: Some("{{@(a)!(p) | @(b)!(q)}}"),
             should_normalize: true,
             min_rewrites: 2,
             description: "Independent communications on different channels",
@@ -305,7 +301,7 @@ fn main() {
 
         TestCase {
             name: "simple_forward",
-            input: "{for(in -> x) {out!(*(x))}, in!(data)}",
+            input: "{for(in -> x) {out!(*(x))} | in!(data)}",
             expected_output: Some("{out!(*(@(data)))}"),
             should_normalize: true,
             min_rewrites: 1,
@@ -314,7 +310,7 @@ fn main() {
 
         TestCase {
             name: "pipeline_two_stage",
-            input: "{for(c1 -> x) {c2!(*(x))}, for(c2 -> y) {result!(*(y))}, c1!(data)}",
+            input: "{for(c1 -> x) {c2!(*(x))} | for(c2 -> y) {result!(*(y))} | c1!(data)}",
             expected_output: Some("{result!(*(@(*(@(data)))))}"),
             should_normalize: true,
             min_rewrites: 2,
@@ -323,8 +319,8 @@ fn main() {
 
         TestCase {
             name: "broadcast",
-            input: "{for(in -> x) {{out1!(*(x)), out2!(*(x))}}, in!(data)}",
-            expected_output: Some("{{out1!(*(@(data))), out2!(*(@(data)))}}"),
+            input: "{for(in -> x) {{out1!(*(x)) | out2!(*(x))}} | in!(data)}",
+            expected_output: Some("{{out1!(*(@(data))) | out2!(*(@(data)))}}"),
             should_normalize: true,
             min_rewrites: 1,
             description: "Broadcast: one input, multiple outputs",
@@ -336,8 +332,8 @@ fn main() {
 
         TestCase {
             name: "nested_parallel_comm",
-            input: "{{for(c -> x) {x!(p)}, c!(a)}, observer}",
-            expected_output: Some("{@(a)!(p), observer}"),
+            input: "{{for(c -> x) {x!(p)} | c!(a)} | observer}",
+            expected_output: Some("{@(a)!(p) | observer}"),
             should_normalize: true,
             min_rewrites: 1,
             description: "Communication inside nested parallel composition",
@@ -345,7 +341,7 @@ fn main() {
 
         TestCase {
             name: "drop_inside_output",
-            input: "{for(c -> x) {result!(*(x))}, c!(data)}",
+            input: "{for(c -> x) {result!(*(x))} | c!(data)}",
             expected_output: Some("{result!(*(@(data)))}"),
             should_normalize: true,
             min_rewrites: 2, // Comm, then drop inside output
@@ -358,7 +354,7 @@ fn main() {
 
         TestCase {
             name: "substitution_in_output",
-            input: "{for(c -> x) {x!(*(x))}, c!(self)}",
+            input: "{for(c -> x) {x!(*(x))} | c!(self)}",
             expected_output: Some("{@(self)!(*(@(self)))}"),
             should_normalize: true,
             min_rewrites: 1,
@@ -367,8 +363,8 @@ fn main() {
 
         TestCase {
             name: "substitution_with_drop",
-            input: "{for(c -> x) {*(x)}, c!({p, q})}",
-            expected_output: Some("{p, q}"),
+            input: "{for(c -> x) {*(x)} | c!({p | q})}",
+            expected_output: Some("{p | q}"),
             should_normalize: true,
             min_rewrites: 2,
             description: "Substitution followed by drop of complex process",
@@ -376,8 +372,8 @@ fn main() {
 
         TestCase {
             name: "nested_substitution",
-            input: "{for(c1 -> x) {for(c2 -> y) {{out!(*(x)), out!(*(y))}}}, c1!(a)}",
-            expected_output: Some("{for(c2 -> y) {{out!(*(@(a))), out!(*(y))}}}"),
+            input: "{for(c1 -> x) {for(c2 -> y) {{out!(*(x)) | out!(*(y))}}} | c1!(a)}",
+            expected_output: Some("{for(c2 -> y) {{out!(*(@(a))) | out!(*(y))}}}"),
             should_normalize: true,
             min_rewrites: 1,
             description: "Nested input with partial substitution",
@@ -389,8 +385,8 @@ fn main() {
 
         TestCase {
             name: "congruence_basic",
-            input: "{{*(@(p)), q}, observer}",
-            expected_output: Some("{{p, q}, observer}"),
+            input: "{{*(@(p)) | q} | observer}",
+            expected_output: Some("{{p | q} | observer}"),
             should_normalize: true,
             min_rewrites: 1,
             description: "Rewrite applies under parallel constructor",
@@ -407,8 +403,8 @@ fn main() {
 
         TestCase {
             name: "congruence_with_comm",
-            input: "{for(c -> x) {*(x)}, c!(result), observer}",
-            expected_output: Some("{result, observer}"),
+            input: "{for(c -> x) {*(x)} | c!(result) | observer}",
+            expected_output: Some("{result | observer}"),
             should_normalize: true,
             min_rewrites: 2,
             description: "Communication and drop under parallel context",
@@ -420,7 +416,7 @@ fn main() {
 
         TestCase {
             name: "self_communication",
-            input: "{for(@(p) -> x) {x!(result)}, @(p)!(data)}",
+            input: "{for(@(p) -> x) {x!(result)} | @(p)!(data)}",
             expected_output: Some("{{@(data)!(result)}}"),
             should_normalize: true,
             min_rewrites: 1,
@@ -429,8 +425,8 @@ fn main() {
 
         TestCase {
             name: "zero_in_parallel",
-            input: "{for(c -> x) {{x!(p), 0}}, c!(q)}",
-            expected_output: Some("{@(q)!(p), 0}"),
+            input: "{for(c -> x) {{x!(p) | 0}} | c!(q)}",
+            expected_output: Some("{@(q)!(p) | 0}"),
             should_normalize: true,
             min_rewrites: 1,
             description: "Zero process explicitly in parallel",
@@ -447,8 +443,8 @@ fn main() {
 
         TestCase {
             name: "multiple_independent_comms",
-            input: "{for(a -> x) {x!(p1)}, for(b -> y) {y!(p2)}, for(c -> z) {z!(p3)}, a!(d1), b!(d2), c!(d3)}",
-            expected_output: Some("{{@(d1)!(p1), @(d2)!(p2), @(d3)!(p3)}}"),
+            input: "{for(a -> x) {x!(p1)} | for(b -> y) {y!(p2)} | for(c -> z) {z!(p3)} | a!(d1) | b!(d2) | c!(d3)}",
+            expected_output: Some("{{@(d1)!(p1) | @(d2)!(p2) | @(d3)!(p3)}}"),
             should_normalize: true,
             min_rewrites: 3,
             description: "Three independent communications in parallel",
@@ -456,8 +452,8 @@ fn main() {
 
         TestCase {
             name: "comm_with_complex_continuation",
-            input: "{for(c -> x) {{*(x), y!(*(x)), z!(*(x))}}, c!(data)}",
-            expected_output: Some("{data, y!(*(@(data))), z!(*(@(data)))}"),
+            input: "{for(c -> x) {{*(x) | y!(*(x)) | z!(*(x))}} | c!(data)}",
+            expected_output: Some("{data | y!(*(@(data))) | z!(*(@(data)))}"),
             should_normalize: true,
             min_rewrites: 2, // Comm + drop
             description: "Communication with complex nested continuation",
@@ -465,8 +461,8 @@ fn main() {
 
         TestCase {
             name: "drop_chain",
-            input: "{*(@(p)), *(@(q)), *(@(r))}",
-            expected_output: Some("{p, q, r}"),
+            input: "{*(@(p)) | *(@(q)) | *(@(r))}",
+            expected_output: Some("{p | q | r}"),
             should_normalize: true,
             min_rewrites: 3,
             description: "Multiple independent drops in parallel",
@@ -474,7 +470,7 @@ fn main() {
 
         TestCase {
             name: "variable_shadowing",
-            input: "{for(c -> x) {for(d -> x) {x!(result)}}, c!(outer)}",
+            input: "{for(c -> x) {for(d -> x) {x!(result)}} | c!(outer)}",
             expected_output: Some("{{for(d -> x) {x!(result)}}}"),
             should_normalize: true,
             min_rewrites: 1,
@@ -483,7 +479,7 @@ fn main() {
 
         TestCase {
             name: "equation_in_communication",
-            input: "{for(@(*(n)) -> x){x!(result)}, n!(data)}",
+            input: "{for(@(*(n)) -> x){x!(result)} | n!(data)}",
             expected_output: Some("{@(data)!(result)}"),
             should_normalize: true,
             min_rewrites: 1,
@@ -8,6 +8,7 @@
 //! - Collection projections (extracting elements from collections)
 //! - Congruence rules for equality
 
+use crate::ascent::relations::rule_relation;
 use crate::ast::{GrammarRule, TheoryDef};
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
@@ -21,11 +22,18 @@ pub fn generate_category_rules(theory: &TheoryDef) -> TokenStream {
         let cat = &export.name;
         let cat_lower = format_ident!("{}", cat.to_string().to_lowercase());
         let rw_rel = format_ident!("rw_{}", cat.to_string().to_lowercase());
+        let rule_rel = rule_relation(cat);
+
+        // A rewrite is a labeled rewrite of any rule
+        rules.push(quote! {
+            #rw_rel(s, t) <-- #rule_rel(s, t, _);
+        });
 
         // Expand via rewrites ONLY (not via equality)
         // This prevents exponential term explosion from eq + exploration feedback loop
+        // (not when taking a single step, when the run is seeded with `one_step(true)`)
         rules.push(quote! {
-            #cat_lower(c1) <-- #cat_lower(c0), #rw_rel(c0, c1);
+            #cat_lower(c1) <-- #cat_lower(c0), #rw_rel(c0, c1), !one_step(true);
         });
 
         // Generate deconstruction rules for this category
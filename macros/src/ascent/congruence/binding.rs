@@ -1,4 +1,5 @@
 use super::projections::{generate_binding_proj_declaration, generate_binding_proj_population};
+use crate::ascent::relations::rule_relation;
 use crate::ast::TheoryDef;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
@@ -7,11 +8,11 @@
 /// Generate congruence for binding constructors
 /// Example: if S => T then (PNew x S) => (PNew x T)
 /// Generates:
-/// rw_proc(s, t) <--
+/// rw_proc_rule(s, t, rule) <--
 ///     proc(s),
 ///     if let Proc::PNew(scope) = s,
 ///     let (x, body) = scope.clone().unbind(),
-///     rw_proc(*body, t0),
+///     rw_proc_rule(*body, t0, rule),
 ///     let new_scope = mettail_runtime::Scope::new(x.clone(), Box::new(t0.clone())),
 ///     let t = Proc::PNew(new_scope);
 #[allow(dead_code)]
@@ -68,11 +69,11 @@ pub fn generate_binding_congruence(
     }
 
     Some(quote! {
-        #rw_rel(s, t) <--
+        #rw_rel(s, t, rule) <--
             #cat_lower(s),
             if let #category::#constructor(scope) = s,
             let (#binder_var, body) = scope.clone().unbind(),
-            #rw_rel(*body, #rewritten_field),
+            #rw_rel(*body, #rewritten_field, rule),
             let new_scope_tmp = mettail_runtime::Scope::new(#binder_var.clone(), Box::new(#rewritten_field.clone())),
             let t = #category::#constructor(new_scope_tmp);
     })
@@ -89,12 +90,12 @@ fn generate_binding_congruence_clause(
     constructor: &Ident,
     body_cat: &Ident,
 ) -> TokenStream {
-    let body_rw_rel = format_ident!("rw_{}", body_cat.to_string().to_lowercase());
+    let body_rw_rel = rule_relation(body_cat);
 
     quote! {
-        #rw_rel(parent, result) <--
+        #rw_rel(parent, result, rule) <--
             #proj_rel(parent, binder_var, body),
-            #body_rw_rel(body, body_rewritten),
+            #body_rw_rel(body, body_rewritten, rule),
             let scope_tmp = mettail_runtime::Scope::from_parts_unsafe(
                 binder_var.clone(),
                 Box::new(body_rewritten.clone())
@@ -207,7 +208,7 @@ pub fn generate_projection_based_binding_congruence(
         *binder_idx,
     );
 
-    let rw_rel = format_ident!("rw_{}", category.to_string().to_lowercase());
+    let rw_rel = rule_relation(category);
     let congruence_clause =
         generate_binding_congruence_clause(&rw_rel, &proj_rel, category, &constructor, body_cat);
 
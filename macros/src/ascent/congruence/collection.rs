@@ -18,6 +18,8 @@
     extract_regular_congruence_pattern, find_regular_congruences_for_category,
     generate_regular_congruence_clause,
 };
+use crate::ascent::relations::rule_relation;
+use crate::ascent::rewrites::rewrite_label;
 use crate::ascent::rewrites::rhs::generate_rhs_construction;
 use crate::ast::{Expr, TheoryDef};
 use proc_macro2::TokenStream;
@@ -28,9 +30,9 @@
 /// Example: if S => T then (PPar {S, ...rest}) => (PPar {T, ...rest})
 /// Generates:
 /// ```text
-/// rw_proc(parent, result) <--
+/// rw_proc_rule(parent, result, rule) <--
 ///     ppar_contains(parent, elem),
-///     rw_proc(elem, elem_rewritten),
+///     rw_proc_rule(elem, elem_rewritten, rule),
 ///     if let Proc::PPar(ref bag) = parent,
 ///     let rest = { let mut b = bag.clone(); b.remove(&elem); b },
 ///     let result = Proc::PPar({
@@ -64,16 +66,16 @@ pub fn generate_collection_congruence(
     let contains_rel = format_ident!("{}_contains", constructor.to_string().to_lowercase());
 
     // Generate the element rewrite relation
-    let elem_rw_rel = format_ident!("rw_{}", elem_cat.to_string().to_lowercase());
+    let elem_rw_rel = rule_relation(&elem_cat);
 
     // Generate flatten helper name
     let constructor_lower = format_ident!("{}", constructor.to_string().to_lowercase());
     let insert_helper = format_ident!("insert_into_{}", constructor_lower);
 
     Some(quote! {
-        #rw_rel(parent, result) <--
+        #rw_rel(parent, result, rule) <--
             #contains_rel(parent, elem),
-            #elem_rw_rel(*elem, elem_rewritten),
+            #elem_rw_rel(*elem, elem_rewritten, rule),
             if let #category::#constructor(ref bag) = parent,
             let rest = {
                 let mut b = bag.clone();
@@ -98,7 +100,7 @@ pub fn generate_new_collection_congruence_clauses(
 ) -> Vec<TokenStream> {
     let mut clauses = Vec::new();
 
-    let rw_rel = format_ident!("rw_{}", cong_info.parent_category.to_string().to_lowercase());
+    let rw_rel = rule_relation(&cong_info.parent_category);
     let parent_cat = &cong_info.parent_category;
     let constructor = &cong_info.constructor;
     let constructor_lower = format_ident!("{}", constructor.to_string().to_lowercase());
@@ -132,6 +134,7 @@ pub fn generate_new_collection_congruence_clauses(
             cong_info,
             element_patterns,
             &base_rule.right,
+            &rewrite_label(&base_rule.left),
             &rw_rel,
             parent_cat,
             constructor,
@@ -171,6 +174,7 @@ fn generate_joined_base_rewrite_clause(
     _cong_info: &CollectionCongruenceInfo,
     patterns: &[ElementPatternInfo],
     rhs: &Expr,
+    label: &str,
     rw_rel: &Ident,
     parent_cat: &Ident,
     constructor: &Ident,
@@ -181,8 +185,12 @@ fn generate_joined_base_rewrite_clause(
 
     // First pass: identify shared variables (appear in multiple patterns) and track their categories
     let mut var_pattern_counts: HashMap<String, Vec<(usize, Ident)>> = HashMap::new();
+    let mut rest_capture_names = std::collections::HashSet::new();
     for (pat_idx, pattern) in patterns.iter().enumerate() {
         for capture in &pattern.captures {
+            if capture.field_idx == usize::MAX {
+                rest_capture_names.insert(capture.var_name.clone());
+            }
             var_pattern_counts
                 .entry(capture.var_name.clone())
                 .or_insert_with(Vec::new)
@@ -193,6 +201,7 @@ fn generate_joined_base_rewrite_clause(
     let shared_vars: HashMap<String, (Vec<usize>, Ident)> = var_pattern_counts
         .iter()
         .filter(|(_, pattern_cats)| pattern_cats.len() > 1)
+        .filter(|(name, _)| !rest_capture_names.contains(*name))
         .map(|(name, pattern_cats)| {
             let pattern_idxs: Vec<_> = pattern_cats.iter().map(|(idx, _)| *idx).collect();
             let category = pattern_cats[0].1.clone(); // All should have the same category
@@ -200,8 +209,11 @@ fn generate_joined_base_rewrite_clause(
         })
         .collect();
 
-    // Generate projection joins for all patterns
-    let mut projection_calls = Vec::new();
+    // Generate projection joins for all patterns. A shared variable is resolved
+    // through eq_<cat> from its first occurrence *before* the projection that
+    // repeats it, so that projection is an indexed lookup on (parent, var)
+    // rather than a scan filtered afterwards - this keeps N-way joins usable.
+    let mut join_clauses = Vec::new();
     let mut elem_vars = Vec::new();
     let mut all_capture_vars = Vec::new();
     let mut rest_vars = Vec::new(); // Track rest variables
@@ -261,32 +273,29 @@ fn generate_joined_base_rewrite_clause(
             }
         }
 
-        projection_calls.push(quote! {
+        // Bind shared variables seen in an earlier pattern (transitivity is handled by eqrel,
+        // so checking against the first occurrence is enough)
+        let mut pattern_shared: Vec<_> = shared_vars
+            .iter()
+            .filter(|(_, (pattern_idxs, _))| {
+                pattern_idxs[0] < pat_idx && pattern_idxs.contains(&pat_idx)
+            })
+            .collect();
+        pattern_shared.sort_by(|a, b| a.0.cmp(b.0));
+        for (var_name, (pattern_idxs, category)) in pattern_shared {
+            let eq_rel = format_ident!("eq_{}", category.to_string().to_lowercase());
+            let first_var = format_ident!("cap_{}_p{}", var_name.to_lowercase(), pattern_idxs[0]);
+            let this_var = format_ident!("cap_{}_p{}", var_name.to_lowercase(), pat_idx);
+            join_clauses.push(quote! {
+                #eq_rel(#first_var.clone(), #this_var)
+            });
+        }
+
+        join_clauses.push(quote! {
             #rel_name(#(#proj_args),*)
         });
     }
 
-    // Generate equational checks for shared variables
-    // For each shared variable, check equality between all its occurrences using eq_* relation
-    let mut equational_checks = Vec::new();
-    for (var_name, (pattern_idxs, category)) in &shared_vars {
-        let eq_rel = format_ident!("eq_{}", category.to_string().to_lowercase());
-
-        // For each pair of occurrences, add eq_category(cap_var_p0, cap_var_p1)
-        // We only need to check the first occurrence against all others (transitivity handled by eqrel)
-        if pattern_idxs.len() > 1 {
-            let first_idx = pattern_idxs[0];
-            let first_var = format_ident!("cap_{}_p{}", var_name.to_lowercase(), first_idx);
-
-            for &other_idx in &pattern_idxs[1..] {
-                let other_var = format_ident!("cap_{}_p{}", var_name.to_lowercase(), other_idx);
-                equational_checks.push(quote! {
-                    #eq_rel(#first_var.clone(), #other_var.clone())
-                });
-            }
-        }
-    }
-
     // For nested patterns, we now extract ALL captures via projection (using full pattern matching)
     // So we can use direct RHS reconstruction for all cases
     let rhs_term = generate_rhs_reconstruction(rhs, &all_capture_vars, &rest_vars, theory);
@@ -294,7 +303,9 @@ fn generate_joined_base_rewrite_clause(
         let rhs_term = #rhs_term
     };
 
-    // Generate element removal for all matched elements
+    // Generate element removal for all matched elements. With several patterns,
+    // two of them may pick the same element; removal fails unless the bag holds
+    // enough copies of it.
     let elem_removal = if elem_vars.len() == 1 {
         let elem_var = &elem_vars[0];
         quote! {
@@ -305,44 +316,25 @@ fn generate_joined_base_rewrite_clause(
             }
         }
     } else {
-        let removals = elem_vars.iter().map(|ev| quote! { b.remove(#ev); });
         quote! {
-            let remaining = {
+            if let Some(remaining) = {
                 let mut b = bag.clone();
-                #(#removals)*
-                b
+                if #(b.remove(#elem_vars))&&* { Some(b) } else { None }
             }
         }
     };
 
-    // Build the clause body, conditionally including equational checks
-    let clause_body = if equational_checks.is_empty() {
-        quote! {
-            #rw_rel(parent, result) <--
-                #(#projection_calls),*,
-                #rhs_generation,
-                if let #parent_cat::#constructor(ref bag) = parent,
-                #elem_removal,
-                let result = #parent_cat::#constructor({
-                    let mut bag_result = remaining;
-                    #parent_cat::#insert_helper(&mut bag_result, rhs_term);
-                    bag_result
-                }).normalize();
-        }
-    } else {
-        quote! {
-            #rw_rel(parent, result) <--
-                #(#projection_calls),*,
-                #(#equational_checks),*,
-                #rhs_generation,
-                if let #parent_cat::#constructor(ref bag) = parent,
-                #elem_removal,
-                let result = #parent_cat::#constructor({
-                    let mut bag_result = remaining;
-                    #parent_cat::#insert_helper(&mut bag_result, rhs_term);
-                    bag_result
-                }).normalize();
-        }
+    let clause_body = quote! {
+        #rw_rel(parent, result, #label) <--
+            #(#join_clauses),*,
+            #rhs_generation,
+            if let #parent_cat::#constructor(ref bag) = parent,
+            #elem_removal,
+            let result = #parent_cat::#constructor({
+                let mut bag_result = remaining;
+                #parent_cat::#insert_helper(&mut bag_result, rhs_term);
+                bag_result
+            }).normalize();
     };
 
     clause_body
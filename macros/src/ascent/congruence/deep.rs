@@ -0,0 +1,321 @@
+//! Deep projections for nested collection patterns
+//!
+//! A pattern like `(PAmb N (PPar {(PIn M P), ...rest}))` has a collection pattern
+//! below its root. Matched inline, every candidate element re-scans the inner bag.
+//! Instead, each nested `(Ctor {...})` is lifted into its own relation keyed by the
+//! inner collection term:
+//!
+//! ```text
+//! pamb_proj_c3_b0_p0_d0(t, m, p, rest) <--
+//!     proc(t), if let Proc::PPar(t_f0) = t, for (t_f0_elem_0, _) in t_f0.iter(), ...
+//! ```
+//!
+//! and the enclosing pattern joins it on that term, which Ascent indexes. Lifting
+//! recurses, so nesting of any depth becomes a chain of indexed joins.
+
+use super::analysis::get_constructor_collection_element_type;
+use crate::ascent::rewrites::generate_ascent_pattern;
+use crate::ast::{Expr, TheoryDef};
+use crate::utils::bag_type;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use std::collections::{HashMap, HashSet};
+use syn::Ident;
+
+/// A lifted sub-pattern: `rel(placeholder, captures...)`
+struct DeepJoin {
+    placeholder: String,
+    rel: Ident,
+    captures: Vec<DeepCapture>,
+}
+
+struct DeepCapture {
+    var_name: String,
+    /// `None` for rest variables (bound to a `HashBag`)
+    category: Option<Ident>,
+}
+
+/// Lifting state shared across one top-level pattern
+struct DeepContext<'a> {
+    theory: &'a TheoryDef,
+    rel_prefix: String,
+    joins: Vec<DeepJoin>,
+    rules: Vec<TokenStream>,
+}
+
+/// Like `generate_ascent_pattern`, but nested collection patterns are matched
+/// through deep projection relations. The relation declarations and population
+/// rules are appended to `rules`; names are `<rel_prefix>_d<k>`.
+#[allow(clippy::too_many_arguments)]
+pub fn generate_deep_pattern(
+    expr: &Expr,
+    term_name: &Ident,
+    expected_category: &Ident,
+    rel_prefix: &str,
+    theory: &TheoryDef,
+    bindings: &mut HashMap<String, TokenStream>,
+    variable_categories: &mut HashMap<String, Ident>,
+    clauses: &mut Vec<TokenStream>,
+    duplicate_vars: &HashSet<String>,
+    equational_checks: &mut Vec<TokenStream>,
+    modulo_eq: bool,
+    rules: &mut Vec<TokenStream>,
+) {
+    let mut ctx = DeepContext {
+        theory,
+        rel_prefix: rel_prefix.to_string(),
+        joins: Vec::new(),
+        rules: Vec::new(),
+    };
+    let lifted = lift(expr, true, &mut ctx);
+
+    generate_ascent_pattern(
+        &lifted,
+        term_name,
+        expected_category,
+        theory,
+        bindings,
+        variable_categories,
+        clauses,
+        duplicate_vars,
+        equational_checks,
+        modulo_eq,
+    );
+
+    for join in &ctx.joins {
+        let key = bindings
+            .remove(&join.placeholder)
+            .expect("deep projection placeholder should be bound");
+        variable_categories.remove(&join.placeholder);
+
+        let mut args = vec![key];
+        for capture in &join.captures {
+            let fresh = format_ident!(
+                "{}_{}",
+                join.placeholder.trim_start_matches('_'),
+                capture.var_name.to_lowercase()
+            );
+            args.push(quote! { #fresh });
+
+            // Variables shared with the enclosing pattern are joined up to equality
+            if let Some(existing) = bindings.get(&capture.var_name) {
+                match &capture.category {
+                    Some(cat) => {
+                        let eq_rel = format_ident!("eq_{}", cat.to_string().to_lowercase());
+                        equational_checks.push(quote! { #eq_rel(#existing, #fresh.clone()) });
+                    },
+                    None => equational_checks.push(quote! { if #existing == #fresh.clone() }),
+                }
+                continue;
+            }
+
+            bindings.insert(capture.var_name.clone(), quote! { #fresh.clone() });
+            if let Some(cat) = &capture.category {
+                variable_categories.insert(capture.var_name.clone(), cat.clone());
+            }
+        }
+
+        let rel = &join.rel;
+        clauses.push(quote! { #rel(#(#args),*) });
+    }
+
+    rules.extend(ctx.rules);
+}
+
+/// Replace nested collection sub-patterns with placeholder variables, generating
+/// a deep relation for each
+fn lift(expr: &Expr, is_root: bool, ctx: &mut DeepContext) -> Expr {
+    match expr {
+        Expr::Apply { constructor, args } => {
+            if !is_root && is_collection_apply(expr, ctx.theory) {
+                if let Some(placeholder) = deep_relation(expr, ctx) {
+                    return Expr::Var(placeholder);
+                }
+            }
+            Expr::Apply {
+                constructor: constructor.clone(),
+                args: args.iter().map(|a| lift(a, false, ctx)).collect(),
+            }
+        },
+        Expr::CollectionPattern { constructor, elements, rest } => Expr::CollectionPattern {
+            constructor: constructor.clone(),
+            elements: elements.iter().map(|e| lift(e, false, ctx)).collect(),
+            rest: rest.clone(),
+        },
+        Expr::Var(_) | Expr::Subst { .. } => expr.clone(),
+    }
+}
+
+/// `(Ctor {...})` where `Ctor` has a collection field
+fn is_collection_apply(expr: &Expr, theory: &TheoryDef) -> bool {
+    match expr {
+        Expr::Apply { constructor, args } => {
+            get_constructor_collection_element_type(constructor, theory).is_some()
+                && args
+                    .iter()
+                    .any(|a| matches!(a, Expr::CollectionPattern { .. }))
+        },
+        _ => false,
+    }
+}
+
+/// Generate the deep relation for a nested collection pattern and return the
+/// placeholder standing in for it. Returns `None` (leave the pattern inline)
+/// when a capture has no relation type, e.g. binders or native values.
+fn deep_relation(expr: &Expr, ctx: &mut DeepContext) -> Option<Ident> {
+    let theory = ctx.theory;
+    let Expr::Apply { constructor, .. } = expr else {
+        return None;
+    };
+    let category = theory
+        .terms
+        .iter()
+        .find(|r| r.label == *constructor)?
+        .category
+        .clone();
+    if !is_exported(&category, theory) || contains_binder(expr, theory) {
+        return None;
+    }
+
+    let idx = ctx.joins.len();
+    let rel = format_ident!("{}_d{}", ctx.rel_prefix, idx);
+    let rel_prefix = rel.to_string();
+
+    let mut bindings = HashMap::new();
+    let mut variable_categories = HashMap::new();
+    let mut equational_checks = Vec::new();
+    let mut rules = Vec::new();
+    let cat_lower = format_ident!("{}", category.to_string().to_lowercase());
+    let mut clauses = vec![quote! { #cat_lower(t) }];
+
+    generate_deep_pattern(
+        expr,
+        &format_ident!("t"),
+        &category,
+        &rel_prefix,
+        theory,
+        &mut bindings,
+        &mut variable_categories,
+        &mut clauses,
+        &duplicate_vars(expr),
+        &mut equational_checks,
+        false,
+        &mut rules,
+    );
+
+    let rest_vars = rest_var_types(expr, theory);
+    let mut var_names: Vec<&String> = bindings.keys().collect();
+    var_names.sort();
+
+    let mut captures = Vec::new();
+    let mut field_types = vec![quote! { #category }];
+    let mut capture_lets = Vec::new();
+    let mut rel_fields = vec![quote! { t.clone() }];
+    for var_name in var_names {
+        let (ty, cat) = if let Some(elem_cat) = rest_vars.get(var_name) {
+            {
+                let bag_ty = bag_type(theory);
+                (quote! { #bag_ty<#elem_cat> }, None)
+            }
+        } else {
+            let cat = variable_categories.get(var_name)?;
+            if !is_exported(cat, theory) {
+                return None;
+            }
+            (quote! { #cat }, Some(cat.clone()))
+        };
+        let var_ident = format_ident!("{}", var_name.to_lowercase());
+        let binding = &bindings[var_name];
+        field_types.push(ty);
+        capture_lets.push(quote! { let #var_ident = #binding });
+        rel_fields.push(quote! { #var_ident.clone() });
+        captures.push(DeepCapture {
+            var_name: var_name.clone(),
+            category: cat,
+        });
+    }
+
+    clauses.extend(equational_checks);
+    clauses.extend(capture_lets);
+
+    ctx.rules.extend(rules);
+    ctx.rules.push(quote! {
+        relation #rel(#(#field_types),*);
+    });
+    ctx.rules.push(quote! {
+        #rel(#(#rel_fields),*) <--
+            #(#clauses),*;
+    });
+
+    let placeholder = format_ident!("__deep{}", idx);
+    ctx.joins.push(DeepJoin {
+        placeholder: placeholder.to_string(),
+        rel,
+        captures,
+    });
+    Some(placeholder)
+}
+
+fn is_exported(category: &Ident, theory: &TheoryDef) -> bool {
+    theory.exports.iter().any(|e| e.name == *category)
+}
+
+fn contains_binder(expr: &Expr, theory: &TheoryDef) -> bool {
+    match expr {
+        Expr::Apply { constructor, args } => {
+            theory
+                .terms
+                .iter()
+                .any(|r| r.label == *constructor && !r.bindings.is_empty())
+                || args.iter().any(|a| contains_binder(a, theory))
+        },
+        Expr::CollectionPattern { elements, .. } => {
+            elements.iter().any(|e| contains_binder(e, theory))
+        },
+        Expr::Var(_) | Expr::Subst { .. } => false,
+    }
+}
+
+/// Variables occurring more than once in a pattern
+pub fn duplicate_vars(expr: &Expr) -> HashSet<String> {
+    fn count(expr: &Expr, counts: &mut HashMap<String, usize>) {
+        match expr {
+            Expr::Var(v) => *counts.entry(v.to_string()).or_default() += 1,
+            Expr::Apply { args, .. } => args.iter().for_each(|a| count(a, counts)),
+            Expr::CollectionPattern { elements, .. } => {
+                elements.iter().for_each(|e| count(e, counts))
+            },
+            Expr::Subst { .. } => {},
+        }
+    }
+    let mut counts = HashMap::new();
+    count(expr, &mut counts);
+    counts
+        .into_iter()
+        .filter(|(_, n)| *n > 1)
+        .map(|(v, _)| v)
+        .collect()
+}
+
+/// Rest variables of a pattern, with the element category of their collection
+fn rest_var_types(expr: &Expr, theory: &TheoryDef) -> HashMap<String, Ident> {
+    fn walk(expr: &Expr, theory: &TheoryDef, out: &mut HashMap<String, Ident>) {
+        if let Expr::Apply { constructor, args } = expr {
+            for arg in args {
+                if let Expr::CollectionPattern { elements, rest, .. } = arg {
+                    if let (Some(rest), Some(elem_cat)) =
+                        (rest, get_constructor_collection_element_type(constructor, theory))
+                    {
+                        out.insert(rest.to_string(), elem_cat);
+                    }
+                    elements.iter().for_each(|e| walk(e, theory, out));
+                } else {
+                    walk(arg, theory, out);
+                }
+            }
+        }
+    }
+    let mut out = HashMap::new();
+    walk(expr, theory, &mut out);
+    out
+}
@@ -5,13 +5,16 @@
 //! - Regular congruences (if S => T then Constructor(...S...) => Constructor(...T...))
 //! - Binding congruences (if S => T then (new x.S) => (new x.T))
 //! - Projection-based approaches for efficient matching
+//! - Deep projections for collection patterns nested below the root
 
+use crate::ascent::relations::rule_relation;
 use crate::ast::{Expr, RewriteRule, TheoryDef};
 use proc_macro2::TokenStream;
 
 mod analysis;
 mod binding;
 mod collection;
+mod deep;
 mod projections;
 mod regular;
 
@@ -30,6 +33,9 @@
 // Re-export from binding
 pub use binding::generate_projection_based_binding_congruence;
 
+// Re-export from deep
+pub use deep::generate_deep_pattern;
+
 // Re-export from projections
 pub use projections::generate_congruence_projections;
 
@@ -56,8 +62,7 @@ pub fn generate_congruence_rewrite(
                             // Collection congruence - use simple approach for non-projection cases
                             let cat_lower =
                                 quote::format_ident!("{}", category.to_string().to_lowercase());
-                            let rw_rel =
-                                quote::format_ident!("rw_{}", category.to_string().to_lowercase());
+                            let rw_rel = rule_relation(&category);
                             let rest_var = if let Expr::CollectionPattern { rest, .. } = arg {
                                 rest.as_ref()
                             } else {
@@ -113,7 +118,7 @@ pub fn generate_congruence_rewrite(
     } else {
         // Regular non-binding congruence
         let cat_lower = quote::format_ident!("{}", category.to_string().to_lowercase());
-        let rw_rel = quote::format_ident!("rw_{}", category.to_string().to_lowercase());
+        let rw_rel = rule_relation(&category);
         regular::generate_regular_congruence(
             &category,
             &cat_lower,
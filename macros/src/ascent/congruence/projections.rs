@@ -15,11 +15,13 @@
     extract_element_patterns_from_base_rewrite, extract_variable_categories,
     find_base_rewrites_for_category, CaptureInfo, CollectionCongruenceInfo, ElementPatternInfo,
 };
+use super::deep;
 use super::regular::{
     extract_regular_congruence_pattern, find_regular_congruences_for_category,
     RegularCongruencePattern,
 };
 use crate::ast::{Expr, GrammarItem, TheoryDef};
+use crate::utils::bag_type;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 use syn::Ident;
@@ -149,7 +151,7 @@ fn generate_base_rewrite_projection(
     base_lhs: &Expr, // Full LHS of the base rewrite for nested pattern matching
     theory: &TheoryDef,
 ) -> (Vec<TokenStream>, ElementPatternInfo) {
-    use crate::ascent::rewrites;
+    let bag_ty = bag_type(theory);
     use std::collections::HashMap;
 
     let mut result = Vec::new();
@@ -178,20 +180,24 @@ fn generate_base_rewrite_projection(
         let mut variable_categories: HashMap<String, Ident> = HashMap::new();
         let mut clauses = Vec::new();
         let mut equational_checks = Vec::new();
-        let duplicate_vars = std::collections::HashSet::new(); // No duplicates in single pattern
+        let duplicate_vars = deep::duplicate_vars(pattern_expr);
 
-        // Generate pattern matching clauses for the element expression
+        // Generate pattern matching clauses for the element expression; collections
+        // nested inside it are matched through deep projections
         let elem_ident = format_ident!("elem");
-        rewrites::generate_ascent_pattern(
+        deep::generate_deep_pattern(
             pattern_expr,
             &elem_ident,
             elem_cat,
+            &rel_name.to_string(),
             theory,
             &mut bindings,
             &mut variable_categories,
             &mut clauses,
             &duplicate_vars,
             &mut equational_checks,
+            theory.options.rewrite_modulo_equations,
+            &mut result,
         );
 
         // Build relation signature from bindings
@@ -207,7 +213,12 @@ fn generate_base_rewrite_projection(
         let mut rhs_bindings = HashMap::new();
         let mut nested_rest_vars = Vec::new(); // Rest variables from nested patterns
 
-        for (var_name, binding_ts) in &bindings {
+        // In name order, so the generated program is the same from build to build
+        let mut var_names: Vec<&String> = bindings.keys().collect();
+        var_names.sort();
+
+        for &var_name in &var_names {
+            let binding_ts = &bindings[var_name];
             let var_ident = format_ident!("{}", var_name.to_lowercase());
             // Try to get category from variable_categories (for duplicates) or infer from var_categories
             let cat = variable_categories
@@ -222,7 +233,7 @@ fn generate_base_rewrite_projection(
             } else {
                 // No category found - this is a rest variable from nested pattern matching
                 // Add it to projection signature as HashBag<ElementCategory>
-                field_types.push(quote! { mettail_runtime::HashBag<#elem_cat> });
+                field_types.push(quote! { #bag_ty<#elem_cat> });
                 rel_fields.push(quote! { #var_ident.clone() });
                 // For rest variables, the binding from pattern matching is just the identifier
                 // We need to add .clone() since HashBag doesn't implement Copy
@@ -243,7 +254,7 @@ fn generate_base_rewrite_projection(
 
         // Add rest bag to signature if rest variable present
         if let Some(rest_var) = &pattern.rest_var {
-            field_types.push(quote! { mettail_runtime::HashBag<#elem_cat> });
+            field_types.push(quote! { #bag_ty<#elem_cat> });
             let rest_ident = format_ident!("rest_{}", rest_var.to_string().to_lowercase());
             rel_fields.push(quote! { #rest_ident.clone() });
         }
@@ -289,15 +300,16 @@ fn generate_base_rewrite_projection(
                 if let #parent_cat::#collection_constructor(ref bag_field) = parent,
                 for (elem, _count) in bag_field.iter(),
                 #(#clauses),*,
+                #(#equational_checks,)*
                 #(#capture_bindings),*
                 #rest_computation;
         };
 
         // Build updated pattern with extracted captures
-        // Use the order from bindings to ensure consistent ordering with projection signature
+        // Use the order of the projection signature
         // Include both regular captures and rest variables (rest variables have no category in variable_categories)
-        let updated_captures: Vec<CaptureInfo> = bindings
-            .keys()
+        let updated_captures: Vec<CaptureInfo> = var_names
+            .into_iter()
             .filter_map(|var_name| {
                 // Try to get category - if found, it's a regular capture; if not, it's a rest variable
                 let cat_opt = variable_categories
@@ -347,7 +359,7 @@ fn generate_base_rewrite_projection(
 
     // Add rest bag to signature if rest variable present
     if pattern.rest_var.is_some() {
-        field_types.push(quote! { mettail_runtime::HashBag<#elem_cat> });
+        field_types.push(quote! { #bag_ty<#elem_cat> });
     }
 
     // Generate relation declaration
@@ -394,6 +406,19 @@ fn generate_base_rewrite_projection(
         quote! {}
     };
 
+    // Match the element, up to eq_<elem_cat> when rewriting modulo equations
+    let elem_match = if theory.options.rewrite_modulo_equations {
+        let eq_rel = format_ident!("eq_{}", elem_cat.to_string().to_lowercase());
+        let elem_rel = format_ident!("{}", elem_cat.to_string().to_lowercase());
+        quote! {
+            #eq_rel(elem.clone(), elem_eq),
+            #elem_rel(elem_eq),
+            if let #elem_cat::#elem_constructor(#field_patterns) = elem_eq
+        }
+    } else {
+        quote! { if let #elem_cat::#elem_constructor(#field_patterns) = elem }
+    };
+
     // Generate the population rule with conditional capture bindings
     let population_rule = if pattern.captures.is_empty() {
         // No captures - simpler pattern without bindings
@@ -402,7 +427,7 @@ fn generate_base_rewrite_projection(
                 #parent_cat_lower(parent),
                 if let #parent_cat::#collection_constructor(ref bag_field) = parent,
                 for (elem, _count) in bag_field.iter(),
-                if let #elem_cat::#elem_constructor(#field_patterns) = elem,
+                #elem_match,
                 #rest_computation;
         }
     } else {
@@ -412,7 +437,7 @@ fn generate_base_rewrite_projection(
                 #parent_cat_lower(parent),
                 if let #parent_cat::#collection_constructor(ref bag_field) = parent,
                 for (elem, _count) in bag_field.iter(),
-                if let #elem_cat::#elem_constructor(#field_patterns) = elem,
+                #elem_match,
                 #capture_bindings
                 #rest_computation;
         }
@@ -15,6 +15,7 @@
     contains_collection_pattern, extract_category, is_collection_congruence,
     CollectionCongruenceInfo,
 };
+use crate::ascent::relations::rule_relation;
 use crate::ast::{Expr, RewriteRule, TheoryDef};
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
@@ -26,10 +27,10 @@
 /// Generate congruence for regular (non-binding) constructors
 /// Example: if S => T then (PPar P S) => (PPar P T)
 /// Generates:
-/// rw_proc(s, t) <--
+/// rw_proc_rule(s, t, rule) <--
 ///     proc(s),
 ///     if let Proc::PPar(p, s0) = s,
-///     rw_proc(**s0, t0),
+///     rw_proc_rule(**s0, t0, rule),
 ///     let t = Proc::PPar(p.clone(), Box::new(t0.clone()));
 pub fn generate_regular_congruence(
     category: &Ident,
@@ -72,10 +73,10 @@ pub fn generate_regular_congruence(
         .collect();
 
     Some(quote! {
-        #rw_rel(s, t) <--
+        #rw_rel(s, t, rule) <--
             #cat_lower(s),
             if let #category::#constructor(#(#field_patterns),*) = s,
-            #rw_rel(**s0, #rewritten_field),
+            #rw_rel(**s0, #rewritten_field, rule),
             let t = #category::#constructor(#(#recon_args),*);
     })
 }
@@ -100,7 +101,7 @@ pub fn generate_regular_congruence_clause(
         reg_idx
     );
 
-    let elem_rw_rel = format_ident!("rw_{}", pattern.category.to_string().to_lowercase());
+    let elem_rw_rel = rule_relation(&pattern.category);
     let elem_constructor = &pattern.constructor;
     let elem_cat = &pattern.category;
 
@@ -159,9 +160,9 @@ pub fn generate_regular_congruence_clause(
     };
 
     quote! {
-        #rw_rel(parent, result) <--
+        #rw_rel(parent, result, rule) <--
             #rel_name #proj_args,
-            #elem_rw_rel(body, body_rewritten),
+            #elem_rw_rel(body, body_rewritten, rule),
             if let #parent_cat::#constructor(ref bag) = parent,
             let remaining = {
                 let mut b = bag.clone();
@@ -13,7 +13,8 @@
 
 use crate::ascent::congruence;
 use crate::ascent::rewrites;
-use crate::ast::{Equation, Expr, TheoryDef};
+use crate::ast::{Equation, EquationBackend, Expr, TheoryDef};
+use crate::utils::bag_type;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 use std::collections::{HashMap, HashSet};
@@ -36,6 +37,24 @@ pub fn generate_equation_rules(theory: &TheoryDef) -> TokenStream {
         });
     }
 
+    // With the e-graph backend, each term is related to the representative of
+    // its saturated e-class instead: the e-graph closes equations under
+    // congruence (including collections), so no clauses are generated for it
+    if theory.options.equation_backend == EquationBackend::EGraph {
+        let egraph = format_ident!("{}EGraph", theory.name);
+        for export in &theory.exports {
+            let cat_lower = format_ident!("{}", export.name.to_string().to_lowercase());
+            let eq_rel = format_ident!("eq_{}", export.name.to_string().to_lowercase());
+            let canonical = format_ident!("canonical_{}", export.name.to_string().to_lowercase());
+            rules.push(quote! {
+                #eq_rel(t.clone(), #egraph::#canonical(t)) <-- #cat_lower(t);
+            });
+        }
+        return quote! {
+            #(#rules)*
+        };
+    }
+
     // Add congruence rules for all constructors
     // If arg1 == arg2, then Constructor(arg1) == Constructor(arg2)
     let congruence_rules = generate_congruence_rules(theory);
@@ -188,13 +207,17 @@ fn generate_equation_pattern_via_rewrite_logic(
         &mut clauses,
         &duplicate_vars,
         &mut equational_checks,
+        false, // equations always match syntactically
     );
 
     // Convert bindings to equation format
     // Rewrite bindings are TokenStream like `term.clone()` or `(*field).clone()`
     // For equations, we need explicit `let var = ...` bindings after pattern matching
     // so the RHS can reference simple variable names
+    // In name order, so the generated program is the same from build to build
     let mut explicit_bindings = Vec::new();
+    let mut rewrite_bindings: Vec<_> = rewrite_bindings.into_iter().collect();
+    rewrite_bindings.sort_by(|a, b| a.0.cmp(&b.0));
 
     for (var_name, binding_expr) in &rewrite_bindings {
         // Skip internal binder variable names (binder_0, binder_1, etc.)
@@ -262,6 +285,7 @@ fn generate_equation_rhs_from_idents(
     bindings: &HashMap<String, Ident>,
     theory: &TheoryDef,
 ) -> TokenStream {
+    let bag_ty = bag_type(theory);
     match expr {
         Expr::Var(var) => {
             // Check if this is a constructor or a variable
@@ -375,7 +399,7 @@ fn generate_equation_rhs_from_idents(
 
                 quote! {
                     #category::#cons({
-                        let mut bag = mettail_runtime::HashBag::new();
+                        let mut bag = #bag_ty::new();
                         #(#elem_inserts)*
                         #rest_merge
                         bag
@@ -629,7 +653,7 @@ fn generate_equation_rhs(
                 .map(|e| generate_equation_rhs(e, bindings, theory, false))
                 .collect();
 
-            let coll_type = quote! { mettail_runtime::HashBag };
+            let coll_type = bag_type(theory);
 
             if let Some(rest_var) = rest {
                 // Merge rest with new elements
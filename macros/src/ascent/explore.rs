@@ -0,0 +1,162 @@
+//! Generated exploration helper
+//!
+//! Emits a unit struct named after the theory with an `explore` function that
+//! runs the theory's Ascent program from a term of the primary (first exported)
+//! category and collects the result into a `mettail_runtime::RewriteGraph`,
+//! whose edges carry the label of the rule that took them (`rw_<cat>_rule`):
+//!
+//! ```text
+//! let graph = RhoCalc::explore(term);
+//! println!("{}", graph.to_dot());
+//! ```
+//!
+//! With the theory's `stats` option, `explore_with_stats` also returns the
+//! run's `mettail_runtime::ExploreStats`, running the copy of the program that
+//! counts rule firings (see the `stats` module); `explore` itself runs the
+//! program as is.
+//!
+//! `step` runs the same program seeded with the `one_step(true)` fact, which turns
+//! off exploring rewrite targets, for the rewrites of the term alone.
+//!
+//! Must be emitted after the `ascent_source!` block, whose macro it includes.
+
+use super::ProgramLayout;
+use crate::ast::TheoryDef;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+pub fn generate_explore(theory: &TheoryDef, layout: &ProgramLayout) -> TokenStream {
+    let Some(primary) = theory.exports.first() else {
+        return quote! {};
+    };
+
+    let theory_name = &theory.name;
+    let source_name = format_ident!("{}_source", theory.name.to_string().to_lowercase());
+    let category = &primary.name;
+    let cat_lower = category.to_string().to_lowercase();
+    let cat_rel = format_ident!("{}", cat_lower);
+    let rw_rel = format_ident!("rw_{}", cat_lower);
+    let rule_rel = super::relations::rule_relation(category);
+    let eq_common = format_ident!("__eq_{}_ind_common", cat_lower);
+    let stats_source_name =
+        format_ident!("{}_stats_source", theory.name.to_string().to_lowercase());
+
+    // Collect a run `prog` from `term` into `graph`
+    let build_graph = quote! {
+        // The category relation also holds every subterm; keep only what the
+        // root rewrites to
+        let mut steps: ::std::collections::HashMap<&#category, Vec<(&#category, &str)>> =
+            ::std::collections::HashMap::new();
+        for (from, to, rule) in prog.#rule_rel.iter() {
+            steps.entry(from).or_default().push((to, *rule));
+        }
+
+        let mut graph = mettail_runtime::RewriteGraph::new();
+        graph.set_root(term.clone());
+        let mut frontier = vec![&term];
+        while let Some(from) = frontier.pop() {
+            for &(to, rule) in steps.get(from).into_iter().flatten() {
+                if graph.index_of(to).is_none() {
+                    frontier.push(to);
+                }
+                graph.add_edge(from.clone(), to.clone(), rule);
+            }
+        }
+        for (lhs, rhs) in prog.#eq_common.iter_all_added() {
+            graph.add_equivalence(lhs, rhs);
+        }
+    };
+
+    let explore_with_stats = if theory.options.stats {
+        let relation_names = layout.relations.iter().map(|rel| rel.name.to_string());
+        let relation_sizes = layout.relations.iter().map(|rel| {
+            if rel.is_eqrel {
+                let common = format_ident!("__{}_ind_common", rel.name);
+                quote! { prog.#common.count_exact() }
+            } else {
+                let name = &rel.name;
+                quote! { prog.#name.len() }
+            }
+        });
+        let rule_count = layout.rule_labels.len();
+        let rule_labels = &layout.rule_labels;
+        quote! {
+            /// `explore`, with the number of facts per relation, firings per rule, and
+            /// Ascent's iterations and time per SCC
+            pub fn explore_with_stats(
+                term: #category,
+            ) -> (mettail_runtime::RewriteGraph<#category>, mettail_runtime::ExploreStats) {
+                let start = ::std::time::Instant::now();
+                let (prog, firings) = mettail_runtime::count_firings(#rule_count, || {
+                    ::ascent::ascent_run! {
+                        include_source!(#stats_source_name);
+                        #cat_rel(term.clone());
+                    }
+                });
+                let ascent_time = start.elapsed();
+
+                #build_graph
+
+                let labels: [&str; #rule_count] = [#(#rule_labels),*];
+                let stats = mettail_runtime::ExploreStats {
+                    relations: vec![#((#relation_names.to_string(), #relation_sizes)),*],
+                    rules: labels
+                        .iter()
+                        .zip(firings)
+                        .map(|(label, firings)| mettail_runtime::RuleStats {
+                            label: label.to_string(),
+                            firings,
+                        })
+                        .collect(),
+                    sccs: prog
+                        .scc_iters
+                        .iter()
+                        .zip(prog.scc_times.iter())
+                        .map(|(&iterations, &time)| mettail_runtime::SccStats { iterations, time })
+                        .collect(),
+                    ascent_time,
+                    graph_time: start.elapsed() - ascent_time,
+                };
+                (graph, stats)
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    quote! {
+        /// Entry points for running this theory
+        pub struct #theory_name;
+
+        impl #theory_name {
+            /// Run the rewrite engine from `term` and collect the terms it rewrites to
+            /// (transitively), the steps between them, their equivalence classes and
+            /// normal forms
+            pub fn explore(term: #category) -> mettail_runtime::RewriteGraph<#category> {
+                let prog = ::ascent::ascent_run! {
+                    include_source!(#source_name);
+                    #cat_rel(term.clone());
+                };
+                #build_graph
+                graph
+            }
+
+            #explore_with_stats
+
+            /// The terms `term` rewrites to in one step, without exploring the terms
+            /// they rewrite to
+            pub fn step(term: #category) -> Vec<#category> {
+                let prog = ::ascent::ascent_run! {
+                    include_source!(#source_name);
+                    #cat_rel(term.clone());
+                    one_step(true);
+                };
+                prog.#rw_rel
+                    .iter()
+                    .filter(|(from, _)| from == &term)
+                    .map(|(_, to)| to.clone())
+                    .collect()
+            }
+        }
+    }
+}
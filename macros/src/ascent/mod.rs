@@ -9,6 +9,8 @@
 //! - `equations` - Equality/equation rules with congruence
 //! - `rewrites/` - Base rewrite rules and pattern/RHS generation
 //! - `congruence/` - Congruence rules for rewrites (collection, regular, binding)
+//! - `explore` - `<Theory>::explore`, collecting a run into a `RewriteGraph`
+//! - `stats` - Rule firing counters for `<Theory>::explore_with_stats`
 //!
 //! ## Generated Code Components
 //!
@@ -23,7 +25,9 @@
 
 mod categories;
 mod equations;
+mod explore;
 mod relations;
+mod stats;
 mod writer;
 
 pub mod congruence;
@@ -32,15 +36,21 @@
 // Re-export key functions
 pub use categories::generate_category_rules;
 pub use equations::generate_equation_rules;
+pub use explore::generate_explore;
 pub use relations::generate_relations;
+pub use stats::ProgramLayout;
 
 // Re-export congruence types and functions used by lib.rs
 pub use congruence::{extract_collection_congruence_info, generate_congruence_projections};
 
 pub use rewrites::{generate_freshness_functions, generate_rewrite_clauses};
 
-/// Main entry point: Generate complete Ascent source for a theory
-pub fn generate_ascent_source(theory: &TheoryDef) -> TokenStream {
+/// Main entry point: Generate complete Ascent source for a theory, and the
+/// layout of the program it declares (for `explore_with_stats`)
+///
+/// With the `stats` option there is a second source, `<theory>_stats_source`:
+/// the same program with its rules instrumented to count firings.
+pub fn generate_ascent_source(theory: &TheoryDef) -> (TokenStream, ProgramLayout) {
     let theory_name = theory.name.to_string().to_lowercase();
     let source_name = format_ident!("{}_source", theory_name);
 
@@ -49,7 +59,10 @@ pub fn generate_ascent_source(theory: &TheoryDef) -> TokenStream {
     let equation_rules = generate_equation_rules(theory);
     let rewrite_rules = generate_rewrite_rules(theory);
 
-    let result = quote! {
+    let mut layout = ProgramLayout::default();
+    layout.declare_relations(&relations);
+
+    let mut result = quote! {
         ::ascent::ascent_source! {
             #source_name:
 
@@ -63,10 +76,31 @@ pub fn generate_ascent_source(theory: &TheoryDef) -> TokenStream {
         }
     };
 
+    let stats_source_name = format_ident!("{}_stats_source", theory_name);
+    if theory.options.stats {
+        let counted_category_rules = layout.instrument_rules("category", category_rules.clone());
+        let counted_equation_rules = layout.instrument_rules("equation", equation_rules.clone());
+        let counted_rewrite_rules = layout.instrument_rules("rewrite", rewrite_rules.clone());
+        result.extend(quote! {
+            ::ascent::ascent_source! {
+                #stats_source_name:
+
+                #relations
+
+                #counted_category_rules
+
+                #counted_equation_rules
+
+                #counted_rewrite_rules
+            }
+        });
+    }
+
     // Format and write the generated Ascent source to file
     let formatted_source = format_ascent_source(
         &theory_name,
         &source_name,
+        theory.options.stats.then_some(&stats_source_name),
         &relations,
         &category_rules,
         &equation_rules,
@@ -78,13 +112,14 @@ pub fn generate_ascent_source(theory: &TheoryDef) -> TokenStream {
         eprintln!("Warning: Failed to write Ascent Datalog file: {}", e);
     }
 
-    result
+    (result, layout)
 }
 
 /// Format Ascent source for display and file output
 fn format_ascent_source(
     theory_name: &str,
     source_name: &Ident,
+    stats_source_name: Option<&Ident>,
     relations: &TokenStream,
     category_rules: &TokenStream,
     equation_rules: &TokenStream,
@@ -94,7 +129,15 @@ fn format_ascent_source(
 
     output.push_str(&format!("// Generated Ascent Datalog for {} theory\n", theory_name));
     output.push_str("// This file is generated by the theory! macro and is for inspection only.\n");
-    output.push_str("// Do not edit manually - changes will be overwritten.\n\n");
+    output.push_str("// Do not edit manually - changes will be overwritten.\n");
+    if let Some(stats_source_name) = stats_source_name {
+        output.push_str(&format!(
+            "// {} (for explore_with_stats) is this program with each rule ending in\n\
+             // `if mettail_runtime::fire(<rule index>)`, which counts its firings.\n",
+            stats_source_name
+        ));
+    }
+    output.push('\n');
 
     output.push_str("ascent_source! {\n");
     output.push_str(&format!("    {}:\n\n", source_name));
@@ -241,18 +284,19 @@ fn generate_semantic_rules(theory: &TheoryDef) -> Vec<TokenStream> {
 
             if non_terminals.len() == 2 {
                 let category = &rule.category;
-                let label = &rule.label;
+                let constructor = &rule.label;
 
                 // Generate rule with proper variable extraction in the head
-                let rw_rel = format_ident!("rw_{}", category.to_string().to_lowercase());
+                let rw_rel = relations::rule_relation(category);
+                let label = constructor.to_string();
                 let cat_rel = format_ident!("{}", category.to_string().to_lowercase());
                 let num_lit = format_ident!("NumLit");
 
                 // Pattern: rw_cat(s, t) with body that matches and extracts a, b
                 rules.push(quote! {
-                    #rw_rel(s, t) <--
+                    #rw_rel(s, t, #label) <--
                         #cat_rel(s),
-                        if let #category::#label(left, right) = s,
+                        if let #category::#constructor(left, right) = s,
                         if let #category::#num_lit(a) = left.as_ref(),
                         if let #category::#num_lit(b) = right.as_ref(),
                         let t = #category::#num_lit(a #op_token b);
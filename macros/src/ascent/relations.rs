@@ -5,9 +5,16 @@
 
 use crate::ascent::congruence::get_constructor_collection_element_type;
 use crate::ast::TheoryDef;
-use proc_macro2::TokenStream;
+use proc_macro2::{Ident, TokenStream};
 use quote::{format_ident, quote};
 
+/// `rw_<cat>_rule(from, to, label)`: the rewrites of a category with their
+/// rule's label. Rewrite and congruence rules derive these, and `rw_<cat>`
+/// forgets the label.
+pub fn rule_relation(category: &Ident) -> Ident {
+    format_ident!("rw_{}_rule", category.to_string().to_lowercase())
+}
+
 /// Generate all relation declarations for a theory
 pub fn generate_relations(theory: &TheoryDef) -> TokenStream {
     let mut relations = Vec::new();
@@ -31,15 +38,24 @@ pub fn generate_relations(theory: &TheoryDef) -> TokenStream {
         });
     }
 
-    // Rewrite relations (per-category, typed)
+    // Rewrite relations (per-category, typed), and the same steps with the
+    // label of the rule that took each
     for export in &theory.exports {
         let cat = &export.name;
         let rw_rel = format_ident!("rw_{}", cat.to_string().to_lowercase());
+        let rule_rel = rule_relation(cat);
         relations.push(quote! {
             relation #rw_rel(#cat, #cat);
+            relation #rule_rel(#cat, #cat, &'static str);
         });
     }
 
+    // Seeded with `one_step(true)` by `<Theory>::step`, which must not explore
+    // rewrite targets (a nullary relation would do, but Ascent warns on negating one)
+    relations.push(quote! {
+        relation one_step(bool);
+    });
+
     // Collection projection relations (automatic)
     // For each constructor with a collection field, generate a "contains" relation
     // Example: PPar(HashBag<Proc>) generates: relation ppar_contains(Proc, Proc);
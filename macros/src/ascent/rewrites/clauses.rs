@@ -11,9 +11,10 @@
     clippy::unnecessary_filter_map
 )]
 
-use super::generate_ascent_pattern;
+use super::rewrite_label;
 use super::rhs::generate_ascent_rhs;
-use crate::ascent::congruence::extract_category;
+use crate::ascent::congruence::{extract_category, generate_deep_pattern};
+use crate::ascent::relations::rule_relation;
 use crate::ast::{Expr, RewriteRule, TheoryDef};
 use crate::utils::has_native_type;
 use proc_macro2::TokenStream;
@@ -32,7 +33,7 @@ pub fn generate_rewrite_clauses(theory: &TheoryDef) -> Vec<TokenStream> {
     let collection_cong_categories =
         congruence::find_collection_congruence_element_categories(theory);
 
-    for (_rule_idx, rule) in theory.rewrites.iter().enumerate() {
+    for (rule_idx, rule) in theory.rewrites.iter().enumerate() {
         // Skip congruence rules (handled elsewhere)
         if rule.premise.is_some() {
             continue;
@@ -75,7 +76,7 @@ pub fn generate_rewrite_clauses(theory: &TheoryDef) -> Vec<TokenStream> {
         }
 
         // OLD PATH: For non-collection patterns or uncovered categories
-        all_clauses.push(generate_rewrite_clause(rule, theory));
+        all_clauses.push(generate_rewrite_clause(rule_idx, rule, theory));
     }
 
     all_clauses
@@ -83,7 +84,7 @@ pub fn generate_rewrite_clauses(theory: &TheoryDef) -> Vec<TokenStream> {
 
 /// Generate a single rewrite rule as an Ascent clause
 /// Example output:
-/// rw_proc(s, t) <--
+/// rw_proc_rule(s, t, "PPar(PInput, POutput)") <--
 ///     proc(s),
 ///     if let Proc::PPar(p_in, p_out) = s,
 ///     if let Proc::PInput(chan1, scope) = &**p_in,
@@ -92,11 +93,12 @@ pub fn generate_rewrite_clauses(theory: &TheoryDef) -> Vec<TokenStream> {
 ///     let (x, p) = scope.clone().unbind(),
 ///     if !p.contains_free(&x),
 ///     let t = p.substitute(&x, &Name::NQuote((**q).clone()));
-fn generate_rewrite_clause(rule: &RewriteRule, theory: &TheoryDef) -> TokenStream {
+fn generate_rewrite_clause(rule_idx: usize, rule: &RewriteRule, theory: &TheoryDef) -> TokenStream {
     let category = extract_category(&rule.left, theory)
         .expect("Failed to extract category from rewrite rule LHS");
     let cat_lower = quote::format_ident!("{}", category.to_string().to_lowercase());
-    let rw_rel = quote::format_ident!("rw_{}", category.to_string().to_lowercase());
+    let rw_rel = rule_relation(&category);
+    let label = rewrite_label(&rule.left);
 
     // Track variable occurrences for duplicate detection
     let mut var_occurrences: HashMap<String, Vec<usize>> = HashMap::new();
@@ -120,18 +122,29 @@ fn generate_rewrite_clause(rule: &RewriteRule, theory: &TheoryDef) -> TokenStrea
     clauses.push(quote! { #cat_lower(s) });
 
     // Generate pattern matching with category tracking
+    // Collections nested below the root are matched through deep projections
     let lhs_category = extract_category(&rule.left, theory)
         .expect("Failed to extract category from rewrite rule LHS");
-    generate_ascent_pattern(
+    let deep_prefix = match &rule.left {
+        Expr::Apply { constructor, .. } => {
+            format!("{}_rw{}", constructor.to_string().to_lowercase(), rule_idx)
+        },
+        _ => format!("{}_rw{}", cat_lower, rule_idx),
+    };
+    let mut deep_rules = Vec::new();
+    generate_deep_pattern(
         &rule.left,
         &quote::format_ident!("s"),
         &lhs_category,
+        &deep_prefix,
         theory,
         &mut bindings,
         &mut variable_categories,
         &mut clauses,
         &duplicate_vars,
         &mut equational_checks,
+        theory.options.rewrite_modulo_equations,
+        &mut deep_rules,
     );
 
     // Add equational checks for duplicate variables
@@ -231,7 +244,7 @@ fn generate_rewrite_clause(rule: &RewriteRule, theory: &TheoryDef) -> TokenStrea
 
     // Generate main rewrite clause
     let main_clause = quote! {
-        #rw_rel(s, t) <--
+        #rw_rel(s, t, #label) <--
             #(#clauses),*;
     };
 
@@ -313,6 +326,7 @@ fn generate_rewrite_clause(rule: &RewriteRule, theory: &TheoryDef) -> TokenStrea
     }
 
     quote! {
+        #(#deep_rules)*
         #main_clause
         #(#fact_clauses)*
     }
@@ -6,7 +6,7 @@
 //! - RHS construction
 //! - Freshness functions
 
-use crate::ast::TheoryDef;
+use crate::ast::{Expr, TheoryDef};
 use proc_macro2::TokenStream;
 use quote::quote;
 
@@ -18,6 +18,29 @@
 pub use clauses::generate_rewrite_clauses;
 pub use patterns::generate_ascent_pattern;
 
+/// The label of the steps a base rewrite takes: the constructors of its
+/// left-hand side, without variables, as `PPar{PInput, POutput}`
+pub fn rewrite_label(left: &Expr) -> String {
+    fn skeleton(expr: &Expr) -> Option<String> {
+        match expr {
+            Expr::Apply { constructor, args } => {
+                let parts: Vec<String> = args.iter().filter_map(skeleton).collect();
+                Some(match args.as_slice() {
+                    _ if parts.is_empty() => constructor.to_string(),
+                    [Expr::CollectionPattern { .. }] => format!("{}{}", constructor, parts[0]),
+                    _ => format!("{}({})", constructor, parts.join(", ")),
+                })
+            },
+            Expr::CollectionPattern { elements, .. } => {
+                let parts: Vec<String> = elements.iter().filter_map(skeleton).collect();
+                Some(format!("{{{}}}", parts.join(", ")))
+            },
+            Expr::Var(_) | Expr::Subst { .. } => None,
+        }
+    }
+    skeleton(left).unwrap_or_else(|| "rewrite".to_string())
+}
+
 /// Generate freshness checking functions
 pub fn generate_freshness_functions(_theory: &TheoryDef) -> TokenStream {
     quote! {
@@ -23,6 +23,9 @@
 /// Handles nested patterns and tracks bindings for equational checks
 /// `expected_category` is the category this expression should have based on its context
 /// Made public for use in congruence projection generation
+///
+/// With `modulo_eq`, the pattern root is matched up to `eq_<cat>`; nested positions
+/// stay syntactic (see `TheoryOptions::rewrite_modulo_equations`)
 pub fn generate_ascent_pattern(
     expr: &Expr,
     term_name: &Ident,
@@ -33,6 +36,7 @@ pub fn generate_ascent_pattern(
     clauses: &mut Vec<TokenStream>,
     duplicate_vars: &std::collections::HashSet<String>,
     equational_checks: &mut Vec<TokenStream>,
+    modulo_eq: bool,
 ) {
     match expr {
         Expr::Var(var) => {
@@ -55,6 +59,8 @@ pub fn generate_ascent_pattern(
             if is_nullary_constructor {
                 // Match the constructor exactly
                 let constructor_ident = var;
+                let term_name =
+                    equivalent_term(term_name, expected_category, theory, clauses, modulo_eq);
                 clauses.push(quote! {
                     if let #expected_category::#constructor_ident = #term_name
                 });
@@ -110,6 +116,8 @@ pub fn generate_ascent_pattern(
                     )
                 });
 
+            let term_name = &equivalent_term(term_name, &category, theory, clauses, modulo_eq);
+
             // Check if this is a binder constructor
             if !grammar_rule.bindings.is_empty() {
                 generate_ascent_binder_pattern(
@@ -160,11 +168,41 @@ pub fn generate_ascent_pattern(
                 clauses,
                 duplicate_vars,
                 equational_checks,
+                modulo_eq,
             );
         },
     }
 }
 
+/// For rewriting modulo equations: join `eq_<cat>(term, term_eq)` and return
+/// `term_eq`, so the following constructor match applies to any equivalent term.
+/// `term_eq` must itself be in `<cat>`: congruence closure builds equivalents that
+/// were never seen, and rewriting those would keep producing fresh terms.
+/// Returns `term_name` unchanged when `modulo_eq` is off.
+fn equivalent_term(
+    term_name: &Ident,
+    category: &Ident,
+    theory: &TheoryDef,
+    clauses: &mut Vec<TokenStream>,
+    modulo_eq: bool,
+) -> Ident {
+    let is_exported = theory.exports.iter().any(|e| e.name == *category);
+    if !modulo_eq || !is_exported {
+        return term_name.clone();
+    }
+
+    let eq_rel = quote::format_ident!("eq_{}", category.to_string().to_lowercase());
+    let cat_rel = quote::format_ident!("{}", category.to_string().to_lowercase());
+    let eq_term = quote::format_ident!("{}_eq", term_name);
+    clauses.push(quote! {
+        #eq_rel(#term_name.clone(), #eq_term)
+    });
+    clauses.push(quote! {
+        #cat_rel(#eq_term)
+    });
+    eq_term
+}
+
 /// Generate Ascent pattern matching for collection patterns with rest variables
 /// Handles patterns like `{P, Q, ...rest}` by:
 /// 1. Matching the constructor that contains a collection
@@ -184,6 +222,7 @@ fn generate_ascent_collection_pattern(
     clauses: &mut Vec<TokenStream>,
     duplicate_vars: &std::collections::HashSet<String>,
     equational_checks: &mut Vec<TokenStream>,
+    modulo_eq: bool,
 ) {
     // Find the constructor that contains a collection field
     let constructor_ident = if let Some(cons) = constructor {
@@ -227,6 +266,7 @@ fn generate_ascent_collection_pattern(
     // Generate pattern match for the constructor
     // For now, assume the collection is the only field (like PPar(HashBag<Proc>))
     let bag_var = quote::format_ident!("{}_bag", term_name);
+    let term_name = &equivalent_term(term_name, expected_category, theory, clauses, modulo_eq);
 
     clauses.push(quote! {
         if let #expected_category::#constructor_ident(#bag_var) = #term_name
@@ -264,6 +304,7 @@ fn generate_ascent_collection_pattern(
             clauses,
             duplicate_vars,
             equational_checks,
+            false,
         );
     }
 
@@ -470,6 +511,7 @@ fn generate_ascent_binder_pattern(
                 clauses,
                 duplicate_vars,
                 equational_checks,
+                false,
             );
         } else {
             // Regular field - find which field it corresponds to
@@ -498,6 +540,7 @@ fn generate_ascent_binder_pattern(
                             clauses,
                             duplicate_vars,
                             equational_checks,
+                            false,
                         );
                     }
                 }
@@ -615,6 +658,7 @@ fn generate_ascent_regular_pattern(
                         clauses,
                         duplicate_vars,
                         equational_checks,
+                        false,
                     );
                 }
 
@@ -657,6 +701,7 @@ fn generate_ascent_regular_pattern(
                         clauses,
                         duplicate_vars,
                         equational_checks,
+                        false,
                     );
                 } else {
                     // Check if field has native type (like i32)
@@ -687,6 +732,7 @@ fn generate_ascent_regular_pattern(
                             clauses,
                             duplicate_vars,
                             equational_checks,
+                            false,
                         );
                     }
                 }
@@ -707,6 +753,7 @@ fn generate_ascent_regular_pattern(
                         clauses,
                         duplicate_vars,
                         equational_checks,
+                        false,
                     );
                 } else {
                     // Nested constructor - create inner term and recurse
@@ -725,6 +772,7 @@ fn generate_ascent_regular_pattern(
                         clauses,
                         duplicate_vars,
                         equational_checks,
+                        false,
                     );
                 }
             },
@@ -1,6 +1,6 @@
 use crate::ascent::congruence;
 use crate::ast::{Expr, TheoryDef};
-use crate::utils::{has_native_type, native_type_to_string};
+use crate::utils::{bag_type, has_native_type, native_type_to_string};
 use proc_macro2::TokenStream;
 use quote::quote;
 use std::collections::HashMap;
@@ -37,7 +37,7 @@ fn generate_ascent_collection_rhs(
             .map(|e| generate_rhs_construction(e, bindings, theory))
             .collect();
 
-        let coll_type = quote! { mettail_runtime::HashBag };
+        let coll_type = bag_type(theory);
 
         if let Some((category, label)) = constructor_context {
             // Use flatten helper
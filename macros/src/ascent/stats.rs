@@ -0,0 +1,171 @@
+//! Instrumentation for exploration statistics (the `stats` option)
+//!
+//! Ascent reports the size of each relation and its iterations and time per
+//! SCC, but not how often each rule fires. So the rules of a second copy of the
+//! program, `<theory>_stats_source`, end with a counting clause:
+//!
+//! ```text
+//! rw_proc(s, t) <-- proc(s), ..., if mettail_runtime::fire(12);
+//! ```
+//!
+//! which `explore_with_stats` reports under a label naming the rule's section
+//! and relations, `rewrite: rw_proc <-- proc, ppar_contains`. The clause always
+//! holds, so it does not change what the program derives. `explore` runs the
+//! uninstrumented program, which is what the inspection file
+//! (`<theory>-datalog.rs`) shows.
+
+use proc_macro2::{Delimiter, Ident, Spacing, TokenStream, TokenTree};
+use quote::quote;
+
+/// What `explore_with_stats` needs to know about the generated program
+#[derive(Default)]
+pub struct ProgramLayout {
+    /// Every relation, in declaration order
+    pub relations: Vec<Relation>,
+
+    /// A label per instrumented rule, by rule index
+    pub rule_labels: Vec<String>,
+}
+
+/// A relation of the generated program
+pub struct Relation {
+    pub name: Ident,
+
+    /// Whether it is an equivalence relation (`#[ds(eqrel)]`), whose facts are
+    /// kept in `__<name>_ind_common` rather than in the relation's own field
+    pub is_eqrel: bool,
+}
+
+impl ProgramLayout {
+    /// Record the relations declared in `items`
+    pub fn declare_relations(&mut self, items: &TokenStream) {
+        for item in split_items(items.clone()) {
+            for pair in item.windows(2) {
+                if let [TokenTree::Ident(keyword), TokenTree::Ident(name)] = pair {
+                    if keyword == "relation" {
+                        let is_eqrel = item.iter().any(|token| {
+                            matches!(token, TokenTree::Group(g) if g.delimiter() == Delimiter::Bracket
+                                && g.to_string().contains("eqrel"))
+                        });
+                        self.relations
+                            .push(Relation { name: name.clone(), is_eqrel });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Number the rules of `items`, ending each body with a counting clause
+    pub fn instrument_rules(&mut self, section: &str, items: TokenStream) -> TokenStream {
+        self.declare_relations(&items);
+
+        let mut output = TokenStream::new();
+        for mut item in split_items(items) {
+            if let Some(arrow) = find_arrow(&item) {
+                let label = format!(
+                    "{}: {} <-- {}",
+                    section,
+                    relations_used(&item[..arrow]).join(", "),
+                    relations_used(&item[arrow + 3..]).join(", ")
+                );
+                let index = self.rule_labels.len();
+                self.rule_labels.push(label);
+
+                let semicolon = item.pop();
+                output.extend(item);
+                output.extend(quote! { , if mettail_runtime::fire(#index) });
+                output.extend(semicolon);
+            } else {
+                output.extend(item);
+            }
+        }
+        output
+    }
+}
+
+/// Split a program into top-level items, each with its closing `;`
+fn split_items(tokens: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut items = vec![Vec::new()];
+    for token in tokens {
+        let ends_item = matches!(&token, TokenTree::Punct(p) if p.as_char() == ';');
+        items.last_mut().unwrap().push(token);
+        if ends_item {
+            items.push(Vec::new());
+        }
+    }
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+/// The position of a rule's `<--`, if the item is a rule
+fn find_arrow(item: &[TokenTree]) -> Option<usize> {
+    item.windows(3).position(|window| match window {
+        [TokenTree::Punct(a), TokenTree::Punct(b), TokenTree::Punct(c)] => {
+            a.as_char() == '<'
+                && a.spacing() == Spacing::Joint
+                && b.as_char() == '-'
+                && c.as_char() == '-'
+        },
+        _ => false,
+    })
+}
+
+/// The relations of a rule's head or body clauses: names applied to arguments
+/// at the start of a clause (so not `if let Proc::PPar(..)`, `for (x, y) in ..`
+/// or `!rel(..)`)
+fn relations_used(clauses: &[TokenTree]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for (idx, token) in clauses.iter().enumerate() {
+        let TokenTree::Ident(name) = token else {
+            continue;
+        };
+        let starts_clause =
+            idx == 0 || matches!(&clauses[idx - 1], TokenTree::Punct(p) if p.as_char() == ',');
+        let applied = matches!(
+            clauses.get(idx + 1),
+            Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis
+        );
+        let name = name.to_string();
+        let keyword = matches!(name.as_str(), "for" | "if" | "let" | "agg");
+        if starts_clause && applied && !keyword && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instrument_rules() {
+        let mut layout = ProgramLayout::default();
+        let rules = layout.instrument_rules(
+            "rewrite",
+            quote! {
+                #[ds(crate::eqrel)] relation eq_proc(Proc, Proc);
+                relation ppar_contains(Proc, Proc);
+                rw_proc(s, t) <-- proc(s), if let Proc::PPar(bag) = s, for (e, _) in bag.iter(), ppar_contains(s, e), let t = e.clone();
+                proc(t) <-- rw_proc(_, t);
+            },
+        );
+
+        let relations: Vec<_> = layout
+            .relations
+            .iter()
+            .map(|rel| (rel.name.to_string(), rel.is_eqrel))
+            .collect();
+        assert_eq!(
+            relations,
+            vec![("eq_proc".to_string(), true), ("ppar_contains".to_string(), false)]
+        );
+        assert_eq!(
+            layout.rule_labels,
+            vec!["rewrite: rw_proc <-- proc, ppar_contains", "rewrite: proc <-- rw_proc"]
+        );
+        let rules = rules.to_string();
+        assert!(rules.contains("let t = e . clone () , if mettail_runtime :: fire (0usize) ;"));
+        assert!(rules.contains("rw_proc (_ , t) , if mettail_runtime :: fire (1usize) ;"));
+    }
+}
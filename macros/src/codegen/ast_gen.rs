@@ -1,7 +1,11 @@
 #![allow(clippy::cmp_owned, clippy::single_match)]
 
-use super::{display, generate_var_label, is_integer_rule, is_var_rule, subst, termgen};
+use super::{
+    binary, display, egraph, free_vars, generate_hole_label, generate_var_label, holes, is_integer_rule,
+    is_var_rule, spans, subst, termgen, traversal,
+};
 use crate::ast::{BuiltinOp, GrammarItem, GrammarRule, TheoryDef};
+use crate::utils::bag_type;
 use proc_macro2::TokenStream;
 use quote::quote;
 use std::collections::HashMap;
@@ -17,6 +21,12 @@ pub fn generate_ast(theory: &TheoryDef) -> TokenStream {
     let eval_impl = generate_eval_method(theory);
     let rewrite_impl = generate_rewrite_application(theory);
     let env_impl = generate_env_infrastructure(theory);
+    let egraph_impl = egraph::generate_egraph(theory);
+    let binary_impl = binary::generate_binary(theory);
+    let traversal_impl = traversal::generate_traversal(theory);
+    let free_vars_impl = free_vars::generate_free_vars(theory);
+    let spans_impl = spans::generate_spans(theory);
+    let holes_impl = holes::generate_holes(theory);
 
     // Generate LALRPOP module reference
     let theory_name = &theory.name;
@@ -46,6 +56,18 @@ pub fn generate_ast(theory: &TheoryDef) -> TokenStream {
 
         #env_impl
 
+        #egraph_impl
+
+        #binary_impl
+
+        #traversal_impl
+
+        #free_vars_impl
+
+        #spans_impl
+
+        #holes_impl
+
         #[cfg(not(test))]
         #[allow(unused_imports)]
         lalrpop_util::lalrpop_mod!(pub #theory_mod);
@@ -95,10 +117,21 @@ fn generate_ast_enums(theory: &TheoryDef) -> TokenStream {
             }
         }
 
+        // Metavariable for partial terms and patterns, written `?name`
+        if theory.options.holes {
+            let hole_label = generate_hole_label(cat_name);
+            variants.push(quote! {
+                #hole_label(String)
+            });
+        }
+
+        // Serialize/Deserialize are derived when mettail-runtime's `serde` feature is on
         quote! {
-            #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, mettail_runtime::BoundTerm)]
-            pub enum #cat_name {
-                #(#variants),*
+            mettail_runtime::__derive_serde! {
+                #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, mettail_runtime::BoundTerm)]
+                pub enum #cat_name {
+                    #(#variants),*
+                }
             }
         }
     }).collect();
@@ -114,7 +147,7 @@ fn generate_variant(rule: &GrammarRule, theory: &TheoryDef) -> TokenStream {
     // Check if this rule has bindings
     if !rule.bindings.is_empty() {
         // This constructor has binders - generate Scope type
-        return generate_binder_variant(rule);
+        return generate_binder_variant(rule, theory);
     }
 
     // Count non-terminal and collection items (these become fields)
@@ -178,7 +211,7 @@ enum FieldType {
             FieldType::Collection { coll_type, element_type } => {
                 // Single collection field
                 let coll_type_ident = match coll_type {
-                    crate::ast::CollectionType::HashBag => quote! { mettail_runtime::HashBag },
+                    crate::ast::CollectionType::HashBag => bag_type(theory),
                     crate::ast::CollectionType::HashSet => quote! { std::collections::HashSet },
                     crate::ast::CollectionType::Vec => quote! { Vec },
                 };
@@ -198,7 +231,7 @@ enum FieldType {
                 },
                 FieldType::Collection { coll_type, element_type } => {
                     let coll_type_ident = match coll_type {
-                        crate::ast::CollectionType::HashBag => quote! { mettail_runtime::HashBag },
+                        crate::ast::CollectionType::HashBag => bag_type(theory),
                         crate::ast::CollectionType::HashSet => quote! { std::collections::HashSet },
                         crate::ast::CollectionType::Vec => quote! { Vec },
                     };
@@ -211,7 +244,7 @@ enum FieldType {
     }
 }
 
-fn generate_binder_variant(rule: &GrammarRule) -> TokenStream {
+fn generate_binder_variant(rule: &GrammarRule, theory: &TheoryDef) -> TokenStream {
     let label = &rule.label;
 
     // For now, support single binder binding in single body
@@ -256,7 +289,7 @@ fn generate_binder_variant(rule: &GrammarRule) -> TokenStream {
                 GrammarItem::Collection { coll_type, element_type, .. } => {
                     // Collection becomes a field with the appropriate collection type
                     let coll_type_ident = match coll_type {
-                        crate::ast::CollectionType::HashBag => quote! { mettail_runtime::HashBag },
+                        crate::ast::CollectionType::HashBag => bag_type(theory),
                         crate::ast::CollectionType::HashSet => quote! { std::collections::HashSet },
                         crate::ast::CollectionType::Vec => quote! { Vec },
                     };
@@ -281,6 +314,7 @@ fn generate_binder_variant(rule: &GrammarRule) -> TokenStream {
 
 /// For each constructor with a collection field, generates a helper function that automatically flattens nested collections of the same type.
 fn generate_flatten_helpers(theory: &TheoryDef) -> TokenStream {
+    let bag_ty = bag_type(theory);
     use quote::format_ident;
 
     // Group rules by category
@@ -307,7 +341,7 @@ fn generate_flatten_helpers(theory: &TheoryDef) -> TokenStream {
             /// If elem is itself a #label, recursively merges its contents instead of nesting.
             /// This ensures that collection constructors are always flat, never nested.
             pub fn #helper_name(
-                bag: &mut mettail_runtime::HashBag<#category>,
+                bag: &mut #bag_ty<#category>,
                 elem: #category
             ) {
                 match elem {
@@ -361,6 +395,7 @@ impl #cat_name {
 
 /// Generate normalize functions that recursively flatten nested collections
 fn generate_normalize_functions(theory: &TheoryDef) -> TokenStream {
+    let bag_ty = bag_type(theory);
     use quote::format_ident;
 
     let mut impls = Vec::new();
@@ -407,7 +442,7 @@ fn generate_normalize_functions(theory: &TheoryDef) -> TokenStream {
                     Some(quote! {
                         #category::#label(bag) => {
                             // Rebuild the bag using the flattening insert helper
-                            let mut new_bag = mettail_runtime::HashBag::new();
+                            let mut new_bag = #bag_ty::new();
                             for (elem, count) in bag.iter() {
                                 for _ in 0..count {
                                     // Recursively normalize the element before inserting
@@ -1220,14 +1255,14 @@ pub fn parse_and_eval_with_env(
             ) -> Result<#native_type, String> {
                 #check_var_ref_fn
 
-                mettail_runtime::clear_var_cache();
+                let vars = mettail_runtime::VarContext::new();
 
                 let trimmed = input.trim();
 
                 // Parse the input (handles both assignments and expressions)
                 let parser = #parser_mod::#cat_parser::new();
                 let term = parser
-                    .parse(trimmed)
+                    .parse(&vars, trimmed)
                     .map_err(|e| format!("parse error: {:?}", e))?;
 
                 // Use Ascent to rewrite to normal form (generated function)
@@ -1319,6 +1354,7 @@ fn test_generate_simple_enum() {
             equations: vec![],
             rewrites: vec![],
             semantics: vec![],
+            options: TheoryOptions::default(),
         };
 
         let output = generate_ast(&theory);
@@ -1365,6 +1401,7 @@ fn test_generate_multiple_categories() {
             equations: vec![],
             rewrites: vec![],
             semantics: vec![],
+            options: TheoryOptions::default(),
         };
 
         let output = generate_ast(&theory);
@@ -1415,6 +1452,7 @@ fn test_automatic_var_generation() {
             equations: vec![],
             rewrites: vec![],
             semantics: vec![],
+            options: TheoryOptions::default(),
         };
 
         let output = generate_ast(&theory);
@@ -1491,6 +1529,7 @@ fn test_automatic_var_generation_with_existing_var() {
             equations: vec![],
             rewrites: vec![],
             semantics: vec![],
+            options: TheoryOptions::default(),
         };
 
         let output = generate_ast(&theory);
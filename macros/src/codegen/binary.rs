@@ -0,0 +1,284 @@
+//! Binary encoding generation
+//!
+//! Generates `to_bytes`/`from_bytes` for each exported category on top of
+//! `mettail_runtime::{BinaryWriter, BinaryReader}`. Each theory gets a writer
+//! and a reader holding one table per category, which is what lets repeated
+//! subterms be written once and referenced by index afterwards. Constructor
+//! tags are rule indices within their category, followed by the auto-generated
+//! Var variant and then the Hole variant, each only if the category has it.
+//! Bags and sets are written in element order, so equal terms encode to the
+//! same bytes.
+
+use crate::ast::{CollectionType, GrammarRule, TheoryDef};
+use crate::codegen::{generate_hole_label, generate_var_label, is_var_rule, variant_fields, Field};
+use crate::utils::{bag_type, has_native_type};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::Ident;
+
+pub fn generate_binary(theory: &TheoryDef) -> TokenStream {
+    let theory_name = theory.name.to_string();
+    let writer = format_ident!("__{}BinaryWriter", theory.name);
+    let reader = format_ident!("__{}BinaryReader", theory.name);
+    let schema = format_ident!("__{}_SCHEMA", theory_name.to_uppercase());
+
+    let tables: Vec<(Ident, &Ident)> = theory
+        .exports
+        .iter()
+        .map(|e| (format_ident!("{}", e.name.to_string().to_lowercase()), &e.name))
+        .collect();
+    let table_names: Vec<&Ident> = tables.iter().map(|(t, _)| t).collect();
+
+    let mut signature = String::new();
+    let mut impls = Vec::new();
+
+    for &(ref table, category) in &tables {
+        let rules: Vec<&GrammarRule> = theory
+            .terms
+            .iter()
+            .filter(|r| r.category == *category)
+            .collect();
+
+        let mut encode_arms = Vec::new();
+        let mut decode_arms = Vec::new();
+        for (tag, rule) in rules.iter().enumerate() {
+            let tag = tag as u32;
+            let fields = variant_fields(rule);
+            signature.push_str(&format!("{}.{}{}\n", category, rule.label, field_sig(&fields)));
+
+            let label = &rule.label;
+            if fields.is_empty() {
+                encode_arms.push(quote! {
+                    #category::#label => enc.w.write_u32(#tag)
+                });
+                decode_arms.push(quote! { #tag => #category::#label });
+                continue;
+            }
+
+            let names: Vec<Ident> = (0..fields.len()).map(|i| format_ident!("f{}", i)).collect();
+            let encodes = fields.iter().zip(&names).map(|(f, n)| encode_field(f, n));
+            let decodes = fields.iter().map(|f| decode_field(f, category, theory));
+            encode_arms.push(quote! {
+                #category::#label(#(#names),*) => {
+                    enc.w.write_u32(#tag);
+                    #(#encodes)*
+                }
+            });
+            decode_arms.push(quote! { #tag => #category::#label(#(#decodes),*) });
+        }
+
+        let mut next_tag = rules.len() as u32;
+        let has_var_rule = rules.iter().any(|rule| is_var_rule(rule));
+        if !has_var_rule && has_native_type(category, theory).is_none() {
+            let tag = next_tag;
+            next_tag += 1;
+            let var_label = generate_var_label(category);
+            signature.push_str(&format!("{}.{}(Var)\n", category, var_label));
+            encode_arms.push(quote! {
+                #category::#var_label(v) => {
+                    enc.w.write_u32(#tag);
+                    enc.w.write_var(&v.0);
+                }
+            });
+            decode_arms
+                .push(quote! { #tag => #category::#var_label(mettail_runtime::OrdVar(dec.r.read_var()?)) });
+        }
+
+        if theory.options.holes {
+            let tag = next_tag;
+            let hole_label = generate_hole_label(category);
+            signature.push_str(&format!("{}.{}(Hole)\n", category, hole_label));
+            encode_arms.push(quote! {
+                #category::#hole_label(name) => {
+                    enc.w.write_u32(#tag);
+                    enc.w.write_str(name);
+                }
+            });
+            decode_arms.push(quote! { #tag => #category::#hole_label(dec.r.read_string()?) });
+        }
+
+        let category_name = category.to_string();
+        impls.push(quote! {
+            impl #category {
+                /// Encode this term in the compact binary format
+                /// (see `mettail_runtime::BinaryWriter`); repeated subterms are stored once
+                pub fn to_bytes(&self) -> Vec<u8> {
+                    let mut enc = #writer::default();
+                    self.encode_binary(&mut enc);
+                    enc.w.finish(#theory_name, #schema)
+                }
+
+                /// Decode a term written by `to_bytes`, interning its free variables in `vars`
+                pub fn from_bytes(
+                    vars: &mettail_runtime::VarContext,
+                    bytes: &[u8],
+                ) -> Result<Self, mettail_runtime::DecodeError> {
+                    let mut dec = #reader {
+                        r: mettail_runtime::BinaryReader::new(bytes, #theory_name, #schema, vars)?,
+                        #(#table_names: Vec::new(),)*
+                    };
+                    let term = Self::decode_binary(&mut dec)?;
+                    dec.r.finish()?;
+                    Ok(term)
+                }
+
+                fn encode_binary<'a>(&'a self, enc: &mut #writer<'a>) {
+                    if let Some(&id) = enc.#table.get(self) {
+                        enc.w.write_u32(id + 1);
+                        return;
+                    }
+                    enc.w.write_u32(0);
+                    match self {
+                        #(#encode_arms),*
+                    }
+                    let id = enc.#table.len() as u32;
+                    enc.#table.insert(self, id);
+                }
+
+                fn decode_binary(dec: &mut #reader) -> Result<Self, mettail_runtime::DecodeError> {
+                    let reference = dec.r.read_u32()?;
+                    if reference != 0 {
+                        return dec
+                            .#table
+                            .get(reference as usize - 1)
+                            .cloned()
+                            .ok_or(mettail_runtime::DecodeError::InvalidReference(reference));
+                    }
+                    let term = match dec.r.read_u32()? {
+                        #(#decode_arms,)*
+                        tag => {
+                            return Err(mettail_runtime::DecodeError::InvalidTag {
+                                category: #category_name,
+                                tag,
+                            })
+                        },
+                    };
+                    dec.#table.push(term.clone());
+                    Ok(term)
+                }
+            }
+        });
+    }
+
+    let writer_fields = tables
+        .iter()
+        .map(|(t, c)| quote! { #t: ::std::collections::HashMap<&'a #c, u32> });
+    let reader_fields = tables.iter().map(|(t, c)| quote! { #t: Vec<#c> });
+
+    quote! {
+        const #schema: u64 = mettail_runtime::schema_fingerprint(#signature);
+
+        /// Binary encoder state: the subterms of each category written so far
+        #[derive(Default)]
+        struct #writer<'a> {
+            w: mettail_runtime::BinaryWriter,
+            #(#writer_fields,)*
+        }
+
+        /// Binary decoder state: the subterms of each category read so far
+        struct #reader<'b> {
+            r: mettail_runtime::BinaryReader<'b>,
+            #(#reader_fields,)*
+        }
+
+        #(#impls)*
+    }
+}
+
+/// Field shapes for the schema fingerprint
+fn field_sig(fields: &[Field]) -> String {
+    let parts: Vec<String> = fields
+        .iter()
+        .map(|f| match f {
+            Field::Var | Field::RawVar => "Var".to_string(),
+            Field::Native => "Native".to_string(),
+            Field::Term(cat) => cat.to_string(),
+            Field::Scope(cat) => format!("Scope<{}>", cat),
+            Field::Collection(coll, elem) => format!("{:?}<{}>", coll, elem),
+        })
+        .collect();
+    format!("({})", parts.join(","))
+}
+
+fn encode_field(field: &Field, name: &Ident) -> TokenStream {
+    match field {
+        Field::Var => quote! { enc.w.write_var(&#name.0); },
+        Field::RawVar => quote! { enc.w.write_var(#name); },
+        Field::Native => quote! { mettail_runtime::BinaryValue::write_binary(#name, &mut enc.w); },
+        Field::Term(_) => quote! { #name.encode_binary(enc); },
+        Field::Scope(_) => quote! {
+            enc.w.write_binder(#name.unsafe_pattern());
+            #name.unsafe_body().encode_binary(enc);
+        },
+        Field::Collection(CollectionType::HashBag, _) => quote! {
+            let mut entries: Vec<_> = #name.iter().collect();
+            entries.sort();
+            enc.w.write_u64(entries.len() as u64);
+            for (elem, count) in entries {
+                elem.encode_binary(enc);
+                enc.w.write_u64(count as u64);
+            }
+        },
+        Field::Collection(CollectionType::HashSet, _) => quote! {
+            let mut elems: Vec<_> = #name.iter().collect();
+            elems.sort();
+            enc.w.write_u64(elems.len() as u64);
+            for elem in elems {
+                elem.encode_binary(enc);
+            }
+        },
+        Field::Collection(CollectionType::Vec, _) => quote! {
+            enc.w.write_u64(#name.len() as u64);
+            for elem in #name.iter() {
+                elem.encode_binary(enc);
+            }
+        },
+    }
+}
+
+fn decode_field(field: &Field, category: &Ident, theory: &TheoryDef) -> TokenStream {
+    match field {
+        Field::Var => quote! { mettail_runtime::OrdVar(dec.r.read_var()?) },
+        Field::RawVar => quote! { dec.r.read_var()? },
+        Field::Native => {
+            // Same i32 fallback as the AST
+            let native = has_native_type(category, theory)
+                .map(|t| quote! { #t })
+                .unwrap_or_else(|| quote! { i32 });
+            quote! { <#native as mettail_runtime::BinaryValue>::read_binary(&mut dec.r)? }
+        },
+        Field::Term(cat) => quote! { Box::new(#cat::decode_binary(dec)?) },
+        Field::Scope(cat) => quote! {
+            mettail_runtime::Scope::from_parts_unsafe(
+                dec.r.read_binder()?,
+                Box::new(#cat::decode_binary(dec)?),
+            )
+        },
+        Field::Collection(CollectionType::HashBag, elem) => {
+            let bag_ty = bag_type(theory);
+            quote! {{
+                let mut bag = #bag_ty::new();
+                let mut size = 0usize;
+                for _ in 0..dec.r.read_len()? {
+                    let elem = #elem::decode_binary(dec)?;
+                    let count = dec.r.read_count()?;
+                    size = size
+                        .checked_add(count)
+                        .ok_or(mettail_runtime::DecodeError::InvalidValue)?;
+                    bag.insert_n(elem, count);
+                }
+                bag
+            }}
+        },
+        Field::Collection(CollectionType::HashSet, elem) => quote! {
+            (0..dec.r.read_len()?)
+                .map(|_| #elem::decode_binary(dec))
+                .collect::<Result<std::collections::HashSet<_>, _>>()?
+        },
+        Field::Collection(CollectionType::Vec, elem) => quote! {
+            (0..dec.r.read_len()?)
+                .map(|_| #elem::decode_binary(dec))
+                .collect::<Result<Vec<_>, _>>()?
+        },
+    }
+}
@@ -6,7 +6,7 @@
 #![allow(clippy::cmp_owned)]
 
 use crate::ast::{GrammarItem, GrammarRule, TheoryDef};
-use crate::codegen::{generate_var_label, is_var_rule};
+use crate::codegen::{generate_hole_label, generate_var_label, is_var_rule};
 use crate::utils::has_native_type;
 use proc_macro2::TokenStream;
 use quote::quote;
@@ -62,6 +62,16 @@ fn generate_display_impl(
         match_arms.push(var_arm);
     }
 
+    if theory.options.holes {
+        let hole_label = generate_hole_label(category);
+        match_arms.push(quote! {
+            #category::#hole_label(name) if name == "..." => write!(f, "...")
+        });
+        match_arms.push(quote! {
+            #category::#hole_label(name) => write!(f, "?{}", name)
+        });
+    }
+
     quote! {
         impl std::fmt::Display for #category {
             fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -434,6 +444,7 @@ fn test_display_generation() {
             equations: vec![],
             rewrites: vec![],
             semantics: vec![],
+            options: TheoryOptions::default(),
         };
 
         let display_impl = generate_display(&theory);
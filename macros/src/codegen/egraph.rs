@@ -0,0 +1,749 @@
+//! E-graph equation backend
+//!
+//! For theories declaring `options { equation_backend: egraph }`, generates:
+//! - `<Theory>Node`: one e-node variant per constructor, children as e-class ids
+//! - `impl mettail_runtime::egraph::Language for <Theory>Node`
+//! - `<Theory>EGraph`: typed wrapper with `add_<cat>`, `extract_<cat>`,
+//!   `union`, `rebuild`, `saturate` (applies the theory's equations) and
+//!   `canonical_<cat>`
+//!
+//! Collection fields become sorted child lists, so AC equality is handled by
+//! hash-consing instead of per-constructor congruence clauses. The Ascent
+//! program has no congruence or equation clauses for this backend: it relates
+//! every term to its `canonical_<cat>` representative in `eq_<cat>` (see
+//! `ascent::equations`).
+
+use super::{generate_hole_label, generate_var_label, is_integer_rule, is_var_rule};
+use crate::ast::{CollectionType, EquationBackend, Expr, GrammarItem, GrammarRule, TheoryDef};
+use crate::utils::{bag_type, has_native_type};
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::Ident;
+
+/// A field of a constructor, as seen by the e-graph
+enum NodeField {
+    /// `Box<Cat>` - one child e-class
+    Child(Ident),
+    /// Collection of `Cat` - list of child e-classes
+    Collection(CollectionType, Ident),
+    /// Binder scope over a body of category `Cat` - binder payload + child
+    Scope(Ident),
+    /// Opaque payload compared by value (variables, native literals)
+    Leaf(TokenStream),
+}
+
+/// A node variant: the term constructor it mirrors and its fields
+struct NodeVariant {
+    category: Ident,
+    label: Ident,
+    node_label: Ident,
+    fields: Vec<NodeField>,
+}
+
+pub fn generate_egraph(theory: &TheoryDef) -> TokenStream {
+    if theory.options.equation_backend != EquationBackend::EGraph {
+        return quote! {};
+    }
+
+    let node_name = format_ident!("{}Node", theory.name);
+    let egraph_name = format_ident!("{}EGraph", theory.name);
+    let variants = collect_variants(theory);
+
+    let node_enum = generate_node_enum(&node_name, &variants);
+    let language_impl = generate_language_impl(&node_name, &variants);
+    let add_fns = generate_add_functions(theory, &node_name, &variants);
+    let extract_fns = generate_extract_functions(theory, &node_name, &variants);
+    let rewrites = generate_equation_rewrites(theory, &node_name, &variants);
+    let canonical_fns = generate_canonical_functions(theory);
+
+    quote! {
+        #node_enum
+
+        #language_impl
+
+        /// E-graph over the terms of this theory
+        ///
+        /// Congruence closure (including AC collections) is maintained by the
+        /// e-graph; `saturate` applies the theory's equations.
+        #[derive(Debug, Clone, Default)]
+        pub struct #egraph_name {
+            pub egraph: mettail_runtime::EGraph<#node_name>,
+        }
+
+        impl #egraph_name {
+            /// Iteration bound for `saturate` in the `canonical_<cat>` functions
+            pub const SATURATION_LIMIT: usize = 32;
+
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            /// Merge two e-classes; call `rebuild` before querying
+            pub fn union(&mut self, a: mettail_runtime::egraph::Id, b: mettail_runtime::egraph::Id) -> bool {
+                self.egraph.union(a, b)
+            }
+
+            /// Restore congruence after a batch of unions
+            pub fn rebuild(&mut self) -> usize {
+                self.egraph.rebuild()
+            }
+
+            /// Whether two e-classes have been merged
+            pub fn equivalent(&self, a: mettail_runtime::egraph::Id, b: mettail_runtime::egraph::Id) -> bool {
+                self.egraph.find(a) == self.egraph.find(b)
+            }
+
+            /// Apply the theory's equations until saturation or `iter_limit`
+            pub fn saturate(&mut self, iter_limit: usize) -> usize {
+                let rules = Self::rewrites();
+                self.egraph.saturate(&rules, iter_limit)
+            }
+
+            #rewrites
+
+            #(#canonical_fns)*
+
+            #(#add_fns)*
+
+            #(#extract_fns)*
+        }
+    }
+}
+
+fn collect_variants(theory: &TheoryDef) -> Vec<NodeVariant> {
+    let mut variants = Vec::new();
+
+    for export in &theory.exports {
+        let category = &export.name;
+        let rules: Vec<&GrammarRule> = theory
+            .terms
+            .iter()
+            .filter(|r| r.category == *category)
+            .collect();
+
+        for rule in &rules {
+            variants.push(NodeVariant {
+                category: category.clone(),
+                label: rule.label.clone(),
+                node_label: rule.label.clone(),
+                fields: rule_fields(rule, theory),
+            });
+        }
+
+        // Auto-generated Var variant (see ast_gen::generate_ast_enums)
+        if !rules.iter().any(|r| is_var_rule(r)) && export.native_type.is_none() {
+            variants.push(NodeVariant {
+                category: category.clone(),
+                label: generate_var_label(category),
+                node_label: format_ident!("{}Var", category),
+                fields: vec![NodeField::Leaf(quote! { mettail_runtime::OrdVar })],
+            });
+        }
+
+        if theory.options.holes {
+            variants.push(NodeVariant {
+                category: category.clone(),
+                label: generate_hole_label(category),
+                node_label: format_ident!("{}Hole", category),
+                fields: vec![NodeField::Leaf(quote! { String })],
+            });
+        }
+    }
+
+    variants
+}
+
+/// Mirror the field layout of `ast_gen::generate_variant` / `generate_binder_variant`
+fn rule_fields(rule: &GrammarRule, theory: &TheoryDef) -> Vec<NodeField> {
+    if is_integer_rule(rule) {
+        let native = has_native_type(&rule.category, theory)
+            .map(|ty| quote! { #ty })
+            .unwrap_or_else(|| quote! { i32 });
+        return vec![NodeField::Leaf(native)];
+    }
+
+    let binder = rule.bindings.first().map(|(b, bodies)| (*b, bodies[0]));
+
+    let mut fields = Vec::new();
+    for (i, item) in rule.items.iter().enumerate() {
+        if let Some((binder_idx, body_idx)) = binder {
+            if i == binder_idx {
+                continue;
+            }
+            if i == body_idx {
+                if let GrammarItem::NonTerminal(cat) = item {
+                    fields.push(NodeField::Scope(cat.clone()));
+                }
+                continue;
+            }
+        }
+        match item {
+            GrammarItem::NonTerminal(cat) if cat == "Var" => {
+                if binder.is_some() {
+                    fields.push(NodeField::Leaf(quote! { mettail_runtime::Var<String> }));
+                } else {
+                    fields.push(NodeField::Leaf(quote! { mettail_runtime::OrdVar }));
+                }
+            },
+            GrammarItem::NonTerminal(cat) => fields.push(NodeField::Child(cat.clone())),
+            GrammarItem::Collection { coll_type, element_type, .. } => {
+                fields.push(NodeField::Collection(coll_type.clone(), element_type.clone()))
+            },
+            GrammarItem::Binder { .. } | GrammarItem::Terminal(_) => {},
+        }
+    }
+    fields
+}
+
+fn field_vars(prefix: &str, n: usize) -> Vec<Ident> {
+    (0..n).map(|i| format_ident!("{}{}", prefix, i)).collect()
+}
+
+fn generate_node_enum(node_name: &Ident, variants: &[NodeVariant]) -> TokenStream {
+    let defs = variants.iter().map(|v| {
+        let label = &v.node_label;
+        let types: Vec<TokenStream> = v
+            .fields
+            .iter()
+            .flat_map(|f| match f {
+                NodeField::Child(_) => vec![quote! { mettail_runtime::egraph::Id }],
+                NodeField::Collection(..) => vec![quote! { Vec<mettail_runtime::egraph::Id> }],
+                NodeField::Scope(_) => vec![
+                    quote! { mettail_runtime::Binder<String> },
+                    quote! { mettail_runtime::egraph::Id },
+                ],
+                NodeField::Leaf(ty) => vec![ty.clone()],
+            })
+            .collect();
+        if types.is_empty() {
+            quote! { #label }
+        } else {
+            quote! { #label(#(#types),*) }
+        }
+    });
+
+    quote! {
+        /// E-node for this theory: constructors with children replaced by e-class ids
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum #node_name {
+            #(#defs),*
+        }
+    }
+}
+
+/// Number of flattened node fields a term field expands to
+fn flat_len(field: &NodeField) -> usize {
+    match field {
+        NodeField::Scope(_) => 2,
+        _ => 1,
+    }
+}
+
+fn generate_language_impl(node_name: &Ident, variants: &[NodeVariant]) -> TokenStream {
+    let mut children_arms = Vec::new();
+    let mut map_arms = Vec::new();
+    let mut matches_arms = Vec::new();
+    let mut normalize_arms = Vec::new();
+
+    for v in variants {
+        let label = &v.node_label;
+        let n: usize = v.fields.iter().map(flat_len).sum();
+        if n == 0 {
+            children_arms.push(quote! { #node_name::#label => vec![] });
+            map_arms.push(quote! { #node_name::#label => #node_name::#label });
+            matches_arms.push(quote! { (#node_name::#label, #node_name::#label) => true });
+            continue;
+        }
+
+        let a = field_vars("a", n);
+        let b = field_vars("b", n);
+
+        let mut children = Vec::new();
+        let mut mapped = Vec::new();
+        let mut checks = Vec::new();
+        let mut normalize = Vec::new();
+        let mut idx = 0;
+        for field in &v.fields {
+            match field {
+                NodeField::Child(_) => {
+                    let x = &a[idx];
+                    children.push(quote! { ids.push(*#x); });
+                    mapped.push(quote! { f(*#x) });
+                },
+                NodeField::Collection(coll_type, _) => {
+                    let (x, y) = (&a[idx], &b[idx]);
+                    children.push(quote! { ids.extend(#x.iter().copied()); });
+                    mapped.push(quote! { #x.iter().map(|&c| f(c)).collect() });
+                    checks.push(quote! { #x.len() == #y.len() });
+                    match coll_type {
+                        CollectionType::HashBag => normalize.push(quote! { #x.sort(); }),
+                        CollectionType::HashSet => {
+                            normalize.push(quote! { #x.sort(); #x.dedup(); })
+                        },
+                        CollectionType::Vec => {},
+                    }
+                },
+                NodeField::Scope(_) => {
+                    let (px, py) = (&a[idx], &b[idx]);
+                    let bx = &a[idx + 1];
+                    children.push(quote! { ids.push(*#bx); });
+                    mapped.push(quote! { #px.clone() });
+                    mapped.push(quote! { f(*#bx) });
+                    checks.push(quote! { #px == #py });
+                },
+                NodeField::Leaf(_) => {
+                    let (x, y) = (&a[idx], &b[idx]);
+                    mapped.push(quote! { #x.clone() });
+                    checks.push(quote! { #x == #y });
+                },
+            }
+            idx += flat_len(field);
+        }
+
+        children_arms.push(quote! {
+            #node_name::#label(#(#a),*) => {
+                let mut ids = Vec::new();
+                #(#children)*
+                ids
+            }
+        });
+        map_arms.push(quote! {
+            #node_name::#label(#(#a),*) => #node_name::#label(#(#mapped),*)
+        });
+        let check = if checks.is_empty() {
+            quote! { true }
+        } else {
+            quote! { #(#checks)&&* }
+        };
+        matches_arms.push(quote! {
+            (#node_name::#label(#(#a),*), #node_name::#label(#(#b),*)) => #check
+        });
+        if !normalize.is_empty() {
+            normalize_arms.push(quote! {
+                #node_name::#label(#(#a),*) => { #(#normalize)* }
+            });
+        }
+    }
+
+    quote! {
+        #[allow(unused_variables, unused_mut)]
+        impl mettail_runtime::egraph::Language for #node_name {
+            fn children(&self) -> Vec<mettail_runtime::egraph::Id> {
+                match self {
+                    #(#children_arms),*
+                }
+            }
+
+            fn map_children(
+                &self,
+                mut f: impl FnMut(mettail_runtime::egraph::Id) -> mettail_runtime::egraph::Id,
+            ) -> Self {
+                match self {
+                    #(#map_arms),*
+                }
+            }
+
+            #[allow(unreachable_patterns)]
+            fn matches(&self, other: &Self) -> bool {
+                match (self, other) {
+                    #(#matches_arms,)*
+                    _ => false,
+                }
+            }
+
+            #[allow(unreachable_patterns)]
+            fn normalize(&mut self) {
+                match self {
+                    #(#normalize_arms)*
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+fn generate_add_functions(
+    theory: &TheoryDef,
+    node_name: &Ident,
+    variants: &[NodeVariant],
+) -> Vec<TokenStream> {
+    theory
+        .exports
+        .iter()
+        .map(|export| {
+            let category = &export.name;
+            let add_fn = format_ident!("add_{}", category.to_string().to_lowercase());
+
+            let arms: Vec<TokenStream> = variants
+                .iter()
+                .filter(|v| v.category == *category)
+                .map(|v| {
+                    let label = &v.label;
+                    let node_label = &v.node_label;
+                    if v.fields.is_empty() {
+                        return quote! {
+                            #category::#label => self.egraph.add(#node_name::#node_label)
+                        };
+                    }
+                    let f = field_vars("f", v.fields.len());
+                    let mut lets = Vec::new();
+                    let mut args = Vec::new();
+                    for (field, var) in v.fields.iter().zip(f.iter()) {
+                        match field {
+                            NodeField::Child(cat) => {
+                                let add = format_ident!("add_{}", cat.to_string().to_lowercase());
+                                lets.push(quote! { let #var = self.#add(#var); });
+                                args.push(quote! { #var });
+                            },
+                            NodeField::Collection(CollectionType::HashBag, elem) => {
+                                let add = format_ident!("add_{}", elem.to_string().to_lowercase());
+                                lets.push(quote! {
+                                    let mut ids = Vec::new();
+                                    for (elem, count) in #var.iter() {
+                                        let id = self.#add(elem);
+                                        ids.extend(std::iter::repeat(id).take(count));
+                                    }
+                                    let #var = ids;
+                                });
+                                args.push(quote! { #var });
+                            },
+                            NodeField::Collection(_, elem) => {
+                                let add = format_ident!("add_{}", elem.to_string().to_lowercase());
+                                lets.push(quote! {
+                                    let #var: Vec<_> = #var.iter().map(|elem| self.#add(elem)).collect();
+                                });
+                                args.push(quote! { #var });
+                            },
+                            NodeField::Scope(body_cat) => {
+                                let add =
+                                    format_ident!("add_{}", body_cat.to_string().to_lowercase());
+                                let binder = format_ident!("{}_binder", var);
+                                lets.push(quote! {
+                                    let #binder = #var.unsafe_pattern().clone();
+                                    let #var = self.#add(#var.unsafe_body());
+                                });
+                                args.push(quote! { #binder });
+                                args.push(quote! { #var });
+                            },
+                            NodeField::Leaf(_) => {
+                                args.push(quote! { #var.clone() });
+                            },
+                        }
+                    }
+                    quote! {
+                        #category::#label(#(#f),*) => {
+                            #(#lets)*
+                            self.egraph.add(#node_name::#node_label(#(#args),*))
+                        }
+                    }
+                })
+                .collect();
+
+            quote! {
+                /// Add a term (and all its subterms), returning its e-class
+                pub fn #add_fn(&mut self, term: &#category) -> mettail_runtime::egraph::Id {
+                    match term {
+                        #(#arms),*
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+fn generate_extract_functions(
+    theory: &TheoryDef,
+    node_name: &Ident,
+    variants: &[NodeVariant],
+) -> Vec<TokenStream> {
+    let bag_ty = bag_type(theory);
+    theory
+        .exports
+        .iter()
+        .map(|export| {
+            let category = &export.name;
+            let cat_lower = category.to_string().to_lowercase();
+            let extract_fn = format_ident!("extract_{}", cat_lower);
+            let build_fn = format_ident!("build_{}", cat_lower);
+
+            let arms: Vec<TokenStream> = variants
+                .iter()
+                .filter(|v| v.category == *category)
+                .map(|v| {
+                    let label = &v.label;
+                    let node_label = &v.node_label;
+                    let n: usize = v.fields.iter().map(flat_len).sum();
+                    if n == 0 {
+                        return quote! { #node_name::#node_label => #category::#label };
+                    }
+                    let a = field_vars("a", n);
+                    let mut args = Vec::new();
+                    let mut idx = 0;
+                    for field in &v.fields {
+                        let x = &a[idx];
+                        match field {
+                            NodeField::Child(cat) => {
+                                let build =
+                                    format_ident!("build_{}", cat.to_string().to_lowercase());
+                                args.push(quote! { Box::new(self.#build(ex, *#x)) });
+                            },
+                            NodeField::Collection(coll_type, elem) => {
+                                let build =
+                                    format_ident!("build_{}", elem.to_string().to_lowercase());
+                                let coll = match coll_type {
+                                    CollectionType::HashBag => quote! { #bag_ty },
+                                    CollectionType::HashSet => {
+                                        quote! { std::collections::HashSet }
+                                    },
+                                    CollectionType::Vec => quote! { Vec },
+                                };
+                                args.push(quote! {
+                                    #x.iter().map(|&c| self.#build(ex, c)).collect::<#coll<_>>()
+                                });
+                            },
+                            NodeField::Scope(body_cat) => {
+                                let build =
+                                    format_ident!("build_{}", body_cat.to_string().to_lowercase());
+                                let body = &a[idx + 1];
+                                args.push(quote! {
+                                    mettail_runtime::Scope::from_parts_unsafe(
+                                        #x.clone(),
+                                        Box::new(self.#build(ex, *#body)),
+                                    )
+                                });
+                            },
+                            NodeField::Leaf(_) => args.push(quote! { #x.clone() }),
+                        }
+                        idx += flat_len(field);
+                    }
+                    quote! {
+                        #node_name::#node_label(#(#a),*) => #category::#label(#(#args),*)
+                    }
+                })
+                .collect();
+
+            let cat_str = category.to_string();
+            quote! {
+                /// Extract the smallest term of the e-class `id`
+                pub fn #extract_fn(&self, id: mettail_runtime::egraph::Id) -> #category {
+                    let ex = mettail_runtime::egraph::Extractor::new(&self.egraph);
+                    self.#build_fn(&ex, id)
+                }
+
+                #[allow(unreachable_patterns)]
+                fn #build_fn(
+                    &self,
+                    ex: &mettail_runtime::egraph::Extractor<'_, #node_name>,
+                    id: mettail_runtime::egraph::Id,
+                ) -> #category {
+                    match ex.best_node(id) {
+                        #(#arms,)*
+                        other => panic!("e-node {:?} is not a {}", other, #cat_str),
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+/// `canonical_<cat>`: saturate a term's own e-graph and extract the smallest
+/// member of its class. The Ascent program joins terms through these
+/// representatives.
+fn generate_canonical_functions(theory: &TheoryDef) -> Vec<TokenStream> {
+    theory
+        .exports
+        .iter()
+        .map(|export| {
+            let category = &export.name;
+            let cat_lower = category.to_string().to_lowercase();
+            let canonical_fn = format_ident!("canonical_{}", cat_lower);
+            let add_fn = format_ident!("add_{}", cat_lower);
+            let extract_fn = format_ident!("extract_{}", cat_lower);
+            quote! {
+                /// The smallest term equal to `term` under the theory's equations
+                pub fn #canonical_fn(term: &#category) -> #category {
+                    let mut eg = Self::new();
+                    let id = eg.#add_fn(term);
+                    eg.saturate(Self::SATURATION_LIMIT);
+                    eg.#extract_fn(id)
+                }
+            }
+        })
+        .collect()
+}
+
+/// Translate the theory's equations into e-graph rewrites (both directions).
+///
+/// Only equations built from variables and plain constructors are supported
+/// by e-matching. The e-graph replaces the Ascent equation clauses for this
+/// backend, so any other equation is reported with `compile_error!` at the
+/// offending term instead of being dropped.
+fn generate_equation_rewrites(
+    theory: &TheoryDef,
+    node_name: &Ident,
+    variants: &[NodeVariant],
+) -> TokenStream {
+    let mut rules = Vec::new();
+    let mut errors = Vec::new();
+
+    for (i, equation) in theory.equations.iter().enumerate() {
+        if let Some(cond) = equation.conditions.first() {
+            errors.push(
+                syn::Error::new(
+                    cond.var.span(),
+                    "equation_backend: egraph does not support freshness conditions on equations",
+                )
+                .to_compile_error(),
+            );
+            continue;
+        }
+        let mut lhs_vars = Vec::new();
+        let mut rhs_vars = Vec::new();
+        let lhs = pattern_code(&equation.left, node_name, variants, &mut lhs_vars);
+        let rhs = pattern_code(&equation.right, node_name, variants, &mut rhs_vars);
+        let (lhs, rhs) = match (lhs, rhs) {
+            (Ok(lhs), Ok(rhs)) => (lhs, rhs),
+            (Err(e), _) | (_, Err(e)) => {
+                errors.push(e.to_compile_error());
+                continue;
+            },
+        };
+
+        let lhs_is_var = is_pattern_var(&equation.left, variants);
+        let rhs_is_var = is_pattern_var(&equation.right, variants);
+        let forward = !lhs_is_var && rhs_vars.iter().all(|v| lhs_vars.contains(v));
+        let backward = !rhs_is_var && lhs_vars.iter().all(|v| rhs_vars.contains(v));
+
+        if !forward && !backward {
+            errors.push(
+                syn::Error::new(
+                    expr_span(&equation.left),
+                    "equation_backend: egraph cannot orient this equation as a rewrite: \
+                     neither side is a constructor pattern binding all variables of the other",
+                )
+                .to_compile_error(),
+            );
+            continue;
+        }
+        if forward {
+            let name = format!("eq{}", i);
+            rules.push(quote! {
+                {
+                    let mut lhs = mettail_runtime::egraph::Pattern::new();
+                    { let p = &mut lhs; #lhs; }
+                    let mut rhs = mettail_runtime::egraph::Pattern::new();
+                    { let p = &mut rhs; #rhs; }
+                    mettail_runtime::egraph::Rewrite::new(#name, lhs, rhs)
+                }
+            });
+        }
+        if backward {
+            let name = format!("eq{}-rev", i);
+            rules.push(quote! {
+                {
+                    let mut lhs = mettail_runtime::egraph::Pattern::new();
+                    { let p = &mut lhs; #rhs; }
+                    let mut rhs = mettail_runtime::egraph::Pattern::new();
+                    { let p = &mut rhs; #lhs; }
+                    mettail_runtime::egraph::Rewrite::new(#name, lhs, rhs)
+                }
+            });
+        }
+    }
+
+    quote! {
+        /// The theory's equations as e-graph rewrites
+        pub fn rewrites() -> Vec<mettail_runtime::egraph::Rewrite<#node_name>> {
+            #(#errors)*
+            vec![#(#rules),*]
+        }
+    }
+}
+
+fn find_variant<'a>(name: &Ident, variants: &'a [NodeVariant]) -> Option<&'a NodeVariant> {
+    variants.iter().find(|v| v.label == *name)
+}
+
+fn is_pattern_var(expr: &Expr, variants: &[NodeVariant]) -> bool {
+    matches!(expr, Expr::Var(name) if find_variant(name, variants).is_none())
+}
+
+/// Span of the head identifier of `expr`, for error reporting
+fn expr_span(expr: &Expr) -> Span {
+    match expr {
+        Expr::Var(name) => name.span(),
+        Expr::Apply { constructor, .. } => constructor.span(),
+        Expr::Subst { var, .. } => var.span(),
+        Expr::CollectionPattern { constructor, elements, rest } => constructor
+            .as_ref()
+            .or(rest.as_ref())
+            .map(|id| id.span())
+            .or_else(|| elements.first().map(expr_span))
+            .unwrap_or_else(Span::call_site),
+    }
+}
+
+/// Generate code pushing `expr` into a pattern bound to `p`; the code
+/// evaluates to the index of the pattern's root node.
+///
+/// Fails on terms e-matching cannot express: substitutions, collections and
+/// binders.
+fn pattern_code(
+    expr: &Expr,
+    node_name: &Ident,
+    variants: &[NodeVariant],
+    vars: &mut Vec<String>,
+) -> syn::Result<TokenStream> {
+    let unsupported = |what: &str| {
+        syn::Error::new(
+            expr_span(expr),
+            format!("equation_backend: egraph does not support {} in equations", what),
+        )
+    };
+    match expr {
+        Expr::Var(name) => match find_variant(name, variants) {
+            Some(v) if v.fields.is_empty() => {
+                let node_label = &v.node_label;
+                Ok(quote! { p.node(#node_name::#node_label) })
+            },
+            Some(_) => Err(unsupported("constructors used without their arguments")),
+            None => {
+                let name = name.to_string();
+                vars.push(name.clone());
+                Ok(quote! { p.var(#name) })
+            },
+        },
+        Expr::Apply { constructor, args } => {
+            let v = find_variant(constructor, variants)
+                .ok_or_else(|| unsupported("unknown constructors"))?;
+            let node_label = &v.node_label;
+            if args.is_empty() && v.fields.is_empty() {
+                return Ok(quote! { p.node(#node_name::#node_label) });
+            }
+            if let Some(field) = v.fields.iter().find(|f| !matches!(f, NodeField::Child(_))) {
+                return Err(unsupported(match field {
+                    NodeField::Collection(..) => "collection constructors",
+                    NodeField::Scope(_) => "binders",
+                    _ => "variable or literal fields",
+                }));
+            }
+            if v.fields.len() != args.len() {
+                return Err(unsupported("constructors applied to the wrong number of arguments"));
+            }
+            let children = field_vars("c", args.len());
+            let mut lets = Vec::new();
+            for (arg, child) in args.iter().zip(children.iter()) {
+                let code = pattern_code(arg, node_name, variants, vars)?;
+                lets.push(quote! { let #child = #code; });
+            }
+            Ok(quote! {
+                {
+                    #(#lets)*
+                    p.node(#node_name::#node_label(#(#children),*))
+                }
+            })
+        },
+        Expr::Subst { .. } => Err(unsupported("substitutions")),
+        Expr::CollectionPattern { .. } => Err(unsupported("collection patterns")),
+    }
+}
@@ -0,0 +1,131 @@
+//! Free-variable query generation
+//!
+//! Built on the traversal visitors: each exported category gets `free_vars`,
+//! `free_vars_of_category::<C>`, `occurs` and `rename_free`. A variable
+//! belongs to the category of the constructor that stores it, so the `x` in
+//! `NVar(x)` is a `Name` variable and the one in calculator's `Assign(x, e)`
+//! is an `Int` variable. Bound variables are de Bruijn indices inside scope
+//! bodies and are never reported or renamed.
+
+use super::traversal::{visit_fn, visit_mut_fn};
+use crate::ast::TheoryDef;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::Ident;
+
+pub fn generate_free_vars(theory: &TheoryDef) -> TokenStream {
+    let category_trait = format_ident!("{}Category", theory.name);
+    let visitor = format_ident!("{}Visitor", theory.name);
+    let visitor_mut = format_ident!("{}VisitorMut", theory.name);
+    let collector = format_ident!("__{}FreeVars", theory.name);
+    let renamer = format_ident!("__{}RenameFree", theory.name);
+
+    let categories: Vec<&Ident> = theory.exports.iter().map(|e| &e.name).collect();
+    let names: Vec<String> = categories.iter().map(|c| c.to_string()).collect();
+    let visit: Vec<Ident> = categories.iter().map(|c| visit_fn(c)).collect();
+    let visit_mut: Vec<Ident> = categories.iter().map(|c| visit_mut_fn(c)).collect();
+
+    quote! {
+        /// Implemented by each category of the theory, to select variables by
+        /// category with `free_vars_of_category`
+        pub trait #category_trait {
+            /// The category's name
+            const CATEGORY: &'static str;
+        }
+
+        #(
+            impl #category_trait for #categories {
+                const CATEGORY: &'static str = #names;
+            }
+        )*
+
+        /// Collects free variables, tracking the category of the term being walked
+        struct #collector {
+            only: Option<&'static str>,
+            current: &'static str,
+            vars: std::collections::BTreeSet<mettail_runtime::OrdVar>,
+        }
+
+        impl #visitor for #collector {
+            #(
+                fn #visit(&mut self, term: &#categories) {
+                    let outer = std::mem::replace(&mut self.current, #names);
+                    term.walk(self);
+                    self.current = outer;
+                }
+            )*
+
+            fn visit_var(&mut self, var: &mettail_runtime::Var<String>) {
+                if matches!(var, mettail_runtime::Var::Free(_))
+                    && self.only.is_none_or(|only| only == self.current)
+                {
+                    self.vars.insert(mettail_runtime::OrdVar(var.clone()));
+                }
+            }
+        }
+
+        /// Renames one free variable
+        struct #renamer<'a> {
+            from: &'a mettail_runtime::FreeVar<String>,
+            to: &'a mettail_runtime::FreeVar<String>,
+        }
+
+        impl #visitor_mut for #renamer<'_> {
+            fn visit_var_mut(&mut self, var: &mut mettail_runtime::Var<String>) {
+                if let mettail_runtime::Var::Free(v) = var {
+                    if v == self.from {
+                        *v = self.to.clone();
+                    }
+                }
+            }
+        }
+
+        #(
+            impl #categories {
+                /// The free variables of this term, of every category
+                pub fn free_vars(&self) -> std::collections::BTreeSet<mettail_runtime::OrdVar> {
+                    self.collect_free_vars(None)
+                }
+
+                /// The free variables of this term that belong to category `C`
+                pub fn free_vars_of_category<C: #category_trait>(
+                    &self,
+                ) -> std::collections::BTreeSet<mettail_runtime::OrdVar> {
+                    self.collect_free_vars(Some(C::CATEGORY))
+                }
+
+                /// Whether `var` occurs free in this term
+                pub fn occurs(&self, var: &mettail_runtime::FreeVar<String>) -> bool {
+                    self.free_vars()
+                        .contains(&mettail_runtime::OrdVar(mettail_runtime::Var::Free(var.clone())))
+                }
+
+                /// This term with every free occurrence of `from` replaced by `to`
+                ///
+                /// Bound variables are indices, so the renaming cannot capture `to`.
+                pub fn rename_free(
+                    &self,
+                    from: &mettail_runtime::FreeVar<String>,
+                    to: &mettail_runtime::FreeVar<String>,
+                ) -> Self {
+                    let mut term = self.clone();
+                    #visitor_mut::#visit_mut(&mut #renamer { from, to }, &mut term);
+                    term
+                }
+
+                fn collect_free_vars(
+                    &self,
+                    only: Option<&'static str>,
+                ) -> std::collections::BTreeSet<mettail_runtime::OrdVar> {
+                    let mut collector = #collector {
+                        only,
+                        current: #names,
+                        vars: std::collections::BTreeSet::new(),
+                    };
+                    #visitor::#visit(&mut collector, self);
+                    collector.vars
+                }
+            }
+        )*
+    }
+}
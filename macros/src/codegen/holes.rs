@@ -0,0 +1,251 @@
+//! Hole (metavariable) support for the `holes` option
+//!
+//! Each category gets a `<X>Hole(String)` variant, parsed as `?name`, which
+//! turns terms into patterns. This emits `<Theory>Bindings` (hole name to the
+//! term it matched) and, per category:
+//!
+//! - `match_pattern(&self, pattern)`: match this term against a pattern. A
+//!   hole matches any term of its category, and repeated holes must match
+//!   equal terms; `?_` matches without binding. Free variables match by name.
+//!   Collection elements match in any order, and a `...` element matches all
+//!   elements not matched otherwise (in a `Vec`, it must come last). Binders
+//!   are opened together, so a hole in a body binds the term's bound variable
+//!   as a variable of that name.
+//! - `fill_holes(&self, bindings)`: replace bound holes by their terms
+//! - `holes(&self)`: the names of the holes in a term
+//! - `is_pattern(&self)`: whether a term has any hole; the REPL's
+//!   `parse_term` rejects those, so only `parse_pattern` makes patterns
+
+use crate::ast::{CollectionType, GrammarRule, TheoryDef};
+use crate::codegen::traversal::{visit_fn, visit_mut_fn};
+use crate::codegen::{generate_hole_label, generate_var_label, is_var_rule, variant_fields, Field};
+use crate::utils::has_native_type;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::Ident;
+
+/// Generated names shared by every category's code
+struct Names {
+    bindings: Ident,
+    term: Ident,
+    term_ref: Ident,
+}
+
+pub fn generate_holes(theory: &TheoryDef) -> TokenStream {
+    if !theory.options.holes {
+        return quote! {};
+    }
+
+    let names = Names {
+        bindings: format_ident!("{}Bindings", theory.name),
+        term: format_ident!("{}Term", theory.name),
+        term_ref: format_ident!("{}TermRef", theory.name),
+    };
+    let Names { bindings, term, .. } = &names;
+    let visitor = format_ident!("{}Visitor", theory.name);
+    let visitor_mut = format_ident!("{}VisitorMut", theory.name);
+    let collector = format_ident!("__{}Holes", theory.name);
+    let filler = format_ident!("__{}FillHoles", theory.name);
+
+    let categories: Vec<&Ident> = theory.exports.iter().map(|e| &e.name).collect();
+    let hole_labels: Vec<Ident> = categories.iter().map(|c| generate_hole_label(c)).collect();
+    let visit: Vec<Ident> = categories.iter().map(|c| visit_fn(c)).collect();
+    let visit_mut: Vec<Ident> = categories.iter().map(|c| visit_mut_fn(c)).collect();
+
+    let impls = categories
+        .iter()
+        .map(|category| generate_category_matching(category, theory, &names));
+
+    quote! {
+        /// The terms matched by the holes of a pattern, by hole name
+        pub type #bindings = std::collections::BTreeMap<String, #term>;
+
+        /// Collects the names of holes
+        struct #collector {
+            names: std::collections::BTreeSet<String>,
+            /// Whether there was any hole, including `?_` and `...`
+            any: bool,
+        }
+
+        impl #visitor for #collector {
+            #(
+                fn #visit(&mut self, term: &#categories) {
+                    match term {
+                        #categories::#hole_labels(name) => {
+                            self.any = true;
+                            if name != "..." && name != "_" {
+                                self.names.insert(name.clone());
+                            }
+                        },
+                        _ => term.walk(self),
+                    }
+                }
+            )*
+        }
+
+        /// Replaces bound holes by their terms
+        struct #filler<'a> {
+            bindings: &'a #bindings,
+        }
+
+        impl #visitor_mut for #filler<'_> {
+            #(
+                #[allow(irrefutable_let_patterns)]
+                fn #visit_mut(&mut self, term: &mut #categories) {
+                    if let #categories::#hole_labels(name) = term {
+                        if let Some(#term::#categories(bound)) = self.bindings.get(name.as_str()) {
+                            *term = bound.clone();
+                        }
+                        return;
+                    }
+                    term.walk_mut(self);
+                }
+            )*
+        }
+
+        #(#impls)*
+    }
+}
+
+fn generate_category_matching(category: &Ident, theory: &TheoryDef, names: &Names) -> TokenStream {
+    let Names { bindings, term, term_ref } = names;
+    let hole_label = generate_hole_label(category);
+    let visit = visit_fn(category);
+    let visit_mut = visit_mut_fn(category);
+    let visitor = format_ident!("{}Visitor", theory.name);
+    let visitor_mut = format_ident!("{}VisitorMut", theory.name);
+    let collector = format_ident!("__{}Holes", theory.name);
+    let filler = format_ident!("__{}FillHoles", theory.name);
+
+    let rules: Vec<&GrammarRule> = theory
+        .terms
+        .iter()
+        .filter(|r| r.category == *category)
+        .collect();
+
+    let mut arms = Vec::new();
+    for rule in &rules {
+        let label = &rule.label;
+        let fields = variant_fields(rule);
+        if fields.is_empty() {
+            arms.push(quote! { (#category::#label, #category::#label) => true });
+            continue;
+        }
+        let ps: Vec<Ident> = (0..fields.len()).map(|i| format_ident!("p{}", i)).collect();
+        let ts: Vec<Ident> = (0..fields.len()).map(|i| format_ident!("t{}", i)).collect();
+        let checks = fields
+            .iter()
+            .zip(ps.iter().zip(&ts))
+            .map(|(f, (p, t))| match_field(f, p, t, bindings));
+        arms.push(quote! {
+            (#category::#label(#(#ps),*), #category::#label(#(#ts),*)) => {
+                #(#checks)&&*
+            }
+        });
+    }
+
+    let has_var_rule = rules.iter().any(|rule| is_var_rule(rule));
+    if !has_var_rule && has_native_type(category, theory).is_none() {
+        let var_label = generate_var_label(category);
+        arms.push(quote! {
+            (#category::#var_label(p), #category::#var_label(t)) => {
+                mettail_runtime::vars_match(&p.0, &t.0)
+            }
+        });
+    }
+
+    quote! {
+        impl #category {
+            /// Match this term against `pattern`, returning what its holes matched
+            pub fn match_pattern(&self, pattern: &Self) -> Option<#bindings> {
+                let mut bindings = #bindings::new();
+                pattern.match_into(self, &mut bindings).then_some(bindings)
+            }
+
+            /// This term with each hole bound in `bindings` replaced by its term
+            pub fn fill_holes(&self, bindings: &#bindings) -> Self {
+                let mut filled = self.clone();
+                #visitor_mut::#visit_mut(&mut #filler { bindings }, &mut filled);
+                filled
+            }
+
+            /// The names of the holes in this term, other than `?_` and `...`
+            pub fn holes(&self) -> std::collections::BTreeSet<String> {
+                let mut collector = #collector { names: Default::default(), any: false };
+                #visitor::#visit(&mut collector, self);
+                collector.names
+            }
+
+            /// Whether this term has any hole, so is only a pattern
+            pub fn is_pattern(&self) -> bool {
+                let mut collector = #collector { names: Default::default(), any: false };
+                #visitor::#visit(&mut collector, self);
+                collector.any
+            }
+
+            fn is_rest_hole(&self) -> bool {
+                matches!(self, #category::#hole_label(name) if name == "...")
+            }
+
+            /// Match `term` against this pattern, extending `bindings`
+            fn match_into(&self, term: &Self, bindings: &mut #bindings) -> bool {
+                match (self, term) {
+                    (#category::#hole_label(name), _) if name == "..." || name == "_" => true,
+                    (#category::#hole_label(name), _) => match bindings.get(name.as_str()) {
+                        Some(bound) => bound.as_ref() == #term_ref::#category(term),
+                        None => {
+                            bindings.insert(name.clone(), #term::#category(term.clone()));
+                            true
+                        },
+                    },
+                    #(#arms,)*
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+fn match_field(field: &Field, p: &Ident, t: &Ident, bindings: &Ident) -> TokenStream {
+    match field {
+        Field::Var => quote! { mettail_runtime::vars_match(&#p.0, &#t.0) },
+        Field::RawVar => quote! { mettail_runtime::vars_match(#p, #t) },
+        Field::Native => quote! { #p == #t },
+        Field::Term(_) => quote! { #p.match_into(#t, bindings) },
+        // Open both bodies with the term's fresh binder, so a hole under the
+        // binder captures a variable rather than a de Bruijn index
+        Field::Scope(_) => quote! {{
+            let (_, t_body, _, p_body) = #t.clone().unbind2(#p.clone());
+            p_body.match_into(&t_body, bindings)
+        }},
+        Field::Collection(CollectionType::Vec, _) => quote! {{
+            let rest = #p.last().is_some_and(|e| e.is_rest_hole());
+            let n = #p.len() - rest as usize;
+            (if rest { #t.len() >= n } else { #t.len() == n })
+                && #p[..n].iter().zip(#t.iter()).all(|(p, t)| p.match_into(t, bindings))
+        }},
+        Field::Collection(coll, elem) => {
+            let elements = |name: &Ident| match coll {
+                CollectionType::HashBag => quote! {
+                    #name.iter().flat_map(|(e, n)| std::iter::repeat_n(e, n)).collect()
+                },
+                _ => quote! { #name.iter().collect() },
+            };
+            let pattern_elems = elements(p);
+            let term_elems = elements(t);
+            quote! {{
+                let mut patterns: Vec<&#elem> = #pattern_elems;
+                let before = patterns.len();
+                patterns.retain(|e| !e.is_rest_hole());
+                let terms: Vec<&#elem> = #term_elems;
+                mettail_runtime::match_unordered(
+                    &patterns,
+                    &terms,
+                    patterns.len() < before,
+                    bindings,
+                    &mut |p: &#elem, t: &#elem, b: &mut #bindings| p.match_into(t, b),
+                )
+            }}
+        },
+    }
+}
@@ -4,14 +4,22 @@
 //! substitution logic, term generation, and parser integration.
 
 mod ast_gen;
+mod binary;
 mod display;
+mod egraph;
+mod free_vars;
+mod holes;
+mod repl;
+mod spans;
 mod subst;
+mod traversal;
 pub mod termgen;
 
 pub mod blockly;
 pub mod parser;
 
 pub use ast_gen::*;
+pub use repl::generate_repl_theory;
 
 use crate::ast::{GrammarItem, GrammarRule};
 use syn::Ident;
@@ -45,3 +53,71 @@ pub fn generate_var_label(category: &Ident) -> Ident {
         .collect::<String>();
     quote::format_ident!("{}Var", first_letter)
 }
+
+/// Generate the Hole variant label for a category (`holes` option)
+///
+/// Convention: First letter of category + "Hole"
+/// Examples: Proc -> PHole, Name -> NHole
+pub fn generate_hole_label(category: &Ident) -> Ident {
+    let var_label = generate_var_label(category).to_string();
+    quote::format_ident!("{}Hole", var_label.trim_end_matches("Var"))
+}
+
+/// How a constructor field is stored, mirroring `ast_gen::generate_variant`
+pub enum Field {
+    /// `OrdVar`
+    Var,
+    /// `Var<String>` (non-body variable fields of binder constructors)
+    RawVar,
+    /// The category's native type
+    Native,
+    /// `Box<Cat>`
+    Term(Ident),
+    /// `Scope<Binder<String>, Box<Cat>>`
+    Scope(Ident),
+    Collection(crate::ast::CollectionType, Ident),
+}
+
+/// The fields of a constructor, in order
+#[allow(clippy::cmp_owned)]
+pub fn variant_fields(rule: &GrammarRule) -> Vec<Field> {
+    if let Some((binder_idx, body_indices)) = rule.bindings.first() {
+        let body_idx = body_indices[0];
+        return rule
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| i != binder_idx)
+            .filter_map(|(i, item)| match item {
+                GrammarItem::NonTerminal(cat) if i == body_idx => Some(Field::Scope(cat.clone())),
+                GrammarItem::NonTerminal(cat) if cat.to_string() == "Var" => Some(Field::RawVar),
+                GrammarItem::NonTerminal(cat) => Some(Field::Term(cat.clone())),
+                GrammarItem::Collection { coll_type, element_type, .. } => {
+                    Some(Field::Collection(coll_type.clone(), element_type.clone()))
+                },
+                _ => None,
+            })
+            .collect();
+    }
+
+    let single = rule
+        .items
+        .iter()
+        .filter(|item| matches!(item, GrammarItem::NonTerminal(_) | GrammarItem::Collection { .. }))
+        .count()
+        == 1;
+    rule.items
+        .iter()
+        .filter_map(|item| match item {
+            GrammarItem::NonTerminal(cat) if single && cat.to_string() == "Integer" => {
+                Some(Field::Native)
+            },
+            GrammarItem::NonTerminal(cat) if cat.to_string() == "Var" => Some(Field::Var),
+            GrammarItem::NonTerminal(cat) => Some(Field::Term(cat.clone())),
+            GrammarItem::Collection { coll_type, element_type, .. } => {
+                Some(Field::Collection(coll_type.clone(), element_type.clone()))
+            },
+            _ => None,
+        })
+        .collect()
+}
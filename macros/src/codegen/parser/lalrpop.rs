@@ -6,8 +6,8 @@
 #![allow(clippy::cmp_owned, clippy::useless_format)]
 
 use crate::ast::{GrammarItem, GrammarRule, TheoryDef};
-use crate::codegen::{is_integer_rule, is_var_rule};
-use crate::utils::{has_native_type, native_type_to_string};
+use crate::codegen::{generate_hole_label, is_integer_rule, is_var_rule};
+use crate::utils::{bag_type_path, has_native_type, native_type_to_string};
 
 /// Generates Var label for a category (first letter + "Var")
 fn generate_var_label(category: &syn::Ident) -> String {
@@ -95,8 +95,8 @@ pub fn generate_lalrpop_grammar(theory: &TheoryDef) -> String {
 
     grammar.push('\n');
 
-    // Add grammar directive
-    grammar.push_str("grammar;\n\n");
+    // Add grammar directive; variables are interned in the caller's context
+    grammar.push_str("grammar(vars: &mettail_runtime::VarContext);\n\n");
 
     // Add identifier token definition (needed for binders and variables)
     grammar.push_str("Ident: String = {\n");
@@ -106,6 +106,14 @@ pub fn generate_lalrpop_grammar(theory: &TheoryDef) -> String {
     // Generate native type token parsers if needed
     grammar.push_str(&generate_native_type_tokens(theory));
 
+    // Metavariables `?name`, and `...` for "anything else" (`holes` option)
+    if theory.options.holes {
+        grammar.push_str("Hole: String = {\n");
+        grammar.push_str("    r\"\\?[a-zA-Z_][a-zA-Z0-9_]*\" => <>[1..].to_string(),\n");
+        grammar.push_str("    \"...\" => <>.to_string(),\n");
+        grammar.push_str("};\n\n");
+    }
+
     // Generate productions for each exported category
     for export in &theory.exports {
         let cat_name = &export.name;
@@ -215,7 +223,7 @@ fn generate_tiered_production(
     if !var_terminal_rules.is_empty() {
         for rule in var_terminal_rules.iter() {
             production.push_str("    ");
-            production.push_str(&generate_var_terminal_alternative(rule, &cat_str));
+            production.push_str(&spanned(generate_var_terminal_alternative(rule, &cat_str)));
             production.push_str(",\n");
         }
     }
@@ -230,7 +238,7 @@ fn generate_tiered_production(
     // Generate left-recursive rules for infix operators
     for rule in infix_rules.iter() {
         production.push_str("    ");
-        production.push_str(&generate_infix_alternative(rule, &cat_str));
+        production.push_str(&spanned(generate_infix_alternative(rule, &cat_str)));
         production.push_str(",\n");
     }
 
@@ -259,60 +267,69 @@ fn generate_tiered_production(
             if let Some(integer_rule) = filtered_other_rules.iter().find(|r| is_integer_rule(r)) {
                 let integer_label = integer_rule.label.to_string();
                 production.push_str(&format!(
-                    "    \"-\" <i:Integer> => {}::{}(-i),\n",
-                    cat_str, integer_label
+                    "    {},\n",
+                    spanned(format!("\"-\" <i:Integer> => {}::{}(-i)", cat_str, integer_label))
                 ));
             }
         }
     }
 
     // Add non-infix rules (excluding var+terminal rules, which are handled at top level)
-    for (i, rule) in filtered_other_rules.iter().enumerate() {
-        production.push_str("    ");
-        production.push_str(&generate_rule_alternative_with_theory(rule, Some(theory)));
-
-        if i < filtered_other_rules.len() - 1 {
-            production.push_str(",\n");
-        } else {
-            production.push('\n');
-        }
-    }
+    let mut alternatives: Vec<String> = filtered_other_rules
+        .iter()
+        .map(|rule| spanned(generate_rule_alternative_with_theory(rule, theory)))
+        .collect();
 
     // Automatically adds Var alternative if it doesn't exist (lowest precedence)
     // But check for native types first - if category has native type, use native literal parser
     if !has_var_rule {
-        let var_label = generate_var_label(category);
-        // Check if this category has a native type
-        if let Some(native_type) = has_native_type(category, theory) {
-            let type_str = native_type_to_string(native_type);
-            if type_str == "i32" || type_str == "i64" {
-                // Use Integer token for native integer types
-                // Also add unary minus support for negative numbers
-                production.push_str(&format!(
-                    "    \"-\" <i:Integer> => {}::{}(-i),\n",
-                    cat_str, var_label
-                ));
-                production.push_str(&format!("    <i:Integer> => {}::{}(i)\n", cat_str, var_label));
-            } else {
-                // Other native types - fall back to Var for now
-                production.push_str(&format!(
-                    "    <v:Ident> => {}::{}(mettail_runtime::OrdVar(Var::Free(mettail_runtime::get_or_create_var(v))))\n",
-                    cat_str, var_label
-                ));
-            }
-        } else {
-            // No native type - use regular Var
-            production.push_str(&format!(
-                "    <v:Ident> => {}::{}(mettail_runtime::OrdVar(Var::Free(mettail_runtime::get_or_create_var(v))))\n",
-                cat_str, var_label
-            ));
-        }
+        alternatives.extend(auto_var_alternatives(category, theory).into_iter().map(spanned));
+    }
+    if theory.options.holes {
+        alternatives.push(spanned(hole_alternative(category)));
     }
+    production.push_str(&format!("    {}\n", alternatives.join(",\n    ")));
 
     production.push_str("};\n");
     production
 }
 
+/// The alternatives parsing a category's auto-generated Var variant: integer
+/// literals for native integer categories, identifiers otherwise
+fn auto_var_alternatives(category: &syn::Ident, theory: &TheoryDef) -> Vec<String> {
+    let var_label = generate_var_label(category);
+    if let Some(native_type) = has_native_type(category, theory) {
+        let type_str = native_type_to_string(native_type);
+        if type_str == "i32" || type_str == "i64" {
+            // Use Integer token for native integer types
+            // Also add unary minus support for negative numbers
+            return vec![
+                format!("\"-\" <i:Integer> => {}::{}(-i)", category, var_label),
+                format!("<i:Integer> => {}::{}(i)", category, var_label),
+            ];
+        }
+        // Other native types - fall back to Var for now
+    }
+    vec![format!(
+        "<v:Ident> => {}::{}(mettail_runtime::OrdVar(Var::Free(vars.var(v))))",
+        category, var_label
+    )]
+}
+
+/// The alternative parsing a category's Hole variant (`holes` option)
+fn hole_alternative(category: &syn::Ident) -> String {
+    format!("<h:Hole> => {}::{}(h)", category, generate_hole_label(category))
+}
+
+/// Wrap an alternative so that it records the span of the term it builds, if
+/// the parse's `VarContext` records spans
+fn spanned(alternative: String) -> String {
+    let (pattern, action) = alternative
+        .split_once(" => ")
+        .expect("alternative has an action");
+    format!("<lo:@L> {} <hi:@R> => vars.spanned(lo, hi, {})", pattern, action)
+}
+
 /// Generate alternative for infix operator (left-associative)
 fn generate_infix_alternative(rule: &GrammarRule, cat_str: &str) -> String {
     let label = &rule.label;
@@ -381,7 +398,7 @@ fn generate_var_terminal_alternative(rule: &GrammarRule, cat_str: &str) -> Strin
                 if nt.to_string() == "Var" {
                     // Var should parse as Ident, then convert to OrdVar
                     pattern.push_str(&format!(" <{}:Ident>", var_name));
-                    args.push(format!("mettail_runtime::OrdVar(Var::Free(mettail_runtime::get_or_create_var({})))", var_name));
+                    args.push(format!("mettail_runtime::OrdVar(Var::Free(vars.var({})))", var_name));
                 } else if nt == category {
                     // Recursive reference: use CatInfix to avoid circular reference
                     pattern.push_str(&format!(" <{}:{}Infix>", var_name, cat_str));
@@ -420,50 +437,21 @@ fn generate_simple_production(
     production.push_str(&format!("pub {}: {} = {{\n", category, category));
 
     // Generate alternative for each rule
-    for (i, rule) in rules.iter().enumerate() {
-        production.push_str("    ");
-        // Pass theory context for native type detection
-        production.push_str(&generate_rule_alternative_with_theory(rule, Some(theory)));
-
-        // Adds comma unless it's the last rule and we won't add Var
-        if i < rules.len() - 1 || !has_var_rule {
-            production.push_str(",\n");
-        } else {
-            production.push('\n');
-        }
-    }
+    // Pass theory context for native type detection
+    let mut alternatives: Vec<String> = rules
+        .iter()
+        .map(|rule| spanned(generate_rule_alternative_with_theory(rule, theory)))
+        .collect();
 
     // Automatically adds Var alternative if it doesn't exist (lowest precedence)
     // But check for native types first - if category has native type, use native literal parser
     if !has_var_rule {
-        let var_label = generate_var_label(category);
-        // Check if this category has a native type
-        if let Some(native_type) = has_native_type(category, theory) {
-            let type_str = native_type_to_string(native_type);
-            if type_str == "i32" || type_str == "i64" {
-                // Use Integer token for native integer types
-                // Also add unary minus support for negative numbers
-                production.push_str(&format!(
-                    "    \"-\" <i:Integer> => {}::{}(-i),\n",
-                    category, var_label
-                ));
-                production
-                    .push_str(&format!("    <i:Integer> => {}::{}(i)\n", category, var_label));
-            } else {
-                // Other native types - fall back to Var for now
-                production.push_str(&format!(
-                    "    <v:Ident> => {}::{}(mettail_runtime::OrdVar(Var::Free(mettail_runtime::get_or_create_var(v))))\n",
-                    category, var_label
-                ));
-            }
-        } else {
-            // No native type - use regular Var
-            production.push_str(&format!(
-                "    <v:Ident> => {}::{}(mettail_runtime::OrdVar(Var::Free(mettail_runtime::get_or_create_var(v))))\n",
-                category, var_label
-            ));
-        }
+        alternatives.extend(auto_var_alternatives(category, theory).into_iter().map(spanned));
     }
+    if theory.options.holes {
+        alternatives.push(spanned(hole_alternative(category)));
+    }
+    production.push_str(&format!("    {}\n", alternatives.join(",\n    ")));
 
     production.push_str("};\n");
     production
@@ -472,7 +460,7 @@ fn generate_simple_production(
 /// Generate a LALRPOP alternative for a single grammar rule (with theory context for native types)
 fn generate_rule_alternative_with_theory(
     rule: &GrammarRule,
-    _theory: Option<&TheoryDef>,
+    theory: &TheoryDef,
 ) -> String {
     let label = &rule.label;
     let mut alt = String::new();
@@ -495,8 +483,8 @@ fn generate_rule_alternative_with_theory(
             },
             GrammarItem::NonTerminal(nt) if nt == "Var" => {
                 // Variable: parse identifier as variable node
-                // Use get_or_create_var to ensure same name = same ID within a parse
-                alt.push_str(&format!("<v:Ident> => {}::{}(mettail_runtime::OrdVar(Var::Free(mettail_runtime::get_or_create_var(v))))",
+                // Intern through the parse's VarContext so same name = same ID within a parse
+                alt.push_str(&format!("<v:Ident> => {}::{}(mettail_runtime::OrdVar(Var::Free(vars.var(v))))",
                     rule.category, label));
             },
             GrammarItem::NonTerminal(nt) => {
@@ -519,6 +507,7 @@ fn generate_rule_alternative_with_theory(
                     element_type,
                     separator,
                     delimiters.as_ref(),
+                    theory,
                 );
             },
             GrammarItem::Binder { .. } => {
@@ -528,14 +517,14 @@ fn generate_rule_alternative_with_theory(
         }
     } else {
         // Multiple items: need to handle sequence
-        alt.push_str(&generate_sequence_alternative(rule));
+        alt.push_str(&generate_sequence_alternative(rule, theory));
     }
 
     alt
 }
 
 /// Generate alternative for a rule with multiple items
-fn generate_sequence_alternative(rule: &GrammarRule) -> String {
+fn generate_sequence_alternative(rule: &GrammarRule, theory: &TheoryDef) -> String {
     let label = &rule.label;
     let category = &rule.category;
 
@@ -559,7 +548,7 @@ fn generate_sequence_alternative(rule: &GrammarRule) -> String {
                 if nt.to_string() == "Var" {
                     // Var should parse as Ident, then convert to OrdVar
                     pattern.push_str(&format!(" <{}:Ident>", var_name));
-                    args.push(format!("mettail_runtime::OrdVar(Var::Free(mettail_runtime::get_or_create_var({})))", var_name));
+                    args.push(format!("mettail_runtime::OrdVar(Var::Free(vars.var({})))", var_name));
                 } else {
                     pattern.push_str(&format!(" <{}:{}>", var_name, nt));
                     args.push(format!("Box::new({})", var_name));
@@ -579,6 +568,7 @@ fn generate_sequence_alternative(rule: &GrammarRule) -> String {
                     element_type,
                     separator,
                     delimiters.as_ref(),
+                    theory,
                 );
             },
             GrammarItem::Binder { category: _binder_cat } => {
@@ -654,12 +644,12 @@ fn generate_binder_alternative(rule: &GrammarRule) -> String {
     // and use it as the binder, so moniker can properly bind it
     let mut action = format!(" => {{\n");
     action.push_str("        use mettail_runtime::BoundTerm;\n");
-    action.push_str(&format!("        let free_vars = {}.free_vars();\n", body_var));
+    action.push_str(&format!("        let free_vars = BoundTerm::free_vars(&{});\n", body_var));
     action.push_str(&format!("        let binder = if let Some(fv) = free_vars.iter().find(|fv| fv.pretty_name.as_deref() == Some(&{})) {{\n", binder_var));
     action.push_str("            Binder((*fv).clone())\n");
     action.push_str("        } else {\n");
     action.push_str(&format!(
-        "            Binder(mettail_runtime::get_or_create_var({}))\n",
+        "            Binder(vars.var({}))\n",
         binder_var
     ));
     action.push_str("        };\n");
@@ -688,6 +678,7 @@ fn generate_collection_alternative(
     element_type: &syn::Ident,
     separator: &str,
     delimiters: Option<&(String, String)>,
+    theory: &TheoryDef,
 ) -> String {
     let label = &rule.label;
     let category = &rule.category;
@@ -709,7 +700,7 @@ fn generate_collection_alternative(
 
     // Determine the collection type constructor
     let coll_constructor = match coll_type {
-        crate::ast::CollectionType::HashBag => "mettail_runtime::HashBag",
+        crate::ast::CollectionType::HashBag => bag_type_path(theory),
         crate::ast::CollectionType::HashSet => "std::collections::HashSet",
         crate::ast::CollectionType::Vec => "Vec",
     };
@@ -843,6 +834,7 @@ fn test_automatic_var_in_parser() {
             equations: vec![],
             rewrites: vec![],
             semantics: vec![],
+            options: TheoryOptions::default(),
         };
 
         let grammar = generate_lalrpop_grammar(&theory);
@@ -852,14 +844,14 @@ fn test_automatic_var_in_parser() {
         // Proc -> PVar
         assert!(
             grammar.contains(
-                "PVar(mettail_runtime::OrdVar(Var::Free(mettail_runtime::get_or_create_var(v))))"
+                "PVar(mettail_runtime::OrdVar(Var::Free(vars.var(v))))"
             ),
             "Expected PVar parser alternative for Proc category"
         );
         // Name -> NVar
         assert!(
             grammar.contains(
-                "NVar(mettail_runtime::OrdVar(Var::Free(mettail_runtime::get_or_create_var(v))))"
+                "NVar(mettail_runtime::OrdVar(Var::Free(vars.var(v))))"
             ),
             "Expected NVar parser alternative for Name category"
         );
@@ -898,6 +890,7 @@ fn test_automatic_var_in_parser_with_existing_var() {
             equations: vec![],
             rewrites: vec![],
             semantics: vec![],
+            options: TheoryOptions::default(),
         };
 
         let grammar = generate_lalrpop_grammar(&theory);
@@ -908,7 +901,7 @@ fn test_automatic_var_in_parser_with_existing_var() {
         assert_eq!(pvar_count, 1, "Expected exactly one PVar alternative, found {}", pvar_count);
         assert!(
             grammar.contains(
-                "PVar(mettail_runtime::OrdVar(Var::Free(mettail_runtime::get_or_create_var(v))))"
+                "PVar(mettail_runtime::OrdVar(Var::Free(vars.var(v))))"
             ),
             "Expected PVar parser alternative"
         );
@@ -1009,6 +1002,7 @@ fn test_calculator_like_grammar() {
             equations: vec![],
             rewrites: vec![],
             semantics: vec![],
+            options: TheoryOptions::default(),
         };
 
         let grammar = generate_lalrpop_grammar(&theory);
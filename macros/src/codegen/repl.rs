@@ -0,0 +1,311 @@
+//! Generated REPL integration (mettail-runtime's `repl` feature)
+//!
+//! Implements `mettail_runtime::repl::Theory` for the theory's unit struct,
+//! parsing and exploring terms of the primary (first exported) category, which
+//! are wrapped in a `<Theory>ReplTerm` implementing `repl::Term`. The counts
+//! it reports are taken from the definition: constructors include the
+//! generated `<X>Var` variants but not the `<X>Hole` variants of the `holes`
+//! option, which only occur in patterns, and rewrites include congruences.
+//! The syntax it reports (for the REPL's completion and highlighting) is read
+//! off the grammar rules. With the `holes` option, patterns parse into a
+//! `<Theory>ReplPattern`, which matches with the generated `match_pattern`;
+//! with the `stats` option, runs report `<Theory>::explore_with_stats`.
+
+use crate::ast::{GrammarItem, TheoryDef};
+use crate::codegen::is_var_rule;
+use crate::utils::has_native_type;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+pub fn generate_repl_theory(theory: &TheoryDef) -> TokenStream {
+    let Some(primary) = theory.exports.first() else {
+        return quote! {};
+    };
+
+    let theory_name = &theory.name;
+    let name = theory.name.to_string().to_lowercase();
+    let theory_mod = format_ident!("{}", name);
+    let category = &primary.name;
+    let parser = format_ident!("{}Parser", category);
+    let wrapper = format_ident!("{}ReplTerm", theory.name);
+
+    let categories: Vec<String> = theory.exports.iter().map(|e| e.name.to_string()).collect();
+    // `<X>Hole` variants are left out: terms never contain them
+    let constructor_count = theory.terms.len() + auto_var_count(theory);
+    let equation_count = theory.equations.len();
+    let rewrite_count = theory.rewrites.len();
+    let SyntaxParts {
+        terminals,
+        constructors,
+        binder_prefixes,
+        delimiters,
+    } = syntax_parts(theory);
+    let open_delimiters = delimiters.iter().map(|(open, _)| open);
+    let close_delimiters = delimiters.iter().map(|(_, close)| close);
+    let (parse_pattern, pattern_wrapper) = generate_repl_patterns(theory);
+    // Holes make patterns, which only `parse_pattern` returns
+    let reject_holes = if theory.options.holes {
+        quote! {
+            if term.is_pattern() {
+                mettail_runtime::repl::anyhow::bail!(
+                    "Holes (`?name`, `...`) are only allowed in patterns, as in `query`"
+                );
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // Without the `stats` option, the trait's default reports no statistics
+    let run_ascent_with_stats = if theory.options.stats {
+        quote! {
+            fn run_ascent_with_stats(
+                &self,
+                term: Box<dyn mettail_runtime::repl::Term>,
+            ) -> mettail_runtime::repl::anyhow::Result<(
+                mettail_runtime::repl::AscentResults,
+                Option<mettail_runtime::ExploreStats>,
+            )> {
+                let term = term.as_any().downcast_ref::<#wrapper>().ok_or_else(|| {
+                    mettail_runtime::repl::anyhow::anyhow!(concat!("Expected ", stringify!(#wrapper)))
+                })?;
+                let (graph, stats) = #theory_name::explore_with_stats(term.0.clone());
+                Ok((mettail_runtime::repl::AscentResults::from_rewrite_graph(&graph), Some(stats)))
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    quote! {
+        mettail_runtime::__repl_theory! {
+            impl mettail_runtime::repl::Theory for #theory_name {
+                fn name(&self) -> &str {
+                    #name
+                }
+
+                fn categories(&self) -> Vec<String> {
+                    vec![#(#categories.to_string()),*]
+                }
+
+                fn constructor_count(&self) -> usize {
+                    #constructor_count
+                }
+
+                fn equation_count(&self) -> usize {
+                    #equation_count
+                }
+
+                fn rewrite_count(&self) -> usize {
+                    #rewrite_count
+                }
+
+                fn parse_term(
+                    &self,
+                    input: &str,
+                ) -> mettail_runtime::repl::anyhow::Result<Box<dyn mettail_runtime::repl::Term>> {
+                    let vars = mettail_runtime::VarContext::new();
+                    let term = #theory_mod::#parser::new()
+                        .parse(&vars, input)
+                        .map_err(|e| mettail_runtime::repl::anyhow::anyhow!("Parse error: {:?}", e))?;
+                    #reject_holes
+                    Ok(Box::new(#wrapper(term)))
+                }
+
+                fn run_ascent(
+                    &self,
+                    term: Box<dyn mettail_runtime::repl::Term>,
+                ) -> mettail_runtime::repl::anyhow::Result<mettail_runtime::repl::AscentResults> {
+                    let term = term.as_any().downcast_ref::<#wrapper>().ok_or_else(|| {
+                        mettail_runtime::repl::anyhow::anyhow!(concat!("Expected ", stringify!(#wrapper)))
+                    })?;
+                    let graph = #theory_name::explore(term.0.clone());
+                    Ok(mettail_runtime::repl::AscentResults::from_rewrite_graph(&graph))
+                }
+
+                #run_ascent_with_stats
+
+                fn step(
+                    &self,
+                    term: &dyn mettail_runtime::repl::Term,
+                ) -> mettail_runtime::repl::anyhow::Result<Vec<Box<dyn mettail_runtime::repl::Term>>> {
+                    let term = term.as_any().downcast_ref::<#wrapper>().ok_or_else(|| {
+                        mettail_runtime::repl::anyhow::anyhow!(concat!("Expected ", stringify!(#wrapper)))
+                    })?;
+                    Ok(#theory_name::step(term.0.clone())
+                        .into_iter()
+                        .map(|target| Box::new(#wrapper(target)) as Box<dyn mettail_runtime::repl::Term>)
+                        .collect())
+                }
+
+                fn format_term(&self, term: &dyn mettail_runtime::repl::Term) -> String {
+                    term.to_string()
+                }
+
+                fn syntax(&self) -> mettail_runtime::repl::Syntax {
+                    mettail_runtime::repl::Syntax::new(
+                        vec![#(#terminals.to_string()),*],
+                        vec![#(#constructors.to_string()),*],
+                        vec![#(vec![#(#binder_prefixes.to_string()),*]),*],
+                        vec![#((#open_delimiters.to_string(), #close_delimiters.to_string())),*],
+                    )
+                }
+
+                #parse_pattern
+            }
+
+            #pattern_wrapper
+
+            /// A term of this theory's primary category, for the REPL
+            #[derive(Clone, PartialEq, Eq)]
+            pub struct #wrapper(pub #category);
+
+            impl mettail_runtime::repl::Term for #wrapper {
+                fn clone_box(&self) -> Box<dyn mettail_runtime::repl::Term> {
+                    Box::new(self.clone())
+                }
+
+                fn term_id(&self) -> u64 {
+                    mettail_runtime::repl::term_id(&self.0)
+                }
+
+                fn term_eq(&self, other: &dyn mettail_runtime::repl::Term) -> bool {
+                    other.as_any().downcast_ref::<#wrapper>() == Some(self)
+                }
+
+                fn as_any(&self) -> &dyn std::any::Any {
+                    self
+                }
+            }
+
+            impl std::fmt::Display for #wrapper {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    write!(f, "{}", self.0)
+                }
+            }
+
+            impl std::fmt::Debug for #wrapper {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    write!(f, "{:?}", self.0)
+                }
+            }
+        }
+    }
+}
+
+/// `parse_pattern` and the `<Theory>ReplPattern` it returns (`holes` option)
+fn generate_repl_patterns(theory: &TheoryDef) -> (TokenStream, TokenStream) {
+    if !theory.options.holes {
+        return (quote! {}, quote! {});
+    }
+    let name = theory.name.to_string().to_lowercase();
+    let theory_mod = format_ident!("{}", name);
+    let category = &theory.exports[0].name;
+    let parser = format_ident!("{}Parser", category);
+    let wrapper = format_ident!("{}ReplTerm", theory.name);
+    let pattern = format_ident!("{}ReplPattern", theory.name);
+    let term = format_ident!("{}Term", theory.name);
+    let categories: Vec<&syn::Ident> = theory.exports.iter().map(|e| &e.name).collect();
+
+    let parse_pattern = quote! {
+        fn parse_pattern(
+            &self,
+            input: &str,
+        ) -> mettail_runtime::repl::anyhow::Result<Box<dyn mettail_runtime::repl::Pattern>> {
+            let vars = mettail_runtime::VarContext::new();
+            let pattern = #theory_mod::#parser::new()
+                .parse(&vars, input)
+                .map_err(|e| mettail_runtime::repl::anyhow::anyhow!("Parse error: {:?}", e))?;
+            Ok(Box::new(#pattern(pattern)))
+        }
+    };
+
+    let pattern_wrapper = quote! {
+        /// A pattern of this theory's primary category, for the REPL
+        pub struct #pattern(pub #category);
+
+        impl mettail_runtime::repl::Pattern for #pattern {
+            fn match_term(
+                &self,
+                term: &dyn mettail_runtime::repl::Term,
+            ) -> Option<mettail_runtime::repl::Bindings> {
+                let term = term.as_any().downcast_ref::<#wrapper>()?;
+                let bindings = term.0.match_pattern(&self.0)?;
+                Some(
+                    bindings
+                        .into_iter()
+                        .map(|(hole, t)| match t {
+                            #(#term::#categories(t) => (hole, t.to_string()),)*
+                        })
+                        .collect(),
+                )
+            }
+        }
+    };
+
+    (parse_pattern, pattern_wrapper)
+}
+
+/// The tokens of the grammar rules, as passed to `repl::Syntax::new`
+struct SyntaxParts {
+    terminals: Vec<String>,
+    constructors: Vec<String>,
+    binder_prefixes: Vec<Vec<String>>,
+    delimiters: Vec<(String, String)>,
+}
+
+fn syntax_parts(theory: &TheoryDef) -> SyntaxParts {
+    let mut parts = SyntaxParts {
+        terminals: Vec::new(),
+        constructors: Vec::new(),
+        binder_prefixes: Vec::new(),
+        delimiters: Vec::new(),
+    };
+    for rule in &theory.terms {
+        parts.constructors.push(rule.label.to_string());
+        for (i, item) in rule.items.iter().enumerate() {
+            match item {
+                GrammarItem::Terminal(terminal) => parts.terminals.push(terminal.clone()),
+                GrammarItem::Binder { .. } => {
+                    let prefix: Vec<String> = rule.items[..i]
+                        .iter()
+                        .rev()
+                        .map_while(|item| match item {
+                            GrammarItem::Terminal(terminal) => Some(terminal.clone()),
+                            _ => None,
+                        })
+                        .collect();
+                    if !prefix.is_empty() {
+                        parts
+                            .binder_prefixes
+                            .push(prefix.into_iter().rev().collect());
+                    }
+                },
+                GrammarItem::Collection { separator, delimiters, .. } => {
+                    parts.terminals.push(separator.clone());
+                    if let Some((open, close)) = delimiters {
+                        parts.terminals.extend([open.clone(), close.clone()]);
+                        parts.delimiters.push((open.clone(), close.clone()));
+                    }
+                },
+                GrammarItem::NonTerminal(_) => {},
+            }
+        }
+    }
+    parts
+}
+
+/// Number of categories that get a generated `<X>Var` variant
+fn auto_var_count(theory: &TheoryDef) -> usize {
+    theory
+        .exports
+        .iter()
+        .filter(|export| {
+            let has_var_rule = theory
+                .terms
+                .iter()
+                .any(|rule| rule.category == export.name && is_var_rule(rule));
+            !has_var_rule && has_native_type(&export.name, theory).is_none()
+        })
+        .count()
+}
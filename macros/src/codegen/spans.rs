@@ -0,0 +1,43 @@
+//! Source span generation
+//!
+//! Generated parsers record the span of every constructor when their
+//! `VarContext` was created with `VarContext::with_spans`; the `spans` method
+//! emitted here turns that recording into a `mettail_runtime::SpanTable` keyed
+//! by the term paths of the traversal API.
+
+use crate::ast::TheoryDef;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::Ident;
+
+pub fn generate_spans(theory: &TheoryDef) -> TokenStream {
+    let term_ref = format_ident!("{}TermRef", theory.name);
+    let categories: Vec<&Ident> = theory.exports.iter().map(|e| &e.name).collect();
+    let key_arms: Vec<TokenStream> = categories
+        .iter()
+        .map(|c| quote! { #term_ref::#c(t) => mettail_runtime::span_key(t) })
+        .collect();
+    let key = quote! {
+        |term| match term {
+            #(#key_arms,)*
+        }
+    };
+
+    quote! {
+        #(
+            impl #categories {
+                /// The source spans of this term's subterms, recorded when it was
+                /// parsed with `vars` (see `VarContext::with_spans`)
+                ///
+                /// Call right after parsing: the recording is consumed.
+                pub fn spans(&self, vars: &mettail_runtime::VarContext) -> mettail_runtime::SpanTable {
+                    vars.take_spans(
+                        #term_ref::from(self),
+                        |term| term.children(),
+                        #key,
+                    )
+                }
+            }
+        )*
+    }
+}
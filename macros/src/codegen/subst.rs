@@ -7,8 +7,8 @@
 #![allow(clippy::cmp_owned)]
 
 use crate::ast::{GrammarItem, GrammarRule, TheoryDef};
-use crate::codegen::generate_var_label;
-use crate::utils::has_native_type;
+use crate::codegen::{generate_hole_label, generate_var_label};
+use crate::utils::{bag_type, has_native_type};
 use proc_macro2::TokenStream;
 use quote::quote;
 use syn::Ident;
@@ -95,7 +95,7 @@ impl #category {
     }
 
     // Generate the main substitute method (same-category)
-    let main_method = generate_substitute_method(category, rules, category);
+    let main_method = generate_substitute_method(category, rules, category, theory);
 
     // Generate cross-category substitute methods for OTHER categories
     let cross_methods: Vec<TokenStream> = subst_cats
@@ -103,7 +103,7 @@ impl #category {
         .filter(|cat| **cat != category_str)
         .map(|cat_str| {
             let cat = syn::Ident::new(cat_str, proc_macro2::Span::call_site());
-            generate_cross_category_substitute_method(category, rules, &cat)
+            generate_cross_category_substitute_method(category, rules, &cat, theory)
         })
         .collect();
 
@@ -122,10 +122,11 @@ fn generate_substitute_method(
     category: &Ident,
     rules: &[&GrammarRule],
     _replacement_cat: &Ident,
+    theory: &TheoryDef,
 ) -> TokenStream {
     let mut match_arms: Vec<TokenStream> = rules
         .iter()
-        .map(|rule| generate_substitution_arm(category, rule, category))
+        .map(|rule| generate_substitution_arm(category, rule, category, theory))
         .collect();
 
     // Check if Var variant was auto-generated
@@ -139,6 +140,12 @@ fn generate_substitute_method(
         match_arms.push(var_arm);
     }
 
+    // Holes are metavariables, not object variables
+    if theory.options.holes {
+        let hole_label = generate_hole_label(category);
+        match_arms.push(quote! { #category::#hole_label(_) => self.clone() });
+    }
+
     quote! {
         pub fn substitute(
             &self,
@@ -157,12 +164,13 @@ fn generate_cross_category_substitute_method(
     category: &Ident,
     rules: &[&GrammarRule],
     binder_cat: &Ident,
+    theory: &TheoryDef,
 ) -> TokenStream {
     let method_name = quote::format_ident!("substitute_{}", binder_cat.to_string().to_lowercase());
 
     let mut match_arms: Vec<TokenStream> = rules
         .iter()
-        .map(|rule| generate_substitution_arm(category, rule, binder_cat))
+        .map(|rule| generate_substitution_arm(category, rule, binder_cat, theory))
         .collect();
 
     // Check if Var variant was auto-generated
@@ -172,6 +180,12 @@ fn generate_cross_category_substitute_method(
         match_arms.push(var_arm);
     }
 
+    // Holes are metavariables, not object variables
+    if theory.options.holes {
+        let hole_label = generate_hole_label(category);
+        match_arms.push(quote! { #category::#hole_label(_) => self.clone() });
+    }
+
     quote! {
         /// Substitute `replacement` (of type #binder_cat) for free occurrences of `var` in this term
         ///
@@ -211,6 +225,7 @@ fn generate_substitution_arm(
     category: &Ident,
     rule: &GrammarRule,
     replacement_cat: &Ident,
+    theory: &TheoryDef,
 ) -> TokenStream {
     let label = &rule.label;
 
@@ -239,11 +254,11 @@ fn generate_substitution_arm(
 
     // Check if this has bindings (uses Scope)
     if !rule.bindings.is_empty() {
-        return generate_scope_substitution_arm(category, rule, replacement_cat);
+        return generate_scope_substitution_arm(category, rule, replacement_cat, theory);
     }
 
     // Regular constructor - substitute in all subterms
-    generate_regular_substitution_arm(category, rule, replacement_cat)
+    generate_regular_substitution_arm(category, rule, replacement_cat, theory)
 }
 
 /// Check if a rule is a variable constructor (Var category)
@@ -285,7 +300,9 @@ fn generate_scope_substitution_arm(
     category: &Ident,
     rule: &GrammarRule,
     replacement_cat: &Ident,
+    theory: &TheoryDef,
 ) -> TokenStream {
+    let bag_ty = bag_type(theory);
     let label = &rule.label;
 
     let (binder_idx, body_indices) = &rule.bindings[0];
@@ -398,7 +415,7 @@ fn generate_scope_substitution_arm(
                                 let helper_name = quote::format_ident!("insert_into_{}", rule.label.to_string().to_lowercase());
                                 quote! {
                                     {
-                                        let mut bag = mettail_runtime::HashBag::new();
+                                        let mut bag = #bag_ty::new();
                                         for (elem, count) in #field_name.iter() {
                                             let subst_elem = elem.#subst_method(var, replacement);
                                             for _ in 0..count {
@@ -474,7 +491,9 @@ fn generate_regular_substitution_arm(
     category: &Ident,
     rule: &GrammarRule,
     replacement_cat: &Ident,
+    theory: &TheoryDef,
 ) -> TokenStream {
+    let bag_ty = bag_type(theory);
     let label = &rule.label;
 
     // Check if this constructor has a Var field
@@ -606,7 +625,7 @@ enum FieldInfo {
                             );
                             quote! {
                                 {
-                                    let mut bag = mettail_runtime::HashBag::new();
+                                    let mut bag = #bag_ty::new();
                                     for (elem, count) in #field.iter() {
                                         let subst_elem = elem.#subst_method(var, replacement);
                                         for _ in 0..count {
@@ -688,6 +707,7 @@ fn test_generate_simple_substitution() {
             equations: vec![],
             rewrites: vec![],
             semantics: vec![],
+            options: TheoryOptions::default(),
         };
 
         let output = generate_substitution(&theory);
@@ -12,6 +12,7 @@
 )]
 
 use crate::ast::{GrammarItem, GrammarRule, TheoryDef};
+use crate::utils::bag_type;
 use proc_macro2::TokenStream;
 use quote::quote;
 use syn::Ident;
@@ -47,6 +48,8 @@ fn generate_context_struct(theory: &TheoryDef) -> TokenStream {
     quote! {
         struct GenerationContext {
             vars: Vec<String>,
+            // Shared with nested contexts so binders and bodies agree on variables
+            var_ctx: std::rc::Rc<mettail_runtime::VarContext>,
             initial_var_count: usize,  // Track how many vars were in initial pool
             max_depth: usize,
             max_collection_width: usize,
@@ -91,6 +94,7 @@ fn new(vars: Vec<String>, max_depth: usize, max_collection_width: usize) -> Self
                 let initial_var_count = vars.len();
                 Self {
                     vars,
+                    var_ctx: std::rc::Rc::new(mettail_runtime::VarContext::new()),
                     initial_var_count,
                     max_depth,
                     max_collection_width,
@@ -100,12 +104,14 @@ fn new(vars: Vec<String>, max_depth: usize, max_collection_width: usize) -> Self
 
             fn new_with_extended_vars(
                 vars: Vec<String>,
+                var_ctx: std::rc::Rc<mettail_runtime::VarContext>,
                 initial_var_count: usize,
                 max_depth: usize,
                 max_collection_width: usize
             ) -> Self {
                 Self {
                     vars,
+                    var_ctx,
                     initial_var_count,
                     max_depth,
                     max_collection_width,
@@ -130,7 +136,7 @@ fn generate_category_generation_method(cat_name: Ident, theory: &TheoryDef) -> T
     let method_name = category_to_generate_method(&cat_name);
     let field_name = category_to_field_name(&cat_name);
 
-    // Get all rules for this category
+    // Get all rules for this category (holes have none, so are never generated)
     let rules: Vec<&GrammarRule> = theory
         .terms
         .iter()
@@ -217,7 +223,7 @@ fn generate_depth_0_cases(
                                 terms.push(#cat_name::#label(
                                     mettail_runtime::OrdVar(
                                         mettail_runtime::Var::Free(
-                                            mettail_runtime::get_or_create_var(var_name)
+                                            self.var_ctx.var(var_name)
                                         )
                                     )
                                 ));
@@ -399,37 +405,11 @@ fn generate_nary_case(
     arg_cats: &[Ident],
     theory: &TheoryDef,
 ) -> TokenStream {
-    // For now, use a simplified approach: all args at depth-1
-    // This is less comprehensive but simpler
-    let n = arg_cats.len();
-
-    let field_names: Vec<TokenStream> = arg_cats
-        .iter()
-        .enumerate()
-        .map(|(i, cat)| {
-            if !is_exported(cat, theory) {
-                return quote! {};
-            }
-            let field = category_to_field_name(cat);
-            let di = syn::Ident::new(&format!("d{}", i), proc_macro2::Span::call_site());
-            let argi = syn::Ident::new(&format!("args{}", i), proc_macro2::Span::call_site());
-            quote! {
-                if let Some(#argi) = self.#field.get(&#di)
-            }
-        })
-        .collect();
-
-    let arg_iters: Vec<TokenStream> = (0..n)
-        .map(|i| {
-            let argi = syn::Ident::new(&format!("args{}", i), proc_macro2::Span::call_site());
-            let argi_single = syn::Ident::new(&format!("arg{}", i), proc_macro2::Span::call_site());
-            quote! {
-                for #argi_single in #argi
-            }
-        })
-        .collect();
+    if !arg_cats.iter().all(|cat| is_exported(cat, theory)) {
+        return quote! {};
+    }
 
-    let constructor_args: Vec<TokenStream> = (0..n)
+    let constructor_args: Vec<TokenStream> = (0..arg_cats.len())
         .map(|i| {
             let argi = syn::Ident::new(&format!("arg{}", i), proc_macro2::Span::call_site());
             quote! {
@@ -438,15 +418,34 @@ fn generate_nary_case(
         })
         .collect();
 
-    // Simplified: just use depth-1 for all args
+    // Simplified: all args at depth-1, less comprehensive than the binary case
+    // Built inside out: the push, then one loop per argument, then one lookup per argument
+    let mut body = quote! {
+        terms.push(#cat_name::#label(#(#constructor_args),*));
+    };
+    for i in (0..arg_cats.len()).rev() {
+        let argi = syn::Ident::new(&format!("arg{}", i), proc_macro2::Span::call_site());
+        let argsi = syn::Ident::new(&format!("args{}", i), proc_macro2::Span::call_site());
+        body = quote! {
+            for #argi in #argsi {
+                #body
+            }
+        };
+    }
+    for (i, cat) in arg_cats.iter().enumerate().rev() {
+        let field = category_to_field_name(cat);
+        let argsi = syn::Ident::new(&format!("args{}", i), proc_macro2::Span::call_site());
+        body = quote! {
+            if let Some(#argsi) = self.#field.get(&d) {
+                #body
+            }
+        };
+    }
+
     quote! {
         if depth > 0 {
             let d = depth - 1;
-            #(#field_names)* {
-                #(#arg_iters)* {
-                    terms.push(#cat_name::#label(#(#constructor_args),*));
-                }
-            }
+            #body
         }
     }
 }
@@ -535,6 +534,7 @@ fn generate_simple_binder_case(
         // Create temporary context for generating bodies that can use the binder
         let mut temp_ctx = GenerationContext::new_with_extended_vars(
             extended_vars,
+            self.var_ctx.clone(),
             self.initial_var_count,
             depth - 1,
             self.max_collection_width
@@ -551,7 +551,7 @@ fn generate_simple_binder_case(
 
         // Create scopes with bodies that may reference the binder
         for body in bodies_with_binder {
-            let binder_var = mettail_runtime::get_or_create_var(&binder_name);
+            let binder_var = self.var_ctx.var(&binder_name);
             let binder = mettail_runtime::Binder(binder_var);
             // Scope::new will automatically close free occurrences of binder_var in body
             let scope = mettail_runtime::Scope::new(binder, Box::new(body));
@@ -583,6 +583,7 @@ fn generate_binder_with_one_arg(
 
         let mut temp_ctx = GenerationContext::new_with_extended_vars(
             extended_vars,
+            self.var_ctx.clone(),
             self.initial_var_count,
             depth - 1,
             self.max_collection_width
@@ -602,7 +603,7 @@ fn generate_binder_with_one_arg(
             if let Some(args1) = self.#arg_field.get(&d1) {
                 for arg1 in args1 {
                     for body in &bodies_with_binder {
-                        let binder_var = mettail_runtime::get_or_create_var(&binder_name);
+                        let binder_var = self.var_ctx.var(&binder_name);
                         let binder = mettail_runtime::Binder(binder_var);
                         // Scope::new will close free binder_var in body to bound variable
                         let scope = mettail_runtime::Scope::new(binder, Box::new(body.clone()));
@@ -685,6 +686,7 @@ fn generate_binder_with_multiple_args(
 
             let mut temp_ctx = GenerationContext::new_with_extended_vars(
                 extended_vars,
+                self.var_ctx.clone(),
                 self.initial_var_count,
                 d,
                 self.max_collection_width
@@ -701,7 +703,7 @@ fn generate_binder_with_multiple_args(
             #(#arg_fields)* {
                 #(#arg_loops)* {
                     for body in &bodies_with_binder {
-                        let binder_var = mettail_runtime::get_or_create_var(&binder_name);
+                        let binder_var = self.var_ctx.var(&binder_name);
                         let binder = mettail_runtime::Binder(binder_var);
                         let scope = mettail_runtime::Scope::new(binder, Box::new(body.clone()));
                         terms.push(#cat_name::#label(
@@ -768,6 +770,7 @@ fn generate_pure_collection_case(
     element_cat: &Ident,
     theory: &TheoryDef,
 ) -> TokenStream {
+    let bag_ty = bag_type(theory);
     if !is_exported(element_cat, theory) {
         return quote! {};
     }
@@ -779,14 +782,14 @@ fn generate_pure_collection_case(
         for size in 0..=self.max_collection_width {
             if size == 0 {
                 // Empty collection
-                let bag = mettail_runtime::HashBag::new();
+                let bag = #bag_ty::new();
                 terms.push(#cat_name::#label(bag));
             } else if size == 1 {
                 // Single element bags
                 for d in 0..depth {
                     if let Some(elems) = self.#field_name.get(&d) {
                         for elem in elems {
-                            let mut bag = mettail_runtime::HashBag::new();
+                            let mut bag = #bag_ty::new();
                             bag.insert(elem.clone());
                             terms.push(#cat_name::#label(bag));
                         }
@@ -800,7 +803,7 @@ fn generate_pure_collection_case(
                             if let Some(elems2) = self.#field_name.get(&d2) {
                                 for elem1 in elems1 {
                                     for elem2 in elems2 {
-                                        let mut bag = mettail_runtime::HashBag::new();
+                                        let mut bag = #bag_ty::new();
                                         bag.insert(elem1.clone());
                                         bag.insert(elem2.clone());
                                         terms.push(#cat_name::#label(bag));
@@ -821,7 +824,7 @@ fn generate_pure_collection_case(
                                         for elem1 in elems1 {
                                             for elem2 in elems2 {
                                                 for elem3 in elems3 {
-                                                    let mut bag = mettail_runtime::HashBag::new();
+                                                    let mut bag = #bag_ty::new();
                                                     bag.insert(elem1.clone());
                                                     bag.insert(elem2.clone());
                                                     bag.insert(elem3.clone());
@@ -1,6 +1,10 @@
 //! Term generation for theories
 //!
 //! Provides both exhaustive enumeration and random sampling of terms.
+//!
+//! Both build terms from the theory's grammar rules (and variables) only, so
+//! they never produce the `<X>Hole` variants of the `holes` option: generated
+//! terms are never patterns.
 
 mod exhaustive;
 mod random;
@@ -12,6 +12,7 @@
 )]
 
 use crate::ast::{GrammarItem, GrammarRule, TheoryDef};
+use crate::utils::bag_type;
 use proc_macro2::TokenStream;
 use quote::quote;
 use syn::Ident;
@@ -31,6 +32,7 @@ pub fn generate_random_generation(theory: &TheoryDef) -> TokenStream {
 
 /// Generate random generation methods for a specific category
 fn generate_random_for_category(cat_name: &Ident, theory: &TheoryDef) -> TokenStream {
+    // Holes have no grammar rule, so are never generated
     let rules: Vec<&GrammarRule> = theory
         .terms
         .iter()
@@ -56,7 +58,8 @@ impl #cat_name {
             pub fn generate_random_at_depth(vars: &[String], depth: usize, max_collection_width: usize) -> Self {
                 use rand::Rng;
                 let mut rng = rand::thread_rng();
-                Self::generate_random_at_depth_internal(vars, depth, max_collection_width, &mut rng, 0)
+                let var_ctx = mettail_runtime::VarContext::new();
+                Self::generate_random_at_depth_internal(vars, depth, max_collection_width, &mut rng, &var_ctx, 0)
             }
 
             /// Generate a random term at exactly the given depth with a seed
@@ -76,7 +79,8 @@ pub fn generate_random_at_depth_with_seed(
             ) -> Self {
                 use rand::{SeedableRng, Rng};
                 let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
-                Self::generate_random_at_depth_internal(vars, depth, max_collection_width, &mut rng, 0)
+                let var_ctx = mettail_runtime::VarContext::new();
+                Self::generate_random_at_depth_internal(vars, depth, max_collection_width, &mut rng, &var_ctx, 0)
             }
 
             fn generate_random_at_depth_internal<R: rand::Rng>(
@@ -84,6 +88,7 @@ fn generate_random_at_depth_internal<R: rand::Rng>(
                 depth: usize,
                 max_collection_width: usize,
                 rng: &mut R,
+                var_ctx: &mettail_runtime::VarContext,
                 binding_depth: usize,
             ) -> Self {
                 if depth == 0 {
@@ -144,7 +149,7 @@ fn generate_random_depth_0(
                             #cat_name::#label(
                                 mettail_runtime::OrdVar(
                                     mettail_runtime::Var::Free(
-                                        mettail_runtime::get_or_create_var(&vars[idx])
+                                        var_ctx.var(&vars[idx])
                                     )
                                 )
                             )
@@ -152,7 +157,7 @@ fn generate_random_depth_0(
                             #cat_name::#label(
                                 mettail_runtime::OrdVar(
                                     mettail_runtime::Var::Free(
-                                        mettail_runtime::get_or_create_var("_")
+                                        var_ctx.var("_")
                                     )
                                 )
                             )
@@ -321,7 +326,7 @@ fn generate_random_unary(
     }
 
     quote! {
-        let arg = #arg_cat::generate_random_at_depth_internal(vars, depth - 1, max_collection_width, rng, binding_depth);
+        let arg = #arg_cat::generate_random_at_depth_internal(vars, depth - 1, max_collection_width, rng, var_ctx, binding_depth);
         #cat_name::#label(Box::new(arg))
     }
 }
@@ -355,35 +360,35 @@ fn generate_random_binary(
                 let idx = rng.gen_range(0..vars.len());
                 mettail_runtime::OrdVar(
                     mettail_runtime::Var::Free(
-                        mettail_runtime::get_or_create_var(&vars[idx])
+                        var_ctx.var(&vars[idx])
                     )
                 )
             } else {
                 mettail_runtime::OrdVar(
                     mettail_runtime::Var::Free(
-                        mettail_runtime::get_or_create_var("_")
+                        var_ctx.var("_")
                     )
                 )
             };
             // Var is depth 0, so second arg can be depth - 1
-            let arg2 = Box::new(#arg2_cat::generate_random_at_depth_internal(vars, depth - 1, max_collection_width, rng, binding_depth));
+            let arg2 = Box::new(#arg2_cat::generate_random_at_depth_internal(vars, depth - 1, max_collection_width, rng, var_ctx, binding_depth));
             #cat_name::#label(arg1, arg2)
         }
     } else if is_arg2_var {
         // Second arg is Var, first is recursive
         quote! {
-            let arg1 = Box::new(#arg1_cat::generate_random_at_depth_internal(vars, depth - 1, max_collection_width, rng, binding_depth));
+            let arg1 = Box::new(#arg1_cat::generate_random_at_depth_internal(vars, depth - 1, max_collection_width, rng, var_ctx, binding_depth));
             let arg2 = if !vars.is_empty() {
                 let idx = rng.gen_range(0..vars.len());
                 mettail_runtime::OrdVar(
                     mettail_runtime::Var::Free(
-                        mettail_runtime::get_or_create_var(&vars[idx])
+                        var_ctx.var(&vars[idx])
                     )
                 )
             } else {
                 mettail_runtime::OrdVar(
                     mettail_runtime::Var::Free(
-                        mettail_runtime::get_or_create_var("_")
+                        var_ctx.var("_")
                     )
                 )
             };
@@ -399,8 +404,8 @@ fn generate_random_binary(
                 depth - 1
             };
 
-            let arg1 = #arg1_cat::generate_random_at_depth_internal(vars, d1, max_collection_width, rng, binding_depth);
-            let arg2 = #arg2_cat::generate_random_at_depth_internal(vars, d2, max_collection_width, rng, binding_depth);
+            let arg1 = #arg1_cat::generate_random_at_depth_internal(vars, d1, max_collection_width, rng, var_ctx, binding_depth);
+            let arg2 = #arg2_cat::generate_random_at_depth_internal(vars, d2, max_collection_width, rng, var_ctx, binding_depth);
             #cat_name::#label(Box::new(arg1), Box::new(arg2))
         }
     }
@@ -418,7 +423,7 @@ fn generate_random_nary(
             return quote! { panic!("Non-exported category") };
         }
         quote! {
-            Box::new(#cat::generate_random_at_depth_internal(vars, depth - 1, max_collection_width, rng, binding_depth))
+            Box::new(#cat::generate_random_at_depth_internal(vars, depth - 1, max_collection_width, rng, var_ctx, binding_depth))
         }
     }).collect();
 
@@ -493,10 +498,11 @@ fn generate_random_simple_binder(
             depth - 1,
             max_collection_width,
             rng,
+            var_ctx,
             binding_depth + 1
         );
 
-        let binder_var = mettail_runtime::get_or_create_var(&binder_name);
+        let binder_var = var_ctx.var(&binder_name);
         let binder = mettail_runtime::Binder(binder_var);
         let scope = mettail_runtime::Scope::new(binder, Box::new(body));
 
@@ -523,7 +529,7 @@ fn generate_random_binder_with_one_arg(
             depth - 1
         };
 
-        let arg1 = #arg_cat::generate_random_at_depth_internal(vars, d1, max_collection_width, rng, binding_depth);
+        let arg1 = #arg_cat::generate_random_at_depth_internal(vars, d1, max_collection_width, rng, var_ctx, binding_depth);
 
         let binder_name = format!("x{}", binding_depth);
         let mut extended_vars = vars.to_vec();
@@ -533,10 +539,11 @@ fn generate_random_binder_with_one_arg(
             d2,
             max_collection_width,
             rng,
+            var_ctx,
             binding_depth + 1
         );
 
-        let binder_var = mettail_runtime::get_or_create_var(&binder_name);
+        let binder_var = var_ctx.var(&binder_name);
         let binder = mettail_runtime::Binder(binder_var);
         let scope = mettail_runtime::Scope::new(binder, Box::new(body));
 
@@ -560,7 +567,7 @@ fn generate_random_binder_with_multiple_args(
             return quote! { panic!("Non-exported category") };
         }
         quote! {
-            Box::new(#cat::generate_random_at_depth_internal(vars, depth - 1, max_collection_width, rng, binding_depth))
+            Box::new(#cat::generate_random_at_depth_internal(vars, depth - 1, max_collection_width, rng, var_ctx, binding_depth))
         }
     }).collect();
 
@@ -573,10 +580,11 @@ fn generate_random_binder_with_multiple_args(
             depth - 1,
             max_collection_width,
             rng,
+            var_ctx,
             binding_depth + 1
         );
 
-        let binder_var = mettail_runtime::get_or_create_var(&binder_name);
+        let binder_var = var_ctx.var(&binder_name);
         let binder = mettail_runtime::Binder(binder_var);
         let scope = mettail_runtime::Scope::new(binder, Box::new(body));
 
@@ -590,6 +598,7 @@ fn generate_random_collection_constructor(
     rule: &GrammarRule,
     theory: &TheoryDef,
 ) -> TokenStream {
+    let bag_ty = bag_type(theory);
     let label = &rule.label;
 
     // Find the collection field
@@ -610,7 +619,7 @@ fn generate_random_collection_constructor(
         {
             // Choose a random collection size (0 to max_collection_width)
             let size = rng.gen_range(0..=max_collection_width);
-            let mut bag = mettail_runtime::HashBag::new();
+            let mut bag = #bag_ty::new();
 
             for _ in 0..size {
                 // Generate element at random depth < current depth
@@ -620,6 +629,7 @@ fn generate_random_collection_constructor(
                     elem_depth,
                     max_collection_width,
                     rng,
+                    var_ctx,
                     binding_depth
                 );
                 bag.insert(elem);
@@ -0,0 +1,560 @@
+//! Traversal generation
+//!
+//! For a theory `T` with exported categories `Proc`, `Name`, ... this emits:
+//!
+//! - `TTermRef<'a>` / `TTerm`: a (borrowed / owned) term of any category
+//! - `TVisitor` / `TVisitorMut`: one `visit_<cat>` method per category plus
+//!   `visit_var`, whose defaults recurse via `walk` / `walk_mut`
+//! - on each category: `walk`, `walk_mut`, `children`, `fold`, `size`, `depth`,
+//!   and the positional API `subterm`, `positions` and `replace_at` over
+//!   `mettail_runtime::TermPath`
+//!
+//! Children are numbered in field order. Collection elements come in sorted
+//! order, once per occurrence, so positions are stable for equal terms.
+//! Bodies of binders are visited as stored, i.e. with bound variables as
+//! de Bruijn indices.
+
+use crate::ast::{CollectionType, GrammarRule, TheoryDef};
+use crate::codegen::{generate_var_label, is_var_rule, variant_fields, Field};
+use crate::utils::{bag_type, has_native_type};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::Ident;
+
+/// Generated names shared by every category's code
+struct Names {
+    term: Ident,
+    term_ref: Ident,
+    visitor: Ident,
+    visitor_mut: Ident,
+}
+
+pub fn generate_traversal(theory: &TheoryDef) -> TokenStream {
+    let names = Names {
+        term: format_ident!("{}Term", theory.name),
+        term_ref: format_ident!("{}TermRef", theory.name),
+        visitor: format_ident!("{}Visitor", theory.name),
+        visitor_mut: format_ident!("{}VisitorMut", theory.name),
+    };
+    let Names { term, term_ref, visitor, visitor_mut } = &names;
+
+    let categories: Vec<&Ident> = theory.exports.iter().map(|e| &e.name).collect();
+    let visit: Vec<Ident> = categories.iter().map(|c| visit_fn(c)).collect();
+    let visit_mut: Vec<Ident> = categories.iter().map(|c| visit_mut_fn(c)).collect();
+    let visit_docs: Vec<String> = categories
+        .iter()
+        .map(|c| format!("Called on every `{}`; the default visits its children", c))
+        .collect();
+
+    let impls = categories
+        .iter()
+        .map(|category| generate_category_traversal(category, theory, &names));
+
+    quote! {
+        /// A borrowed term of any category
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub enum #term_ref<'a> {
+            #(#categories(&'a #categories)),*
+        }
+
+        /// A term of any category
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub enum #term {
+            #(#categories(#categories)),*
+        }
+
+        #(
+            impl From<#categories> for #term {
+                fn from(term: #categories) -> Self {
+                    #term::#categories(term)
+                }
+            }
+
+            impl<'a> From<&'a #categories> for #term_ref<'a> {
+                fn from(term: &'a #categories) -> Self {
+                    #term_ref::#categories(term)
+                }
+            }
+        )*
+
+        impl<'a> #term_ref<'a> {
+            /// Immediate subterms, in position order
+            pub fn children(self) -> Vec<#term_ref<'a>> {
+                match self {
+                    #(#term_ref::#categories(t) => t.children()),*
+                }
+            }
+
+            /// Fold `f` over this term and all its subterms, in pre-order
+            pub fn fold<A>(self, init: A, f: &mut impl FnMut(A, #term_ref<'a>) -> A) -> A {
+                let mut acc = f(init, self);
+                for child in self.children() {
+                    acc = child.fold(acc, f);
+                }
+                acc
+            }
+
+            /// Number of subterms, including this one
+            pub fn size(self) -> usize {
+                self.fold(0, &mut |n, _| n + 1)
+            }
+
+            /// Length of the longest path to a leaf, counting both ends
+            pub fn depth(self) -> usize {
+                1 + self.children().into_iter().map(|c| c.depth()).max().unwrap_or(0)
+            }
+
+            /// The subterm at `path`, if there is one
+            pub fn subterm(self, path: &mettail_runtime::TermPath) -> Option<#term_ref<'a>> {
+                let mut current = self;
+                for &index in path.indices() {
+                    current = current.children().into_iter().nth(index)?;
+                }
+                Some(current)
+            }
+
+            /// Every subterm with its position, in pre-order
+            pub fn positions(self) -> Vec<(mettail_runtime::TermPath, #term_ref<'a>)> {
+                let mut out = Vec::new();
+                let mut stack = vec![(mettail_runtime::TermPath::root(), self)];
+                while let Some((path, t)) = stack.pop() {
+                    for (i, child) in t.children().into_iter().enumerate().rev() {
+                        stack.push((path.child(i), child));
+                    }
+                    out.push((path, t));
+                }
+                out
+            }
+
+            /// Dispatch to the visitor method for this term's category
+            pub fn accept<V: #visitor + ?Sized>(self, visitor: &mut V) {
+                match self {
+                    #(#term_ref::#categories(t) => visitor.#visit(t)),*
+                }
+            }
+
+            /// An owned copy of this term
+            pub fn cloned(self) -> #term {
+                match self {
+                    #(#term_ref::#categories(t) => #term::#categories(t.clone())),*
+                }
+            }
+        }
+
+        impl #term {
+            /// Borrow this term
+            pub fn as_ref(&self) -> #term_ref<'_> {
+                match self {
+                    #(#term::#categories(t) => #term_ref::#categories(t)),*
+                }
+            }
+        }
+
+        impl std::fmt::Display for #term_ref<'_> {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                match self {
+                    #(#term_ref::#categories(t) => write!(f, "{}", t)),*
+                }
+            }
+        }
+
+        impl std::fmt::Display for #term {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "{}", self.as_ref())
+            }
+        }
+
+        /// Read-only traversal; override the methods for the nodes of interest
+        /// and call `walk` from them to keep descending
+        pub trait #visitor {
+            #(
+                #[doc = #visit_docs]
+                fn #visit(&mut self, term: &#categories) {
+                    term.walk(self)
+                }
+            )*
+
+            /// Called on every variable (free, or bound under a binder)
+            fn visit_var(&mut self, _var: &mettail_runtime::Var<String>) {}
+        }
+
+        /// In-place traversal; override the methods for the nodes of interest
+        /// and call `walk_mut` from them to keep descending. Collections are
+        /// rebuilt from their visited elements (call `normalize` to re-flatten).
+        pub trait #visitor_mut {
+            #(
+                #[doc = #visit_docs]
+                fn #visit_mut(&mut self, term: &mut #categories) {
+                    term.walk_mut(self)
+                }
+            )*
+
+            /// Called on every variable (free, or bound under a binder)
+            fn visit_var_mut(&mut self, _var: &mut mettail_runtime::Var<String>) {}
+        }
+
+        #(#impls)*
+    }
+}
+
+pub(super) fn visit_fn(category: &Ident) -> Ident {
+    format_ident!("visit_{}", category.to_string().to_lowercase())
+}
+
+pub(super) fn visit_mut_fn(category: &Ident) -> Ident {
+    format_ident!("visit_{}_mut", category.to_string().to_lowercase())
+}
+
+fn generate_category_traversal(category: &Ident, theory: &TheoryDef, names: &Names) -> TokenStream {
+    let Names { term, term_ref, visitor, visitor_mut } = names;
+
+    let rules: Vec<&GrammarRule> = theory
+        .terms
+        .iter()
+        .filter(|r| r.category == *category)
+        .collect();
+
+    let mut walk_arms = Vec::new();
+    let mut walk_mut_arms = Vec::new();
+    let mut children_arms = Vec::new();
+    let mut replace_arms = Vec::new();
+
+    for rule in &rules {
+        let label = &rule.label;
+        let fields = variant_fields(rule);
+        if fields.is_empty() {
+            continue;
+        }
+        let bound: Vec<Ident> = (0..fields.len()).map(|i| format_ident!("f{}", i)).collect();
+
+        let walks = fields.iter().zip(&bound).map(|(f, n)| walk_field(f, n));
+        let walk_muts = fields
+            .iter()
+            .zip(&bound)
+            .map(|(f, n)| walk_mut_field(f, n, theory));
+        walk_arms.push(quote! {
+            #category::#label(#(#bound),*) => { #(#walks)* }
+        });
+        walk_mut_arms.push(quote! {
+            #category::#label(#(#bound),*) => { #(#walk_muts)* }
+        });
+
+        // Fields without subterms are not bound, so the arms stay warning-free
+        let term_bound: Vec<TokenStream> = fields
+            .iter()
+            .zip(&bound)
+            .map(|(f, n)| match f {
+                Field::Var | Field::RawVar | Field::Native => quote! { _ },
+                _ => quote! { #n },
+            })
+            .collect();
+        if fields
+            .iter()
+            .all(|f| matches!(f, Field::Var | Field::RawVar | Field::Native))
+        {
+            continue;
+        }
+        let children = fields
+            .iter()
+            .zip(&bound)
+            .map(|(f, n)| children_field(f, n, term_ref));
+        let replaces = fields
+            .iter()
+            .zip(&bound)
+            .map(|(f, n)| replace_field(f, n, rule, theory));
+        children_arms.push(quote! {
+            #category::#label(#(#term_bound),*) => { #(#children)* }
+        });
+        replace_arms.push(quote! {
+            #category::#label(#(#term_bound),*) => { #(#replaces)* }
+        });
+    }
+
+    let has_var_rule = rules.iter().any(|rule| is_var_rule(rule));
+    if !has_var_rule && has_native_type(category, theory).is_none() {
+        let var_label = generate_var_label(category);
+        walk_arms.push(quote! { #category::#var_label(v) => visitor.visit_var(&v.0) });
+        walk_mut_arms.push(quote! { #category::#var_label(v) => visitor.visit_var_mut(&mut v.0) });
+    }
+
+    quote! {
+        impl #category {
+            /// Visit the immediate subterms and variables of this term
+            #[allow(unreachable_patterns)]
+            pub fn walk<V: #visitor + ?Sized>(&self, visitor: &mut V) {
+                match self {
+                    #(#walk_arms,)*
+                    _ => {},
+                }
+            }
+
+            /// Visit the immediate subterms and variables of this term in place
+            #[allow(unreachable_patterns)]
+            pub fn walk_mut<V: #visitor_mut + ?Sized>(&mut self, visitor: &mut V) {
+                match self {
+                    #(#walk_mut_arms,)*
+                    _ => {},
+                }
+            }
+
+            /// Immediate subterms, in position order
+            #[allow(unreachable_patterns)]
+            pub fn children(&self) -> Vec<#term_ref<'_>> {
+                let mut out = Vec::new();
+                match self {
+                    #(#children_arms,)*
+                    _ => {},
+                }
+                out
+            }
+
+            /// Fold `f` over this term and all its subterms, in pre-order
+            pub fn fold<'a, A>(&'a self, init: A, f: &mut impl FnMut(A, #term_ref<'a>) -> A) -> A {
+                #term_ref::#category(self).fold(init, f)
+            }
+
+            /// Number of subterms, including this one
+            pub fn size(&self) -> usize {
+                #term_ref::#category(self).size()
+            }
+
+            /// Length of the longest path to a leaf, counting both ends
+            pub fn depth(&self) -> usize {
+                #term_ref::#category(self).depth()
+            }
+
+            /// The subterm at `path`, if there is one
+            pub fn subterm(&self, path: &mettail_runtime::TermPath) -> Option<#term_ref<'_>> {
+                #term_ref::#category(self).subterm(path)
+            }
+
+            /// Every subterm with its position, in pre-order
+            pub fn positions(&self) -> Vec<(mettail_runtime::TermPath, #term_ref<'_>)> {
+                #term_ref::#category(self).positions()
+            }
+
+            /// This term with the subterm at `path` replaced, or `None` if there is
+            /// no such subterm or it has a different category
+            pub fn replace_at(
+                &self,
+                path: &mettail_runtime::TermPath,
+                replacement: impl Into<#term>,
+            ) -> Option<Self> {
+                self.replace_at_path(path.indices(), replacement.into())
+            }
+
+            #[allow(unreachable_patterns, unused_assignments, unused_mut, unused_variables)]
+            fn replace_at_path(&self, path: &[usize], replacement: #term) -> Option<Self> {
+                let Some((&first, rest)) = path.split_first() else {
+                    return match replacement {
+                        #term::#category(t) => Some(t),
+                        _ => None,
+                    };
+                };
+                let mut index = first;
+                let mut term = self.clone();
+                match &mut term {
+                    #(#replace_arms,)*
+                    _ => {},
+                }
+                None
+            }
+        }
+    }
+}
+
+fn walk_field(field: &Field, name: &Ident) -> TokenStream {
+    match field {
+        Field::Var => quote! { visitor.visit_var(&#name.0); },
+        Field::RawVar => quote! { visitor.visit_var(#name); },
+        Field::Native => quote! { let _ = #name; },
+        Field::Term(cat) => {
+            let visit = visit_fn(cat);
+            quote! { visitor.#visit(#name); }
+        },
+        Field::Scope(cat) => {
+            let visit = visit_fn(cat);
+            quote! { visitor.#visit(#name.unsafe_body()); }
+        },
+        Field::Collection(CollectionType::HashBag, elem) => {
+            let visit = visit_fn(elem);
+            quote! {
+                let mut elems: Vec<_> = #name.iter().collect();
+                elems.sort();
+                for (elem, count) in elems {
+                    for _ in 0..count {
+                        visitor.#visit(elem);
+                    }
+                }
+            }
+        },
+        Field::Collection(CollectionType::HashSet, elem) => {
+            let visit = visit_fn(elem);
+            quote! {
+                let mut elems: Vec<_> = #name.iter().collect();
+                elems.sort();
+                for elem in elems {
+                    visitor.#visit(elem);
+                }
+            }
+        },
+        Field::Collection(CollectionType::Vec, elem) => {
+            let visit = visit_fn(elem);
+            quote! {
+                for elem in #name.iter() {
+                    visitor.#visit(elem);
+                }
+            }
+        },
+    }
+}
+
+fn walk_mut_field(field: &Field, name: &Ident, theory: &TheoryDef) -> TokenStream {
+    match field {
+        Field::Var => quote! { visitor.visit_var_mut(&mut #name.0); },
+        Field::RawVar => quote! { visitor.visit_var_mut(#name); },
+        Field::Native => quote! { let _ = #name; },
+        Field::Term(cat) => {
+            let visit = visit_mut_fn(cat);
+            quote! { visitor.#visit(#name); }
+        },
+        Field::Scope(cat) => {
+            let visit = visit_mut_fn(cat);
+            quote! { visitor.#visit(#name.unsafe_body_mut()); }
+        },
+        Field::Collection(CollectionType::HashBag, elem) => {
+            let visit = visit_mut_fn(elem);
+            let bag_ty = bag_type(theory);
+            quote! {
+                let mut elems: Vec<(#elem, usize)> =
+                    #name.iter().map(|(e, count)| (e.clone(), count)).collect();
+                elems.sort();
+                let mut bag = #bag_ty::new();
+                for (mut elem, count) in elems {
+                    visitor.#visit(&mut elem);
+                    for _ in 0..count {
+                        bag.insert(elem.clone());
+                    }
+                }
+                *#name = bag;
+            }
+        },
+        Field::Collection(CollectionType::HashSet, elem) => {
+            let visit = visit_mut_fn(elem);
+            quote! {
+                let mut elems: Vec<#elem> = #name.drain().collect();
+                elems.sort();
+                for elem in elems.iter_mut() {
+                    visitor.#visit(elem);
+                }
+                *#name = elems.into_iter().collect();
+            }
+        },
+        Field::Collection(CollectionType::Vec, elem) => {
+            let visit = visit_mut_fn(elem);
+            quote! {
+                for elem in #name.iter_mut() {
+                    visitor.#visit(elem);
+                }
+            }
+        },
+    }
+}
+
+fn children_field(field: &Field, name: &Ident, term_ref: &Ident) -> TokenStream {
+    match field {
+        Field::Var | Field::RawVar | Field::Native => quote! {},
+        Field::Term(cat) => quote! { out.push(#term_ref::#cat(&**#name)); },
+        Field::Scope(cat) => quote! { out.push(#term_ref::#cat(&**#name.unsafe_body())); },
+        Field::Collection(CollectionType::HashBag, elem) => quote! {
+            let mut elems: Vec<_> = #name.iter().collect();
+            elems.sort();
+            for (elem, count) in elems {
+                for _ in 0..count {
+                    out.push(#term_ref::#elem(elem));
+                }
+            }
+        },
+        Field::Collection(CollectionType::HashSet, elem) => quote! {
+            let mut elems: Vec<_> = #name.iter().collect();
+            elems.sort();
+            out.extend(elems.into_iter().map(#term_ref::#elem));
+        },
+        Field::Collection(CollectionType::Vec, elem) => quote! {
+            out.extend(#name.iter().map(#term_ref::#elem));
+        },
+    }
+}
+
+/// Replacement code for one field: if `index` falls inside it, rebuild and
+/// return; otherwise skip past its children
+fn replace_field(
+    field: &Field,
+    name: &Ident,
+    rule: &GrammarRule,
+    theory: &TheoryDef,
+) -> TokenStream {
+    match field {
+        Field::Var | Field::RawVar | Field::Native => quote! {},
+        Field::Term(_) => quote! {
+            if index == 0 {
+                **#name = #name.replace_at_path(rest, replacement)?;
+                return Some(term);
+            }
+            index -= 1;
+        },
+        Field::Scope(_) => quote! {
+            if index == 0 {
+                let body = #name.unsafe_body().replace_at_path(rest, replacement)?;
+                **#name.unsafe_body_mut() = body;
+                return Some(term);
+            }
+            index -= 1;
+        },
+        Field::Collection(CollectionType::HashBag, elem) => {
+            // Re-insert through the flattening helper, as the parser does
+            let insert = if *elem == rule.category && theory.exports.iter().any(|e| e.name == *elem)
+            {
+                let category = &rule.category;
+                let helper = format_ident!("insert_into_{}", rule.label.to_string().to_lowercase());
+                quote! { #category::#helper(#name, new) }
+            } else {
+                quote! { #name.insert(new) }
+            };
+            quote! {
+                let mut elems: Vec<&#elem> = #name
+                    .iter()
+                    .flat_map(|(e, count)| std::iter::repeat_n(e, count))
+                    .collect();
+                elems.sort();
+                if index < elems.len() {
+                    let old = elems[index].clone();
+                    let new = old.replace_at_path(rest, replacement)?;
+                    #name.remove(&old);
+                    #insert;
+                    return Some(term);
+                }
+                index -= elems.len();
+            }
+        },
+        Field::Collection(CollectionType::HashSet, elem) => quote! {
+            let mut elems: Vec<&#elem> = #name.iter().collect();
+            elems.sort();
+            if index < elems.len() {
+                let old = elems[index].clone();
+                let new = old.replace_at_path(rest, replacement)?;
+                #name.remove(&old);
+                #name.insert(new);
+                return Some(term);
+            }
+            index -= elems.len();
+        },
+        Field::Collection(CollectionType::Vec, _) => quote! {
+            if index < #name.len() {
+                #name[index] = #name[index].replace_at_path(rest, replacement)?;
+                return Some(term);
+            }
+            index -= #name.len();
+        },
+    }
+}
@@ -6,10 +6,10 @@
 //! - Rewrite engine (Ascent-based)
 //! - Term generation and manipulation
 
-mod ast;
 mod codegen;
 mod utils;
-mod validation;
+
+use mettail_syntax::{ast, validation};
 
 // Ascent generation modules
 mod ascent; // Organized Ascent generation
@@ -19,13 +19,14 @@
 use syn::parse_macro_input;
 
 use ascent::generate_ascent_source;
+use ascent::generate_explore;
 use ascent::generate_freshness_functions;
 use ast::TheoryDef;
 use codegen::blockly::{
     generate_blockly_definitions, write_blockly_blocks, write_blockly_categories,
 };
-use codegen::generate_ast;
 use codegen::parser::{generate_lalrpop_grammar, write_grammar_file};
+use codegen::{generate_ast, generate_repl_theory};
 use validation::validate_theory;
 
 #[proc_macro]
@@ -46,7 +47,13 @@ pub fn theory(input: TokenStream) -> TokenStream {
     let freshness_fns = generate_freshness_functions(&theory_def);
 
     // Generate Ascent datalog source (includes rewrites as Ascent clauses)
-    let ascent_code = generate_ascent_source(&theory_def);
+    let (ascent_code, layout) = generate_ascent_source(&theory_def);
+
+    // Generate `<Theory>::explore` (after the Ascent source it includes)
+    let explore_code = generate_explore(&theory_def, &layout);
+
+    // Generate the REPL's `Theory` impl for the same struct (mettail-runtime's `repl` feature)
+    let repl_code = generate_repl_theory(&theory_def);
 
     // Generate LALRPOP grammar file with precedence handling
     let grammar = generate_lalrpop_grammar(&theory_def);
@@ -67,6 +74,8 @@ pub fn theory(input: TokenStream) -> TokenStream {
         #ast_code
         #freshness_fns
         #ascent_code
+        #explore_code
+        #repl_code
     };
 
     TokenStream::from(combined)
@@ -1,6 +1,22 @@
-use crate::ast::TheoryDef;
+use crate::ast::{BagBackend, TheoryDef};
+use proc_macro2::TokenStream;
 use syn::Ident;
 
+/// Path of the runtime type backing `HashBag(...)` collection fields
+pub fn bag_type_path(theory: &TheoryDef) -> &'static str {
+    match theory.options.bag_backend {
+        BagBackend::HashMap => "mettail_runtime::HashBag",
+        BagBackend::Persistent => "mettail_runtime::PersistentBag",
+    }
+}
+
+/// `bag_type_path` as tokens, for `quote!`
+pub fn bag_type(theory: &TheoryDef) -> TokenStream {
+    bag_type_path(theory)
+        .parse()
+        .expect("bag type paths are valid Rust paths")
+}
+
 /// Check if a category has a native type and return it
 pub fn has_native_type<'a>(category: &Ident, theory: &'a TheoryDef) -> Option<&'a syn::Type> {
     theory
@@ -0,0 +1,183 @@
+//! Non-interactive mode, for scripts and CI
+//!
+//! `mettail --script file.mtl` and `mettail -e '<command>'` run REPL commands
+//! without a prompt. Each command prints one line of JSON describing the
+//! session after it (JSON Lines), and the first failing command stops the
+//! run, so the exit status tells whether the whole script succeeded.
+
+use crate::repl::Repl;
+use crate::state::ReplState;
+use anyhow::Result;
+use serde::Serialize;
+use std::io::Write;
+
+/// The outcome of one command
+#[derive(Debug, Serialize)]
+pub struct CommandRecord {
+    /// Line number in the script (1-based); 0 for `--theory` and `-e` commands
+    pub line: usize,
+    pub command: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub theory: Option<String>,
+    pub current: Option<CurrentTerm>,
+}
+
+/// The current term and what can be reached from it
+#[derive(Debug, Serialize)]
+pub struct CurrentTerm {
+    pub term: String,
+    pub term_id: u64,
+    pub is_normal_form: bool,
+    /// Targets of the rewrites from this term, in `apply` order
+    pub rewrites: Vec<RewriteTarget>,
+    /// Size of the explored rewrite graph
+    pub graph: GraphSummary,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RewriteTarget {
+    pub term: String,
+    pub rule: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GraphSummary {
+    pub terms: usize,
+    pub rewrites: usize,
+    pub normal_forms: Vec<String>,
+}
+
+/// Run `commands` (numbered lines; blank lines and `#` comments are skipped),
+/// writing one JSON record per command to `out`
+///
+/// Returns whether every command succeeded; `quit` ends the run early.
+pub fn run<'a>(
+    repl: &mut Repl,
+    commands: impl IntoIterator<Item = (usize, &'a str)>,
+    out: &mut impl Write,
+) -> Result<bool> {
+    for (line, command) in commands {
+        let command = command.trim();
+        if command.is_empty() || command.starts_with('#') {
+            continue;
+        }
+        if command == "quit" || command == "exit" {
+            break;
+        }
+
+        let result = repl.execute(command);
+        let record = CommandRecord {
+            line,
+            command: command.to_string(),
+            ok: result.is_ok(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+            theory: repl.state().theory_name().map(String::from),
+            current: current_term(repl.state()),
+        };
+        writeln!(out, "{}", serde_json::to_string(&record)?)?;
+        if result.is_err() {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn current_term(state: &ReplState) -> Option<CurrentTerm> {
+    let results = state.ascent_results()?;
+    let id = state.current_graph_id()?;
+    let display = |id: u64| {
+        results
+            .all_terms
+            .iter()
+            .find(|t| t.term_id == id)
+            .map(|t| t.display.clone())
+    };
+
+    Some(CurrentTerm {
+        term: display(id).or_else(|| state.current_term().map(|t| t.to_string()))?,
+        term_id: id,
+        is_normal_form: results.rewrites_from(id).is_empty(),
+        rewrites: results
+            .rewrites_from(id)
+            .into_iter()
+            .map(|r| RewriteTarget {
+                term: display(r.to_id).unwrap_or_default(),
+                rule: r.rule_name.clone(),
+            })
+            .collect(),
+        graph: GraphSummary {
+            terms: results.all_terms.len(),
+            rewrites: results.rewrites.len(),
+            normal_forms: results
+                .normal_forms()
+                .into_iter()
+                .map(|t| t.display.clone())
+                .collect(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::build_registry;
+
+    fn run_script(script: &str) -> (bool, Vec<serde_json::Value>) {
+        let mut repl = Repl::batch(build_registry().unwrap()).unwrap();
+        let mut out = Vec::new();
+        let ok =
+            run(&mut repl, script.lines().enumerate().map(|(i, l)| (i + 1, l)), &mut out).unwrap();
+        let records = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        (ok, records)
+    }
+
+    #[test]
+    fn test_script_records() {
+        let (ok, records) = run_script(
+            "load rhocalc\n# a comment\n\nterm: {a!(0) | for(a->x){*(x)}}\napply 0\napply 0\n",
+        );
+        assert!(ok);
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0]["theory"], "rhocalc");
+        assert!(records[0]["current"].is_null());
+
+        let start = &records[1]["current"];
+        assert_eq!(start["graph"]["terms"], 3);
+        assert_eq!(start["graph"]["normal_forms"][0], "{0}");
+        assert_eq!(start["rewrites"][0]["rule"], "PPar{PInput, POutput}");
+
+        assert_eq!(records[3]["line"], 6);
+        assert_eq!(records[3]["current"]["term"], "{0}");
+        assert_eq!(records[3]["current"]["is_normal_form"], true);
+    }
+
+    #[test]
+    fn test_load_term_from_file() {
+        let path = std::env::temp_dir().join(format!("mettail-term-{}.rho", std::process::id()));
+        std::fs::write(&path, "{\n  @(0)!(0)\n  | for(@(0)->x){\n      *(x)\n    }\n}\n").unwrap();
+        let (ok, records) = run_script(&format!("load rhocalc\nload-term {}\n", path.display()));
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(ok);
+        assert_eq!(records[1]["current"]["graph"]["terms"], 3);
+        assert_eq!(records[1]["current"]["rewrites"][0]["term"], "{*(@(0))}");
+    }
+
+    #[test]
+    fn test_script_stops_at_first_error() {
+        let (ok, records) = run_script("load rhocalc\nterm: {a!(\nterm: 0\n");
+        assert!(!ok);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1]["ok"], false);
+        assert!(records[1]["error"]
+            .as_str()
+            .unwrap()
+            .contains("Parse error"));
+    }
+}
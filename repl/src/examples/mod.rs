@@ -31,6 +31,13 @@ pub fn as_str(&self) -> &'static str {
             TheoryName::Calculator => "calculator",
         }
     }
+
+    /// The theory registered under `name`, if it has examples
+    pub fn from_name(name: &str) -> Option<Self> {
+        [TheoryName::RhoCalculus, TheoryName::AmbientCalculus, TheoryName::Calculator]
+            .into_iter()
+            .find(|theory| theory.as_str() == name)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
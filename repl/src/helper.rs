@@ -0,0 +1,440 @@
+//! Completion and highlighting for the interactive REPL
+//!
+//! Both follow the loaded theory: `term:` input completes the grammar's
+//! keywords and constructor labels, and is colored by token, with terminals,
+//! variables and binders told apart using the theory's [`Syntax`]. When the
+//! cursor is on one of the theory's delimiters, its partner is marked, and
+//! while some are unclosed, Enter continues the term on a new line.
+
+use crate::examples::{Example, TheoryName};
+use crate::theory::{Syntax, Theory};
+use colored::Colorize;
+use rustyline::completion::{Completer, FilenameCompleter, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{Context, Helper, Result as RustyResult};
+use std::borrow::Cow;
+use std::cell::Cell;
+
+/// The commands, as completed at the start of a line
+const COMMANDS: &[&str] = &[
+    "help",
+    "load",
+    "load-file",
+    "list-theories",
+    "info",
+    "term:",
+    "load-term",
+    "example",
+    "list-examples",
+    "rewrites",
+    "equations",
+    "normal-forms",
+    "apply",
+    "goto",
+    "query",
+    "reach?",
+    "path",
+    "stats",
+    "simulate",
+    "back",
+    "forward",
+    "history",
+    "jump",
+    "export",
+    "save",
+    "restore",
+    "quit",
+    "exit",
+];
+
+/// The commands whose argument is a file
+const FILE_COMMANDS: &[&str] = &["load-file", "load-term", "export", "save", "restore"];
+
+/// The rustyline helper of the interactive REPL
+pub struct ReplHelper {
+    theories: Vec<String>,
+    examples: Vec<&'static str>,
+    syntax: Syntax,
+    files: FilenameCompleter,
+    /// Whether to mark the delimiter at the cursor: not once the line is done
+    mark_delimiters: Cell<bool>,
+}
+
+/// What a token of `term:` input is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Terminal,
+    Variable,
+    /// A variable in a binding position, like `x` in `for(a->x)`
+    Binder,
+    /// Anything else, such as a literal
+    Other,
+}
+
+/// A token of `term:` input, as a byte range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub start: usize,
+    pub end: usize,
+    pub kind: TokenKind,
+}
+
+impl ReplHelper {
+    pub fn new() -> Self {
+        Self {
+            theories: Vec::new(),
+            examples: Vec::new(),
+            syntax: Syntax::default(),
+            files: FilenameCompleter::new(),
+            mark_delimiters: Cell::new(false),
+        }
+    }
+
+    /// Complete `load` with these theory names
+    pub fn set_theories(&mut self, mut theories: Vec<String>) {
+        theories.sort();
+        self.theories = theories;
+    }
+
+    /// Complete and highlight for `theory`
+    pub fn set_theory(&mut self, theory: &dyn Theory) {
+        self.syntax = theory.syntax();
+        self.examples = TheoryName::from_name(theory.name())
+            .map(Example::by_theory)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|example| example.name)
+            .collect();
+    }
+
+    /// The candidates for the word ending at `pos`, and where that word starts
+    fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let before = &line[..pos];
+        if let Some(input) = before.strip_prefix("term:") {
+            let start = pos - input.len() + word_start(input);
+            let word = &line[start..pos];
+            let keywords = self
+                .syntax
+                .terminals
+                .iter()
+                .filter(|t| t.starts_with(|c: char| c.is_alphabetic()));
+            let candidates = keywords
+                .chain(&self.syntax.constructors)
+                .filter(|c| c.starts_with(word))
+                .cloned()
+                .collect();
+            return (start, candidates);
+        }
+
+        let start = before.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = &line[start..pos];
+        let names: Vec<&str> = match before.split_whitespace().next() {
+            _ if start == 0 => COMMANDS.to_vec(),
+            Some("load") => self.theories.iter().map(String::as_str).collect(),
+            Some("example") => self.examples.clone(),
+            _ => Vec::new(),
+        };
+        let candidates = names
+            .into_iter()
+            .filter(|name| name.starts_with(word))
+            .map(str::to_string)
+            .collect();
+        (start, candidates)
+    }
+
+    /// The tokens of `input`, the text after `term:`
+    pub fn tokens(&self, input: &str) -> Vec<Token> {
+        let mut tokens: Vec<Token> = Vec::new();
+        let mut pos = 0;
+        while let Some(c) = input[pos..].chars().next() {
+            if c.is_whitespace() {
+                pos += c.len_utf8();
+                continue;
+            }
+            let start = pos;
+            let kind = if let Some(terminal) = self.terminal_at(input, pos) {
+                pos += terminal.len();
+                TokenKind::Terminal
+            } else if is_ident_start(c) {
+                pos += identifier_len(&input[pos..]);
+                if self.follows_binder_prefix(input, &tokens) {
+                    TokenKind::Binder
+                } else {
+                    TokenKind::Variable
+                }
+            } else {
+                pos += c.len_utf8();
+                TokenKind::Other
+            };
+            tokens.push(Token { start, end: pos, kind });
+        }
+        tokens
+    }
+
+    /// The longest terminal at `pos`, not counting keywords that are only the
+    /// start of an identifier
+    fn terminal_at(&self, input: &str, pos: usize) -> Option<&str> {
+        let rest = &input[pos..];
+        self.syntax
+            .terminals
+            .iter()
+            .filter(|t| !t.is_empty() && rest.starts_with(t.as_str()))
+            .filter(|t| !t.ends_with(is_ident_char) || !rest[t.len()..].starts_with(is_ident_char))
+            .max_by_key(|t| t.len())
+            .map(String::as_str)
+    }
+
+    /// Whether the terminals just before the next token end with a binder prefix
+    fn follows_binder_prefix(&self, input: &str, tokens: &[Token]) -> bool {
+        self.syntax.binder_prefixes.iter().any(|prefix| {
+            prefix.len() <= tokens.len()
+                && tokens[tokens.len() - prefix.len()..]
+                    .iter()
+                    .zip(prefix)
+                    .all(|(token, terminal)| {
+                        token.kind == TokenKind::Terminal
+                            && &input[token.start..token.end] == terminal
+                    })
+        })
+    }
+
+    /// The index of the token matching the delimiter token `index`, if any
+    pub fn matching_delimiter(&self, input: &str, tokens: &[Token], index: usize) -> Option<usize> {
+        let delimiters = &self.syntax.delimiters;
+
+        // Openers awaiting their closer, with the closer
+        let mut open: Vec<(usize, &str)> = Vec::new();
+        for (i, token) in tokens.iter().enumerate() {
+            if token.kind != TokenKind::Terminal {
+                continue;
+            }
+            let text = &input[token.start..token.end];
+            if let Some(&(opener, _)) = open.last().filter(|(_, close)| *close == text) {
+                open.pop();
+                if i == index {
+                    return Some(opener);
+                }
+                if opener == index {
+                    return Some(i);
+                }
+            } else if let Some((_, close)) = delimiters.iter().find(|(o, _)| o == text) {
+                open.push((i, close));
+            }
+        }
+        None
+    }
+
+    /// Whether `input` has a delimiter that is not closed yet
+    pub fn is_unclosed(&self, input: &str) -> bool {
+        let tokens = self.tokens(input);
+        let mut open: Vec<&str> = Vec::new();
+        for token in &tokens {
+            if token.kind != TokenKind::Terminal {
+                continue;
+            }
+            let text = &input[token.start..token.end];
+            if open.last() == Some(&text) {
+                open.pop();
+            } else if let Some((_, close)) = self.syntax.delimiters.iter().find(|(o, _)| o == text)
+            {
+                open.push(close);
+            }
+        }
+        !open.is_empty()
+    }
+
+    /// `input` (the text after `term:`, at `offset` in the line) with its
+    /// tokens colored, and the delimiter at `cursor` and its partner marked
+    fn highlight_term(&self, input: &str, offset: usize, cursor: Option<usize>) -> String {
+        let tokens = self.tokens(input);
+        let marked: Vec<usize> = cursor
+            .and_then(|cursor| {
+                let at = |i: &usize| tokens[*i].start + offset == cursor;
+                let after = |i: &usize| tokens[*i].end + offset == cursor;
+                let index = (0..tokens.len())
+                    .find(at)
+                    .or_else(|| (0..tokens.len()).find(after))?;
+                let partner = self.matching_delimiter(input, &tokens, index)?;
+                Some(vec![index, partner])
+            })
+            .unwrap_or_default();
+
+        let mut out = String::with_capacity(input.len() * 2);
+        let mut last = 0;
+        for (i, token) in tokens.iter().enumerate() {
+            out.push_str(&input[last..token.start]);
+            let text = &input[token.start..token.end];
+            let colored = match token.kind {
+                TokenKind::Terminal => text.blue(),
+                TokenKind::Variable => text.cyan(),
+                TokenKind::Binder => text.yellow().bold(),
+                TokenKind::Other => text.normal(),
+            };
+            if marked.contains(&i) {
+                out.push_str(&colored.bold().reversed().to_string());
+            } else {
+                out.push_str(&colored.to_string());
+            }
+            last = token.end;
+        }
+        out.push_str(&input[last..]);
+        out
+    }
+}
+
+impl Default for ReplHelper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Where the identifier ending `text` starts
+fn word_start(text: &str) -> usize {
+    text.char_indices()
+        .rev()
+        .find(|&(_, c)| !is_ident_char(c))
+        .map_or(0, |(i, c)| i + c.len_utf8())
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn identifier_len(text: &str) -> usize {
+    text.find(|c: char| !is_ident_char(c)).unwrap_or(text.len())
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        ctx: &Context<'_>,
+    ) -> RustyResult<(usize, Vec<Pair>)> {
+        let command = line.split_whitespace().next().unwrap_or_default();
+        if FILE_COMMANDS.contains(&command) && line[..pos].contains(char::is_whitespace) {
+            return self.files.complete(line, pos, ctx);
+        }
+
+        let (start, candidates) = self.candidates(line, pos);
+        let pairs = candidates
+            .into_iter()
+            .map(|c| Pair { display: c.clone(), replacement: c })
+            .collect();
+        Ok((start, pairs))
+    }
+}
+
+impl Highlighter for ReplHelper {
+    fn highlight<'l>(&self, line: &'l str, pos: usize) -> Cow<'l, str> {
+        let Some(input) = line.strip_prefix("term:") else {
+            return Cow::Borrowed(line);
+        };
+        let offset = line.len() - input.len();
+        let cursor = self.mark_delimiters.get().then_some(pos);
+        Cow::Owned(format!("term:{}", self.highlight_term(input, offset, cursor)))
+    }
+
+    fn highlight_char(&self, line: &str, _pos: usize, forced: bool) -> bool {
+        // Term input is re-colored as it is typed and the cursor moves; the
+        // final (forced) refresh leaves the delimiters unmarked
+        self.mark_delimiters.set(!forced);
+        line.starts_with("term:") && !forced
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Validator for ReplHelper {
+    fn validate(&self, ctx: &mut ValidationContext) -> RustyResult<ValidationResult> {
+        match ctx.input().strip_prefix("term:") {
+            Some(input) if self.is_unclosed(input) => Ok(ValidationResult::Incomplete),
+            _ => Ok(ValidationResult::Valid(None)),
+        }
+    }
+}
+
+impl Helper for ReplHelper {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theories::RhoCalc;
+
+    fn rhocalc_helper() -> ReplHelper {
+        let mut helper = ReplHelper::new();
+        helper.set_theories(vec!["rhocalc".to_string(), "ambient".to_string()]);
+        helper.set_theory(&RhoCalc);
+        helper
+    }
+
+    fn kinds(helper: &ReplHelper, input: &str) -> Vec<(String, TokenKind)> {
+        helper
+            .tokens(input)
+            .into_iter()
+            .map(|t| (input[t.start..t.end].to_string(), t.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_completion() {
+        let helper = rhocalc_helper();
+        assert_eq!(
+            helper.candidates("re", 2),
+            (0, vec!["rewrites".into(), "reach?".into(), "restore".into()])
+        );
+        assert_eq!(helper.candidates("load rh", 7), (5, vec!["rhocalc".into()]));
+        let (start, examples) = helper.candidates("example ", 8);
+        assert_eq!(start, 8);
+        assert!(!examples.is_empty());
+        assert_eq!(helper.candidates("term: {f", 8), (7, vec!["for".into()]));
+        assert_eq!(helper.candidates("term: PIn", 9), (6, vec!["PInput".into()]));
+    }
+
+    #[test]
+    fn test_term_tokens() {
+        let helper = rhocalc_helper();
+        let tokens = kinds(&helper, "for(a->x){*(x)}");
+        assert_eq!(tokens[0], ("for".to_string(), TokenKind::Terminal));
+        assert_eq!(tokens[2], ("a".to_string(), TokenKind::Variable));
+        assert_eq!(tokens[4], ("x".to_string(), TokenKind::Binder));
+        assert_eq!(tokens[9], ("x".to_string(), TokenKind::Variable));
+        // A keyword is not the start of an identifier
+        assert_eq!(kinds(&helper, "format")[0].1, TokenKind::Variable);
+    }
+
+    #[test]
+    fn test_matching_delimiters() {
+        let helper = rhocalc_helper();
+        let input = "{a!(0) | for(a->x){*(x)}}";
+        let tokens = helper.tokens(input);
+        let at = |offset: usize| tokens.iter().position(|t| t.start == offset).unwrap();
+        // The bag's braces, a constructor's parentheses, and unmatched input
+        assert_eq!(helper.matching_delimiter(input, &tokens, 0), Some(tokens.len() - 1));
+        assert_eq!(helper.matching_delimiter(input, &tokens, at(3)), Some(at(5)));
+        assert_eq!(helper.matching_delimiter(input, &tokens, at(1)), None);
+        let unclosed = "for(a->x){*(x)";
+        let tokens = helper.tokens(unclosed);
+        assert_eq!(helper.matching_delimiter(unclosed, &tokens, 6), None);
+    }
+
+    #[test]
+    fn test_unclosed_input_continues() {
+        let helper = rhocalc_helper();
+        assert!(helper.is_unclosed("{a!(0) |"));
+        assert!(helper.is_unclosed("{a!(0) |\n for(a->x){"));
+        assert!(!helper.is_unclosed("{a!(0) |\n for(a->x){*(x)}}"));
+        // Stray closers are left to the parser to report
+        assert!(!helper.is_unclosed("a!(0))"));
+    }
+}
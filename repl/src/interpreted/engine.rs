@@ -0,0 +1,508 @@
+//! Rewriting with rules loaded at runtime
+//!
+//! Rewrites apply at the root of a term; they reach subterms only through the
+//! theory's congruence rules (`if S => T then ...`), as in the generated
+//! Ascent programs. Equations are oriented left to right and applied anywhere
+//! in a term, to a fixpoint, so each explored term is in normal form with
+//! respect to them. Collection patterns (`{P, ...rest}`) match the elements
+//! in every possible way, and each match is a separate rewrite.
+//!
+//! As in generated code, a collection congruence (`if S => T then
+//! (PPar {S, ...rest}) => ...`) also lifts the rewrites on that constructor
+//! whose collection pattern has no rest, so `(PPar {A, B}) => R` applies to
+//! any `PPar` containing `A` and `B`, keeping the other elements.
+
+use super::grammar::{CollectionKind, Freshness, Grammar, Item, Pattern, Rule, RuleDef};
+use super::term::DynTerm;
+use anyhow::{anyhow, bail, Result};
+use mettail_runtime::{Binder, FreeVar, HashBag, OrdVar, RewriteGraph, Scope, Var};
+use std::collections::{BTreeMap, HashSet};
+
+/// How many terms `explore` visits before giving up
+pub const MAX_TERMS: usize = 10_000;
+
+/// How many times equations are applied to one term before giving up
+const MAX_EQUATION_STEPS: usize = 1_000;
+
+/// What a pattern variable matched
+#[derive(Debug, Clone)]
+enum Bound {
+    Term(DynTerm),
+    /// The variable of an opened binder
+    Binder(FreeVar<String>),
+    /// The unmatched elements of a collection
+    Rest(Vec<DynTerm>),
+}
+
+type Bindings = BTreeMap<String, Bound>;
+
+/// The rest bound when a rewrite is lifted by a collection congruence
+const LIFTED_REST: &str = "...";
+
+pub struct Engine<'a> {
+    grammar: &'a Grammar,
+    /// The theory's rewrites and their lifts into collection congruences
+    rewrites: Vec<RuleDef>,
+}
+
+impl<'a> Engine<'a> {
+    pub fn new(grammar: &'a Grammar) -> Self {
+        let mut rewrites = grammar.rewrites.clone();
+        for congruence in grammar.rewrites.iter().filter(|rw| rw.premise.is_some()) {
+            if let Pattern::Apply(label, args) = &congruence.left {
+                if let [Pattern::Collection { rest: Some(_), .. }] = args.as_slice() {
+                    rewrites.extend(grammar.rewrites.iter().filter_map(|rw| lift(label, rw)));
+                }
+            }
+        }
+        Engine { grammar, rewrites }
+    }
+
+    /// Every term reachable from `term`, with the rewrites between them
+    pub fn explore(&self, term: DynTerm) -> Result<RewriteGraph<DynTerm>> {
+        let root = self.normalize(&term)?;
+        let mut graph = RewriteGraph::new();
+        graph.set_root(root.clone());
+        let mut frontier = vec![root];
+        while let Some(from) = frontier.pop() {
+            for (to, label) in self.step(&from)? {
+                if graph.index_of(&to).is_none() {
+                    if graph.len() >= MAX_TERMS {
+                        bail!("exploration stopped after {} terms", MAX_TERMS);
+                    }
+                    frontier.push(to.clone());
+                }
+                graph.add_edge(from.clone(), to, label);
+            }
+        }
+        Ok(graph)
+    }
+
+    /// The terms `term` rewrites to in one step, normalized by the equations,
+    /// each with the label of the rule that took it. A congruence step carries
+    /// the label of the step taken inside it.
+    pub fn step(&self, term: &DynTerm) -> Result<Vec<(DynTerm, String)>> {
+        let mut results = Vec::new();
+        let mut seen = HashSet::new();
+        for rule in &self.rewrites {
+            for bindings in self.matches(&rule.left, term, Bindings::new())? {
+                if !fresh(rule, &bindings) {
+                    continue;
+                }
+                let premises = match &rule.premise {
+                    None => vec![(bindings, rule.label.clone())],
+                    Some((source, target)) => {
+                        let Some(Bound::Term(redex)) = bindings.get(source) else {
+                            bail!(
+                                "congruence premise {} is not bound by the left-hand side",
+                                source
+                            );
+                        };
+                        let mut premises = Vec::new();
+                        for (reduct, label) in self.step(redex)? {
+                            let mut bindings = bindings.clone();
+                            bindings.insert(target.clone(), Bound::Term(reduct));
+                            premises.push((bindings, label));
+                        }
+                        premises
+                    },
+                };
+                for (bindings, label) in premises {
+                    let result = self.normalize(&self.build(&rule.right, &bindings)?)?;
+                    if seen.insert((result.clone(), label.clone())) {
+                        results.push((result, label));
+                    }
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    /// Apply the equations, left to right, until none applies
+    pub fn normalize(&self, term: &DynTerm) -> Result<DynTerm> {
+        let mut term = self.normalize_children(term)?;
+        for _ in 0..MAX_EQUATION_STEPS {
+            match self.apply_equation(&term)? {
+                Some(next) => term = self.normalize_children(&next)?,
+                None => return Ok(term),
+            }
+        }
+        bail!("equations did not reach a normal form after {} steps", MAX_EQUATION_STEPS)
+    }
+
+    fn normalize_children(&self, term: &DynTerm) -> Result<DynTerm> {
+        Ok(match term {
+            DynTerm::Var(_) => term.clone(),
+            DynTerm::App(rule, fields) => DynTerm::App(
+                *rule,
+                fields
+                    .iter()
+                    .map(|f| self.normalize(f))
+                    .collect::<Result<_>>()?,
+            ),
+            // Open the scope, so equations may move subterms across the binder
+            DynTerm::Abs(scope) => {
+                let (binder, body) = scope.clone().unbind();
+                DynTerm::Abs(Scope::new(binder, Box::new(self.normalize(&body)?)))
+            },
+            DynTerm::Bag(bag) => {
+                let mut normalized = Vec::new();
+                for (e, n) in bag.iter() {
+                    normalized.extend(std::iter::repeat_n(self.normalize(e)?, n));
+                }
+                DynTerm::Bag(normalized.into_iter().collect())
+            },
+            DynTerm::List(items) => DynTerm::List(
+                items
+                    .iter()
+                    .map(|e| self.normalize(e))
+                    .collect::<Result<_>>()?,
+            ),
+        })
+    }
+
+    fn apply_equation(&self, term: &DynTerm) -> Result<Option<DynTerm>> {
+        for eq in &self.grammar.equations {
+            for bindings in self.matches(&eq.left, term, Bindings::new())? {
+                if fresh(eq, &bindings) {
+                    let result = self.build(&eq.right, &bindings)?;
+                    if result != *term {
+                        return Ok(Some(result));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Every way `pattern` matches `term`, extending `bindings`
+    fn matches(
+        &self,
+        pattern: &Pattern,
+        term: &DynTerm,
+        bindings: Bindings,
+    ) -> Result<Vec<Bindings>> {
+        match pattern {
+            Pattern::Var(name) if self.grammar.rule(name).is_some() => {
+                self.matches(&Pattern::Apply(name.clone(), Vec::new()), term, bindings)
+            },
+            Pattern::Var(name) => Ok(match bindings.get(name) {
+                Some(bound) => {
+                    let same = match bound {
+                        Bound::Term(t) => t == term,
+                        Bound::Binder(v) => *term == DynTerm::free(v.clone()),
+                        Bound::Rest(_) => false,
+                    };
+                    if same {
+                        vec![bindings]
+                    } else {
+                        vec![]
+                    }
+                },
+                None => {
+                    let mut bindings = bindings;
+                    bindings.insert(name.clone(), Bound::Term(term.clone()));
+                    vec![bindings]
+                },
+            }),
+            Pattern::Apply(label, args) => {
+                let rule = self.rule(label)?;
+                let DynTerm::App(index, fields) = term else {
+                    return Ok(vec![]);
+                };
+                if *index != rule.index {
+                    return Ok(vec![]);
+                }
+                let mut results = vec![bindings];
+                for (i, arg) in self.arguments(rule, args)? {
+                    if matches!(rule.items[i], Item::Binder(_)) {
+                        continue;
+                    }
+                    let field = rule
+                        .field_index(i)
+                        .and_then(|k| fields.get(k))
+                        .ok_or_else(|| anyhow!("{} has too few fields", label))?;
+                    let mut next = Vec::new();
+                    for bindings in results {
+                        next.extend(self.match_field(rule, i, arg, args, field, bindings)?);
+                    }
+                    results = next;
+                }
+                Ok(results)
+            },
+            Pattern::Collection { .. } => {
+                bail!("a collection pattern must be a constructor argument")
+            },
+            Pattern::Subst(..) => bail!("subst is not allowed in a left-hand side"),
+        }
+    }
+
+    /// Match the argument for item `i` of `rule` against its field
+    fn match_field(
+        &self,
+        rule: &Rule,
+        i: usize,
+        arg: &Pattern,
+        args: &[Pattern],
+        field: &DynTerm,
+        bindings: Bindings,
+    ) -> Result<Vec<Bindings>> {
+        match (field, arg) {
+            (DynTerm::Abs(scope), body) => {
+                let binder_arg = rule
+                    .binding
+                    .and_then(|(binder, _)| self.argument_position(rule, binder))
+                    .and_then(|k| args.get(k));
+                let Some(Pattern::Var(binder_name)) = binder_arg else {
+                    bail!("{}: the binder argument must be a variable", rule.label);
+                };
+                let (Binder(var), body_term) = scope.clone().unbind();
+                let mut bindings = bindings;
+                bindings.insert(binder_name.clone(), Bound::Binder(var));
+                self.matches(body, &body_term, bindings)
+            },
+            (DynTerm::Bag(bag), Pattern::Collection { elements, rest }) => {
+                let terms: Vec<DynTerm> = bag
+                    .iter()
+                    .flat_map(|(e, n)| std::iter::repeat_n(e.clone(), n))
+                    .collect();
+                self.match_elements(elements, rest.as_deref(), &terms, bindings, true)
+            },
+            (DynTerm::List(items), Pattern::Collection { elements, rest }) => {
+                self.match_elements(elements, rest.as_deref(), items, bindings, false)
+            },
+            _ => self.matches(arg, field, bindings),
+        }
+        .map_err(|e| anyhow!("{} (in {}, item {})", e, rule.label, i))
+    }
+
+    /// Match `patterns` against distinct elements of `terms` (in order unless
+    /// `unordered`), binding the rest
+    fn match_elements(
+        &self,
+        patterns: &[Pattern],
+        rest: Option<&str>,
+        terms: &[DynTerm],
+        bindings: Bindings,
+        unordered: bool,
+    ) -> Result<Vec<Bindings>> {
+        let Some((first, others)) = patterns.split_first() else {
+            return Ok(match rest {
+                Some(rest) => {
+                    let mut bindings = bindings;
+                    bindings.insert(rest.to_string(), Bound::Rest(terms.to_vec()));
+                    vec![bindings]
+                },
+                None if terms.is_empty() => vec![bindings],
+                None => vec![],
+            });
+        };
+        let candidates = if unordered {
+            0..terms.len()
+        } else {
+            0..terms.len().min(1)
+        };
+        let mut results = Vec::new();
+        for k in candidates {
+            for bindings in self.matches(first, &terms[k], bindings.clone())? {
+                let mut remaining = terms.to_vec();
+                remaining.remove(k);
+                results.extend(self.match_elements(others, rest, &remaining, bindings, unordered)?);
+            }
+        }
+        Ok(results)
+    }
+
+    /// Instantiate `pattern` with `bindings`
+    fn build(&self, pattern: &Pattern, bindings: &Bindings) -> Result<DynTerm> {
+        match pattern {
+            Pattern::Var(name) if self.grammar.rule(name).is_some() => {
+                self.build(&Pattern::Apply(name.clone(), Vec::new()), bindings)
+            },
+            Pattern::Var(name) => match bindings.get(name) {
+                Some(Bound::Term(t)) => Ok(t.clone()),
+                Some(Bound::Binder(v)) => Ok(DynTerm::free(v.clone())),
+                Some(Bound::Rest(_)) => bail!("{} is a collection rest, not a term", name),
+                None => bail!("{} is not bound by the left-hand side", name),
+            },
+            Pattern::Apply(label, args) => {
+                let rule = self.rule(label)?;
+                let mut fields = Vec::new();
+                for (i, arg) in self.arguments(rule, args)? {
+                    match &rule.items[i] {
+                        Item::Binder(_) => {},
+                        Item::Collection { kind, .. } => {
+                            fields.push(self.build_collection(rule, *kind, arg, bindings)?)
+                        },
+                        _ if rule.binding.is_some_and(|(_, body)| body == i) => {
+                            let binder = rule
+                                .binding
+                                .and_then(|(binder, _)| self.argument_position(rule, binder))
+                                .and_then(|k| args.get(k));
+                            let var = match binder {
+                                Some(Pattern::Var(x)) => match bindings.get(x) {
+                                    Some(Bound::Binder(v)) => v.clone(),
+                                    Some(Bound::Term(DynTerm::Var(OrdVar(Var::Free(v))))) => {
+                                        v.clone()
+                                    },
+                                    _ => FreeVar::fresh_named(x.clone()),
+                                },
+                                _ => bail!("{}: the binder argument must be a variable", label),
+                            };
+                            let body = self.build(arg, bindings)?;
+                            fields.push(DynTerm::Abs(Scope::new(Binder(var), Box::new(body))));
+                        },
+                        _ => fields.push(self.build(arg, bindings)?),
+                    }
+                }
+                Ok(DynTerm::App(rule.index, fields))
+            },
+            Pattern::Subst(term, var, replacement) => {
+                let var = match bindings.get(var) {
+                    Some(Bound::Binder(v)) => v.clone(),
+                    Some(Bound::Term(DynTerm::Var(OrdVar(Var::Free(v))))) => v.clone(),
+                    _ => bail!("subst: {} is not a variable", var),
+                };
+                Ok(self
+                    .build(term, bindings)?
+                    .subst(&var, &self.build(replacement, bindings)?))
+            },
+            Pattern::Collection { .. } => {
+                bail!("a collection pattern must be a constructor argument")
+            },
+        }
+    }
+
+    /// Build a collection field of `rule`, flattening nested uses of the same
+    /// constructor into it
+    fn build_collection(
+        &self,
+        rule: &Rule,
+        kind: CollectionKind,
+        pattern: &Pattern,
+        bindings: &Bindings,
+    ) -> Result<DynTerm> {
+        let Pattern::Collection { elements, rest } = pattern else {
+            return self.build(pattern, bindings);
+        };
+        let mut items = Vec::new();
+        for element in elements {
+            items.push(self.build(element, bindings)?);
+        }
+        if let Some(rest) = rest {
+            match bindings.get(rest) {
+                Some(Bound::Rest(terms)) => items.extend(terms.iter().cloned()),
+                _ => bail!("{} is not a collection rest", rest),
+            }
+        }
+        Ok(match kind {
+            CollectionKind::Bag => {
+                let mut bag = HashBag::new();
+                for item in items {
+                    flatten_into(&mut bag, rule.index, item);
+                }
+                DynTerm::Bag(bag)
+            },
+            CollectionKind::List => DynTerm::List(items),
+        })
+    }
+
+    fn rule(&self, label: &str) -> Result<&'a Rule> {
+        self.grammar
+            .rule(label)
+            .ok_or_else(|| anyhow!("unknown constructor {}", label))
+    }
+
+    /// The pattern arguments of `rule` paired with their item positions
+    fn arguments<'p>(&self, rule: &Rule, args: &'p [Pattern]) -> Result<Vec<(usize, &'p Pattern)>> {
+        let positions: Vec<usize> = (0..rule.items.len())
+            .filter(|&i| rule.is_argument(i))
+            .collect();
+        if positions.len() != args.len() {
+            bail!("{} takes {} arguments, found {}", rule.label, positions.len(), args.len());
+        }
+        Ok(positions.into_iter().zip(args).collect())
+    }
+
+    /// The index among the arguments of item `i`
+    fn argument_position(&self, rule: &Rule, i: usize) -> Option<usize> {
+        rule.is_argument(i)
+            .then(|| (0..i).filter(|&j| rule.is_argument(j)).count())
+    }
+}
+
+/// `rw` matching any collection of `label` that contains its elements, if
+/// it is a rewrite on `label` whose collection pattern has no rest
+fn lift(label: &str, rw: &RuleDef) -> Option<RuleDef> {
+    let Pattern::Apply(l, args) = &rw.left else {
+        return None;
+    };
+    let [Pattern::Collection { elements, rest: None }] = args.as_slice() else {
+        return None;
+    };
+    if l != label || rw.premise.is_some() {
+        return None;
+    }
+    let rest = Some(LIFTED_REST.to_string());
+    let right = match &rw.right {
+        Pattern::Apply(r, args) if r == label => match args.as_slice() {
+            [Pattern::Collection { elements, rest: None }] => Pattern::Collection {
+                elements: elements.clone(),
+                rest: rest.clone(),
+            },
+            _ => return None,
+        },
+        right => Pattern::Collection {
+            elements: vec![right.clone()],
+            rest: rest.clone(),
+        },
+    };
+    Some(RuleDef {
+        label: rw.label.clone(),
+        left: Pattern::Apply(
+            label.to_string(),
+            vec![Pattern::Collection { elements: elements.clone(), rest }],
+        ),
+        right: Pattern::Apply(label.to_string(), vec![right]),
+        freshness: rw.freshness.clone(),
+        premise: None,
+    })
+}
+
+/// Insert `item` into a collection of rule `rule`, merging in the elements
+/// of an `item` built by the same rule
+fn flatten_into(bag: &mut HashBag<DynTerm>, rule: usize, item: DynTerm) {
+    match item {
+        DynTerm::App(r, fields) if r == rule && matches!(fields.as_slice(), [DynTerm::Bag(_)]) => {
+            if let Some(DynTerm::Bag(inner)) = fields.into_iter().next() {
+                for (e, n) in inner.iter() {
+                    for _ in 0..n {
+                        flatten_into(bag, rule, e.clone());
+                    }
+                }
+            }
+        },
+        item => bag.insert(item),
+    }
+}
+
+/// Whether the freshness conditions of `rule` hold
+fn fresh(rule: &RuleDef, bindings: &Bindings) -> bool {
+    rule.freshness.iter().all(|(var, target)| {
+        let var = match bindings.get(var) {
+            Some(Bound::Binder(v)) => v,
+            Some(Bound::Term(DynTerm::Var(OrdVar(Var::Free(v))))) => v,
+            _ => return true,
+        };
+        match (
+            target,
+            bindings.get(match target {
+                Freshness::Term(t) | Freshness::Rest(t) => t,
+            }),
+        ) {
+            (_, Some(Bound::Term(t))) => !t.occurs(var),
+            (_, Some(Bound::Rest(ts))) => ts.iter().all(|t| !t.occurs(var)),
+            _ => true,
+        }
+    })
+}
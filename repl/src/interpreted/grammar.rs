@@ -0,0 +1,320 @@
+//! Theory definitions as plain data
+//!
+//! `TheoryDef` holds `syn` identifiers, which are neither `Send` nor `Sync`,
+//! so a loaded theory is converted to these types once. Conversion also
+//! rejects the features the interpreter does not support.
+
+use crate::theory::Syntax;
+use anyhow::{bail, Result};
+use mettail_syntax::ast::{
+    CollectionType, Condition, Equation, Expr, FreshnessTarget, GrammarItem, RewriteRule, TheoryDef,
+};
+use std::collections::HashMap;
+
+/// A theory's grammar and rules
+#[derive(Debug)]
+pub struct Grammar {
+    pub name: String,
+    /// Exported categories; the first is the one terms are parsed in
+    pub categories: Vec<String>,
+    pub rules: Vec<Rule>,
+    pub equations: Vec<RuleDef>,
+    pub rewrites: Vec<RuleDef>,
+    by_label: HashMap<String, usize>,
+}
+
+/// A constructor: `Label . Category ::= items ;`
+#[derive(Debug)]
+pub struct Rule {
+    /// Position in `Grammar::rules`
+    pub index: usize,
+    pub label: String,
+    pub category: String,
+    pub items: Vec<Item>,
+    /// The binder item and the item it binds in, if any
+    pub binding: Option<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    Terminal(String),
+    /// A subterm of a category, or `Var` for a variable
+    NonTerminal(String),
+    Binder(String),
+    Collection {
+        kind: CollectionKind,
+        element: String,
+        separator: String,
+        delimiters: Option<(String, String)>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollectionKind {
+    Bag,
+    List,
+}
+
+/// An equation (oriented left to right) or a rewrite
+#[derive(Debug, Clone)]
+pub struct RuleDef {
+    /// The constructors of the left-hand side, as in the compiled
+    /// `rw_<cat>_rule` labels (`PPar{PInput, POutput}`)
+    pub label: String,
+    pub left: Pattern,
+    pub right: Pattern,
+    pub freshness: Vec<(String, Freshness)>,
+    /// Congruence premise `if S => T`
+    pub premise: Option<(String, String)>,
+}
+
+/// What a variable must be fresh in: `x # P` or `x # ...rest`
+#[derive(Debug, Clone)]
+pub enum Freshness {
+    Term(String),
+    Rest(String),
+}
+
+/// A rule side: the `Expr` of a theory definition
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Var(String),
+    Apply(String, Vec<Pattern>),
+    Subst(Box<Pattern>, String, Box<Pattern>),
+    Collection {
+        elements: Vec<Pattern>,
+        rest: Option<String>,
+    },
+}
+
+impl Rule {
+    /// Whether item `i` is stored as a field of the constructor; a binder
+    /// and its body are stored together, at the body's position
+    pub fn is_field(&self, i: usize) -> bool {
+        match &self.items[i] {
+            Item::Terminal(_) | Item::Binder(_) => false,
+            Item::NonTerminal(_) | Item::Collection { .. } => true,
+        }
+    }
+
+    /// The position among the fields of item `i`, if it is stored as one
+    pub fn field_index(&self, i: usize) -> Option<usize> {
+        self.is_field(i)
+            .then(|| (0..i).filter(|&j| self.is_field(j)).count())
+    }
+
+    /// Whether this is a variable rule (`PVar . Proc ::= Var`), whose terms
+    /// are plain variables
+    pub fn is_var(&self) -> bool {
+        self.items == [Item::NonTerminal("Var".to_string())]
+    }
+
+    /// Whether item `i` is an argument in rule patterns (binders included)
+    pub fn is_argument(&self, i: usize) -> bool {
+        !matches!(self.items[i], Item::Terminal(_))
+    }
+}
+
+impl Grammar {
+    pub fn from_def(def: &TheoryDef) -> Result<Self> {
+        if def.exports.iter().any(|e| e.native_type.is_some()) {
+            bail!("native categories (`![type] as Cat`) are not supported by the interpreter");
+        }
+        if !def.semantics.is_empty() {
+            bail!("`semantics` blocks are not supported by the interpreter");
+        }
+
+        let mut rules = Vec::new();
+        for rule in &def.terms {
+            if rule.bindings.len() > 1 || rule.bindings.iter().any(|(_, body)| body.len() != 1) {
+                bail!("{}: only one binder per constructor is supported", rule.label);
+            }
+            let items = rule
+                .items
+                .iter()
+                .map(|item| {
+                    Ok(match item {
+                        GrammarItem::Terminal(t) => Item::Terminal(t.clone()),
+                        GrammarItem::NonTerminal(cat) => Item::NonTerminal(cat.to_string()),
+                        GrammarItem::Binder { category } => Item::Binder(category.to_string()),
+                        GrammarItem::Collection {
+                            coll_type,
+                            element_type,
+                            separator,
+                            delimiters,
+                        } => Item::Collection {
+                            kind: match coll_type {
+                                CollectionType::HashBag => CollectionKind::Bag,
+                                CollectionType::Vec => CollectionKind::List,
+                                CollectionType::HashSet => bail!(
+                                    "{}: HashSet collections are not supported by the interpreter",
+                                    rule.label
+                                ),
+                            },
+                            element: element_type.to_string(),
+                            separator: separator.clone(),
+                            delimiters: delimiters.clone(),
+                        },
+                    })
+                })
+                .collect::<Result<_>>()?;
+            rules.push(Rule {
+                index: rules.len(),
+                label: rule.label.to_string(),
+                category: rule.category.to_string(),
+                items,
+                binding: rule
+                    .bindings
+                    .first()
+                    .map(|(binder, body)| (*binder, body[0])),
+            });
+        }
+
+        let by_label = rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| (rule.label.clone(), i))
+            .collect();
+
+        Ok(Grammar {
+            name: def.name.to_string(),
+            categories: def.exports.iter().map(|e| e.name.to_string()).collect(),
+            rules,
+            equations: def.equations.iter().map(equation).collect(),
+            rewrites: def.rewrites.iter().map(rewrite).collect::<Result<_>>()?,
+            by_label,
+        })
+    }
+
+    pub fn rule(&self, label: &str) -> Option<&Rule> {
+        self.by_label.get(label).map(|&i| &self.rules[i])
+    }
+
+    pub fn primary(&self) -> &str {
+        &self.categories[0]
+    }
+
+    /// The tokens of the rules, for the REPL's completion and highlighting
+    pub fn syntax(&self) -> Syntax {
+        let mut terminals = Vec::new();
+        let mut binder_prefixes = Vec::new();
+        let mut delimiters = Vec::new();
+        for rule in &self.rules {
+            for item in &rule.items {
+                match item {
+                    Item::Terminal(terminal) => terminals.push(terminal.clone()),
+                    Item::Collection { separator, delimiters: delims, .. } => {
+                        terminals.push(separator.clone());
+                        if let Some((open, close)) = delims {
+                            terminals.extend([open.clone(), close.clone()]);
+                            delimiters.push((open.clone(), close.clone()));
+                        }
+                    },
+                    Item::NonTerminal(_) | Item::Binder(_) => {},
+                }
+            }
+            if let Some((binder, _)) = rule.binding {
+                // The terminals between the previous argument and the binder
+                let prefix: Vec<String> = rule.items[..binder]
+                    .iter()
+                    .rev()
+                    .map_while(|item| match item {
+                        Item::Terminal(terminal) => Some(terminal.clone()),
+                        _ => None,
+                    })
+                    .collect();
+                if !prefix.is_empty() {
+                    binder_prefixes.push(prefix.into_iter().rev().collect());
+                }
+            }
+        }
+        let constructors = self.rules.iter().map(|rule| rule.label.clone());
+        Syntax::new(terminals, constructors, binder_prefixes, delimiters)
+    }
+}
+
+fn equation(eq: &Equation) -> RuleDef {
+    RuleDef {
+        label: label(&eq.left),
+        left: pattern(&eq.left),
+        right: pattern(&eq.right),
+        freshness: eq
+            .conditions
+            .iter()
+            .map(|c| (c.var.to_string(), freshness(&c.term)))
+            .collect(),
+        premise: None,
+    }
+}
+
+fn rewrite(rw: &RewriteRule) -> Result<RuleDef> {
+    if !rw.env_actions.is_empty() {
+        bail!("rewrites that create environment facts are not supported by the interpreter");
+    }
+    let freshness = rw
+        .conditions
+        .iter()
+        .map(|c| match c {
+            Condition::Freshness(c) => Ok((c.var.to_string(), freshness(&c.term))),
+            Condition::EnvQuery { relation, .. } => {
+                bail!("environment conditions ({}) are not supported by the interpreter", relation)
+            },
+        })
+        .collect::<Result<_>>()?;
+    Ok(RuleDef {
+        label: label(&rw.left),
+        left: pattern(&rw.left),
+        right: pattern(&rw.right),
+        freshness,
+        premise: rw
+            .premise
+            .as_ref()
+            .map(|(s, t)| (s.to_string(), t.to_string())),
+    })
+}
+
+fn freshness(target: &FreshnessTarget) -> Freshness {
+    match target {
+        FreshnessTarget::Var(v) => Freshness::Term(v.to_string()),
+        FreshnessTarget::CollectionRest(r) => Freshness::Rest(r.to_string()),
+    }
+}
+
+fn pattern(expr: &Expr) -> Pattern {
+    match expr {
+        Expr::Var(v) => Pattern::Var(v.to_string()),
+        Expr::Apply { constructor, args } => {
+            Pattern::Apply(constructor.to_string(), args.iter().map(pattern).collect())
+        },
+        Expr::Subst { term, var, replacement } => {
+            Pattern::Subst(Box::new(pattern(term)), var.to_string(), Box::new(pattern(replacement)))
+        },
+        Expr::CollectionPattern { elements, rest, .. } => Pattern::Collection {
+            elements: elements.iter().map(pattern).collect(),
+            rest: rest.as_ref().map(|r| r.to_string()),
+        },
+    }
+}
+
+/// The constructor skeleton of `left`, without its variables; generated
+/// code labels rewrites the same way
+fn label(left: &Expr) -> String {
+    fn skeleton(expr: &Expr) -> Option<String> {
+        match expr {
+            Expr::Apply { constructor, args } => {
+                let parts: Vec<String> = args.iter().filter_map(skeleton).collect();
+                Some(match args.as_slice() {
+                    _ if parts.is_empty() => constructor.to_string(),
+                    [Expr::CollectionPattern { .. }] => format!("{}{}", constructor, parts[0]),
+                    _ => format!("{}({})", constructor, parts.join(", ")),
+                })
+            },
+            Expr::CollectionPattern { elements, .. } => {
+                let parts: Vec<String> = elements.iter().filter_map(skeleton).collect();
+                Some(format!("{{{}}}", parts.join(", ")))
+            },
+            Expr::Var(_) | Expr::Subst { .. } => None,
+        }
+    }
+    skeleton(left).unwrap_or_else(|| "rewrite".to_string())
+}
@@ -0,0 +1,336 @@
+//! Theories loaded at runtime from `.mettail` files
+//!
+//! A `.mettail` file holds a theory definition in the syntax of `theory!`
+//! (with or without the `theory! { ... }` around it). It is parsed with
+//! `mettail-syntax`, validated like a compiled theory, and run by a small
+//! rewrite engine instead of generated code, so no recompilation is needed.
+//!
+//! The interpreter supports constructors with at most one binder, `HashBag`
+//! and `Vec` collections, equations, rewrites with freshness conditions, and
+//! congruences. Native types, `semantics` and environments are rejected when
+//! the theory is loaded.
+
+mod engine;
+mod grammar;
+mod parser;
+mod term;
+
+pub use engine::MAX_TERMS;
+pub use grammar::Grammar;
+pub use term::DynTerm;
+
+use crate::theory::{term_id, AscentResults, Syntax, Term, Theory};
+use anyhow::{anyhow, Context, Result};
+use engine::Engine;
+use mettail_runtime::{RewriteGraph, VarContext};
+use mettail_syntax::validation::validate_theory;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::Arc;
+
+/// A theory interpreted from its definition
+pub struct InterpretedTheory {
+    name: String,
+    grammar: Arc<Grammar>,
+    constructor_count: usize,
+}
+
+impl InterpretedTheory {
+    /// Load a theory definition from a file
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read {}", path.display()))?;
+        Self::from_source(&source).map_err(|e| anyhow!("{}: {}", path.display(), e))
+    }
+
+    /// Load a theory definition from source text
+    pub fn from_source(source: &str) -> Result<Self> {
+        let def = mettail_syntax::parse_theory(source).map_err(|e| {
+            let (line, column) = mettail_syntax::line_column(e.span());
+            anyhow!("{}:{}: {}", line, column + 1, e)
+        })?;
+        validate_theory(&def).map_err(|e| {
+            let (line, column) = mettail_syntax::line_column(e.span());
+            anyhow!("{}:{}: {}", line, column + 1, e.message())
+        })?;
+        let grammar = Grammar::from_def(&def)?;
+
+        // Categories without an explicit variable rule get one, as in generated ASTs
+        let constructor_count = grammar.rules.len()
+            + grammar
+                .categories
+                .iter()
+                .filter(|cat| {
+                    !grammar
+                        .rules
+                        .iter()
+                        .any(|r| &r.category == *cat && r.is_var())
+                })
+                .count();
+
+        Ok(InterpretedTheory {
+            name: grammar.name.to_lowercase(),
+            grammar: Arc::new(grammar),
+            constructor_count,
+        })
+    }
+
+    pub fn grammar(&self) -> &Grammar {
+        &self.grammar
+    }
+}
+
+impl Theory for InterpretedTheory {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn categories(&self) -> Vec<String> {
+        self.grammar.categories.clone()
+    }
+
+    fn constructor_count(&self) -> usize {
+        self.constructor_count
+    }
+
+    fn equation_count(&self) -> usize {
+        self.grammar.equations.len()
+    }
+
+    fn rewrite_count(&self) -> usize {
+        self.grammar.rewrites.len()
+    }
+
+    fn parse_term(&self, input: &str) -> Result<Box<dyn Term>> {
+        let vars = VarContext::new();
+        let term = parser::parse(&self.grammar, &vars, input.trim())?;
+        Ok(Box::new(InterpretedTerm { term, grammar: self.grammar.clone() }))
+    }
+
+    fn run_ascent(&self, term: Box<dyn Term>) -> Result<AscentResults> {
+        let term = term
+            .as_any()
+            .downcast_ref::<InterpretedTerm>()
+            .ok_or_else(|| anyhow!("Expected a term of {}", self.grammar.name))?;
+        let explored = Engine::new(&self.grammar).explore(term.term.clone())?;
+
+        // The same graph over terms that display themselves
+        let wrap = |idx: usize| InterpretedTerm {
+            term: explored.node(idx).clone(),
+            grammar: self.grammar.clone(),
+        };
+        let mut graph = RewriteGraph::new();
+        for idx in 0..explored.len() {
+            graph.add_node(wrap(idx));
+        }
+        if let Some(root) = explored.root() {
+            graph.set_root(wrap(root));
+        }
+        for edge in explored.edges() {
+            graph.add_edge(wrap(edge.from), wrap(edge.to), edge.label.clone());
+        }
+        Ok(AscentResults::from_rewrite_graph(&graph))
+    }
+
+    fn step(&self, term: &dyn Term) -> Result<Vec<Box<dyn Term>>> {
+        let term = term
+            .as_any()
+            .downcast_ref::<InterpretedTerm>()
+            .ok_or_else(|| anyhow!("Expected a term of {}", self.grammar.name))?;
+        let engine = Engine::new(&self.grammar);
+        let mut seen = HashSet::new();
+        let targets = engine.step(&engine.normalize(&term.term)?)?;
+        Ok(targets
+            .into_iter()
+            .filter(|(term, _)| seen.insert(term.clone()))
+            .map(|(term, _)| {
+                Box::new(InterpretedTerm { term, grammar: self.grammar.clone() }) as Box<dyn Term>
+            })
+            .collect())
+    }
+
+    fn format_term(&self, term: &dyn Term) -> String {
+        term.to_string()
+    }
+
+    fn syntax(&self) -> Syntax {
+        self.grammar.syntax()
+    }
+}
+
+/// A term of an interpreted theory, displayed in the theory's syntax
+#[derive(Clone)]
+pub struct InterpretedTerm {
+    pub term: DynTerm,
+    grammar: Arc<Grammar>,
+}
+
+impl PartialEq for InterpretedTerm {
+    fn eq(&self, other: &Self) -> bool {
+        self.term == other.term
+    }
+}
+
+impl Eq for InterpretedTerm {}
+
+impl Hash for InterpretedTerm {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.term.hash(state)
+    }
+}
+
+impl Term for InterpretedTerm {
+    fn clone_box(&self) -> Box<dyn Term> {
+        Box::new(self.clone())
+    }
+
+    fn term_id(&self) -> u64 {
+        term_id(self)
+    }
+
+    fn term_eq(&self, other: &dyn Term) -> bool {
+        other.as_any().downcast_ref::<InterpretedTerm>() == Some(self)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+impl fmt::Display for InterpretedTerm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.term.display(&self.grammar))
+    }
+}
+
+impl fmt::Debug for InterpretedTerm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.term)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theories::RhoCalc;
+    use std::collections::BTreeSet;
+
+    const RHOCALC: &str = include_str!("../../theories/rhocalc.mettail");
+
+    /// Displayed `(from, to, label)` rewrites
+    type Edges = BTreeSet<(String, String, String)>;
+
+    /// The reachable terms, normal forms and labelled rewrites, displayed by
+    /// `theory` (which sorts bag elements, unlike generated code)
+    fn explore(
+        theory: &InterpretedTheory,
+        other: &dyn Theory,
+        input: &str,
+    ) -> (BTreeSet<String>, BTreeSet<String>, Edges) {
+        let results = other.run_ascent(other.parse_term(input).unwrap()).unwrap();
+        let display =
+            |t: &crate::theory::TermInfo| theory.parse_term(&t.display).unwrap().to_string();
+        let terms = results.all_terms.iter().map(display).collect();
+        let normal_forms = results.normal_forms().into_iter().map(display).collect();
+        let by_id = |id: u64| display(results.all_terms.iter().find(|t| t.term_id == id).unwrap());
+        let rewrites = results
+            .rewrites
+            .iter()
+            .map(|rw| (by_id(rw.from_id), by_id(rw.to_id), rw.rule_name.clone().unwrap()))
+            .collect();
+        (terms, normal_forms, rewrites)
+    }
+
+    #[test]
+    fn test_interpreted_metadata() {
+        let theory = InterpretedTheory::from_source(RHOCALC).unwrap();
+        assert_eq!(theory.name(), "rhocalc");
+        assert_eq!(theory.categories(), RhoCalc.categories());
+        assert_eq!(theory.constructor_count(), RhoCalc.constructor_count());
+        assert_eq!(theory.equation_count(), RhoCalc.equation_count());
+        assert_eq!(theory.rewrite_count(), RhoCalc.rewrite_count());
+        assert_eq!(theory.syntax(), RhoCalc.syntax());
+    }
+
+    #[test]
+    fn test_interpreted_matches_compiled() {
+        let theory = InterpretedTheory::from_source(RHOCALC).unwrap();
+        for input in [
+            "{a!(0) | for(a->x){*(x)}}",
+            "{for(a->x){*(x)} | a!(b!(0)) | c!(0)}",
+            "{a!(0) | a!(c!(0)) | for(a->x){x!(0)}}",
+            "*(@(0))",
+            "{for(a->x){for(x->y){*(y)}} | a!(0) | @(0)!({b!(0)})}",
+        ] {
+            assert_eq!(
+                explore(&theory, &theory, input),
+                explore(&theory, &RhoCalc, input),
+                "{}",
+                input
+            );
+        }
+
+        // A step inside a bag keeps the label of the rule taken inside it
+        let (_, _, rewrites) = explore(&theory, &theory, "{a!(0) | for(a->x){*(x)}}");
+        let labels: BTreeSet<&str> = rewrites.iter().map(|(_, _, l)| l.as_str()).collect();
+        assert_eq!(labels, BTreeSet::from(["PDrop(NQuote)", "PPar{PInput, POutput}"]));
+    }
+
+    #[test]
+    fn test_interpreted_step_matches_compiled() {
+        let theory = InterpretedTheory::from_source(RHOCALC).unwrap();
+        let step = |other: &dyn Theory, input: &str| -> BTreeSet<String> {
+            let term = other.parse_term(input).unwrap();
+            other
+                .step(term.as_ref())
+                .unwrap()
+                .iter()
+                .map(|t| theory.parse_term(&t.to_string()).unwrap().to_string())
+                .collect()
+        };
+        for input in [
+            "{a!(0) | for(a->x){*(x)}}",
+            "{for(a->x){*(x)} | a!(b!(0)) | a!(c!(0))}",
+            "*(@(0))",
+            "0",
+        ] {
+            assert_eq!(step(&theory, input), step(&RhoCalc, input), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_display_round_trip() {
+        let theory = InterpretedTheory::from_source(RHOCALC).unwrap();
+        let term = theory.parse_term("for(a->x){{x!(0) | *(x)}}").unwrap();
+        // Bag elements display in constructor order
+        assert_eq!(term.to_string(), "for(a->x){{*(x) | x!(0)}}");
+        assert!(theory.parse_term("for(a->x){").is_err());
+    }
+
+    #[test]
+    fn test_theory_invocation_accepted() {
+        let source = format!("theory! {{ {} }}", RHOCALC);
+        let theory = InterpretedTheory::from_source(&source).unwrap();
+        assert_eq!(theory.name(), "rhocalc");
+    }
+
+    #[test]
+    fn test_load_errors_have_locations() {
+        let error = InterpretedTheory::from_source(
+            "name: Bad,\nexports { P },\nterms { A . Q ::= \"a\" ; },",
+        )
+        .err()
+        .unwrap()
+        .to_string();
+        assert!(error.starts_with("3:"), "{}", error);
+
+        let error =
+            InterpretedTheory::from_source("name: Native, exports { ![i32] as Int }, terms { }")
+                .err()
+                .unwrap()
+                .to_string();
+        assert!(error.contains("not supported"), "{}", error);
+    }
+}
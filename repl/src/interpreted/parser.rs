@@ -0,0 +1,291 @@
+//! Parsing terms with a grammar loaded at runtime
+//!
+//! A backtracking parser that returns every parse of a category at a
+//! position (memoized per category and position), so the order of rules does
+//! not matter. Rules whose first item is their own category (`P "|" P`) are
+//! parsed as left-associative suffixes of a shorter parse. An identifier that
+//! no rule accepts parses as a variable of the expected category.
+
+use super::grammar::{CollectionKind, Grammar, Item, Rule};
+use super::term::DynTerm;
+use anyhow::{bail, Result};
+use mettail_runtime::{Binder, Scope, VarContext};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+/// Parse `input` as a term of the grammar's primary category
+pub fn parse(grammar: &Grammar, vars: &VarContext, input: &str) -> Result<DynTerm> {
+    let tokens = tokenize(grammar, input)?;
+    let mut parser = Parser {
+        grammar,
+        vars,
+        tokens: &tokens,
+        memo: HashMap::new(),
+        active: HashSet::new(),
+        furthest: 0,
+    };
+    let parses = parser.category(grammar.primary(), 0);
+    if let Some((term, _)) = parses.iter().find(|(_, end)| *end == tokens.len()) {
+        return Ok(term.clone());
+    }
+    match tokens.get(parser.furthest) {
+        Some(token) => bail!("Parse error: unexpected '{}' at offset {}", token.text, token.start),
+        None => bail!("Parse error: unexpected end of input"),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    text: String,
+    start: usize,
+    is_terminal: bool,
+}
+
+/// Split `input` into the grammar's terminals (longest first) and identifiers
+fn tokenize(grammar: &Grammar, input: &str) -> Result<Vec<Token>> {
+    let mut terminals: Vec<&str> = grammar
+        .rules
+        .iter()
+        .flat_map(|rule| rule.items.iter())
+        .flat_map(|item| match item {
+            Item::Terminal(t) => vec![t.as_str()],
+            Item::Collection { separator, delimiters, .. } => {
+                let mut ts = vec![separator.as_str()];
+                if let Some((open, close)) = delimiters {
+                    ts.extend([open.as_str(), close.as_str()]);
+                }
+                ts
+            },
+            _ => vec![],
+        })
+        .collect();
+    terminals.sort_by_key(|t| std::cmp::Reverse(t.len()));
+    terminals.dedup();
+
+    let is_ident_char = |c: char| c.is_alphanumeric() || c == '_';
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < input.len() {
+        let rest = &input[pos..];
+        let Some(c) = rest.chars().next() else { break };
+        if c.is_whitespace() {
+            pos += c.len_utf8();
+            continue;
+        }
+        let ident_len = if c.is_alphabetic() || c == '_' {
+            rest.find(|c: char| !is_ident_char(c)).unwrap_or(rest.len())
+        } else {
+            0
+        };
+        let terminal = terminals
+            .iter()
+            .find(|t| rest.starts_with(**t))
+            .filter(|t| t.len() >= ident_len);
+        let (len, is_terminal) = match terminal {
+            Some(t) => (t.len(), true),
+            None if ident_len > 0 => (ident_len, false),
+            None => bail!("Parse error: unexpected '{}' at offset {}", c, pos),
+        };
+        tokens.push(Token {
+            text: rest[..len].to_string(),
+            start: pos,
+            is_terminal,
+        });
+        pos += len;
+    }
+    Ok(tokens)
+}
+
+type Parses = Rc<Vec<(DynTerm, usize)>>;
+
+struct Parser<'a> {
+    grammar: &'a Grammar,
+    vars: &'a VarContext,
+    tokens: &'a [Token],
+    memo: HashMap<(String, usize), Parses>,
+    /// Categories being parsed, to cut indirect left recursion
+    active: HashSet<(String, usize)>,
+    /// The furthest token any parse reached, for error messages
+    furthest: usize,
+}
+
+impl Parser<'_> {
+    /// Every parse of `category` starting at token `pos`, with where it ends
+    fn category(&mut self, category: &str, pos: usize) -> Parses {
+        let key = (category.to_string(), pos);
+        if let Some(parses) = self.memo.get(&key) {
+            return parses.clone();
+        }
+        if !self.active.insert(key.clone()) {
+            return Rc::new(Vec::new());
+        }
+
+        let grammar = self.grammar;
+        let (suffix_rules, prefix_rules): (Vec<&Rule>, Vec<&Rule>) = grammar
+            .rules
+            .iter()
+            .filter(|rule| rule.category == category && !rule.is_var())
+            .partition(|rule| rule.items.first() == Some(&Item::NonTerminal(category.into())));
+
+        let mut parses = Vec::new();
+        for rule in prefix_rules {
+            parses.extend(self.rule(rule, 0, pos, Vec::new()));
+        }
+        if let Some(name) = self.ident(pos) {
+            parses.push((DynTerm::free(self.vars.var(name)), pos + 1));
+        }
+        // Extend every parse, including the extended ones, by each suffix rule
+        let mut i = 0;
+        while i < parses.len() {
+            let (left, end) = parses[i].clone();
+            for rule in &suffix_rules {
+                parses.extend(self.rule(rule, 1, end, vec![Field::Term(left.clone())]));
+            }
+            i += 1;
+        }
+
+        self.active.remove(&key);
+        let parses = Rc::new(parses);
+        self.memo.insert(key, parses.clone());
+        parses
+    }
+
+    /// Parses of `rule` from item `item` on, given the fields parsed so far
+    fn rule(
+        &mut self,
+        rule: &Rule,
+        item: usize,
+        pos: usize,
+        fields: Vec<Field>,
+    ) -> Vec<(DynTerm, usize)> {
+        self.furthest = self.furthest.max(pos);
+        let Some(next) = rule.items.get(item) else {
+            return vec![(build(rule, self.vars, fields), pos)];
+        };
+
+        let mut continuations: Vec<(Field, usize)> = Vec::new();
+        match next {
+            Item::Terminal(t) => {
+                if self.terminal(pos, t) {
+                    return self.rule(rule, item + 1, pos + 1, fields);
+                }
+            },
+            Item::Binder(_) => {
+                if let Some(name) = self.ident(pos) {
+                    continuations.push((Field::Binder(name.to_string()), pos + 1));
+                }
+            },
+            Item::NonTerminal(cat) if cat == "Var" => {
+                if let Some(name) = self.ident(pos) {
+                    let var = DynTerm::free(self.vars.var(name));
+                    continuations.push((Field::Term(var), pos + 1));
+                }
+            },
+            Item::NonTerminal(cat) => {
+                for (term, end) in self.category(cat, pos).iter() {
+                    continuations.push((Field::Term(term.clone()), *end));
+                }
+            },
+            Item::Collection { kind, element, separator, delimiters } => {
+                for (elements, end) in self.collection(element, separator, delimiters, pos) {
+                    let term = match kind {
+                        CollectionKind::Bag => DynTerm::Bag(elements.into_iter().collect()),
+                        CollectionKind::List => DynTerm::List(elements),
+                    };
+                    continuations.push((Field::Term(term), end));
+                }
+            },
+        }
+
+        let mut parses = Vec::new();
+        for (field, end) in continuations {
+            let mut fields = fields.clone();
+            fields.push(field);
+            parses.extend(self.rule(rule, item + 1, end, fields));
+        }
+        parses
+    }
+
+    /// Parses of `open elem sep elem ... close` (the elements may be empty)
+    fn collection(
+        &mut self,
+        element: &str,
+        separator: &str,
+        delimiters: &Option<(String, String)>,
+        pos: usize,
+    ) -> Vec<(Vec<DynTerm>, usize)> {
+        let start = match delimiters {
+            Some((open, _)) if self.terminal(pos, open) => pos + 1,
+            Some(_) => return Vec::new(),
+            None => pos,
+        };
+        let close = |parser: &Self, end: usize| match delimiters {
+            Some((_, close)) => parser.terminal(end, close).then_some(end + 1),
+            None => Some(end),
+        };
+
+        let mut results = Vec::new();
+        if let Some(end) = close(self, start) {
+            results.push((Vec::new(), end));
+        }
+        let mut partial: Vec<(Vec<DynTerm>, usize)> = vec![(Vec::new(), start)];
+        while let Some((elements, at)) = partial.pop() {
+            let at = if elements.is_empty() {
+                at
+            } else if self.terminal(at, separator) {
+                at + 1
+            } else {
+                continue;
+            };
+            for (term, end) in self.category(element, at).iter() {
+                let mut elements = elements.clone();
+                elements.push(term.clone());
+                if let Some(close) = close(self, *end) {
+                    results.push((elements.clone(), close));
+                }
+                partial.push((elements, *end));
+            }
+        }
+        results
+    }
+
+    fn terminal(&self, pos: usize, text: &str) -> bool {
+        self.tokens
+            .get(pos)
+            .is_some_and(|t| t.is_terminal && t.text == text)
+    }
+
+    fn ident(&self, pos: usize) -> Option<&str> {
+        self.tokens
+            .get(pos)
+            .filter(|t| !t.is_terminal)
+            .map(|t| t.text.as_str())
+    }
+}
+
+/// A parsed argument of a rule: a binder's name, or a subterm
+#[derive(Clone)]
+enum Field {
+    Binder(String),
+    Term(DynTerm),
+}
+
+/// Build the constructor from its arguments, closing the body of a binder
+/// over the bound variable
+fn build(rule: &Rule, vars: &VarContext, arguments: Vec<Field>) -> DynTerm {
+    let mut binder = None;
+    let mut fields = Vec::new();
+    let argument_items = (0..rule.items.len()).filter(|&i| rule.is_argument(i));
+    for (i, argument) in argument_items.zip(arguments) {
+        match argument {
+            // Occurrences of the name in the body were parsed as the context's variable
+            Field::Binder(name) => binder = Some(vars.var(name)),
+            Field::Term(body) if rule.binding.is_some_and(|(_, b)| b == i) => {
+                let var = binder.take().unwrap_or_else(|| vars.var("_"));
+                fields.push(DynTerm::Abs(Scope::new(Binder(var), Box::new(body))));
+            },
+            Field::Term(term) => fields.push(term),
+        }
+    }
+    DynTerm::App(rule.index, fields)
+}
@@ -0,0 +1,172 @@
+//! Terms of an interpreted theory
+//!
+//! One type serves every theory: a constructor is the index of its rule and
+//! its fields in grammar order. As in generated ASTs, a binder and the item it
+//! binds in are stored as a single `Scope` field, at the position of the body,
+//! and terms are ordered by constructor in declaration order (which decides
+//! the order of bag elements when displayed).
+
+// moniker's derive implements `BoundTerm` inside a constant
+#![allow(non_local_definitions)]
+
+use super::grammar::{Grammar, Item};
+use mettail_runtime::{Binder, BoundTerm, FreeVar, HashBag, OrdVar, Scope, Var};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, BoundTerm)]
+pub enum DynTerm {
+    /// A constructor: the index of its rule in the grammar, and its fields
+    App(usize, Vec<DynTerm>),
+    Abs(Scope<Binder<String>, Box<DynTerm>>),
+    Bag(HashBag<DynTerm>),
+    List(Vec<DynTerm>),
+    Var(OrdVar),
+}
+
+impl DynTerm {
+    pub fn free(var: FreeVar<String>) -> Self {
+        DynTerm::Var(OrdVar(Var::Free(var)))
+    }
+
+    /// Replace the free occurrences of `var` by `replacement`
+    pub fn subst(&self, var: &FreeVar<String>, replacement: &DynTerm) -> DynTerm {
+        match self {
+            DynTerm::Var(OrdVar(Var::Free(v))) if v == var => replacement.clone(),
+            DynTerm::Var(_) => self.clone(),
+            DynTerm::App(rule, fields) => {
+                DynTerm::App(*rule, fields.iter().map(|f| f.subst(var, replacement)).collect())
+            },
+            // Bound occurrences in the body are de Bruijn indices, so free
+            // variables of the replacement cannot be captured
+            DynTerm::Abs(scope) => DynTerm::Abs(Scope::from_parts_unsafe(
+                scope.unsafe_pattern().clone(),
+                Box::new(scope.unsafe_body().subst(var, replacement)),
+            )),
+            DynTerm::Bag(bag) => DynTerm::Bag(
+                bag.iter()
+                    .flat_map(|(e, n)| std::iter::repeat_n(e.subst(var, replacement), n))
+                    .collect(),
+            ),
+            DynTerm::List(items) => {
+                DynTerm::List(items.iter().map(|e| e.subst(var, replacement)).collect())
+            },
+        }
+    }
+
+    /// Whether `var` occurs free in this term
+    pub fn occurs(&self, var: &FreeVar<String>) -> bool {
+        match self {
+            DynTerm::Var(OrdVar(v)) => matches!(v, Var::Free(v) if v == var),
+            DynTerm::App(_, fields) | DynTerm::List(fields) => fields.iter().any(|f| f.occurs(var)),
+            DynTerm::Abs(scope) => scope.unsafe_body().occurs(var),
+            DynTerm::Bag(bag) => bag.iter_elements().any(|e| e.occurs(var)),
+        }
+    }
+
+    /// Display this term in the concrete syntax of `grammar`
+    pub fn display<'a>(&'a self, grammar: &'a Grammar) -> Display<'a> {
+        Display { term: self, grammar }
+    }
+}
+
+/// A term with the grammar it is displayed in
+pub struct Display<'a> {
+    term: &'a DynTerm,
+    grammar: &'a Grammar,
+}
+
+impl fmt::Display for Display<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let DynTerm::App(rule, fields) = self.term else {
+            return write_field(self.term, None, self.grammar, f);
+        };
+        let Some(rule) = self.grammar.rules.get(*rule) else {
+            return write!(f, "?");
+        };
+
+        let mut prev_was_field = false;
+        for (i, item) in rule.items.iter().enumerate() {
+            if let Item::Terminal(t) = item {
+                f.write_str(t)?;
+                prev_was_field = false;
+                continue;
+            }
+            if prev_was_field {
+                f.write_str(" ")?;
+            }
+            prev_was_field = true;
+
+            match item {
+                Item::Binder(_) => {
+                    let scope = rule
+                        .binding
+                        .and_then(|(_, body)| fields.get(rule.field_index(body)?));
+                    if let Some(DynTerm::Abs(scope)) = scope {
+                        let binder = &scope.unsafe_pattern().0;
+                        f.write_str(binder.pretty_name.as_deref().unwrap_or("_"))?;
+                    }
+                },
+                _ => {
+                    if let Some(field) = rule.field_index(i).and_then(|k| fields.get(k)) {
+                        write_field(field, Some(item), self.grammar, f)?;
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_field(
+    term: &DynTerm,
+    item: Option<&Item>,
+    grammar: &Grammar,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    match term {
+        DynTerm::Var(v) => f.write_str(&name_of(&v.0)),
+        DynTerm::App(..) => write!(f, "{}", term.display(grammar)),
+        DynTerm::Abs(scope) => {
+            let (_, body) = scope.clone().unbind();
+            write!(f, "{}", body.display(grammar))
+        },
+        DynTerm::Bag(bag) => {
+            // Sorted, so the display does not depend on the bag's hash order
+            let mut elements: Vec<&DynTerm> = bag
+                .iter()
+                .flat_map(|(e, n)| std::iter::repeat_n(e, n))
+                .collect();
+            elements.sort();
+            let elements = elements.into_iter().map(|e| e.display(grammar).to_string());
+            write_collection(elements, item, f)
+        },
+        DynTerm::List(items) => {
+            let elements = items.iter().map(|e| e.display(grammar).to_string());
+            write_collection(elements, item, f)
+        },
+    }
+}
+
+fn write_collection(
+    elements: impl Iterator<Item = String>,
+    item: Option<&Item>,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    let (separator, delimiters) = match item {
+        Some(Item::Collection { separator, delimiters, .. }) => {
+            (separator.as_str(), delimiters.as_ref())
+        },
+        _ => (",", None),
+    };
+    let (open, close) = delimiters.map_or(("", ""), |(o, c)| (o.as_str(), c.as_str()));
+    let elements: Vec<String> = elements.collect();
+    write!(f, "{}{}{}", open, elements.join(&format!(" {} ", separator)), close)
+}
+
+fn name_of(var: &Var<String>) -> String {
+    let name = match var {
+        Var::Free(fv) => fv.pretty_name.as_deref(),
+        Var::Bound(bv) => bv.pretty_name.as_deref(),
+    };
+    name.unwrap_or("_").to_string()
+}
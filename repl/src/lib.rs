@@ -1,8 +1,15 @@
+pub mod batch;
 pub mod examples;
+pub mod helper;
+pub mod interpreted;
 pub mod pretty;
+pub mod query;
 pub mod registry;
 pub mod repl;
+pub mod session;
+pub mod simulate;
 pub mod state;
+pub mod stats;
 pub mod theories;
 pub mod theory;
 
@@ -10,9 +17,10 @@
 pub use ascent_byods_rels::eqrel;
 
 pub use examples::Example;
+pub use interpreted::InterpretedTheory;
 pub use pretty::format_term_pretty;
 pub use registry::{build_registry, TheoryRegistry};
 pub use repl::Repl;
 pub use state::{HistoryEntry, ReplState};
-pub use theories::{AmbCalculusTheory, RhoCalculusTheory};
+pub use theories::{Ambient, CalculatorTheory, RhoCalc};
 pub use theory::{AscentResults, EquivClass, Rewrite, Term, TermInfo, Theory};
@@ -1,6 +1,8 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
-use mettail_repl::{build_registry, Repl};
+use mettail_repl::{batch, build_registry, Repl};
+use std::io::Read;
+use std::path::PathBuf;
 
 /// MeTTaIL Term Explorer - Interactive REPL for exploring rewrite systems
 #[derive(Parser, Debug)]
@@ -10,6 +12,24 @@ struct Args {
     /// Theory to load on startup
     #[arg(value_name = "THEORY")]
     theory: Option<String>,
+
+    /// Theory to load on startup (same as THEORY)
+    #[arg(
+        short,
+        long = "theory",
+        value_name = "THEORY",
+        conflicts_with = "theory"
+    )]
+    theory_option: Option<String>,
+
+    /// Run the REPL commands in FILE (`-` for stdin) and exit, printing one
+    /// JSON record per command
+    #[arg(short, long, value_name = "FILE")]
+    script: Option<PathBuf>,
+
+    /// Run COMMAND (after any script) and exit; may be repeated
+    #[arg(short = 'e', long = "execute", value_name = "COMMAND")]
+    execute: Vec<String>,
 }
 
 fn main() -> Result<()> {
@@ -22,13 +42,42 @@ fn main() -> Result<()> {
         mettail_repl::TheoryRegistry::new()
     });
 
+    let theory = args.theory.or(args.theory_option);
+    let load = theory.map(|name| format!("load {}", name));
+
+    if args.script.is_some() || !args.execute.is_empty() {
+        let script = match &args.script {
+            Some(path) if path.as_os_str() == "-" => {
+                let mut script = String::new();
+                std::io::stdin().read_to_string(&mut script)?;
+                script
+            },
+            Some(path) => std::fs::read_to_string(path)
+                .with_context(|| format!("Cannot read {}", path.display()))?,
+            None => String::new(),
+        };
+
+        // Script lines keep their line numbers; -e commands and the startup
+        // load are numbered 0
+        let commands = load
+            .iter()
+            .map(|c| (0, c.as_str()))
+            .chain(script.lines().enumerate().map(|(i, l)| (i + 1, l)))
+            .chain(args.execute.iter().map(|c| (0, c.as_str())));
+
+        let mut repl = Repl::batch(registry)?;
+        let ok = batch::run(&mut repl, commands, &mut std::io::stdout().lock())?;
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+
     // Create and run the REPL
     let mut repl = Repl::new(registry)?;
 
-    // If a theory was specified, try to load it
-    if let Some(theory_name) = args.theory {
-        println!("Auto-loading theory: {}", theory_name);
-        // TODO: Actually load the theory
+    // If a theory was specified, load it
+    if let Some(load) = load {
+        if let Err(e) = repl.execute(&load) {
+            eprintln!("Error: {}", e);
+        }
     }
 
     repl.run()?;
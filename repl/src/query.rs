@@ -0,0 +1,242 @@
+//! Questions about an explored rewrite graph (`query`, `reach?`, `path`)
+//!
+//! Patterns are terms in the theory's syntax with `?holes` (see
+//! [`Theory::parse_pattern`]). The graph keeps terms only as their displays,
+//! so each term is re-parsed to be matched, as navigation re-parses the terms
+//! it moves to. Paths are shortest in number of rewrites.
+
+use crate::theory::{AscentResults, Bindings, Pattern, Rewrite, TermInfo, Theory};
+use anyhow::Result;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+
+/// A term of the graph that matched a pattern
+#[derive(Debug)]
+pub struct TermMatch<'a> {
+    pub term: &'a TermInfo,
+    pub bindings: Bindings,
+}
+
+/// A rewrite whose source (and target, if given) matched patterns
+#[derive(Debug)]
+pub struct RewriteMatch<'a> {
+    pub rewrite: &'a Rewrite,
+    pub bindings: Bindings,
+}
+
+/// What each term of the graph matched, by term ID
+pub struct Matcher<'a> {
+    theory: &'a dyn Theory,
+    results: &'a AscentResults,
+}
+
+impl<'a> Matcher<'a> {
+    pub fn new(theory: &'a dyn Theory, results: &'a AscentResults) -> Self {
+        Self { theory, results }
+    }
+
+    /// The bindings of `pattern` for each term of the graph it matches
+    pub fn matches(&self, pattern: &dyn Pattern) -> Result<HashMap<u64, Bindings>> {
+        let mut matches = HashMap::new();
+        for info in &self.results.all_terms {
+            let term = self.theory.parse_term(&info.display)?;
+            if let Some(bindings) = pattern.match_term(term.as_ref()) {
+                matches.insert(info.term_id, bindings);
+            }
+        }
+        Ok(matches)
+    }
+
+    /// The terms matching `pattern`, in graph order; only normal forms with
+    /// `normal_forms_only`
+    pub fn terms(
+        &self,
+        pattern: &dyn Pattern,
+        normal_forms_only: bool,
+    ) -> Result<Vec<TermMatch<'a>>> {
+        let mut matches = self.matches(pattern)?;
+        Ok(self
+            .results
+            .all_terms
+            .iter()
+            .filter(|info| info.is_normal_form || !normal_forms_only)
+            .filter_map(|info| {
+                let bindings = matches.remove(&info.term_id)?;
+                Some(TermMatch { term: info, bindings })
+            })
+            .collect())
+    }
+
+    /// The rewrites from a term matching `from` to one matching `to`, if
+    /// given; a hole in both must match equal terms
+    pub fn rewrites(
+        &self,
+        from: &dyn Pattern,
+        to: Option<&dyn Pattern>,
+    ) -> Result<Vec<RewriteMatch<'a>>> {
+        let sources = self.matches(from)?;
+        let targets = to.map(|to| self.matches(to)).transpose()?;
+        Ok(self
+            .results
+            .rewrites
+            .iter()
+            .filter_map(|rewrite| {
+                let mut bindings = sources.get(&rewrite.from_id)?.clone();
+                if let Some(targets) = &targets {
+                    for (hole, term) in targets.get(&rewrite.to_id)? {
+                        if bindings.get(hole).is_some_and(|bound| bound != term) {
+                            return None;
+                        }
+                        bindings.insert(hole.clone(), term.clone());
+                    }
+                }
+                Some(RewriteMatch { rewrite, bindings })
+            })
+            .collect())
+    }
+}
+
+/// A sequence of rewrites through the graph
+#[derive(Debug)]
+pub struct Path<'a> {
+    /// The term the path starts at
+    pub start: u64,
+    pub rewrites: Vec<&'a Rewrite>,
+}
+
+/// A shortest path from one of `sources` to a term for which `is_target`
+/// holds, if there is one (with no rewrites if a source is a target)
+pub fn shortest_path<'a>(
+    results: &'a AscentResults,
+    sources: impl IntoIterator<Item = u64>,
+    is_target: impl Fn(u64) -> bool,
+) -> Option<Path<'a>> {
+    let mut rewrites_from: HashMap<u64, Vec<&Rewrite>> = HashMap::new();
+    for rewrite in &results.rewrites {
+        rewrites_from.entry(rewrite.from_id).or_default().push(rewrite);
+    }
+
+    // The rewrite each term was first reached by
+    let mut reached_by: HashMap<u64, Option<&Rewrite>> = HashMap::new();
+    let mut queue = VecDeque::new();
+    for source in sources {
+        if reached_by.insert(source, None).is_none() {
+            queue.push_back(source);
+        }
+    }
+
+    while let Some(id) = queue.pop_front() {
+        if is_target(id) {
+            let mut rewrites = Vec::new();
+            let mut start = id;
+            while let Some(Some(rewrite)) = reached_by.get(&start) {
+                rewrites.push(*rewrite);
+                start = rewrite.from_id;
+            }
+            rewrites.reverse();
+            return Some(Path { start, rewrites });
+        }
+        for &rewrite in rewrites_from.get(&id).into_iter().flatten() {
+            if let Entry::Vacant(entry) = reached_by.entry(rewrite.to_id) {
+                entry.insert(Some(rewrite));
+                queue.push_back(rewrite.to_id);
+            }
+        }
+    }
+    None
+}
+
+/// `?hole = term` pairs, for display
+pub fn format_bindings(bindings: &Bindings) -> String {
+    bindings
+        .iter()
+        .map(|(hole, term)| format!("?{} = {}", hole, term))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theories::RhoCalc;
+
+    fn explore(input: &str) -> AscentResults {
+        RhoCalc
+            .run_ascent(RhoCalc.parse_term(input).unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_query_terms_and_normal_forms() {
+        let results = explore("{a!(0) | for(a->x){*(x)}}");
+        let matcher = Matcher::new(&RhoCalc, &results);
+
+        let outputs = matcher
+            .terms(RhoCalc.parse_pattern("{?n!(?p) | ...}").unwrap().as_ref(), false)
+            .unwrap();
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(format_bindings(&outputs[0].bindings), "?n = a, ?p = 0");
+
+        let any = RhoCalc.parse_pattern("?_").unwrap();
+        assert_eq!(matcher.terms(any.as_ref(), false).unwrap().len(), 3);
+        let normal_forms = matcher.terms(any.as_ref(), true).unwrap();
+        assert_eq!(normal_forms.len(), 1);
+        assert_eq!(normal_forms[0].term.display, "{0}");
+    }
+
+    #[test]
+    fn test_query_rewrites() {
+        let results = explore("{@(0)!(0) | for(@(0)->x){*(x)}}");
+        let matcher = Matcher::new(&RhoCalc, &results);
+        let from = RhoCalc.parse_pattern("{?x!(?p) | ...}").unwrap();
+        let to = RhoCalc.parse_pattern("{*(@(?p))}").unwrap();
+
+        let matches = matcher.rewrites(from.as_ref(), Some(to.as_ref())).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(format_bindings(&matches[0].bindings), "?p = 0, ?x = @(0)");
+
+        // A hole on both sides must match the same term: `?x` is `@(0)` in the
+        // source, and `0` in the second target pattern
+        let same = RhoCalc.parse_pattern("{*(?x)}").unwrap();
+        assert_eq!(
+            matcher
+                .rewrites(from.as_ref(), Some(same.as_ref()))
+                .unwrap()
+                .len(),
+            1
+        );
+        let different = RhoCalc.parse_pattern("{*(@(?x))}").unwrap();
+        assert!(matcher
+            .rewrites(from.as_ref(), Some(different.as_ref()))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_shortest_path() {
+        let results = explore("{@(0)!(0) | for(@(0)->x){*(x)}}");
+        let start = RhoCalc
+            .parse_term("{@(0)!(0) | for(@(0)->x){*(x)}}")
+            .unwrap()
+            .term_id();
+        let end = results.normal_forms()[0].term_id;
+
+        let path = shortest_path(&results, [start], |id| id == end).unwrap();
+        assert_eq!(path.start, start);
+        assert_eq!(path.rewrites.len(), 2);
+        assert_eq!(results.term(path.rewrites[0].to_id).unwrap().display, "{*(@(0))}");
+        assert_eq!(path.rewrites[1].to_id, end);
+        let empty = shortest_path(&results, [end, start], |id| id == start).unwrap();
+        assert_eq!((empty.start, empty.rewrites.len()), (start, 0));
+        assert!(shortest_path(&results, [end], |id| id == start).is_none());
+    }
+
+    #[test]
+    fn test_holes_only_in_patterns() {
+        for input in ["?x", "{a!(0) | ...}", "{?_!(0)}"] {
+            assert!(RhoCalc.parse_pattern(input).is_ok());
+            let error = RhoCalc.parse_term(input).err().unwrap();
+            assert!(error.to_string().contains("only allowed in patterns"));
+        }
+    }
+}
@@ -15,7 +15,7 @@ pub fn new() -> Self {
 
     /// Register a theory
     pub fn register(&mut self, theory: Box<dyn Theory>) {
-        let name = theory.name().as_str();
+        let name = theory.name();
         self.theories.insert(name.to_string(), theory);
     }
 
@@ -49,8 +49,8 @@ pub fn build_registry() -> Result<TheoryRegistry> {
     let mut registry = TheoryRegistry::new();
 
     // Register theories
-    registry.register(Box::new(crate::theories::RhoCalculusTheory));
-    registry.register(Box::new(crate::theories::AmbCalculusTheory));
+    registry.register(Box::new(crate::theories::RhoCalc));
+    registry.register(Box::new(crate::theories::Ambient));
     registry.register(Box::new(crate::theories::CalculatorTheory));
 
     if registry.theories.is_empty() {
@@ -1,33 +1,91 @@
 use crate::examples::{Example, ExampleCategory, TheoryName};
+use crate::helper::ReplHelper;
+use crate::interpreted::InterpretedTheory;
 use crate::pretty::format_term_pretty;
+use crate::query::{format_bindings, shortest_path, Matcher, Path as RewritePath};
 use crate::registry::TheoryRegistry;
+use crate::session::Session;
+use crate::simulate::{walk, Simulation};
 use crate::state::ReplState;
-use anyhow::Result;
+use crate::stats::GraphStats;
+use crate::theory::{AscentResults, Bindings, ExploreStats, Theory};
+use anyhow::{Context, Result};
 use colored::Colorize;
+use rand::SeedableRng;
 use rustyline::error::ReadlineError;
-use rustyline::{DefaultEditor, Result as RustyResult};
+use rustyline::history::DefaultHistory;
+use rustyline::{Editor, Result as RustyResult};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
+/// `println!` (or `print!`, with `inline`), unless the REPL is quiet: in batch
+/// mode, whose only output is one JSON record per command, and while a
+/// restored session is replayed
+macro_rules! say {
+    ($repl:expr) => {
+        if !$repl.quiet {
+            println!()
+        }
+    };
+    ($repl:expr, inline $($arg:tt)*) => {
+        if !$repl.quiet {
+            print!($($arg)*)
+        }
+    };
+    ($repl:expr, $($arg:tt)*) => {
+        if !$repl.quiet {
+            println!($($arg)*)
+        }
+    };
+}
+
 /// The main REPL
 pub struct Repl {
     state: ReplState,
     registry: TheoryRegistry,
-    editor: DefaultEditor,
+    editor: Editor<ReplHelper, DefaultHistory>,
+    /// The files theories were loaded from with `load-file`, for `export`
+    loaded_files: HashMap<String, PathBuf>,
+    /// Whether commands print nothing themselves
+    quiet: bool,
 }
 
 impl Repl {
     /// Create a new REPL
     pub fn new(registry: TheoryRegistry) -> RustyResult<Self> {
-        let editor = DefaultEditor::new()?;
+        let mut editor = Editor::new()?;
+        let mut helper = ReplHelper::new();
+        helper.set_theories(registry.list().into_iter().map(String::from).collect());
+        editor.set_helper(Some(helper));
         Ok(Self {
             state: ReplState::new(),
             registry,
             editor,
+            loaded_files: HashMap::new(),
+            quiet: false,
         })
     }
 
+    /// Create a REPL for running commands non-interactively (see [`crate::batch`])
+    pub fn batch(registry: TheoryRegistry) -> RustyResult<Self> {
+        let mut repl = Self::new(registry)?;
+        repl.quiet = true;
+        Ok(repl)
+    }
+
+    /// The session state, as left by the commands run so far
+    pub fn state(&self) -> &ReplState {
+        &self.state
+    }
+
+    /// Run one line of input: a command, `term: <expr>`, or a `#` comment
+    pub fn execute(&mut self, line: &str) -> Result<()> {
+        self.handle_command(line.trim())
+    }
+
     pub fn name_str(&self) -> Option<&str> {
-        self.state.theory_name().map(|name| name.as_str())
+        self.state.theory_name()
     }
 
     /// Run the REPL
@@ -79,7 +137,7 @@ fn print_banner(&self) {
 
     fn make_prompt(&self) -> String {
         if let Some(theory_name) = self.state.theory_name() {
-            format!("{}> ", theory_name.as_str().green())
+            format!("{}> ", theory_name.green())
         } else {
             "mettail> ".to_string()
         }
@@ -94,9 +152,15 @@ fn handle_command(&mut self, line: &str) -> Result<()> {
         // let theory_name = self.name_str().unwrap_or_default();
         // let theory = self.registry.get(theory_name)?;
 
+        // Comments, as in exported scripts
+        if line.starts_with('#') {
+            return Ok(());
+        }
+
         match parts[0] {
             "help" => self.cmd_help(),
             "load" => self.cmd_load(&parts[1..]),
+            "load-file" => self.cmd_load_file(&parts[1..]),
             "list" | "list-theories" => self.cmd_list_theories(),
             "info" => self.cmd_info(),
             "rewrites" => self.cmd_rewrites(),
@@ -104,10 +168,24 @@ fn handle_command(&mut self, line: &str) -> Result<()> {
             "normal-forms" | "nf" => self.cmd_normal_forms(),
             "apply" => self.cmd_apply(&parts[1..]),
             "goto" => self.cmd_goto(&parts[1..]),
+            "goto-term" => self.cmd_goto_term(line["goto-term".len()..].trim()),
+            "back" => self.cmd_back(),
+            "forward" => self.cmd_forward(),
+            "history" => self.cmd_history(),
+            "jump" => self.cmd_jump(&parts[1..]),
+            "query" => self.cmd_query(&parts[1..]),
+            "reach?" => self.cmd_reach(&parts[1..]),
+            "path" => self.cmd_path(&parts[1..]),
+            "stats" => self.cmd_stats(&parts[1..]),
+            "simulate" => self.cmd_simulate(&parts[1..]),
+            "export" => self.cmd_export(&parts[1..]),
+            "save" => self.cmd_save(&parts[1..]),
+            "restore" => self.cmd_restore(&parts[1..]),
+            "load-term" => self.cmd_load_term(&parts[1..]),
             "example" => self.cmd_example(&parts[1..]),
-            "list-examples" => self.cmd_list_examples(&self.state.theory_name().unwrap()),
+            "list-examples" => self.cmd_list_examples(self.state.theory_name().unwrap()),
             "quit" | "exit" => {
-                println!("Goodbye!");
+                say!(self, "Goodbye!");
                 std::process::exit(0);
             },
             _ => {
@@ -125,29 +203,62 @@ fn handle_command(&mut self, line: &str) -> Result<()> {
     }
 
     fn cmd_help(&self) -> Result<()> {
-        println!();
-        println!("{}", "Available commands:".bold());
-        println!();
-        println!("{}", "  Theory Management:".yellow());
-        println!("    {}  Load a theory", "load <name>".green());
-        println!("    {}        Show available theories", "list-theories".green());
-        println!("    {}              Show theory information", "info".green());
-        println!();
-        println!("{}", "  Term Input:".yellow());
-        println!("    {}    Parse and load a term", "term: <expr>".green());
-        println!("    {}    Load example process", "example <name>".green());
-        println!("    {}    List available examples", "list-examples".green());
-        println!();
-        println!("{}", "  Navigation:".yellow());
-        println!("    {}           List rewrites from current term", "rewrites".green());
-        println!("    {}        Show normal forms", "normal-forms".green());
-        println!("    {} Apply rewrite N", "apply <N>".green());
-        println!("    {}              Go to normal form N", "goto <N>".green());
-        println!();
-        println!("{}", "  General:".yellow());
-        println!("    {}              Show this help", "help".green());
-        println!("    {}        Exit REPL", "quit, exit".green());
-        println!();
+        say!(self);
+        say!(self, "{}", "Available commands:".bold());
+        say!(self);
+        say!(self, "{}", "  Theory Management:".yellow());
+        say!(self, "    {}  Load a theory", "load <name>".green());
+        say!(self, "    {} Load a theory from a .mettail file", "load-file <path>".green());
+        say!(self, "    {}        Show available theories", "list-theories".green());
+        say!(self, "    {}              Show theory information", "info".green());
+        say!(self);
+        say!(self, "{}", "  Term Input:".yellow());
+        say!(self, "    {}    Parse and load a term", "term: <expr>".green());
+        say!(self, "    {} Parse and load the term in a file", "load-term <file>".green());
+        say!(self, "    {}    Load example process", "example <name>".green());
+        say!(self, "    {}    List available examples", "list-examples".green());
+        say!(self);
+        say!(self, "{}", "  Navigation:".yellow());
+        say!(self, "    {}           List rewrites from current term", "rewrites".green());
+        say!(self, "    {}        Show normal forms", "normal-forms".green());
+        say!(self, "    {} Apply rewrite N", "apply <N>".green());
+        say!(self, "    {}              Go to normal form N", "goto <N>".green());
+        say!(self, "    {}     Go to a term of the graph", "goto-term <term>".green());
+        say!(self);
+        say!(self, "{}", "  Queries:".yellow());
+        say!(
+            self,
+            "    {}     Terms matching a pattern with ?holes",
+            "query <pattern>".green()
+        );
+        say!(self, "    {}  Normal forms matching a pattern", "query nf <pattern>".green());
+        say!(self, "    {} Rewrites between patterns", "query rewrites <p> [=> <q>]".green());
+        say!(self, "    {}      Shortest path from the current term", "reach? <term>".green());
+        say!(self, "    {}    Shortest path between terms", "path <from> <to>".green());
+        say!(self, "    {}       Statistics of the exploration", "stats [all]".green());
+        say!(self);
+        say!(self, "{}", "  Simulation:".yellow());
+        say!(
+            self,
+            "    {} Random walks of up to N rewrites",
+            "simulate <N> [--seed S] [--runs K] [term]".green()
+        );
+        say!(self);
+        say!(self, "{}", "  History:".yellow());
+        say!(self, "    {}               Go back to the previous term", "back".green());
+        say!(self, "    {}            Go forward again", "forward".green());
+        say!(self, "    {}            Show the path taken so far", "history".green());
+        say!(self, "    {}            Jump to history entry N", "jump <N>".green());
+        say!(self, "    {}     Export the path as a script", "export [file]".green());
+        say!(self);
+        say!(self, "{}", "  Sessions:".yellow());
+        say!(self, "    {} Save the session", "save <file> [--no-results]".green());
+        say!(self, "    {}     Restore a saved session", "restore <file>".green());
+        say!(self);
+        say!(self, "{}", "  General:".yellow());
+        say!(self, "    {}              Show this help", "help".green());
+        say!(self, "    {}        Exit REPL", "quit, exit".green());
+        say!(self);
         Ok(())
     }
 
@@ -165,59 +276,81 @@ fn cmd_load(&mut self, args: &[&str]) -> Result<()> {
             );
         }
 
-        println!("Loading theory: {}", theory_name.green());
+        say!(self, "Loading theory: {}", theory_name.green());
 
         // Get the theory from the registry (for display info)
         let theory = self.registry.get(theory_name)?;
 
         // Print theory info
-        println!("  ✓ {} categories", theory.categories().len());
-        println!("  ✓ {} constructors", theory.constructor_count());
-        println!("  ✓ {} equations", theory.equation_count());
-        println!("  ✓ {} rewrite rules", theory.rewrite_count());
-        println!();
+        say!(self, "  ✓ {} categories", theory.categories().len());
+        say!(self, "  ✓ {} constructors", theory.constructor_count());
+        say!(self, "  ✓ {} equations", theory.equation_count());
+        say!(self, "  ✓ {} rewrite rules", theory.rewrite_count());
+        say!(self);
 
         // Store the theory name in state
         self.state.load_theory(theory.name());
 
-        println!("{} Theory loaded successfully!", "✓".green());
-        println!("Use {} to parse and execute a term.", "'term: <expr>'".cyan());
-        println!();
+        // Complete and highlight for this theory (and any theory loaded from a file)
+        if let Some(helper) = self.editor.helper_mut() {
+            helper.set_theories(self.registry.list().into_iter().map(String::from).collect());
+            helper.set_theory(theory);
+        }
+
+        say!(self, "{} Theory loaded successfully!", "✓".green());
+        say!(self, "Use {} to parse and execute a term.", "'term: <expr>'".cyan());
+        say!(self);
 
         Ok(())
     }
 
+    fn cmd_load_file(&mut self, args: &[&str]) -> Result<()> {
+        if args.is_empty() {
+            anyhow::bail!("Usage: load-file <path>");
+        }
+
+        let path = Path::new(args[0]);
+        let theory = InterpretedTheory::load(path)?;
+        let name = theory.name().to_string();
+        say!(self, "Interpreting {} ({})", path.display(), "no recompilation".dimmed());
+
+        // A loaded file replaces a theory of the same name, including a compiled one
+        self.registry.register(Box::new(theory));
+        self.loaded_files.insert(name.clone(), path.to_path_buf());
+        self.cmd_load(&[&name])
+    }
+
     fn cmd_list_theories(&self) -> Result<()> {
-        println!();
-        println!("{}", "Available theories:".bold());
-        println!();
+        say!(self);
+        say!(self, "{}", "Available theories:".bold());
+        say!(self);
 
         let theories = self.registry.list();
         if theories.is_empty() {
-            println!("  {}", "No theories available.".yellow());
-            println!("  {}", "Build mettail-examples first with: cargo build".dimmed());
+            say!(self, "  {}", "No theories available.".yellow());
+            say!(self, "  {}", "Build mettail-examples first with: cargo build".dimmed());
         } else {
             for theory in theories {
-                println!("  - {}", theory.green());
+                say!(self, "  - {}", theory.green());
             }
         }
 
-        println!();
+        say!(self);
         Ok(())
     }
 
     fn cmd_info(&self) -> Result<()> {
         if let Some(theory_name) = self.state.theory_name() {
-            let theory = self.registry.get(theory_name.as_str())?;
-            println!();
-            println!("{} {}", "Theory:".bold(), theory.name().as_str().green());
-            println!("  Categories: {}", theory.categories().len());
-            println!("  Constructors: {}", theory.constructor_count());
-            println!("  Equations: {}", theory.equation_count());
-            println!("  Rewrites: {}", theory.rewrite_count());
-            println!();
+            let theory = self.registry.get(theory_name)?;
+            say!(self);
+            say!(self, "{} {}", "Theory:".bold(), theory.name().green());
+            say!(self, "  Categories: {}", theory.categories().len());
+            say!(self, "  Constructors: {}", theory.constructor_count());
+            say!(self, "  Equations: {}", theory.equation_count());
+            say!(self, "  Rewrites: {}", theory.rewrite_count());
+            say!(self);
         } else {
-            println!("{} No theory loaded. Use 'load <name>' first.", "Info:".yellow());
+            say!(self, "{} No theory loaded. Use 'load <name>' first.", "Info:".yellow());
         }
         Ok(())
     }
@@ -230,43 +363,54 @@ fn cmd_parse_term(&mut self, term_str: &str) -> Result<()> {
             .ok_or_else(|| anyhow::anyhow!("No theory loaded. Use 'load <theory>' first."))?;
 
         // Get the theory from the registry
-        let theory = self.registry.get(theory_name.as_str())?;
+        let theory = self.registry.get(theory_name)?;
 
-        println!();
-        print!("Parsing... ");
+        say!(self);
+        say!(self, inline "Parsing... ");
 
         // Parse the term
         let term = theory.parse_term(term_str)?;
-        println!("{}", "✓".green());
+        say!(self, "{}", "✓".green());
 
-        print!("Running Ascent... ");
+        say!(self, inline "Running Ascent... ");
 
         let start_time = Instant::now();
         // Run Ascent
-        let results = theory.run_ascent(term.clone_box())?;
+        let (results, stats) = theory.run_ascent_with_stats(term.clone_box())?;
         let end_time = Instant::now();
         let duration = end_time.duration_since(start_time);
-        println!("Time taken: {:?}", duration);
-        println!("{}", "Done!".green());
+        say!(self, "Time taken: {:?}", duration);
+        say!(self, "{}", "Done!".green());
 
-        println!();
-        println!("Computed:");
-        println!("  - {} terms", results.all_terms.len());
-        println!("  - {} rewrites", results.rewrites.len());
-        println!("  - {} normal forms", results.normal_forms().len());
-        println!();
+        say!(self);
+        say!(self, "Computed:");
+        say!(self, "  - {} terms", results.all_terms.len());
+        say!(self, "  - {} rewrites", results.rewrites.len());
+        say!(self, "  - {} normal forms", results.normal_forms().len());
+        say!(self);
 
-        println!("{}", "Current term:".bold());
+        say!(self, "{}", "Current term:".bold());
         let formatted = format_term_pretty(&format!("{}", term));
-        println!("{}", formatted.cyan());
-        println!();
+        say!(self, "{}", formatted.cyan());
+        say!(self);
 
         // Store in state
         self.state.set_term(term, results)?;
+        self.state.set_explore_stats(stats);
 
         Ok(())
     }
 
+    fn cmd_load_term(&mut self, args: &[&str]) -> Result<()> {
+        let [path] = args else {
+            anyhow::bail!("Usage: load-term <file>");
+        };
+        let source =
+            std::fs::read_to_string(path).with_context(|| format!("Cannot read {}", path))?;
+        // The history records the term itself, so exported scripts do not need the file
+        self.cmd_parse_term(source.trim())
+    }
+
     fn cmd_equations(&self) -> Result<()> {
         let results = self
             .state
@@ -274,8 +418,8 @@ fn cmd_equations(&self) -> Result<()> {
             .ok_or_else(|| anyhow::anyhow!("No term loaded. Use 'term: <expr>' first."))?;
 
         let equivalences = results.equivalences.clone();
-        println!();
-        println!("{}", "Equivalence Classes:".bold());
+        say!(self);
+        say!(self, "{}", "Equivalence Classes:".bold());
         for equ_class in equivalences {
             let terms = equ_class
                 .term_ids
@@ -290,9 +434,9 @@ fn cmd_equations(&self) -> Result<()> {
                         .as_str()
                 })
                 .collect::<Vec<_>>();
-            println!("  {}", terms.join(" == "));
+            say!(self, "  {}", terms.join(" == "));
         }
-        println!();
+        say!(self);
         Ok(())
     }
 
@@ -314,15 +458,16 @@ fn cmd_rewrites(&self) -> Result<()> {
             .filter(|r| r.from_id == current_id)
             .collect();
 
-        println!();
+        say!(self);
         if available_rewrites.is_empty() {
-            println!(
+            say!(
+                self,
                 "{} No rewrites available from current term (it's a normal form).",
                 "✓".green()
             );
         } else {
-            println!("{} available from current term:", "Rewrites".bold());
-            println!();
+            say!(self, "{} available from current term:", "Rewrites".bold());
+            say!(self);
             for (idx, rewrite) in available_rewrites.iter().enumerate() {
                 // Find the target term display
                 let target_display = results
@@ -335,15 +480,15 @@ fn cmd_rewrites(&self) -> Result<()> {
                 // Pretty print the target
                 let formatted = format_term_pretty(target_display);
 
-                println!("  {}) {}", idx.to_string().cyan(), "→".yellow());
+                say!(self, "  {}) {}", idx.to_string().cyan(), "→".yellow());
                 // Indent each line of the formatted output
                 for line in formatted.lines() {
-                    println!("     {}", line.green());
+                    say!(self, "     {}", line.green());
                 }
-                println!();
+                say!(self);
             }
         }
-        println!();
+        say!(self);
         Ok(())
     }
 
@@ -355,22 +500,22 @@ fn cmd_normal_forms(&self) -> Result<()> {
 
         let normal_forms = results.normal_forms();
 
-        println!();
+        say!(self);
         if normal_forms.is_empty() {
-            println!("{} No normal forms computed.", "Warning:".yellow());
+            say!(self, "{} No normal forms computed.", "Warning:".yellow());
         } else {
-            println!("{} ({} total):", "Normal forms".bold(), normal_forms.len());
-            println!();
+            say!(self, "{} ({} total):", "Normal forms".bold(), normal_forms.len());
+            say!(self);
             for (idx, nf) in normal_forms.iter().enumerate() {
                 let formatted = format_term_pretty(&nf.display);
-                println!("  {})", idx.to_string().cyan());
+                say!(self, "  {})", idx.to_string().cyan());
                 for line in formatted.lines() {
-                    println!("    {}", line.green());
+                    say!(self, "    {}", line.green());
                 }
-                println!();
+                say!(self);
             }
         }
-        println!();
+        say!(self);
         Ok(())
     }
 
@@ -388,7 +533,7 @@ fn cmd_apply(&mut self, args: &[&str]) -> Result<()> {
             .theory_name()
             .ok_or_else(|| anyhow::anyhow!("No theory loaded"))?;
 
-        let theory = self.registry.get(theory_name.as_str())?;
+        let theory = self.registry.get(theory_name)?;
 
         let results = self
             .state
@@ -423,17 +568,22 @@ fn cmd_apply(&mut self, args: &[&str]) -> Result<()> {
         // Parse the target term and update its ID to match what's in the graph
         let target_term = theory.parse_term(&target_info.display)?;
 
-        println!();
-        println!("{}", "Applied rewrite →".yellow());
+        say!(self);
+        say!(self, "{}", "Applied rewrite →".yellow());
         let formatted = format_term_pretty(&target_info.display);
         for line in formatted.lines() {
-            println!("  {}", line.green());
+            say!(self, "  {}", line.green());
         }
-        println!();
+        say!(self);
 
-        // Update state - pass the target_id so we can track position in the graph
-        self.state
-            .set_term_with_id(target_term, results.clone(), rewrite.to_id)?;
+        // Record the step, with the target's ID so we can track position in the graph
+        // The rewrite's number depends on the graph's order, so the history
+        // records its target instead
+        let rule = rewrite.rule_name.clone();
+        let to_id = rewrite.to_id;
+        let command = format!("goto-term {}", target_term);
+        self.state.step_to(target_term, to_id, rule, command);
+        self.print_breadcrumb();
 
         Ok(())
     }
@@ -452,7 +602,7 @@ fn cmd_goto(&mut self, args: &[&str]) -> Result<()> {
             .theory_name()
             .ok_or_else(|| anyhow::anyhow!("No theory loaded"))?;
 
-        let theory = self.registry.get(theory_name.as_str())?;
+        let theory = self.registry.get(theory_name)?;
 
         let results = self
             .state
@@ -473,21 +623,673 @@ fn cmd_goto(&mut self, args: &[&str]) -> Result<()> {
         // Parse the target term
         let target_term = theory.parse_term(&target_info.display)?;
 
-        println!();
-        println!("{}", "Navigated to normal form:".bold());
+        say!(self);
+        say!(self, "{}", "Navigated to normal form:".bold());
         let formatted = format_term_pretty(&target_info.display);
         for line in formatted.lines() {
-            println!("  {}", line.green());
+            say!(self, "  {}", line.green());
         }
-        println!();
+        say!(self);
 
-        // Update state with the correct graph ID
-        self.state
-            .set_term_with_id(target_term, results.clone(), target_info.term_id)?;
+        // Record the step with the correct graph ID
+        let term_id = target_info.term_id;
+        let command = format!("goto-term {}", target_term);
+        self.state.step_to(target_term, term_id, None, command);
+        self.print_breadcrumb();
 
         Ok(())
     }
 
+    /// Go to a term of the current rewrite graph, by its display; how the
+    /// history records `apply` and `goto`, so replaying it reaches the same terms
+    fn cmd_goto_term(&mut self, term_str: &str) -> Result<()> {
+        if term_str.is_empty() {
+            anyhow::bail!("Usage: goto-term <term>");
+        }
+        let (theory, results) = self.explored()?;
+        let current_id = self
+            .state
+            .current_graph_id()
+            .ok_or_else(|| anyhow::anyhow!("No current term"))?;
+
+        let display = theory.parse_term(term_str)?.to_string();
+        let target_info = results
+            .all_terms
+            .iter()
+            .find(|t| t.display == display)
+            .ok_or_else(|| anyhow::anyhow!("{} is not in the explored graph", display))?;
+        let target_term = theory.parse_term(&target_info.display)?;
+        let term_id = target_info.term_id;
+        // The rule, if the term is one rewrite away
+        let rule = results
+            .rewrites
+            .iter()
+            .find(|r| r.from_id == current_id && r.to_id == term_id)
+            .and_then(|r| r.rule_name.clone());
+
+        say!(self);
+        say!(self, "{}", "Navigated to:".bold());
+        let formatted = format_term_pretty(&display);
+        for line in formatted.lines() {
+            say!(self, "  {}", line.green());
+        }
+        say!(self);
+
+        let command = format!("goto-term {}", target_term);
+        self.state.step_to(target_term, term_id, rule, command);
+        self.print_breadcrumb();
+
+        Ok(())
+    }
+
+    fn cmd_back(&mut self) -> Result<()> {
+        if self.state.go_back().is_none() {
+            anyhow::bail!("Already at the start of the history.");
+        }
+        self.restore_history_entry()
+    }
+
+    fn cmd_forward(&mut self) -> Result<()> {
+        if self.state.go_forward().is_none() {
+            anyhow::bail!("Already at the end of the history.");
+        }
+        self.restore_history_entry()
+    }
+
+    fn cmd_jump(&mut self, args: &[&str]) -> Result<()> {
+        if args.is_empty() {
+            anyhow::bail!("Usage: jump <history-entry-number>");
+        }
+
+        let idx: usize = args[0]
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid number: {}", args[0]))?;
+
+        if self.state.goto(idx).is_none() {
+            anyhow::bail!("History entry {} not found. Use 'history' to see the path.", idx);
+        }
+        self.restore_history_entry()
+    }
+
+    /// Make the current history entry's term the current term, and show it
+    fn restore_history_entry(&mut self) -> Result<()> {
+        let theory_name = self
+            .state
+            .theory_name()
+            .ok_or_else(|| anyhow::anyhow!("No theory loaded"))?;
+        let theory = self.registry.get(theory_name)?;
+
+        let entry = &self.state.history()[self.state.history_index()];
+        let term = theory.parse_term(&entry.display)?;
+
+        say!(self);
+        say!(self, "{}", "Current term:".bold());
+        let formatted = format_term_pretty(&entry.display);
+        for line in formatted.lines() {
+            say!(self, "  {}", line.green());
+        }
+        say!(self);
+
+        self.state.restore_term(term);
+        self.print_breadcrumb();
+        Ok(())
+    }
+
+    fn cmd_history(&self) -> Result<()> {
+        let history = self.state.history();
+        if history.is_empty() {
+            anyhow::bail!("No term loaded. Use 'term: <expr>' first.");
+        }
+
+        say!(self);
+        say!(self, "{}", "History:".bold());
+        say!(self);
+        for (idx, entry) in history.iter().enumerate() {
+            let marker = if idx == self.state.history_index() {
+                "▶"
+            } else {
+                " "
+            };
+            let how = match &entry.rewrite_applied {
+                Some(rule) => format!("{} ({})", entry.command, rule),
+                None if entry.command.starts_with("term:") => "start".to_string(),
+                None => entry.command.clone(),
+            };
+            say!(
+                self,
+                "{} {}) {} {}",
+                marker.yellow(),
+                idx.to_string().cyan(),
+                how.dimmed(),
+                entry.display.green()
+            );
+        }
+        say!(self);
+        Ok(())
+    }
+
+    /// The path from the initial term to the current one, on one line
+    fn print_breadcrumb(&self) {
+        let history = self.state.history();
+        let current = self.state.history_index();
+        let mut crumbs = String::new();
+        for (idx, entry) in history.iter().enumerate() {
+            if idx > 0 {
+                let rule = entry.rewrite_applied.as_deref().unwrap_or("");
+                crumbs.push_str(&format!(" ─{}→ ", rule));
+            }
+            let label = format!("[{}]", idx);
+            if idx == current {
+                crumbs.push_str(&label.bold().to_string());
+            } else {
+                crumbs.push_str(&label.dimmed().to_string());
+            }
+        }
+        say!(self, "{} {}", "Path:".bold(), crumbs);
+        say!(self);
+    }
+
+    fn cmd_export(&self, args: &[&str]) -> Result<()> {
+        let theory_name = self
+            .state
+            .theory_name()
+            .ok_or_else(|| anyhow::anyhow!("No theory loaded"))?;
+        let steps = self.state.script();
+        if steps.is_empty() {
+            anyhow::bail!("No term loaded. Use 'term: <expr>' first.");
+        }
+
+        let load = match self.loaded_files.get(theory_name) {
+            Some(path) => format!("load-file {}", path.display()),
+            None => format!("load {}", theory_name),
+        };
+        let mut script =
+            format!("# MeTTaIL session: {} step(s) in {}\n", steps.len() - 1, theory_name);
+        script.push_str(&load);
+        script.push('\n');
+        for command in &steps {
+            script.push_str(command);
+            script.push('\n');
+        }
+
+        match args.first() {
+            Some(path) => {
+                std::fs::write(path, &script)?;
+                say!(self, "{} Exported {} step(s) to {}", "✓".green(), steps.len() - 1, path);
+            },
+            None => say!(self, inline "{}", script),
+        }
+        Ok(())
+    }
+
+    fn cmd_save(&mut self, args: &[&str]) -> Result<()> {
+        let (path, include_results) = match args {
+            [path] => (path, true),
+            [path, "--no-results"] => (path, false),
+            _ => anyhow::bail!("Usage: save <file> [--no-results]"),
+        };
+
+        let theory_file = self
+            .state
+            .theory_name()
+            .and_then(|name| self.loaded_files.get(name))
+            .map(PathBuf::as_path);
+        let session = Session::capture(&self.state, theory_file, include_results)?;
+        session.save(Path::new(path))?;
+
+        say!(
+            self,
+            "{} Saved {} history entries{} to {}",
+            "✓".green(),
+            session.history.len(),
+            if session.results.is_some() {
+                " and the rewrite graph"
+            } else {
+                ""
+            },
+            path
+        );
+        Ok(())
+    }
+
+    fn cmd_restore(&mut self, args: &[&str]) -> Result<()> {
+        let [path] = args else {
+            anyhow::bail!("Usage: restore <file>");
+        };
+        let session = Session::load(Path::new(path))?;
+
+        match &session.theory_file {
+            Some(file) => self.cmd_load_file(&[&file.to_string_lossy()])?,
+            None => self.cmd_load(&[&session.theory])?,
+        }
+        if session.history.is_empty() {
+            return Ok(());
+        }
+
+        match session.results {
+            Some(results) => {
+                let theory = self.registry.get(&session.theory)?;
+                let current = &session.history[session.history_index];
+                let term = theory.parse_term(&current.display)?;
+                self.state
+                    .restore_history(session.history, session.history_index, results, term);
+            },
+            None => {
+                // Re-explore from the first term and step to each later one by
+                // its display (numbered steps would depend on the graph's order),
+                // then return to the saved position
+                let quiet = std::mem::replace(&mut self.quiet, true);
+                let replayed = session
+                    .history
+                    .iter()
+                    .enumerate()
+                    .try_for_each(|(i, entry)| {
+                        if i == 0 {
+                            self.cmd_parse_term(&entry.display)
+                        } else {
+                            self.cmd_goto_term(&entry.display)
+                        }
+                    });
+                self.quiet = quiet;
+                replayed?;
+                self.state.goto(session.history_index);
+                self.restore_history_entry()?;
+            },
+        }
+
+        say!(
+            self,
+            "{} Restored {} history entries from {}",
+            "✓".green(),
+            self.state.history().len(),
+            path
+        );
+        Ok(())
+    }
+
+    /// The loaded theory and the graph explored from the current term
+    fn explored(&self) -> Result<(&dyn Theory, &AscentResults)> {
+        let theory_name = self
+            .state
+            .theory_name()
+            .ok_or_else(|| anyhow::anyhow!("No theory loaded. Use 'load <theory>' first."))?;
+        let results = self
+            .state
+            .ascent_results()
+            .ok_or_else(|| anyhow::anyhow!("No term loaded. Use 'term: <expr>' first."))?;
+        Ok((self.registry.get(theory_name)?, results))
+    }
+
+    fn cmd_query(&self, args: &[&str]) -> Result<()> {
+        let (kind, pattern) = match args.split_first() {
+            Some((&("terms" | "nf" | "normal-forms" | "rewrites"), rest)) => (args[0], rest),
+            _ => ("terms", args),
+        };
+        if pattern.is_empty() {
+            anyhow::bail!("Usage: query [terms|nf|rewrites] <pattern> [=> <pattern>]");
+        }
+        let input = pattern.join(" ");
+        let (theory, results) = self.explored()?;
+        let matcher = Matcher::new(theory, results);
+
+        say!(self);
+        if kind == "rewrites" {
+            let (from, to) = match input.split_once("=>") {
+                Some((from, to)) => (from.trim(), Some(to.trim())),
+                None => (input.as_str(), None),
+            };
+            let from = theory.parse_pattern(from)?;
+            let to = to.map(|to| theory.parse_pattern(to)).transpose()?;
+            let matches = matcher.rewrites(from.as_ref(), to.as_deref())?;
+            say!(
+                self,
+                "{} matching {} ({} of {}):",
+                "Rewrites".bold(),
+                input.cyan(),
+                matches.len(),
+                results.rewrites.len()
+            );
+            say!(self);
+            for m in &matches {
+                let rule = m.rewrite.rule_name.as_deref().unwrap_or("");
+                say!(
+                    self,
+                    "  {} {} {}",
+                    display_of(results, m.rewrite.from_id).green(),
+                    format!("─{}→", rule).yellow(),
+                    display_of(results, m.rewrite.to_id).green()
+                );
+                self.print_bindings(&m.bindings);
+            }
+        } else {
+            let normal_forms_only = kind != "terms";
+            let pattern = theory.parse_pattern(&input)?;
+            let matches = matcher.terms(pattern.as_ref(), normal_forms_only)?;
+            let (what, total) = if normal_forms_only {
+                ("Normal forms", results.normal_forms().len())
+            } else {
+                ("Terms", results.all_terms.len())
+            };
+            say!(
+                self,
+                "{} matching {} ({} of {}):",
+                what.bold(),
+                input.cyan(),
+                matches.len(),
+                total
+            );
+            say!(self);
+            for m in &matches {
+                say!(self, "  {}", m.term.display.green());
+                self.print_bindings(&m.bindings);
+            }
+        }
+        say!(self);
+        Ok(())
+    }
+
+    fn print_bindings(&self, bindings: &Bindings) {
+        if !bindings.is_empty() {
+            say!(self, "    {}", format_bindings(bindings).dimmed());
+        }
+    }
+
+    fn cmd_reach(&self, args: &[&str]) -> Result<()> {
+        if args.is_empty() {
+            anyhow::bail!("Usage: reach? <term>");
+        }
+        let input = args.join(" ");
+        let (theory, results) = self.explored()?;
+        let current = self
+            .state
+            .current_graph_id()
+            .ok_or_else(|| anyhow::anyhow!("No current term"))?;
+        let targets =
+            Matcher::new(theory, results).matches(theory.parse_pattern(&input)?.as_ref())?;
+
+        say!(self);
+        match shortest_path(results, [current], |id| targets.contains_key(&id)) {
+            Some(path) => {
+                say!(
+                    self,
+                    "{} Reachable from the current term in {} step(s):",
+                    "✓".green(),
+                    path.rewrites.len()
+                );
+                self.print_path(results, &path);
+            },
+            None => say!(
+                self,
+                "{} No term matching {} is reachable from the current term.",
+                "✗".red(),
+                input.cyan()
+            ),
+        }
+        say!(self);
+        Ok(())
+    }
+
+    fn cmd_path(&self, args: &[&str]) -> Result<()> {
+        let (theory, results) = self.explored()?;
+        // Terms contain spaces: split where both halves parse
+        let patterns = (1..args.len()).find_map(|i| {
+            let from = theory.parse_pattern(&args[..i].join(" ")).ok()?;
+            let to = theory.parse_pattern(&args[i..].join(" ")).ok()?;
+            Some((from, to))
+        });
+        let Some((from, to)) = patterns else {
+            anyhow::bail!("Usage: path <from> <to>, where both are terms (or patterns)");
+        };
+        let matcher = Matcher::new(theory, results);
+        let sources = matcher.matches(from.as_ref())?;
+        let targets = matcher.matches(to.as_ref())?;
+
+        say!(self);
+        match shortest_path(results, sources.into_keys(), |id| targets.contains_key(&id)) {
+            Some(path) => {
+                say!(self, "{} ({} step(s)):", "Shortest path".bold(), path.rewrites.len());
+                self.print_path(results, &path);
+            },
+            None => say!(self, "{} No path between the terms.", "✗".red()),
+        }
+        say!(self);
+        Ok(())
+    }
+
+    fn cmd_stats(&self, args: &[&str]) -> Result<()> {
+        let all = match args {
+            [] => false,
+            ["all"] => true,
+            _ => anyhow::bail!("Usage: stats [all]"),
+        };
+        let (_, results) = self.explored()?;
+        let root = self.state.history().first().map(|entry| entry.term_id);
+        let graph = GraphStats::of(results, root);
+
+        say!(self);
+        say!(self, "{}", "Graph:".bold());
+        say!(
+            self,
+            "  {} terms, {} rewrites, {} normal forms",
+            graph.terms,
+            graph.rewrites,
+            graph.normal_forms
+        );
+        say!(
+            self,
+            "  {} equivalence classes of more than one term",
+            graph.equivalence_classes
+        );
+        let depth = graph
+            .depth
+            .map_or("unknown".to_string(), |depth| depth.to_string());
+        say!(self, "  Max branching: {}, depth: {}", graph.max_branching, depth);
+        say!(self);
+
+        match self.state.explore_stats() {
+            Some(stats) => self.print_explore_stats(stats, all),
+            None => {
+                say!(
+                    self,
+                    "{} This theory's exploration keeps no Ascent statistics.",
+                    "Info:".yellow()
+                );
+                say!(self);
+            },
+        }
+        Ok(())
+    }
+
+    fn print_explore_stats(&self, stats: &ExploreStats, all: bool) {
+        const TOP_RULES: usize = 10;
+
+        say!(
+            self,
+            "{} {:?} (Ascent {:?}, graph {:?})",
+            "Time:".bold(),
+            stats.ascent_time + stats.graph_time,
+            stats.ascent_time,
+            stats.graph_time
+        );
+        say!(
+            self,
+            "{} {} in {} SCCs",
+            "Iterations:".bold(),
+            stats.total_iterations(),
+            stats.sccs.len()
+        );
+        say!(self);
+
+        say!(self, "{} ({} facts):", "Relations".bold(), stats.total_facts());
+        let mut relations: Vec<_> = stats.relations.iter().collect();
+        relations.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        let width = relations
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or(0);
+        for (name, count) in relations {
+            say!(self, "  {}  {:>8}", format!("{:<width$}", name).cyan(), count);
+        }
+        say!(self);
+
+        let rules: Vec<_> = if all {
+            stats.rules.iter().collect()
+        } else {
+            stats.busiest_rules().into_iter().take(TOP_RULES).collect()
+        };
+        say!(
+            self,
+            "{} ({} firings; {} of {} rules{}):",
+            "Rule firings".bold(),
+            stats.total_firings(),
+            rules.len(),
+            stats.rules.len(),
+            if all { "" } else { ", busiest first" }
+        );
+        for rule in rules {
+            say!(self, "  {:>8}  {}", rule.firings, rule.label);
+        }
+        say!(self);
+
+        say!(self, "{}", "SCCs:".bold());
+        for (idx, scc) in stats.sccs.iter().enumerate() {
+            say!(
+                self,
+                "  {} {} iterations, {:?}",
+                format!("[{}]", idx).dimmed(),
+                scc.iterations,
+                scc.time
+            );
+        }
+        say!(self);
+    }
+
+    fn cmd_simulate(&self, args: &[&str]) -> Result<()> {
+        const USAGE: &str = "Usage: simulate <steps> [--seed N] [--runs K] [term]";
+        let Some((steps, mut rest)) = args.split_first() else {
+            anyhow::bail!(USAGE);
+        };
+        let max_steps: usize = steps.parse().context(USAGE)?;
+        let mut seed = None;
+        let mut runs = 1;
+        while let [flag @ ("--seed" | "--runs"), value, tail @ ..] = rest {
+            let value = value
+                .parse()
+                .with_context(|| format!("{} needs a number", flag))?;
+            if *flag == "--seed" {
+                seed = Some(value);
+            } else {
+                runs = value as usize;
+            }
+            rest = tail;
+        }
+        if let [flag @ ("--seed" | "--runs")] = rest {
+            anyhow::bail!("{} needs a number", flag);
+        }
+        if runs == 0 {
+            anyhow::bail!("--runs needs at least one run");
+        }
+
+        let theory_name = self
+            .state
+            .theory_name()
+            .ok_or_else(|| anyhow::anyhow!("No theory loaded. Use 'load <theory>' first."))?;
+        let theory = self.registry.get(theory_name)?;
+        // A term given here is not explored, so it may reach any number of terms
+        let start = if rest.is_empty() {
+            self.state
+                .current_term()
+                .ok_or_else(|| anyhow::anyhow!("No term loaded. Give one, or use 'term: <expr>'."))?
+                .clone_box()
+        } else {
+            theory.parse_term(&rest.join(" "))?
+        };
+        let seed = seed.unwrap_or_else(rand::random);
+
+        say!(self);
+        if runs == 1 {
+            let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+            let walk = walk(theory, start.as_ref(), max_steps, &mut rng)?;
+            say!(
+                self,
+                "{} (seed {}): {} step(s), {}",
+                "Random walk".bold(),
+                seed,
+                walk.steps(),
+                if walk.normal_form {
+                    "reached a normal form"
+                } else {
+                    "stopped"
+                }
+            );
+            say!(self);
+            for (idx, term) in walk.terms.iter().enumerate() {
+                if idx > 0 {
+                    say!(self, "      {}", "─→".yellow());
+                }
+                say!(self, "  {} {}", format!("[{}]", idx).dimmed(), term.green());
+            }
+        } else {
+            const TOP_STATES: usize = 10;
+            let simulation = Simulation::run(theory, start.as_ref(), max_steps, runs, seed)?;
+            say!(
+                self,
+                "{} (seed {}): {} runs of up to {} steps",
+                "Simulation".bold(),
+                seed,
+                runs,
+                max_steps
+            );
+            let mean = simulation
+                .mean_steps_to_normal_form()
+                .map_or(String::new(), |mean| format!(", in {:.2} steps on average", mean));
+            say!(
+                self,
+                "  Reached a normal form: {} of {}{}",
+                simulation.normal_forms_reached(),
+                runs,
+                mean
+            );
+            say!(self);
+
+            let states = simulation.final_states();
+            say!(
+                self,
+                "{} ({} distinct{}):",
+                "Final states".bold(),
+                states.len(),
+                if states.len() > TOP_STATES {
+                    ", most frequent first"
+                } else {
+                    ""
+                }
+            );
+            for (term, count, normal_form) in states.into_iter().take(TOP_STATES) {
+                let share = 100.0 * count as f64 / runs as f64;
+                let stopped = if normal_form { "" } else { " (stopped)" };
+                say!(self, "  {:>6} {:>6.1}%  {}{}", count, share, term.green(), stopped.dimmed());
+            }
+        }
+        say!(self);
+        Ok(())
+    }
+
+    fn print_path(&self, results: &AscentResults, path: &RewritePath) {
+        say!(self);
+        say!(self, "  {} {}", "[0]".dimmed(), display_of(results, path.start).green());
+        for (idx, rewrite) in path.rewrites.iter().enumerate() {
+            let rule = rewrite.rule_name.as_deref().unwrap_or("");
+            say!(self, "      {}", format!("─{}→", rule).yellow());
+            say!(
+                self,
+                "  {} {}",
+                format!("[{}]", idx + 1).dimmed(),
+                display_of(results, rewrite.to_id).green()
+            );
+        }
+    }
+
     fn cmd_example(&mut self, args: &[&str]) -> Result<()> {
         if args.is_empty() {
             anyhow::bail!("Usage: example <name>\nUse 'list-examples' to see available examples.");
@@ -502,10 +1304,10 @@ fn cmd_example(&mut self, args: &[&str]) -> Result<()> {
             )
         })?;
 
-        println!();
-        println!("{} {}", "Example:".bold(), example.name.cyan());
-        println!("{} {}", "Description:".bold(), example.description);
-        println!();
+        say!(self);
+        say!(self, "{} {}", "Example:".bold(), example.name.cyan());
+        say!(self, "{} {}", "Description:".bold(), example.description);
+        say!(self);
 
         // Parse and load the example
         self.cmd_parse_term(example.source)?;
@@ -513,10 +1315,12 @@ fn cmd_example(&mut self, args: &[&str]) -> Result<()> {
         Ok(())
     }
 
-    fn cmd_list_examples(&self, theory_name: &TheoryName) -> Result<()> {
-        println!();
-        println!("{}", "Available Examples:".bold());
-        println!();
+    fn cmd_list_examples(&self, theory_name: &str) -> Result<()> {
+        let theory_name = TheoryName::from_name(theory_name);
+
+        say!(self);
+        say!(self, "{}", "Available Examples:".bold());
+        say!(self);
 
         // Group by category
         for &category in &[
@@ -528,19 +1332,28 @@ fn cmd_list_examples(&self, theory_name: &TheoryName) -> Result<()> {
             ExampleCategory::Performance,
             ExampleCategory::EdgeCase,
         ] {
-            let examples = Example::by_theory_and_category(*theory_name, category);
+            let examples = theory_name
+                .map(|theory| Example::by_theory_and_category(theory, category))
+                .unwrap_or_default();
             if !examples.is_empty() {
-                println!("{}", format!("  {:?}:", category).yellow());
+                say!(self, "{}", format!("  {:?}:", category).yellow());
                 for ex in examples {
-                    println!("    {} - {}", ex.name.cyan(), ex.description.dimmed());
+                    say!(self, "    {} - {}", ex.name.cyan(), ex.description.dimmed());
                 }
-                println!();
+                say!(self);
             }
         }
 
-        println!("Use {} to load an example.", "example <name>".green());
-        println!();
+        say!(self, "Use {} to load an example.", "example <name>".green());
+        say!(self);
 
         Ok(())
     }
 }
+
+/// The display of a term of the graph
+fn display_of(results: &AscentResults, term_id: u64) -> &str {
+    results
+        .term(term_id)
+        .map_or("<unknown>", |t| t.display.as_str())
+}
@@ -0,0 +1,208 @@
+//! Saved REPL sessions (`save` / `restore`)
+//!
+//! A session file is JSON: the theory (and the `.mettail` file it came from,
+//! if it was loaded with `load-file`), the navigation history with the
+//! current position, and optionally the explored `AscentResults`. With the
+//! results, `restore` resumes without running Ascent; without them, the file
+//! is small and `restore` replays the history's commands, re-exploring the
+//! initial term.
+
+use crate::state::{HistoryEntry, ReplState};
+use crate::theory::AscentResults;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// The version written by `save`; `restore` rejects other versions
+const VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Session {
+    pub version: u32,
+    pub theory: String,
+    /// The file an interpreted theory was loaded from
+    pub theory_file: Option<PathBuf>,
+    pub history: Vec<HistoryEntry>,
+    pub history_index: usize,
+    pub results: Option<AscentResults>,
+}
+
+impl Session {
+    /// The session in `state`, whose theory was loaded from `theory_file` if given
+    pub fn capture(
+        state: &ReplState,
+        theory_file: Option<&Path>,
+        include_results: bool,
+    ) -> Result<Self> {
+        let Some(theory) = state.theory_name() else {
+            bail!("No theory loaded. Use 'load <name>' first.");
+        };
+        Ok(Session {
+            version: VERSION,
+            theory: theory.to_string(),
+            theory_file: theory_file.map(Path::to_path_buf),
+            history: state.history().to_vec(),
+            history_index: state.history_index(),
+            results: state.ascent_results().filter(|_| include_results).cloned(),
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string(self)?;
+        std::fs::write(path, json).with_context(|| format!("Cannot write {}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read {}", path.display()))?;
+        let session: Session = serde_json::from_str(&json)
+            .with_context(|| format!("{} is not a saved session", path.display()))?;
+        if session.version != VERSION {
+            bail!(
+                "{} was saved by an incompatible version (session format {}, expected {})",
+                path.display(),
+                session.version,
+                VERSION
+            );
+        }
+        if !session.history.is_empty() && session.history_index >= session.history.len() {
+            bail!("{} has no history entry {}", path.display(), session.history_index);
+        }
+        Ok(session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::registry::build_registry;
+    use crate::repl::Repl;
+
+    fn session_file(name: &str) -> String {
+        let path =
+            std::env::temp_dir().join(format!("mettail-{}-{}.json", name, std::process::id()));
+        path.to_string_lossy().into_owned()
+    }
+
+    /// A REPL two steps into a term without free variables, whose display
+    /// (unlike one with names) does not depend on the variables' identities
+    fn explored_repl() -> Repl {
+        let mut repl = Repl::batch(build_registry().unwrap()).unwrap();
+        for command in [
+            "load rhocalc",
+            "term: {@(0)!(0) | for(@(0)->x){*(x)}}",
+            "apply 0",
+            "apply 0",
+            "back",
+        ] {
+            repl.execute(command).unwrap();
+        }
+        repl
+    }
+
+    fn assert_same_session(a: &Repl, b: &Repl) {
+        let (a, b) = (a.state(), b.state());
+        assert_eq!(a.theory_name(), b.theory_name());
+        assert_eq!(a.history_index(), b.history_index());
+        let displays = |s: &crate::state::ReplState| {
+            s.history()
+                .iter()
+                .map(|e| e.display.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(displays(a), displays(b));
+        assert_eq!(
+            a.ascent_results().unwrap().all_terms.len(),
+            b.ascent_results().unwrap().all_terms.len()
+        );
+    }
+
+    #[test]
+    fn test_save_and_restore_with_results() {
+        let repl = &mut explored_repl();
+        let path = session_file("with-results");
+        repl.execute(&format!("save {}", path)).unwrap();
+
+        let mut restored = Repl::batch(build_registry().unwrap()).unwrap();
+        restored.execute(&format!("restore {}", path)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_same_session(repl, &restored);
+        assert_eq!(restored.state().current_graph_id(), repl.state().current_graph_id());
+        // Navigation continues in the saved graph
+        restored.execute("apply 0").unwrap();
+        assert_eq!(restored.state().history()[2].display, "{0}");
+    }
+
+    #[test]
+    fn test_save_and_restore_without_results() {
+        let repl = &mut explored_repl();
+        let path = session_file("without-results");
+        repl.execute(&format!("save {} --no-results", path))
+            .unwrap();
+        assert!(!std::fs::read_to_string(&path)
+            .unwrap()
+            .contains("all_terms"));
+
+        let mut restored = Repl::batch(build_registry().unwrap()).unwrap();
+        restored.execute(&format!("restore {}", path)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_same_session(repl, &restored);
+        restored.execute("forward").unwrap();
+        assert_eq!(restored.state().history_index(), 2);
+    }
+
+    #[test]
+    fn test_restore_replays_by_display() {
+        let repl = &mut explored_repl();
+        let path = session_file("numbered");
+        repl.execute(&format!("save {} --no-results", path))
+            .unwrap();
+        // As written before steps were recorded by term: rewrite numbers
+        // from another run's graph order
+        let saved = std::fs::read_to_string(&path)
+            .unwrap()
+            .replace("goto-term {*(@(0))}", "apply 7")
+            .replace("goto-term {0}", "apply 7");
+        assert_eq!(saved.matches("apply 7").count(), 2);
+        std::fs::write(&path, saved).unwrap();
+
+        let mut restored = Repl::batch(build_registry().unwrap()).unwrap();
+        restored.execute(&format!("restore {}", path)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_same_session(repl, &restored);
+    }
+
+    #[test]
+    fn test_history_replays_by_term() {
+        let repl = &mut explored_repl();
+        repl.execute("forward").unwrap();
+        let script = repl.state().script();
+        // Steps name their targets, not their positions in the graph's order
+        assert_eq!(script[1..], ["goto-term {*(@(0))}", "goto-term {0}"]);
+
+        let mut replayed = Repl::batch(build_registry().unwrap()).unwrap();
+        replayed.execute("load rhocalc").unwrap();
+        for command in &script {
+            replayed.execute(command).unwrap();
+        }
+        assert_same_session(repl, &replayed);
+        assert_eq!(
+            replayed.state().history()[1].rewrite_applied,
+            repl.state().history()[1].rewrite_applied
+        );
+
+        // A term outside the graph is not reached
+        assert!(replayed.execute("goto-term {@(1)!(0)}").is_err());
+    }
+
+    #[test]
+    fn test_restore_rejects_other_files() {
+        let path = session_file("invalid");
+        std::fs::write(&path, "{\"version\": 1}").unwrap();
+        let mut repl = Repl::batch(build_registry().unwrap()).unwrap();
+        let error = repl.execute(&format!("restore {}", path)).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(error.to_string().contains("is not a saved session"), "{}", error);
+    }
+}
@@ -0,0 +1,164 @@
+//! Random walks over rewrites (`simulate`)
+//!
+//! A walk repeatedly takes one of the current term's rewrites, chosen
+//! uniformly, until it reaches a normal form or its step limit. It computes
+//! only the rewrites of the terms it visits ([`Theory::step`]), so it works
+//! where exploring every reachable term would not finish. A simulation runs
+//! many walks from one term, with aggregate statistics of where they end.
+//!
+//! Rewrites are ordered by display before one is chosen, so walks with the
+//! same seed take the same steps.
+
+use crate::theory::{Term, Theory};
+use anyhow::Result;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+/// The terms one walk visited
+#[derive(Debug, Clone)]
+pub struct Walk {
+    /// The displays of the terms, from the start
+    pub terms: Vec<String>,
+
+    /// Whether the last term is a normal form (rather than the walk running
+    /// out of steps)
+    pub normal_form: bool,
+}
+
+impl Walk {
+    /// The number of rewrites taken
+    pub fn steps(&self) -> usize {
+        self.terms.len() - 1
+    }
+
+    /// The term the walk ended at
+    pub fn last(&self) -> &str {
+        self.terms.last().map_or("", String::as_str)
+    }
+}
+
+/// Take up to `max_steps` random rewrites from `start`
+pub fn walk(
+    theory: &dyn Theory,
+    start: &dyn Term,
+    max_steps: usize,
+    rng: &mut impl Rng,
+) -> Result<Walk> {
+    let mut current = start.clone_box();
+    let mut terms = vec![current.to_string()];
+    for _ in 0..max_steps {
+        let mut targets = theory.step(current.as_ref())?;
+        if targets.is_empty() {
+            return Ok(Walk { terms, normal_form: true });
+        }
+        targets.sort_by_cached_key(|target| target.to_string());
+        current = targets.swap_remove(rng.gen_range(0..targets.len()));
+        terms.push(current.to_string());
+    }
+    let normal_form = theory.step(current.as_ref())?.is_empty();
+    Ok(Walk { terms, normal_form })
+}
+
+/// Walks from one term
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    /// The seed of the walks' random choices
+    pub seed: u64,
+    pub walks: Vec<Walk>,
+}
+
+impl Simulation {
+    /// Take `runs` walks of up to `max_steps` rewrites from `start`
+    pub fn run(
+        theory: &dyn Theory,
+        start: &dyn Term,
+        max_steps: usize,
+        runs: usize,
+        seed: u64,
+    ) -> Result<Self> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let walks = (0..runs)
+            .map(|_| walk(theory, start, max_steps, &mut rng))
+            .collect::<Result<_>>()?;
+        Ok(Self { seed, walks })
+    }
+
+    /// The terms walks ended at, with how many ended there and whether they
+    /// are normal forms, most frequent first
+    pub fn final_states(&self) -> Vec<(&str, usize, bool)> {
+        let mut counts: HashMap<&str, (usize, bool)> = HashMap::new();
+        for walk in &self.walks {
+            counts.entry(walk.last()).or_insert((0, walk.normal_form)).0 += 1;
+        }
+        let mut states: Vec<_> = counts
+            .into_iter()
+            .map(|(term, (count, normal_form))| (term, count, normal_form))
+            .collect();
+        states.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        states
+    }
+
+    /// The number of walks that reached a normal form
+    pub fn normal_forms_reached(&self) -> usize {
+        self.walks.iter().filter(|walk| walk.normal_form).count()
+    }
+
+    /// The mean number of steps of the walks that reached a normal form
+    pub fn mean_steps_to_normal_form(&self) -> Option<f64> {
+        let reached = self.normal_forms_reached();
+        if reached == 0 {
+            return None;
+        }
+        let steps: usize = self
+            .walks
+            .iter()
+            .filter(|walk| walk.normal_form)
+            .map(Walk::steps)
+            .sum();
+        Some(steps as f64 / reached as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theories::RhoCalc;
+
+    #[test]
+    fn test_walk_to_normal_form() {
+        let start = RhoCalc
+            .parse_term("{@(0)!(0) | for(@(0)->x){*(x)}}")
+            .unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let walk = walk(&RhoCalc, start.as_ref(), 10, &mut rng).unwrap();
+        assert_eq!(walk.terms[1..], ["{*(@(0))}", "{0}"]);
+        assert_eq!((walk.steps(), walk.normal_form), (2, true));
+
+        let stopped = super::walk(&RhoCalc, start.as_ref(), 1, &mut rng).unwrap();
+        assert_eq!((stopped.last(), stopped.normal_form), ("{*(@(0))}", false));
+    }
+
+    #[test]
+    fn test_simulation_statistics() {
+        // The output on `a` is consumed by one of the two inputs
+        let start = RhoCalc
+            .parse_term("{a!(0) | for(a->x){b!(0)} | for(a->y){c!(0)}}")
+            .unwrap();
+        let simulation = Simulation::run(&RhoCalc, start.as_ref(), 10, 40, 7).unwrap();
+
+        let states = simulation.final_states();
+        assert_eq!(states.len(), 2);
+        assert_eq!(states.iter().map(|(_, count, _)| count).sum::<usize>(), 40);
+        assert!(states
+            .iter()
+            .all(|&(_, count, normal_form)| count > 0 && normal_form));
+        assert_eq!(simulation.normal_forms_reached(), 40);
+        assert_eq!(simulation.mean_steps_to_normal_form(), Some(1.0));
+
+        // The same seed takes the same walks
+        let again = Simulation::run(&RhoCalc, start.as_ref(), 10, 40, 7).unwrap();
+        assert_eq!(again.final_states(), states);
+    }
+}
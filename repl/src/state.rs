@@ -1,11 +1,11 @@
-use crate::examples::TheoryName;
-use crate::theory::{AscentResults, Term};
+use crate::theory::{AscentResults, ExploreStats, Term};
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 
 /// The current state of the REPL session
 pub struct ReplState {
     /// The name of the currently loaded theory
-    theory_name: Option<TheoryName>,
+    theory_name: Option<String>,
 
     /// The current term being explored
     current_term: Option<Box<dyn Term>>,
@@ -21,14 +21,20 @@ pub struct ReplState {
 
     /// Cached Ascent results
     ascent_results: Option<AscentResults>,
+
+    /// Statistics of the Ascent run that produced the results, if the theory keeps them
+    explore_stats: Option<ExploreStats>,
 }
 
 /// An entry in the navigation history
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HistoryEntry {
     pub term_id: u64,
     pub display: String,
     pub rewrite_applied: Option<String>,
+    /// The command that reaches this entry again (`term: ...`, or
+    /// `goto-term ...` for a step in the graph)
+    pub command: String,
 }
 
 impl ReplState {
@@ -41,27 +47,32 @@ pub fn new() -> Self {
             history: Vec::new(),
             history_idx: 0,
             ascent_results: None,
+            explore_stats: None,
         }
     }
 
     /// Load a theory by name
-    pub fn load_theory(&mut self, name: TheoryName) {
-        self.theory_name = Some(name);
+    pub fn load_theory(&mut self, name: &str) {
+        self.theory_name = Some(name.to_string());
         self.current_term = None;
         self.current_graph_id = None;
         self.history.clear();
         self.history_idx = 0;
         self.ascent_results = None;
+        self.explore_stats = None;
     }
 
     /// Get the name of the current theory
-    pub fn theory_name(&self) -> Option<TheoryName> {
-        self.theory_name
+    pub fn theory_name(&self) -> Option<&str> {
+        self.theory_name.as_deref()
     }
 
     /// Set the current term (without running Ascent - that's done externally now)
+    ///
+    /// This starts a new history: its entries are all in one rewrite graph.
     pub fn set_term(&mut self, term: Box<dyn Term>, results: AscentResults) -> Result<()> {
         let graph_id = term.term_id();
+        self.history.clear();
         self.set_term_with_id(term, results, graph_id)
     }
 
@@ -72,21 +83,60 @@ pub fn set_term_with_id(
         results: AscentResults,
         graph_id: u64,
     ) -> Result<()> {
-        // Update state
-        self.current_term = Some(term.clone_box());
-        self.current_graph_id = Some(graph_id);
         self.ascent_results = Some(results);
+        let command = format!("term: {}", term);
+        self.step_to(term, graph_id, None, command);
+        Ok(())
+    }
 
-        // Add to history
+    /// Move to another term of the current rewrite graph, recording the step
+    ///
+    /// Entries after the current one (left by `go_back`) are discarded, as in
+    /// a browser.
+    pub fn step_to(
+        &mut self,
+        term: Box<dyn Term>,
+        graph_id: u64,
+        rewrite_applied: Option<String>,
+        command: String,
+    ) {
         let entry = HistoryEntry {
             term_id: graph_id,
             display: format!("{}", term),
-            rewrite_applied: None,
+            rewrite_applied,
+            command,
         };
+        self.history.truncate(self.history_idx + 1);
         self.history.push(entry);
         self.history_idx = self.history.len() - 1;
 
-        Ok(())
+        self.current_term = Some(term);
+        self.current_graph_id = Some(graph_id);
+    }
+
+    /// Make `term` the current term, for the current history entry
+    ///
+    /// Used after `go_back`, `go_forward` and `goto`, which only move in the
+    /// history; the caller re-parses the entry's display.
+    pub fn restore_term(&mut self, term: Box<dyn Term>) {
+        self.current_graph_id = self.history.get(self.history_idx).map(|e| e.term_id);
+        self.current_term = Some(term);
+    }
+
+    /// Replace the history and results with saved ones, making the entry at
+    /// `history_idx` (whose term is `term`) current
+    pub fn restore_history(
+        &mut self,
+        history: Vec<HistoryEntry>,
+        history_idx: usize,
+        results: AscentResults,
+        term: Box<dyn Term>,
+    ) {
+        self.history = history;
+        self.history_idx = history_idx;
+        self.ascent_results = Some(results);
+        self.explore_stats = None;
+        self.restore_term(term);
     }
 
     /// Get the current term's ID in the rewrite graph
@@ -104,6 +154,16 @@ pub fn ascent_results(&self) -> Option<&AscentResults> {
         self.ascent_results.as_ref()
     }
 
+    /// Set the statistics of the Ascent run that produced the current results
+    pub fn set_explore_stats(&mut self, stats: Option<ExploreStats>) {
+        self.explore_stats = stats;
+    }
+
+    /// Get the statistics of the Ascent run, if the theory keeps them
+    pub fn explore_stats(&self) -> Option<&ExploreStats> {
+        self.explore_stats.as_ref()
+    }
+
     /// Get the history
     pub fn history(&self) -> &[HistoryEntry] {
         &self.history
@@ -149,6 +209,18 @@ pub fn clear_history(&mut self) {
         self.history.clear();
         self.history_idx = 0;
     }
+
+    /// The commands that replay the history up to the current entry, after
+    /// loading the theory
+    pub fn script(&self) -> Vec<String> {
+        if self.history.is_empty() {
+            return Vec::new();
+        }
+        self.history[..=self.history_idx]
+            .iter()
+            .map(|entry| entry.command.clone())
+            .collect()
+    }
 }
 
 impl Default for ReplState {
@@ -156,3 +228,55 @@ fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theories::RhoCalc;
+    use crate::theory::Theory;
+
+    fn step(state: &mut ReplState, input: &str, command: &str) {
+        let term = RhoCalc.parse_term(input).unwrap();
+        let id = term.term_id();
+        state.step_to(term, id, Some("rw_proc".to_string()), command.to_string());
+    }
+
+    #[test]
+    fn test_history_navigation() {
+        let mut state = ReplState::new();
+        state.load_theory("rhocalc");
+        let term = RhoCalc.parse_term("*(@(*(@(0))))").unwrap();
+        state.set_term(term, AscentResults::empty()).unwrap();
+        step(&mut state, "*(@(0))", "goto-term *(@(0))");
+        step(&mut state, "0", "goto-term 0");
+
+        assert_eq!(state.history().len(), 3);
+        assert_eq!(state.go_back().unwrap().display, "*(@(0))");
+        assert_eq!(state.go_back().unwrap().command, "term: *(@(*(@(0))))");
+        assert!(state.go_back().is_none());
+        assert_eq!(state.go_forward().unwrap().display, "*(@(0))");
+        assert_eq!(state.goto(2).unwrap().display, "0");
+        assert!(state.goto(3).is_none());
+    }
+
+    #[test]
+    fn test_step_discards_forward_entries() {
+        let mut state = ReplState::new();
+        let term = RhoCalc.parse_term("*(@(*(@(0))))").unwrap();
+        state.set_term(term, AscentResults::empty()).unwrap();
+        step(&mut state, "*(@(0))", "goto-term *(@(0))");
+        step(&mut state, "0", "goto-term 0");
+
+        state.goto(0);
+        step(&mut state, "0", "goto-term 0");
+        assert_eq!(state.history().len(), 2);
+        assert_eq!(state.history_index(), 1);
+        assert_eq!(
+            state.script(),
+            vec!["term: *(@(*(@(0))))".to_string(), "goto-term 0".to_string()]
+        );
+
+        state.go_back();
+        assert_eq!(state.script().len(), 1);
+    }
+}
@@ -0,0 +1,88 @@
+//! The shape of an explored rewrite graph (`stats`)
+//!
+//! The run that explored it reports its own statistics (relation sizes, rule
+//! firings, iterations; see [`ExploreStats`](crate::theory::ExploreStats));
+//! these are about the result, and hold for every theory.
+
+use crate::theory::AscentResults;
+use std::collections::{HashMap, VecDeque};
+
+/// Counts and shape of a rewrite graph
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphStats {
+    pub terms: usize,
+    pub rewrites: usize,
+    pub normal_forms: usize,
+
+    /// Equivalence classes of more than one term
+    pub equivalence_classes: usize,
+
+    /// The most rewrites from one term
+    pub max_branching: usize,
+
+    /// The most rewrites needed to reach a term from the root, if known
+    pub depth: Option<usize>,
+}
+
+impl GraphStats {
+    /// The statistics of `results`, explored from the term `root`
+    pub fn of(results: &AscentResults, root: Option<u64>) -> Self {
+        let mut out_degree: HashMap<u64, usize> = HashMap::new();
+        for rewrite in &results.rewrites {
+            *out_degree.entry(rewrite.from_id).or_default() += 1;
+        }
+
+        Self {
+            terms: results.all_terms.len(),
+            rewrites: results.rewrites.len(),
+            normal_forms: results.normal_forms().len(),
+            equivalence_classes: results
+                .equivalences
+                .iter()
+                .filter(|class| class.term_ids.len() > 1)
+                .count(),
+            max_branching: out_degree.values().copied().max().unwrap_or(0),
+            depth: root.map(|root| depth(results, root)),
+        }
+    }
+}
+
+/// The greatest distance, in rewrites, from `root` to a term it reaches
+fn depth(results: &AscentResults, root: u64) -> usize {
+    let mut distances = HashMap::from([(root, 0)]);
+    let mut queue = VecDeque::from([root]);
+    while let Some(id) = queue.pop_front() {
+        let next = distances[&id] + 1;
+        for rewrite in results.rewrites.iter().filter(|r| r.from_id == id) {
+            distances.entry(rewrite.to_id).or_insert_with(|| {
+                queue.push_back(rewrite.to_id);
+                next
+            });
+        }
+    }
+    distances.into_values().max().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theories::RhoCalc;
+    use crate::theory::Theory;
+
+    #[test]
+    fn test_graph_stats() {
+        let term = RhoCalc
+            .parse_term("{@(0)!(0) | for(@(0)->x){*(x)}}")
+            .unwrap();
+        let root = term.term_id();
+        let results = RhoCalc.run_ascent(term).unwrap();
+
+        let stats = GraphStats::of(&results, Some(root));
+        assert_eq!(stats.terms, 3);
+        assert_eq!(stats.rewrites, 2);
+        assert_eq!(stats.normal_forms, 1);
+        assert_eq!(stats.max_branching, 1);
+        assert_eq!(stats.depth, Some(2));
+        assert_eq!(GraphStats::of(&results, None).depth, None);
+    }
+}
@@ -1,8 +1,6 @@
-use crate::examples::TheoryName;
-use crate::theory::{AscentResults, Rewrite, Term, TermInfo, Theory};
+use crate::theory::{term_id, AscentResults, Rewrite, Syntax, Term, TermInfo, Theory};
 use anyhow::Result;
 use std::cell::RefCell;
-use std::fmt;
 
 // Import the theory definition from the theories crate
 use mettail_theories::calculator::*;
@@ -12,38 +10,45 @@
 }
 
 /// Calculator theory implementation for REPL
+///
+/// Terms are evaluated against the variables assigned so far; the metadata is
+/// the generated `Calculator` impl's.
 pub struct CalculatorTheory;
 
 impl Theory for CalculatorTheory {
-    fn name(&self) -> TheoryName {
-        TheoryName::Calculator
+    fn name(&self) -> &str {
+        Calculator.name()
     }
 
     fn categories(&self) -> Vec<String> {
-        vec!["Int".to_string(), "Ident".to_string()]
+        Calculator.categories()
     }
 
     fn constructor_count(&self) -> usize {
-        5 // NumLit, Add, Sub, VarRef, Assign
+        Calculator.constructor_count()
     }
 
     fn equation_count(&self) -> usize {
-        0 // No equations defined
+        Calculator.equation_count()
     }
 
     fn rewrite_count(&self) -> usize {
-        1 // Variable substitution rewrite rule
+        Calculator.rewrite_count()
+    }
+
+    fn syntax(&self) -> Syntax {
+        Calculator.syntax()
     }
 
     fn parse_term(&self, input: &str) -> Result<Box<dyn Term>> {
-        mettail_runtime::clear_var_cache();
+        let vars = mettail_runtime::VarContext::new();
 
         let trimmed = input.trim();
 
         // Parse to Int AST
         let parser = calculator::IntParser::new();
         let expr = parser
-            .parse(trimmed)
+            .parse(&vars, trimmed)
             .map_err(|e| anyhow::anyhow!("Parse error: {:?}", e))?;
 
         // Check if it's an assignment
@@ -95,7 +100,7 @@ fn parse_term(&self, input: &str) -> Result<Box<dyn Term>> {
                 }
 
                 // Return the assignment term
-                Ok(Box::new(CalcTerm(expr_clone)) as Box<dyn Term>)
+                Ok(Box::new(CalculatorReplTerm(expr_clone)) as Box<dyn Term>)
             })
         } else {
             // Not an assignment - evaluate the expression using Ascent to get normal form
@@ -124,7 +129,7 @@ fn parse_term(&self, input: &str) -> Result<Box<dyn Term>> {
                     current = next.clone();
                 }
 
-                Ok(Box::new(CalcTerm(current)) as Box<dyn Term>)
+                Ok(Box::new(CalculatorReplTerm(current)) as Box<dyn Term>)
             })
         }
     }
@@ -134,8 +139,8 @@ fn run_ascent(&self, term: Box<dyn Term>) -> Result<AscentResults> {
 
         let calc_term = term
             .as_any()
-            .downcast_ref::<CalcTerm>()
-            .ok_or_else(|| anyhow::anyhow!("Expected CalcTerm"))?;
+            .downcast_ref::<CalculatorReplTerm>()
+            .ok_or_else(|| anyhow::anyhow!("Expected CalculatorReplTerm"))?;
 
         let initial_int = calc_term.0.clone();
 
@@ -164,7 +169,7 @@ fn run_ascent(&self, term: Box<dyn Term>) -> Result<AscentResults> {
         // Build term info (similar to rhocalc/ambient)
         let mut term_infos = Vec::new();
         for int_term in &all_ints {
-            let term_id = compute_term_id(int_term);
+            let term_id = term_id(int_term);
             let has_rewrites = rewrites.iter().any(|(from, _)| from == int_term);
 
             term_infos.push(TermInfo {
@@ -178,8 +183,8 @@ fn run_ascent(&self, term: Box<dyn Term>) -> Result<AscentResults> {
         let rewrite_list: Vec<Rewrite> = rewrites
             .iter()
             .map(|(from, to)| Rewrite {
-                from_id: compute_term_id(from),
-                to_id: compute_term_id(to),
+                from_id: term_id(from),
+                to_id: term_id(to),
                 rule_name: Some("var_substitution".to_string()),
             })
             .collect();
@@ -192,7 +197,7 @@ fn run_ascent(&self, term: Box<dyn Term>) -> Result<AscentResults> {
     }
 
     fn format_term(&self, term: &dyn Term) -> String {
-        if let Some(calc_term) = term.as_any().downcast_ref::<CalcTerm>() {
+        if let Some(calc_term) = term.as_any().downcast_ref::<CalculatorReplTerm>() {
             // Try to evaluate the term
             match std::panic::catch_unwind(|| calc_term.0.eval()) {
                 Ok(value) => format!("{}", value),
@@ -203,51 +208,3 @@ fn format_term(&self, term: &dyn Term) -> String {
         }
     }
 }
-
-/// Wrapper for Int AST that implements Term
-#[derive(Clone)]
-struct CalcTerm(Int);
-
-impl Term for CalcTerm {
-    fn clone_box(&self) -> Box<dyn Term> {
-        Box::new(self.clone())
-    }
-
-    fn term_id(&self) -> u64 {
-        compute_term_id(&self.0)
-    }
-
-    fn term_eq(&self, other: &dyn Term) -> bool {
-        if let Some(other_calc) = other.as_any().downcast_ref::<CalcTerm>() {
-            self.0 == other_calc.0
-        } else {
-            false
-        }
-    }
-
-    fn as_any(&self) -> &dyn std::any::Any {
-        self
-    }
-}
-
-impl fmt::Display for CalcTerm {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0)
-    }
-}
-
-impl fmt::Debug for CalcTerm {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self.0)
-    }
-}
-
-/// Compute a unique ID for an Int term
-fn compute_term_id(term: &Int) -> u64 {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-
-    let mut hasher = DefaultHasher::new();
-    term.hash(&mut hasher);
-    hasher.finish()
-}
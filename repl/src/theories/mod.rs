@@ -1,14 +1,11 @@
 // Theory implementations for the REPL
 //
-// Each theory wrapper implements the Theory trait, providing:
-// - Parsing (using the theories crate)
-// - Ascent execution
-// - Result extraction
+// Theories get a generated `Theory` impl from `theory!` (see
+// `mettail_runtime::repl`). Those needing custom evaluation wrap it:
+// - Calculator: evaluates against an environment of assigned variables
 
-pub mod ambient;
 pub mod calculator;
-pub mod rhocalc;
 
-pub use ambient::AmbCalculusTheory;
 pub use calculator::CalculatorTheory;
-pub use rhocalc::RhoCalculusTheory;
+pub use mettail_theories::ambient::Ambient;
+pub use mettail_theories::rhocalc::RhoCalc;
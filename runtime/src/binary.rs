@@ -0,0 +1,550 @@
+//! Compact binary term encoding
+//!
+//! Generated theories provide `to_bytes`/`from_bytes` on each exported category,
+//! built on the [`BinaryWriter`] and [`BinaryReader`] here. A document is:
+//!
+//! ```text
+//! magic "MTLB" | version | theory name | schema fingerprint
+//! string table | free variable table | root term
+//! ```
+//!
+//! Integers are LEB128 varints (zigzag for signed values). Terms are written
+//! depth-first; each node is either `0, tag, fields...` or `id + 1`, a reference
+//! to the `id`th node of the same category written earlier, so repeated
+//! subterms are stored once. Tags are constructor indices within the category,
+//! and the schema fingerprint changes whenever the theory's constructors do.
+//!
+//! Variable names are interned in the string table. Free variables are written
+//! as indices into the free variable table, which keeps distinct variables
+//! distinct even when they share a name; bound variables are de Bruijn indices.
+
+use crate::{Binder, FreeVar, Var, VarContext};
+use moniker::{BinderIndex, BoundVar, ScopeOffset};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Version of the binary format written by [`BinaryWriter`]
+pub const BINARY_FORMAT_VERSION: u8 = 1;
+
+const MAGIC: &[u8; 4] = b"MTLB";
+
+/// Why a byte string could not be decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input does not start with the format's magic bytes
+    BadMagic,
+    /// The input was written by an unsupported format version
+    UnsupportedVersion(u8),
+    /// The input was written by a different theory
+    TheoryMismatch { expected: String, found: String },
+    /// The theory's constructors changed since the input was written
+    SchemaMismatch,
+    /// The input ended early
+    UnexpectedEof,
+    /// Bytes remain after the root term
+    TrailingBytes(usize),
+    /// Unknown constructor tag for a category
+    InvalidTag { category: &'static str, tag: u32 },
+    /// Reference to a node, string or variable that has not been defined
+    InvalidReference(u32),
+    /// A string is not valid UTF-8
+    InvalidUtf8,
+    /// A value is out of range for its type
+    InvalidValue,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::BadMagic => write!(f, "not a MeTTaIL binary term"),
+            DecodeError::UnsupportedVersion(v) => {
+                write!(f, "unsupported binary format version {}", v)
+            },
+            DecodeError::TheoryMismatch { expected, found } => {
+                write!(f, "term was written by theory {}, expected {}", found, expected)
+            },
+            DecodeError::SchemaMismatch => {
+                write!(f, "term was written by a different version of the theory")
+            },
+            DecodeError::UnexpectedEof => write!(f, "unexpected end of input"),
+            DecodeError::TrailingBytes(n) => write!(f, "{} trailing bytes after term", n),
+            DecodeError::InvalidTag { category, tag } => {
+                write!(f, "invalid constructor tag {} for {}", tag, category)
+            },
+            DecodeError::InvalidReference(r) => write!(f, "invalid reference {}", r),
+            DecodeError::InvalidUtf8 => write!(f, "invalid UTF-8 in string"),
+            DecodeError::InvalidValue => write!(f, "value out of range"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// A stable fingerprint of a theory's constructors (FNV-1a over `signature`)
+///
+/// Generated code calls this at compile time with one line per constructor, so
+/// reordering, adding or removing constructors invalidates old encodings.
+pub const fn schema_fingerprint(signature: &str) -> u64 {
+    let bytes = signature.as_bytes();
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        i += 1;
+    }
+    hash
+}
+
+//=============================================================================
+// WRITER
+//=============================================================================
+
+/// Encoder state for one document: the body written so far and the interned
+/// strings and free variables it refers to
+#[derive(Debug, Default)]
+pub struct BinaryWriter {
+    body: Vec<u8>,
+    strings: Vec<String>,
+    string_ids: HashMap<String, u32>,
+    /// String id + 1 of each free variable's name, 0 if unnamed
+    free_vars: Vec<u32>,
+    free_var_ids: HashMap<FreeVar<String>, u32>,
+}
+
+impl BinaryWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write an unsigned varint
+    pub fn write_u64(&mut self, value: u64) {
+        write_varint_to(&mut self.body, value);
+    }
+
+    /// Write an unsigned varint
+    pub fn write_u32(&mut self, value: u32) {
+        self.write_u64(value as u64)
+    }
+
+    /// Write a signed varint (zigzag encoded)
+    pub fn write_i64(&mut self, value: i64) {
+        self.write_u64(((value << 1) ^ (value >> 63)) as u64)
+    }
+
+    /// Write a length-prefixed string inline
+    pub fn write_str(&mut self, value: &str) {
+        write_str_to(&mut self.body, value);
+    }
+
+    /// Write an optional name as a string table reference
+    fn write_name(&mut self, name: Option<&String>) {
+        let id = name.map_or(0, |name| self.intern(name) + 1);
+        self.write_u32(id);
+    }
+
+    fn intern(&mut self, name: &str) -> u32 {
+        if let Some(&id) = self.string_ids.get(name) {
+            return id;
+        }
+        let id = self.strings.len() as u32;
+        self.strings.push(name.to_string());
+        self.string_ids.insert(name.to_string(), id);
+        id
+    }
+
+    /// Write a free variable as a free variable table reference
+    pub fn write_free_var(&mut self, var: &FreeVar<String>) {
+        let id = match self.free_var_ids.get(var) {
+            Some(&id) => id,
+            None => {
+                let name = var
+                    .pretty_name
+                    .as_ref()
+                    .map_or(0, |name| self.intern(name) + 1);
+                let id = self.free_vars.len() as u32;
+                self.free_vars.push(name);
+                self.free_var_ids.insert(var.clone(), id);
+                id
+            },
+        };
+        self.write_u32(id);
+    }
+
+    /// Write a variable: free variables by reference, bound ones by de Bruijn index
+    pub fn write_var(&mut self, var: &Var<String>) {
+        match var {
+            Var::Free(free) => {
+                self.write_u32(0);
+                self.write_free_var(free);
+            },
+            Var::Bound(bound) => {
+                self.write_u32(1);
+                self.write_u32(bound.scope.0);
+                self.write_u32(bound.binder.0);
+                self.write_name(bound.pretty_name.as_ref());
+            },
+        }
+    }
+
+    /// Write a scope's binder; only its name is kept
+    pub fn write_binder(&mut self, binder: &Binder<String>) {
+        self.write_name(binder.0.pretty_name.as_ref());
+    }
+
+    /// Assemble the document: header, tables and the body written so far
+    pub fn finish(self, theory: &str, fingerprint: u64) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.body.len() + 64);
+        out.extend_from_slice(MAGIC);
+        out.push(BINARY_FORMAT_VERSION);
+        write_str_to(&mut out, theory);
+        out.extend_from_slice(&fingerprint.to_le_bytes());
+        write_varint_to(&mut out, self.strings.len() as u64);
+        for s in &self.strings {
+            write_str_to(&mut out, s);
+        }
+        write_varint_to(&mut out, self.free_vars.len() as u64);
+        for &name in &self.free_vars {
+            write_varint_to(&mut out, name as u64);
+        }
+        out.extend_from_slice(&self.body);
+        out
+    }
+}
+
+fn write_varint_to(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn write_str_to(out: &mut Vec<u8>, value: &str) {
+    write_varint_to(out, value.len() as u64);
+    out.extend_from_slice(value.as_bytes());
+}
+
+//=============================================================================
+// READER
+//=============================================================================
+
+/// Decoder state for one document
+#[derive(Debug)]
+pub struct BinaryReader<'b> {
+    bytes: &'b [u8],
+    pos: usize,
+    strings: Vec<String>,
+    free_vars: Vec<FreeVar<String>>,
+}
+
+impl<'b> BinaryReader<'b> {
+    /// Check the header of `bytes` and read its tables
+    ///
+    /// The first free variable with a given name is interned in `vars`, so
+    /// decoding alongside a parse shares its variables; further distinct
+    /// variables with the same name get fresh identities.
+    pub fn new(
+        bytes: &'b [u8],
+        theory: &str,
+        fingerprint: u64,
+        vars: &VarContext,
+    ) -> Result<Self, DecodeError> {
+        let mut reader = BinaryReader {
+            bytes,
+            pos: 0,
+            strings: Vec::new(),
+            free_vars: Vec::new(),
+        };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(DecodeError::BadMagic);
+        }
+        let version = reader.take(1)?[0];
+        if version != BINARY_FORMAT_VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+        let found = reader.read_string()?;
+        if found != theory {
+            return Err(DecodeError::TheoryMismatch { expected: theory.to_string(), found });
+        }
+        let stored = u64::from_le_bytes(reader.take(8)?.try_into().expect("8 bytes"));
+        if stored != fingerprint {
+            return Err(DecodeError::SchemaMismatch);
+        }
+
+        let string_count = reader.read_len()?;
+        for _ in 0..string_count {
+            let s = reader.read_string()?;
+            reader.strings.push(s);
+        }
+
+        let var_count = reader.read_len()?;
+        let mut seen = std::collections::HashSet::new();
+        for _ in 0..var_count {
+            let var = match reader.read_name()? {
+                Some(name) if seen.insert(name.clone()) => vars.var(name),
+                Some(name) => FreeVar::fresh_named(name),
+                None => FreeVar::fresh_unnamed(),
+            };
+            reader.free_vars.push(var);
+        }
+
+        Ok(reader)
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'b [u8], DecodeError> {
+        let end = self.pos.checked_add(n).ok_or(DecodeError::UnexpectedEof)?;
+        let slice = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or(DecodeError::UnexpectedEof)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    /// Read an unsigned varint
+    pub fn read_u64(&mut self) -> Result<u64, DecodeError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError::InvalidValue)
+    }
+
+    /// Read an unsigned varint that must fit in a `u32`
+    pub fn read_u32(&mut self) -> Result<u32, DecodeError> {
+        u32::try_from(self.read_u64()?).map_err(|_| DecodeError::InvalidValue)
+    }
+
+    /// Read a signed (zigzag encoded) varint
+    pub fn read_i64(&mut self) -> Result<i64, DecodeError> {
+        let value = self.read_u64()?;
+        Ok(((value >> 1) as i64) ^ -((value & 1) as i64))
+    }
+
+    /// Read a collection length, rejecting lengths longer than the input
+    pub fn read_len(&mut self) -> Result<usize, DecodeError> {
+        let len = self.read_u64()?;
+        if len > (self.bytes.len() - self.pos) as u64 {
+            return Err(DecodeError::UnexpectedEof);
+        }
+        Ok(len as usize)
+    }
+
+    /// Read an element's multiplicity in a bag, which is at least 1
+    pub fn read_count(&mut self) -> Result<usize, DecodeError> {
+        match self.read_u64()? {
+            0 => Err(DecodeError::InvalidValue),
+            count => usize::try_from(count).map_err(|_| DecodeError::InvalidValue),
+        }
+    }
+
+    /// Read a length-prefixed inline string
+    pub fn read_string(&mut self) -> Result<String, DecodeError> {
+        let len = self.read_len()?;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| DecodeError::InvalidUtf8)
+    }
+
+    fn read_name(&mut self) -> Result<Option<String>, DecodeError> {
+        match self.read_u32()? {
+            0 => Ok(None),
+            id => self
+                .strings
+                .get(id as usize - 1)
+                .cloned()
+                .map(Some)
+                .ok_or(DecodeError::InvalidReference(id)),
+        }
+    }
+
+    /// Read a free variable table reference
+    pub fn read_free_var(&mut self) -> Result<FreeVar<String>, DecodeError> {
+        let id = self.read_u32()?;
+        self.free_vars
+            .get(id as usize)
+            .cloned()
+            .ok_or(DecodeError::InvalidReference(id))
+    }
+
+    /// Read a variable written by [`BinaryWriter::write_var`]
+    pub fn read_var(&mut self) -> Result<Var<String>, DecodeError> {
+        match self.read_u32()? {
+            0 => Ok(Var::Free(self.read_free_var()?)),
+            1 => Ok(Var::Bound(BoundVar {
+                scope: ScopeOffset(self.read_u32()?),
+                binder: BinderIndex(self.read_u32()?),
+                pretty_name: self.read_name()?,
+            })),
+            _ => Err(DecodeError::InvalidValue),
+        }
+    }
+
+    /// Read a scope's binder as a fresh variable with the written name
+    pub fn read_binder(&mut self) -> Result<Binder<String>, DecodeError> {
+        Ok(Binder(FreeVar::fresh(self.read_name()?)))
+    }
+
+    /// Check that the whole input was consumed
+    pub fn finish(self) -> Result<(), DecodeError> {
+        match self.bytes.len() - self.pos {
+            0 => Ok(()),
+            n => Err(DecodeError::TrailingBytes(n)),
+        }
+    }
+}
+
+//=============================================================================
+// NATIVE VALUES
+//=============================================================================
+
+/// Native values stored in terms (the `Integer` fields of native categories)
+pub trait BinaryValue: Sized {
+    fn write_binary(&self, writer: &mut BinaryWriter);
+    fn read_binary(reader: &mut BinaryReader) -> Result<Self, DecodeError>;
+}
+
+macro_rules! impl_binary_value_unsigned {
+    ($($t:ty),*) => {$(
+        impl BinaryValue for $t {
+            fn write_binary(&self, writer: &mut BinaryWriter) {
+                writer.write_u64(*self as u64)
+            }
+
+            fn read_binary(reader: &mut BinaryReader) -> Result<Self, DecodeError> {
+                <$t>::try_from(reader.read_u64()?).map_err(|_| DecodeError::InvalidValue)
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_binary_value_signed {
+    ($($t:ty),*) => {$(
+        impl BinaryValue for $t {
+            fn write_binary(&self, writer: &mut BinaryWriter) {
+                writer.write_i64(*self as i64)
+            }
+
+            fn read_binary(reader: &mut BinaryReader) -> Result<Self, DecodeError> {
+                <$t>::try_from(reader.read_i64()?).map_err(|_| DecodeError::InvalidValue)
+            }
+        }
+    )*};
+}
+
+impl_binary_value_unsigned!(u8, u16, u32, u64, usize);
+impl_binary_value_signed!(i8, i16, i32, i64, isize);
+
+impl BinaryValue for bool {
+    fn write_binary(&self, writer: &mut BinaryWriter) {
+        writer.write_u32(*self as u32)
+    }
+
+    fn read_binary(reader: &mut BinaryReader) -> Result<Self, DecodeError> {
+        match reader.read_u32()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DecodeError::InvalidValue),
+        }
+    }
+}
+
+impl BinaryValue for String {
+    fn write_binary(&self, writer: &mut BinaryWriter) {
+        writer.write_str(self)
+    }
+
+    fn read_binary(reader: &mut BinaryReader) -> Result<Self, DecodeError> {
+        reader.read_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FINGERPRINT: u64 = schema_fingerprint("Test");
+
+    fn reader<'b>(bytes: &'b [u8], vars: &VarContext) -> BinaryReader<'b> {
+        BinaryReader::new(bytes, "Test", FINGERPRINT, vars).unwrap()
+    }
+
+    #[test]
+    fn varints_round_trip() {
+        let mut writer = BinaryWriter::new();
+        let values = [0u64, 1, 127, 128, 300, u32::MAX as u64, u64::MAX];
+        for v in values {
+            writer.write_u64(v);
+        }
+        for v in [0i64, -1, 1, i64::MIN, i64::MAX] {
+            writer.write_i64(v);
+        }
+        let bytes = writer.finish("Test", FINGERPRINT);
+
+        let vars = VarContext::new();
+        let mut reader = reader(&bytes, &vars);
+        for v in values {
+            assert_eq!(reader.read_u64().unwrap(), v);
+        }
+        for v in [0i64, -1, 1, i64::MIN, i64::MAX] {
+            assert_eq!(reader.read_i64().unwrap(), v);
+        }
+        reader.finish().unwrap();
+    }
+
+    #[test]
+    fn variables_keep_identity() {
+        let vars = VarContext::new();
+        let x = vars.var("x");
+        let other_x = FreeVar::fresh_named("x");
+
+        let mut writer = BinaryWriter::new();
+        writer.write_free_var(&x);
+        writer.write_free_var(&other_x);
+        writer.write_free_var(&x);
+        let bytes = writer.finish("Test", FINGERPRINT);
+
+        let mut reader = reader(&bytes, &vars);
+        let (a, b, c) = (
+            reader.read_free_var().unwrap(),
+            reader.read_free_var().unwrap(),
+            reader.read_free_var().unwrap(),
+        );
+        assert_eq!(a, x);
+        assert_eq!(a, c);
+        assert_ne!(a, b);
+        assert_eq!(b.pretty_name.as_deref(), Some("x"));
+    }
+
+    #[test]
+    fn header_is_checked() {
+        let vars = VarContext::new();
+        let bytes = BinaryWriter::new().finish("Test", FINGERPRINT);
+
+        assert!(BinaryReader::new(&bytes, "Test", FINGERPRINT, &vars).is_ok());
+        assert_eq!(
+            BinaryReader::new(&bytes, "Other", FINGERPRINT, &vars).unwrap_err(),
+            DecodeError::TheoryMismatch {
+                expected: "Other".to_string(),
+                found: "Test".to_string()
+            }
+        );
+        assert_eq!(
+            BinaryReader::new(&bytes, "Test", FINGERPRINT + 1, &vars).unwrap_err(),
+            DecodeError::SchemaMismatch
+        );
+        assert_eq!(
+            BinaryReader::new(b"nope", "Test", FINGERPRINT, &vars).unwrap_err(),
+            DecodeError::BadMagic
+        );
+        assert_eq!(
+            BinaryReader::new(&bytes[..6], "Test", FINGERPRINT, &vars).unwrap_err(),
+            DecodeError::UnexpectedEof
+        );
+    }
+}
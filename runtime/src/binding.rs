@@ -5,47 +5,73 @@
 //! Ascent (which requires Hash for relations) and term generation
 //! (which requires Ord for enumeration).
 
+use crate::span::SpanTree;
+use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt;
 use std::hash::{Hash, Hasher};
-use std::sync::Mutex;
 
 // Re-export moniker types
 pub use moniker::{Binder, BoundPattern, BoundTerm, BoundVar, FreeVar, Var};
 
-// Variable cache for consistent variable identity within a parsing session
-lazy_static::lazy_static! {
-    static ref VAR_CACHE: Mutex<HashMap<String, FreeVar<String>>> =
-        Mutex::new(HashMap::new());
-}
-
-/// Get or create a variable from the cache
+/// Variable interning for one parse (or one generation run)
 ///
-/// This ensures that parsing the same variable name twice produces
-/// the same FreeVar instance, which is critical for correct variable
-/// identity in alpha-equivalence checking.
-pub fn get_or_create_var(name: impl Into<String>) -> FreeVar<String> {
-    let name = name.into();
-    let mut cache = VAR_CACHE.lock().unwrap();
-
-    cache
-        .entry(name.clone())
-        .or_insert_with(|| FreeVar::fresh_named(name))
-        .clone()
-}
-
-/// Clear the variable cache
+/// Parsing the same variable name twice within a context produces the same
+/// `FreeVar`, which is critical for correct variable identity in
+/// alpha-equivalence checking; separate contexts never share variables.
+/// Generated parsers take the context as a parameter:
 ///
-/// Call this before parsing a new term to ensure variables from
-/// different terms don't accidentally share identity.
-pub fn clear_var_cache() {
-    VAR_CACHE.lock().unwrap().clear();
+/// ```ignore
+/// let vars = VarContext::new();
+/// let term = rhocalc::ProcParser::new().parse(&vars, "a!(0)")?;
+/// ```
+///
+/// A context is `Send` but not `Sync`: each thread or session owns its own,
+/// so interning needs no locking.
+#[derive(Debug, Default)]
+pub struct VarContext {
+    vars: RefCell<HashMap<String, FreeVar<String>>>,
+    /// Constructors parsed so far, when recording spans (see `span.rs`)
+    pub(crate) spans: RefCell<Option<Vec<SpanTree>>>,
 }
 
-/// Get the current size of the variable cache
-pub fn var_cache_size() -> usize {
-    VAR_CACHE.lock().unwrap().len()
+impl VarContext {
+    /// Create an empty context
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The variable named `name` in this context, created on first use
+    pub fn var(&self, name: impl Into<String>) -> FreeVar<String> {
+        let name = name.into();
+        self.vars
+            .borrow_mut()
+            .entry(name.clone())
+            .or_insert_with(|| FreeVar::fresh_named(name))
+            .clone()
+    }
+
+    /// The variable named `name`, if it has been created in this context
+    pub fn get(&self, name: &str) -> Option<FreeVar<String>> {
+        self.vars.borrow().get(name).cloned()
+    }
+
+    /// Number of distinct variables in this context
+    pub fn len(&self) -> usize {
+        self.vars.borrow().len()
+    }
+
+    /// Whether no variables have been created in this context
+    pub fn is_empty(&self) -> bool {
+        self.vars.borrow().is_empty()
+    }
+
+    /// Exchange the variables of two contexts
+    #[cfg(feature = "serde")]
+    pub(crate) fn swap(&self, other: &VarContext) {
+        self.vars.swap(&other.vars)
+    }
 }
 
 //=============================================================================
@@ -59,27 +85,58 @@ pub fn var_cache_size() -> usize {
 /// - Using Scopes in HashMap-based data structures (Ascent relations)
 /// - Generating terms in canonical order
 ///
-/// The Hash implementation hashes both pattern and body, which is safe
-/// because Scope's PartialEq already compares these fields alpha-equivalently.
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Equality, hashing and ordering are all up to alpha-equivalence. A closed
+/// body is locally nameless: variables bound by this scope are de Bruijn
+/// `BoundVar`s, which compare and hash by index and ignore their pretty names.
+/// The binder names in the pattern are therefore irrelevant, and only the
+/// number of binders is compared, so `for(a->x){*x}` and `for(a->y){*y}` are
+/// equal, hash identically and order as `Equal`.
+#[derive(Debug, Clone)]
 pub struct Scope<P, T> {
     inner: moniker::Scope<P, T>,
 }
 
-impl<P: Hash, T: Hash> Hash for Scope<P, T> {
+/// Number of binders in a pattern, the only part of it that matters up to
+/// alpha-equivalence
+fn binder_count<P: BoundPattern<String>>(pattern: &P) -> usize {
+    let mut count = 0;
+    pattern.visit_binders(&mut |_| count += 1);
+    count
+}
+
+impl<P, T> PartialEq for Scope<P, T>
+where
+    P: BoundPattern<String>,
+    T: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.inner
+            .unsafe_pattern
+            .pattern_eq(&other.inner.unsafe_pattern)
+            && self.inner.unsafe_body == other.inner.unsafe_body
+    }
+}
+
+impl<P, T> Eq for Scope<P, T>
+where
+    P: BoundPattern<String>,
+    T: Eq,
+{
+}
+
+impl<P: BoundPattern<String>, T: Hash> Hash for Scope<P, T> {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        // Hash the pattern and body directly
-        // This is safe because Scope's PartialEq ensures
-        // that equal scopes have equal patterns and bodies (alpha-equivalently)
-        self.inner.unsafe_pattern.hash(state);
+        // Bound variables in the body hash by de Bruijn index, so the body hash
+        // is already name-independent; the pattern contributes its arity only
+        binder_count(&self.inner.unsafe_pattern).hash(state);
         self.inner.unsafe_body.hash(state);
     }
 }
 
 impl<P, T> PartialOrd for Scope<P, T>
 where
-    P: Clone + PartialEq + Eq + BoundPattern<String> + fmt::Debug,
-    T: Clone + PartialEq + Eq + BoundTerm<String> + fmt::Debug,
+    P: BoundPattern<String>,
+    T: Ord,
 {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -88,27 +145,15 @@ fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
 
 impl<P, T> Ord for Scope<P, T>
 where
-    P: Clone + PartialEq + Eq + BoundPattern<String> + fmt::Debug,
-    T: Clone + PartialEq + Eq + BoundTerm<String> + fmt::Debug,
+    P: BoundPattern<String>,
+    T: Ord,
 {
     fn cmp(&self, other: &Self) -> Ordering {
-        // Clone scopes to unbind without consuming
-        let (p1, t1) = self.clone().unbind();
-        let (p2, t2) = other.clone().unbind();
-
-        // Compare by pretty-printed representation
-        // (since Binder doesn't implement Ord directly)
-        let p1_str = format!("{:?}", p1);
-        let p2_str = format!("{:?}", p2);
-
-        match p1_str.cmp(&p2_str) {
-            Ordering::Equal => {
-                let t1_str = format!("{:?}", t1);
-                let t2_str = format!("{:?}", t2);
-                t1_str.cmp(&t2_str)
-            },
-            ord => ord,
-        }
+        // Compare the closed bodies directly rather than unbinding, which would
+        // freshen the binders and make the order depend on generated ids
+        binder_count(&self.inner.unsafe_pattern)
+            .cmp(&binder_count(&other.inner.unsafe_pattern))
+            .then_with(|| self.inner.unsafe_body.cmp(&other.inner.unsafe_body))
     }
 }
 
@@ -168,6 +213,14 @@ pub fn unsafe_body(&self) -> &T {
         &self.inner.unsafe_body
     }
 
+    /// Mutable access to the body (unsafe - preserves bound variables)
+    ///
+    /// Bound variables in the body are de Bruijn indices, so edits must keep
+    /// them pointing at the same binders.
+    pub fn unsafe_body_mut(&mut self) -> &mut T {
+        &mut self.inner.unsafe_body
+    }
+
     /// Construct a Scope from pattern and body directly (unsafe - no closing)
     ///
     /// This assumes the body already has the correct bound variable structure.
@@ -229,9 +282,10 @@ fn from(scope: moniker::Scope<P, T>) -> Self {
 /// - Using terms as keys in BTree collections
 /// - Canonical term ordering
 ///
-/// The Ord implementation compares variables by their pretty-printed names,
-/// providing a total order (NOT alpha-equivalence respecting).
-/// This is intentional - we want a total order for enumeration.
+/// The Ord implementation is consistent with moniker's alpha-equivalence
+/// respecting `PartialEq`: bound variables order by de Bruijn index (ignoring
+/// their pretty names) and before free variables, which order by name and
+/// then by unique id. Distinct free variables with the same name stay distinct.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[repr(transparent)]
 pub struct OrdVar(pub Var<String>);
@@ -244,8 +298,55 @@ fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
 
 impl Ord for OrdVar {
     fn cmp(&self, other: &Self) -> Ordering {
-        // Compare by Debug representation (variable name)
-        format!("{:?}", self.0).cmp(&format!("{:?}", other.0))
+        match (&self.0, &other.0) {
+            (Var::Bound(a), Var::Bound(b)) => (a.scope, a.binder).cmp(&(b.scope, b.binder)),
+            (Var::Bound(_), Var::Free(_)) => Ordering::Less,
+            (Var::Free(_), Var::Bound(_)) => Ordering::Greater,
+            (Var::Free(a), Var::Free(b)) => a
+                .pretty_name
+                .cmp(&b.pretty_name)
+                .then_with(|| unique_id(a).cmp(&unique_id(b))),
+        }
+    }
+}
+
+/// The numeric id of a free variable. `UniqueId` has neither `Ord` nor an
+/// accessor, but its derived `Hash` writes just its `u32`, which this reads back.
+fn unique_id(var: &FreeVar<String>) -> u64 {
+    let mut reader = IdReader(None);
+    var.unique_id.hash(&mut reader);
+    reader.finish()
+}
+
+/// Reads back a single hashed `u32`. Should the encoding of `UniqueId` ever
+/// change, other writes are folded in with FNV-1a, so the key stays
+/// deterministic per id instead of panicking.
+struct IdReader(Option<u64>);
+
+impl IdReader {
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+}
+
+impl Hasher for IdReader {
+    fn write(&mut self, bytes: &[u8]) {
+        let folded = bytes
+            .iter()
+            .fold(self.0.unwrap_or(Self::FNV_OFFSET), |h, &b| {
+                (h ^ u64::from(b)).wrapping_mul(Self::FNV_PRIME)
+            });
+        self.0 = Some(folded);
+    }
+
+    fn write_u32(&mut self, id: u32) {
+        match self.0 {
+            None => self.0 = Some(id.into()),
+            Some(_) => self.write(&id.to_le_bytes()),
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0.unwrap_or(Self::FNV_OFFSET)
     }
 }
 
@@ -296,3 +397,75 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:?}", self.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+
+    fn hash_of<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// `\name. name`
+    fn identity(name: &str) -> Scope<Binder<String>, OrdVar> {
+        let var = FreeVar::fresh_named(name);
+        Scope::new(Binder(var.clone()), OrdVar(Var::Free(var)))
+    }
+
+    #[test]
+    fn alpha_equivalent_scopes_are_equal() {
+        let x = identity("x");
+        let y = identity("y");
+        assert_eq!(x, y);
+        assert_eq!(hash_of(&x), hash_of(&y));
+        assert_eq!(x.cmp(&y), Ordering::Equal);
+    }
+
+    #[test]
+    fn scopes_differ_on_free_variables() {
+        let ctx = VarContext::new();
+        let a = Scope::new(Binder(FreeVar::fresh_named("x")), OrdVar(Var::Free(ctx.var("a"))));
+        let b = Scope::new(Binder(FreeVar::fresh_named("x")), OrdVar(Var::Free(ctx.var("b"))));
+        assert_ne!(a, b);
+        assert_ne!(a.cmp(&b), Ordering::Equal);
+        assert_ne!(a, identity("x"));
+    }
+
+    #[test]
+    fn ord_var_is_consistent_with_eq() {
+        let ctx = VarContext::new();
+        let a = OrdVar(Var::Free(ctx.var("a")));
+        let b = OrdVar(Var::Free(ctx.var("b")));
+        assert_eq!(a.cmp(&a.clone()), Ordering::Equal);
+        assert_eq!(a.cmp(&b), Ordering::Less);
+
+        // Same name, different variables
+        let other_a = OrdVar(Var::Free(FreeVar::fresh_named("a")));
+        assert_ne!(a, other_a);
+        assert_ne!(a.cmp(&other_a), Ordering::Equal);
+    }
+
+    #[test]
+    fn unique_ids_are_read_without_formatting() {
+        let first = FreeVar::fresh_named("x");
+        let second = FreeVar::fresh_named("x");
+        assert_eq!(unique_id(&first).to_string(), first.unique_id.to_string());
+        assert!(unique_id(&first) < unique_id(&second));
+    }
+
+    #[test]
+    fn id_reader_folds_other_writes() {
+        let read = |f: &dyn Fn(&mut IdReader)| {
+            let mut reader = IdReader(None);
+            f(&mut reader);
+            reader.finish()
+        };
+        assert_eq!(read(&|r| r.write_u32(7)), 7);
+        assert_eq!(read(&|r| r.write(b"abc")), read(&|r| r.write(b"abc")));
+        assert_ne!(read(&|r| r.write(b"abc")), read(&|r| r.write(b"abd")));
+        assert_ne!(read(&|r| r.write_u64(7)), read(&|r| r.write_u64(8)));
+    }
+}
@@ -0,0 +1,678 @@
+//! E-graph backend for equational reasoning
+//!
+//! This module provides a small egg-style e-graph: a union-find over
+//! e-class ids, a hashcons of canonical e-nodes, congruence closure via
+//! rebuilding (which repairs only the parents of merged e-classes), e-matching
+//! of simple patterns, and extraction of a smallest representative per e-class.
+//!
+//! Generated theories that select `equation_backend: egraph` define an
+//! e-node enum implementing [`Language`] and a typed wrapper around
+//! [`EGraph`] that converts terms to e-classes and back.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+use rustc_hash::FxHashMap;
+
+/// Identifier of an e-class
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Id(u32);
+
+impl From<usize> for Id {
+    fn from(n: usize) -> Id {
+        Id(n as u32)
+    }
+}
+
+impl From<Id> for usize {
+    fn from(id: Id) -> usize {
+        id.0 as usize
+    }
+}
+
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// An e-node language: one constructor per term constructor, with children
+/// replaced by e-class ids.
+pub trait Language: Clone + Eq + Hash + fmt::Debug {
+    /// The children of this node, in order
+    fn children(&self) -> Vec<Id>;
+
+    /// Rebuild this node with every child mapped through `f`
+    fn map_children(&self, f: impl FnMut(Id) -> Id) -> Self;
+
+    /// Whether two nodes share the same operator and leaf payload
+    /// (children are ignored)
+    fn matches(&self, other: &Self) -> bool;
+
+    /// Bring the node into canonical form after its children changed.
+    ///
+    /// Nodes for associative-commutative collections sort their children
+    /// here, so that `{a, b}` and `{b, a}` hash-cons to the same e-node.
+    fn normalize(&mut self) {}
+}
+
+//=============================================================================
+// UNION-FIND
+//=============================================================================
+
+#[derive(Debug, Clone, Default)]
+struct UnionFind {
+    parents: Vec<Id>,
+}
+
+impl UnionFind {
+    fn make_set(&mut self) -> Id {
+        let id = Id::from(self.parents.len());
+        self.parents.push(id);
+        id
+    }
+
+    fn find(&self, mut id: Id) -> Id {
+        while self.parents[usize::from(id)] != id {
+            id = self.parents[usize::from(id)];
+        }
+        id
+    }
+
+    fn find_mut(&mut self, id: Id) -> Id {
+        let root = self.find(id);
+        // Path compression
+        let mut cur = id;
+        while cur != root {
+            let next = self.parents[usize::from(cur)];
+            self.parents[usize::from(cur)] = root;
+            cur = next;
+        }
+        root
+    }
+
+    fn union(&mut self, root: Id, other: Id) {
+        self.parents[usize::from(other)] = root;
+    }
+}
+
+//=============================================================================
+// E-GRAPH
+//=============================================================================
+
+/// An equivalence class of e-nodes
+#[derive(Debug, Clone)]
+pub struct EClass<L> {
+    pub id: Id,
+    pub nodes: Vec<L>,
+    /// The nodes that have this class as a child, with their classes
+    parents: Vec<(L, Id)>,
+}
+
+/// An e-graph over the language `L`
+///
+/// Invariants are restored lazily: after a batch of [`EGraph::union`] calls,
+/// call [`EGraph::rebuild`] before querying or extracting.
+#[derive(Debug, Clone)]
+pub struct EGraph<L: Language> {
+    unionfind: UnionFind,
+    memo: FxHashMap<L, Id>,
+    classes: FxHashMap<Id, EClass<L>>,
+    /// Classes merged since the last rebuild, whose parents need repair
+    pending: Vec<Id>,
+}
+
+impl<L: Language> Default for EGraph<L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<L: Language> EGraph<L> {
+    /// Create an empty e-graph
+    pub fn new() -> Self {
+        EGraph {
+            unionfind: UnionFind::default(),
+            memo: FxHashMap::default(),
+            classes: FxHashMap::default(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Number of e-classes
+    pub fn number_of_classes(&self) -> usize {
+        self.classes.len()
+    }
+
+    /// Total number of e-nodes across all e-classes
+    pub fn total_size(&self) -> usize {
+        self.classes.values().map(|c| c.nodes.len()).sum()
+    }
+
+    /// Canonical id of the e-class containing `id`
+    pub fn find(&self, id: Id) -> Id {
+        self.unionfind.find(id)
+    }
+
+    /// Iterate over all e-classes
+    pub fn classes(&self) -> impl Iterator<Item = &EClass<L>> {
+        self.classes.values()
+    }
+
+    /// The e-class with the given id
+    pub fn class(&self, id: Id) -> &EClass<L> {
+        let id = self.find(id);
+        self.classes
+            .get(&id)
+            .unwrap_or_else(|| panic!("unknown e-class {}", id))
+    }
+
+    /// Canonicalize a node: map children to their roots and normalize
+    pub fn canonicalize(&self, node: &L) -> L {
+        let mut node = node.map_children(|c| self.find(c));
+        node.normalize();
+        node
+    }
+
+    /// Look up the e-class of a node, if it is already present
+    pub fn lookup(&self, node: &L) -> Option<Id> {
+        let node = self.canonicalize(node);
+        self.memo.get(&node).map(|&id| self.find(id))
+    }
+
+    /// Add a node, returning the id of its e-class
+    pub fn add(&mut self, node: L) -> Id {
+        let node = self.canonicalize(&node);
+        if let Some(&id) = self.memo.get(&node) {
+            return self.find(id);
+        }
+        let id = self.unionfind.make_set();
+        for child in node.children() {
+            self.classes
+                .get_mut(&child)
+                .expect("e-class missing")
+                .parents
+                .push((node.clone(), id));
+        }
+        self.memo.insert(node.clone(), id);
+        self.classes.insert(
+            id,
+            EClass {
+                id,
+                nodes: vec![node],
+                parents: Vec::new(),
+            },
+        );
+        id
+    }
+
+    /// Merge two e-classes. Returns `true` if they were distinct.
+    pub fn union(&mut self, a: Id, b: Id) -> bool {
+        let a = self.unionfind.find_mut(a);
+        let b = self.unionfind.find_mut(b);
+        if a == b {
+            return false;
+        }
+        // Keep the smaller id as root so ids stay stable for early terms
+        let (root, other) = if a < b { (a, b) } else { (b, a) };
+        self.unionfind.union(root, other);
+        let merged = self.classes.remove(&other).expect("e-class missing");
+        let class = self.classes.get_mut(&root).expect("e-class missing");
+        class.nodes.extend(merged.nodes);
+        class.parents.extend(merged.parents);
+        self.pending.push(root);
+        true
+    }
+
+    /// Restore the congruence invariant.
+    ///
+    /// Only the parents of classes merged since the last rebuild can have
+    /// changed: they are re-canonicalized, and parents that become identical
+    /// force their e-classes to merge, until no further merges happen. Returns
+    /// the number of merges performed.
+    pub fn rebuild(&mut self) -> usize {
+        let mut merges = 0;
+        while !self.pending.is_empty() {
+            let mut todo: Vec<Id> = std::mem::take(&mut self.pending)
+                .into_iter()
+                .map(|id| self.unionfind.find_mut(id))
+                .collect();
+            todo.sort_unstable();
+            todo.dedup();
+            for id in todo {
+                merges += self.repair(id);
+            }
+        }
+        merges
+    }
+
+    /// Re-canonicalize the parents and nodes of a merged class, merging the
+    /// classes of parents that became congruent
+    fn repair(&mut self, id: Id) -> usize {
+        let id = self.unionfind.find_mut(id);
+        let Some(class) = self.classes.get_mut(&id) else {
+            return 0;
+        };
+        let parents = std::mem::take(&mut class.parents);
+
+        for (node, _) in &parents {
+            self.memo.remove(node);
+        }
+        let mut merges = 0;
+        let mut repaired: FxHashMap<L, Id> = FxHashMap::default();
+        for (node, parent) in parents {
+            let node = self.canonicalize(&node);
+            let parent = self.unionfind.find_mut(parent);
+            let parent = match repaired.get(&node) {
+                Some(&congruent) if self.union(congruent, parent) => {
+                    merges += 1;
+                    self.unionfind.find_mut(parent)
+                },
+                _ => parent,
+            };
+            self.memo.insert(node.clone(), parent);
+            repaired.insert(node, parent);
+        }
+
+        // A merge above may have moved this class under another root
+        let id = self.unionfind.find_mut(id);
+        let nodes = std::mem::take(&mut self.classes.get_mut(&id).expect("e-class missing").nodes);
+        let mut canonical: Vec<L> = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            let node = self.canonicalize(&node);
+            if !canonical.contains(&node) {
+                canonical.push(node);
+            }
+        }
+        let class = self.classes.get_mut(&id).expect("e-class missing");
+        class.nodes = canonical;
+        class.parents.extend(repaired);
+        merges
+    }
+
+    /// Find all substitutions under which `pattern` matches e-class `id`
+    pub fn ematch(&self, pattern: &Pattern<L>, id: Id) -> Vec<Subst> {
+        let mut out = Vec::new();
+        if let Some(root) = pattern.root() {
+            self.ematch_node(pattern, root, self.find(id), Subst::default(), &mut out);
+        }
+        out
+    }
+
+    fn ematch_node(
+        &self,
+        pattern: &Pattern<L>,
+        pat: Id,
+        id: Id,
+        subst: Subst,
+        out: &mut Vec<Subst>,
+    ) {
+        match &pattern.nodes[usize::from(pat)] {
+            PatternNode::Var(name) => match subst.get(name) {
+                Some(bound) if self.find(bound) != id => {},
+                Some(_) => out.push(subst),
+                None => {
+                    let mut subst = subst;
+                    subst.insert(name.clone(), id);
+                    out.push(subst);
+                },
+            },
+            PatternNode::Node(pnode) => {
+                let pchildren = pnode.children();
+                for enode in &self.class(id).nodes {
+                    if !pnode.matches(enode) {
+                        continue;
+                    }
+                    let echildren = enode.children();
+                    if echildren.len() != pchildren.len() {
+                        continue;
+                    }
+                    // Match children left to right, threading substitutions
+                    let mut partial = vec![subst.clone()];
+                    for (&pc, &ec) in pchildren.iter().zip(echildren.iter()) {
+                        let mut next = Vec::new();
+                        for s in partial {
+                            self.ematch_node(pattern, pc, self.find(ec), s, &mut next);
+                        }
+                        partial = next;
+                        if partial.is_empty() {
+                            break;
+                        }
+                    }
+                    out.extend(partial);
+                }
+            },
+        }
+    }
+
+    /// Add the instantiation of `pattern` under `subst`, returning its e-class
+    pub fn instantiate(&mut self, pattern: &Pattern<L>, subst: &Subst) -> Id {
+        let mut ids: Vec<Id> = Vec::with_capacity(pattern.nodes.len());
+        for pnode in &pattern.nodes {
+            let id = match pnode {
+                PatternNode::Var(name) => subst
+                    .get(name)
+                    .unwrap_or_else(|| panic!("unbound pattern variable {}", name)),
+                PatternNode::Node(node) => {
+                    let node = node.map_children(|c| ids[usize::from(c)]);
+                    self.add(node)
+                },
+            };
+            ids.push(id);
+        }
+        *ids.last().expect("empty pattern")
+    }
+
+    /// Apply `rules` until saturation or until `iter_limit` iterations ran.
+    ///
+    /// Returns the number of iterations performed.
+    pub fn saturate(&mut self, rules: &[Rewrite<L>], iter_limit: usize) -> usize {
+        self.rebuild();
+        for iteration in 0..iter_limit {
+            let class_ids: Vec<Id> = self.classes.keys().copied().collect();
+            let mut matches = Vec::new();
+            for rule in rules {
+                for &id in &class_ids {
+                    for subst in self.ematch(&rule.lhs, id) {
+                        matches.push((rule, id, subst));
+                    }
+                }
+            }
+
+            let size_before = self.total_size();
+            let mut changed = false;
+            for (rule, id, subst) in matches {
+                let rhs = self.instantiate(&rule.rhs, &subst);
+                changed |= self.union(id, rhs);
+            }
+            self.rebuild();
+
+            if !changed && self.total_size() == size_before {
+                return iteration + 1;
+            }
+        }
+        iter_limit
+    }
+}
+
+//=============================================================================
+// PATTERNS AND REWRITES
+//=============================================================================
+
+/// A substitution from pattern variables to e-classes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Subst {
+    bindings: Vec<(String, Id)>,
+}
+
+impl Subst {
+    /// The e-class bound to `var`, if any
+    pub fn get(&self, var: &str) -> Option<Id> {
+        self.bindings
+            .iter()
+            .find(|(name, _)| name == var)
+            .map(|&(_, id)| id)
+    }
+
+    /// Bind `var` to `id`
+    pub fn insert(&mut self, var: String, id: Id) {
+        self.bindings.push((var, id));
+    }
+}
+
+/// A node in a [`Pattern`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternNode<L> {
+    /// Pattern variable, matches any e-class
+    Var(String),
+    /// Language node whose children index earlier pattern nodes
+    Node(L),
+}
+
+/// A term pattern stored as a post-order list of nodes; the last node is the root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern<L> {
+    nodes: Vec<PatternNode<L>>,
+}
+
+impl<L> Default for Pattern<L> {
+    fn default() -> Self {
+        Pattern { nodes: Vec::new() }
+    }
+}
+
+impl<L> Pattern<L> {
+    /// Create an empty pattern
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a pattern variable, returning its index
+    pub fn var(&mut self, name: impl Into<String>) -> Id {
+        self.nodes.push(PatternNode::Var(name.into()));
+        Id::from(self.nodes.len() - 1)
+    }
+
+    /// Add a node whose children are indices returned by earlier calls
+    pub fn node(&mut self, node: L) -> Id {
+        self.nodes.push(PatternNode::Node(node));
+        Id::from(self.nodes.len() - 1)
+    }
+
+    fn root(&self) -> Option<Id> {
+        if self.nodes.is_empty() {
+            None
+        } else {
+            Some(Id::from(self.nodes.len() - 1))
+        }
+    }
+}
+
+/// A directed rewrite `lhs => rhs` applied by [`EGraph::saturate`]
+#[derive(Debug, Clone)]
+pub struct Rewrite<L> {
+    pub name: String,
+    pub lhs: Pattern<L>,
+    pub rhs: Pattern<L>,
+}
+
+impl<L> Rewrite<L> {
+    pub fn new(name: impl Into<String>, lhs: Pattern<L>, rhs: Pattern<L>) -> Self {
+        Rewrite { name: name.into(), lhs, rhs }
+    }
+}
+
+//=============================================================================
+// EXTRACTION
+//=============================================================================
+
+/// Chooses a smallest (by AST size) node for every e-class
+pub struct Extractor<'a, L: Language> {
+    egraph: &'a EGraph<L>,
+    best: HashMap<Id, (usize, L)>,
+}
+
+impl<'a, L: Language> Extractor<'a, L> {
+    /// Compute best nodes for every e-class of a rebuilt e-graph
+    pub fn new(egraph: &'a EGraph<L>) -> Self {
+        let mut best: HashMap<Id, (usize, L)> = HashMap::new();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for class in egraph.classes() {
+                for node in &class.nodes {
+                    let cost = node.children().iter().try_fold(1usize, |acc, &c| {
+                        best.get(&egraph.find(c))
+                            .map(|(cost, _)| acc.saturating_add(*cost))
+                    });
+                    let Some(cost) = cost else { continue };
+                    let improves = match best.get(&class.id) {
+                        Some((old, _)) => cost < *old,
+                        None => true,
+                    };
+                    if improves {
+                        best.insert(class.id, (cost, node.clone()));
+                        changed = true;
+                    }
+                }
+            }
+        }
+        Extractor { egraph, best }
+    }
+
+    /// The best node of the e-class containing `id`, with its cost
+    pub fn find_best(&self, id: Id) -> Option<(usize, &L)> {
+        self.best
+            .get(&self.egraph.find(id))
+            .map(|(cost, node)| (*cost, node))
+    }
+
+    /// The best node of the e-class containing `id`
+    ///
+    /// Panics if the class only contains infinite terms.
+    pub fn best_node(&self, id: Id) -> &L {
+        self.find_best(id)
+            .map(|(_, node)| node)
+            .unwrap_or_else(|| panic!("e-class {} has no finite term", id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tiny test language: a constant, a unary function, and an AC "sum"
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    enum Arith {
+        Sym(&'static str),
+        Neg(Id),
+        Sum(Vec<Id>),
+    }
+
+    impl Language for Arith {
+        fn children(&self) -> Vec<Id> {
+            match self {
+                Arith::Sym(_) => vec![],
+                Arith::Neg(a) => vec![*a],
+                Arith::Sum(xs) => xs.clone(),
+            }
+        }
+
+        fn map_children(&self, mut f: impl FnMut(Id) -> Id) -> Self {
+            match self {
+                Arith::Sym(s) => Arith::Sym(s),
+                Arith::Neg(a) => Arith::Neg(f(*a)),
+                Arith::Sum(xs) => Arith::Sum(xs.iter().map(|&x| f(x)).collect()),
+            }
+        }
+
+        fn matches(&self, other: &Self) -> bool {
+            match (self, other) {
+                (Arith::Sym(a), Arith::Sym(b)) => a == b,
+                (Arith::Neg(_), Arith::Neg(_)) => true,
+                (Arith::Sum(a), Arith::Sum(b)) => a.len() == b.len(),
+                _ => false,
+            }
+        }
+
+        fn normalize(&mut self) {
+            if let Arith::Sum(xs) = self {
+                xs.sort();
+            }
+        }
+    }
+
+    #[test]
+    fn test_hashcons() {
+        let mut eg = EGraph::new();
+        let a = eg.add(Arith::Sym("a"));
+        let a2 = eg.add(Arith::Sym("a"));
+        assert_eq!(a, a2);
+        assert_eq!(eg.number_of_classes(), 1);
+    }
+
+    #[test]
+    fn test_ac_normalization() {
+        let mut eg = EGraph::new();
+        let a = eg.add(Arith::Sym("a"));
+        let b = eg.add(Arith::Sym("b"));
+        let ab = eg.add(Arith::Sum(vec![a, b]));
+        let ba = eg.add(Arith::Sum(vec![b, a]));
+        assert_eq!(ab, ba);
+    }
+
+    #[test]
+    fn test_congruence() {
+        let mut eg = EGraph::new();
+        let a = eg.add(Arith::Sym("a"));
+        let b = eg.add(Arith::Sym("b"));
+        let c = eg.add(Arith::Sym("c"));
+        let na = eg.add(Arith::Neg(a));
+        let nb = eg.add(Arith::Neg(b));
+        let s1 = eg.add(Arith::Sum(vec![na, c]));
+        let s2 = eg.add(Arith::Sum(vec![c, nb]));
+        assert_ne!(eg.find(s1), eg.find(s2));
+
+        eg.union(a, b);
+        // neg(a) = neg(b), then a + neg(b) = neg(a) + c
+        assert_eq!(eg.rebuild(), 2);
+        assert_eq!(eg.find(na), eg.find(nb));
+        assert_eq!(eg.find(s1), eg.find(s2));
+
+        // The hashcons holds the repaired parents
+        let classes = eg.number_of_classes();
+        assert_eq!(eg.add(Arith::Neg(b)), eg.find(na));
+        assert_eq!(eg.number_of_classes(), classes);
+        assert_eq!(eg.rebuild(), 0);
+    }
+
+    #[test]
+    fn test_saturate_and_extract() {
+        // neg(neg(X)) => X
+        let mut lhs = Pattern::new();
+        let x = lhs.var("X");
+        let n = lhs.node(Arith::Neg(x));
+        lhs.node(Arith::Neg(n));
+        let mut rhs = Pattern::new();
+        rhs.var("X");
+        let rules = vec![Rewrite::new("double-neg", lhs, rhs)];
+
+        let mut eg = EGraph::new();
+        let a = eg.add(Arith::Sym("a"));
+        let na = eg.add(Arith::Neg(a));
+        let nna = eg.add(Arith::Neg(na));
+        let b = eg.add(Arith::Sym("b"));
+        let sum = eg.add(Arith::Sum(vec![nna, b]));
+        let plain = eg.add(Arith::Sum(vec![b, a]));
+
+        eg.saturate(&rules, 10);
+        assert_eq!(eg.find(nna), eg.find(a));
+        assert_eq!(eg.find(sum), eg.find(plain));
+
+        let ex = Extractor::new(&eg);
+        assert_eq!(ex.find_best(nna).map(|(cost, _)| cost), Some(1));
+        assert_eq!(ex.find_best(sum).map(|(cost, _)| cost), Some(3));
+    }
+
+    #[test]
+    fn test_ematch_nonlinear() {
+        // Sum(X, X) only matches sums of two equal classes
+        let mut pat = Pattern::new();
+        let x = pat.var("X");
+        let x2 = pat.var("X");
+        pat.node(Arith::Sum(vec![x, x2]));
+
+        let mut eg = EGraph::new();
+        let a = eg.add(Arith::Sym("a"));
+        let b = eg.add(Arith::Sym("b"));
+        let aa = eg.add(Arith::Sum(vec![a, a]));
+        let ab = eg.add(Arith::Sum(vec![a, b]));
+        assert_eq!(eg.ematch(&pat, aa).len(), 1);
+        assert!(eg.ematch(&pat, ab).is_empty());
+
+        eg.union(a, b);
+        eg.rebuild();
+        assert_eq!(eg.ematch(&pat, ab).len(), 1);
+    }
+}
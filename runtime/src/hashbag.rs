@@ -32,15 +32,32 @@
 /// Uses a `HashMap` to track element counts efficiently.
 /// Equality is based on element counts (order-independent).
 ///
+/// The bag's hash is the wrapping sum of a mixed hash of every occurrence,
+/// maintained on `insert`/`remove`, so hashing a bag is O(1) and independent
+/// of iteration order.
+///
 /// # Type Parameters
 ///
 /// * `T` - Element type, must be `Clone + Hash + Eq`
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct HashBag<T: Clone + Hash + Eq> {
     /// Map from elements to their counts
     counts: HashMap<T, usize, BuildHasherDefault<FxHasher>>,
     /// Total number of elements (sum of all counts)
     total_count: usize,
+    /// Sum of `element_hash` over all occurrences
+    hash: u64,
+}
+
+/// Hash of a single occurrence, mixed so that sums of similar hashes don't collide
+pub(crate) fn element_hash<T: Hash>(item: &T) -> u64 {
+    let mut hasher = FxHasher::default();
+    item.hash(&mut hasher);
+    // splitmix64 finalizer
+    let mut z = hasher.finish().wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
 }
 
 impl<T: Clone + Hash + Eq> HashBag<T> {
@@ -58,6 +75,7 @@ pub fn new() -> Self {
         Self {
             counts: HashMap::default(),
             total_count: 0,
+            hash: 0,
         }
     }
 
@@ -94,8 +112,38 @@ pub fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
     /// assert_eq!(bag.count(&"a"), 2);
     /// ```
     pub fn insert(&mut self, item: T) {
-        *self.counts.entry(item).or_insert(0) += 1;
-        self.total_count += 1;
+        self.insert_n(item, 1);
+    }
+
+    /// Inserts `n` occurrences of an element at once.
+    ///
+    /// # Panics
+    ///
+    /// If the bag would hold more than `usize::MAX` elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mettail_runtime::HashBag;
+    ///
+    /// let mut bag = HashBag::new();
+    /// bag.insert_n("a", 3);
+    /// bag.insert_n("b", 0);
+    /// assert_eq!(bag.count(&"a"), 3);
+    /// assert!(!bag.contains(&"b"));
+    /// ```
+    pub fn insert_n(&mut self, item: T, n: usize) {
+        if n == 0 {
+            return;
+        }
+        self.total_count = self
+            .total_count
+            .checked_add(n)
+            .expect("bag size overflows usize");
+        self.hash = self
+            .hash
+            .wrapping_add(element_hash(&item).wrapping_mul(n as u64));
+        *self.counts.entry(item).or_insert(0) += n;
     }
 
     /// Removes one occurrence of an element from the bag.
@@ -123,6 +171,7 @@ pub fn remove(&mut self, item: &T) -> bool {
         if let Some(count) = self.counts.get_mut(item) {
             *count -= 1;
             self.total_count -= 1;
+            self.hash = self.hash.wrapping_sub(element_hash(item));
             if *count == 0 {
                 self.counts.remove(item);
             }
@@ -249,6 +298,19 @@ pub fn iter_elements(&self) -> impl Iterator<Item = &T> {
             .iter()
             .flat_map(|(k, &count)| std::iter::repeat_n(k, count))
     }
+
+    /// Rebuild the map after elements may have changed in place
+    fn rebuild_with(&mut self, mut f: impl FnMut(&mut T)) {
+        let old_counts = std::mem::take(&mut self.counts);
+        self.hash = 0;
+        for (mut elem, count) in old_counts {
+            f(&mut elem);
+            self.hash = self
+                .hash
+                .wrapping_add(element_hash(&elem).wrapping_mul(count as u64));
+            *self.counts.entry(elem).or_insert(0) += count;
+        }
+    }
 }
 
 // PartialEq: compare by element counts (order-independent)
@@ -260,21 +322,22 @@ fn eq(&self, other: &Self) -> bool {
 
 impl<T: Clone + Hash + Eq> Eq for HashBag<T> {}
 
-// Hash: hash all (element, count) pairs in a deterministic order
-impl<T: Clone + Hash + Eq + fmt::Debug> Hash for HashBag<T> {
+// Hash: the incrementally maintained commutative hash, so equal bags hash
+// equally regardless of insertion order
+impl<T: Clone + Hash + Eq> Hash for HashBag<T> {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        // Hash total count first
         self.total_count.hash(state);
+        self.hash.hash(state);
+    }
+}
 
-        // Collect and sort entries for deterministic hashing
-        // We use Debug format for sorting since we can't assume T: Ord
-        let mut entries: Vec<_> = self.counts.iter().collect();
-        entries.sort_by_key(|(k, _)| format!("{:?}", k));
-
-        for (elem, &count) in entries {
-            elem.hash(state);
-            count.hash(state);
-        }
+// Debug: the map and count, without the internal hash
+impl<T: Clone + Hash + Eq + fmt::Debug> fmt::Debug for HashBag<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HashBag")
+            .field("counts", &self.counts)
+            .field("total_count", &self.total_count)
+            .finish()
     }
 }
 
@@ -342,24 +405,12 @@ fn term_eq(&self, other: &Self) -> bool {
     fn close_term(&mut self, state: ScopeState, on_free: &impl OnFreeFn<N>) {
         // Close each unique element
         // We need to rebuild the map because closing might change element identity
-        let old_counts = std::mem::take(&mut self.counts);
-        self.counts = HashMap::default();
-
-        for (mut elem, count) in old_counts {
-            elem.close_term(state, on_free);
-            self.counts.insert(elem, count);
-        }
+        self.rebuild_with(|elem| elem.close_term(state, on_free));
     }
 
     fn open_term(&mut self, state: ScopeState, on_bound: &impl OnBoundFn<N>) {
         // Open each unique element
-        let old_counts = std::mem::take(&mut self.counts);
-        self.counts = HashMap::default();
-
-        for (mut elem, count) in old_counts {
-            elem.open_term(state, on_bound);
-            self.counts.insert(elem, count);
-        }
+        self.rebuild_with(|elem| elem.open_term(state, on_bound));
     }
 
     fn visit_vars(&self, on_var: &mut impl FnMut(&Var<N>)) {
@@ -370,13 +421,7 @@ fn visit_vars(&self, on_var: &mut impl FnMut(&Var<N>)) {
 
     fn visit_mut_vars(&mut self, on_var: &mut impl FnMut(&mut Var<N>)) {
         // Need to rebuild the map since we need mutable access to keys
-        let old_counts = std::mem::take(&mut self.counts);
-        self.counts = HashMap::default();
-
-        for (mut elem, count) in old_counts {
-            elem.visit_mut_vars(on_var);
-            self.counts.insert(elem, count);
-        }
+        self.rebuild_with(|elem| elem.visit_mut_vars(on_var));
     }
 }
 
@@ -571,4 +616,65 @@ fn ord_by_count_then_elements() {
 
         assert!(bag3 < bag1); // Same count, but bag3 has smaller element
     }
+
+    fn hash_of<T: Hash>(value: &T) -> u64 {
+        use std::collections::hash_map::DefaultHasher;
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Apply a sequence of operations: `(true, x)` inserts `x`, `(false, x)` removes it
+    fn apply(ops: &[(bool, u8)]) -> HashBag<u8> {
+        let mut bag = HashBag::new();
+        for &(insert, x) in ops {
+            if insert {
+                bag.insert(x);
+            } else {
+                bag.remove(&x);
+            }
+        }
+        bag
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_equal_bags_hash_equal(
+            ops1 in proptest::collection::vec((proptest::bool::ANY, 0u8..6), 0..40),
+            ops2 in proptest::collection::vec((proptest::bool::ANY, 0u8..6), 0..40),
+        ) {
+            let (bag1, bag2) = (apply(&ops1), apply(&ops2));
+            if bag1 == bag2 {
+                proptest::prop_assert_eq!(hash_of(&bag1), hash_of(&bag2));
+            }
+        }
+
+        #[test]
+        fn prop_incremental_hash_matches_rebuilt(
+            ops in proptest::collection::vec((proptest::bool::ANY, 0u8..6), 0..40),
+        ) {
+            let bag = apply(&ops);
+            let rebuilt = HashBag::from_iter(bag.iter_elements().copied());
+            proptest::prop_assert_eq!(&bag, &rebuilt);
+            proptest::prop_assert_eq!(hash_of(&bag), hash_of(&rebuilt));
+        }
+
+        #[test]
+        fn prop_hash_independent_of_insertion_order(
+            mut elems in proptest::collection::vec(proptest::num::u32::ANY, 0..30),
+        ) {
+            let bag1 = HashBag::from_iter(elems.clone());
+            elems.reverse();
+            let bag2 = HashBag::from_iter(elems);
+            proptest::prop_assert_eq!(&bag1, &bag2);
+            proptest::prop_assert_eq!(hash_of(&bag1), hash_of(&bag2));
+        }
+
+        #[test]
+        fn prop_different_counts_hash_differently(x in proptest::num::u32::ANY, n in 1usize..5) {
+            let bag1 = HashBag::from_iter(std::iter::repeat_n(x, n));
+            let bag2 = HashBag::from_iter(std::iter::repeat_n(x, n + 1));
+            proptest::prop_assert_ne!(hash_of(&bag1), hash_of(&bag2));
+        }
+    }
 }
@@ -2,7 +2,18 @@
 //!
 //! This crate provides:
 //! - Variable binding support (via moniker wrappers)
-//! - Collection types (HashBag for associative-commutative operations)
+//! - Collection types (HashBag for associative-commutative operations, and
+//!   PersistentBag with structural sharing)
+//! - An e-graph for theories using the e-graph equation backend
+//! - Rewrite graphs with DOT/JSON/GraphML export
+//! - Statistics of exploration runs (relation sizes, rule firings, iterations)
+//! - Single rewrite steps, for random walks over rewrites
+//! - A compact binary term encoding
+//! - Term paths for addressing subterms by position
+//! - Source spans of parsed terms
+//! - Matching support for patterns with holes
+//! - Serde support for terms, behind the `serde` feature
+//! - The REPL's `Theory` interface, behind the `repl` feature
 //! - Utility functions for parsing and variable management
 
 // Variable binding support
@@ -11,7 +22,91 @@
 
 // Collection types
 mod hashbag;
+mod persistent_bag;
 pub use hashbag::HashBag;
+pub use persistent_bag::PersistentBag;
+
+// E-graph equation backend
+pub mod egraph;
+pub use egraph::EGraph;
+
+// Rewrite graphs from exploring a term
+mod rewrite_graph;
+pub use rewrite_graph::{RewriteEdge, RewriteGraph};
+
+// Statistics of exploration runs
+mod stats;
+pub use stats::{count_firings, fire, ExploreStats, RuleStats, SccStats};
+
+// Subterm positions
+mod term_path;
+pub use term_path::TermPath;
+
+// Source spans
+mod span;
+pub use span::{span_key, Span, SpanTable};
+
+// Pattern matching
+mod pattern;
+pub use pattern::{match_unordered, vars_match};
+
+// Binary term encoding
+mod binary;
+pub use binary::{
+    schema_fingerprint, BinaryReader, BinaryValue, BinaryWriter, DecodeError, BINARY_FORMAT_VERSION,
+};
+
+// Serde support
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "serde")]
+pub use serde;
+#[cfg(feature = "serde")]
+pub use serialize::deserialize_with_vars;
+
+/// Derive serde on a generated AST type when the `serde` feature is on
+#[doc(hidden)]
+#[cfg(feature = "serde")]
+#[macro_export]
+macro_rules! __derive_serde {
+    ($($item:tt)*) => {
+        #[derive($crate::serde::Serialize, $crate::serde::Deserialize)]
+        #[serde(crate = "mettail_runtime::serde")]
+        $($item)*
+    };
+}
+
+/// Derive serde on a generated AST type when the `serde` feature is on
+#[doc(hidden)]
+#[cfg(not(feature = "serde"))]
+#[macro_export]
+macro_rules! __derive_serde {
+    ($($item:tt)*) => {
+        $($item)*
+    };
+}
+
+// REPL interface
+#[cfg(feature = "repl")]
+pub mod repl;
+
+/// Emit a generated `repl::Theory` impl when the `repl` feature is on
+#[doc(hidden)]
+#[cfg(feature = "repl")]
+#[macro_export]
+macro_rules! __repl_theory {
+    ($($item:tt)*) => {
+        $($item)*
+    };
+}
+
+/// Emit a generated `repl::Theory` impl when the `repl` feature is on
+#[doc(hidden)]
+#[cfg(not(feature = "repl"))]
+#[macro_export]
+macro_rules! __repl_theory {
+    ($($item:tt)*) => {};
+}
 
 // Re-export LALRPOP utilities for generated parsers
 pub use lalrpop_util::ParseError as LalrpopParseError;
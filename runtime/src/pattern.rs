@@ -0,0 +1,115 @@
+//! Matching helpers for patterns with holes
+//!
+//! Theories with the `holes` option get a `<X>Hole(name)` variant in every
+//! category, written `?name`, and a generated `match_pattern` that binds each
+//! hole to the subterm at its position. These helpers hold the parts of the
+//! matcher that do not depend on the theory: comparing object variables and
+//! matching collection elements up to reordering.
+
+use crate::Var;
+
+/// Whether a variable in a pattern matches one in a term
+///
+/// Patterns are usually parsed separately from the terms they query, so free
+/// variables match by name; bound variables match by de Bruijn index.
+pub fn vars_match(pattern: &Var<String>, term: &Var<String>) -> bool {
+    match (pattern, term) {
+        (Var::Free(p), Var::Free(t)) => {
+            p == t || (p.pretty_name.is_some() && p.pretty_name == t.pretty_name)
+        },
+        (Var::Bound(p), Var::Bound(t)) => p == t,
+        _ => false,
+    }
+}
+
+/// Match each of `patterns` against a distinct element of `terms`, in any
+/// order, backtracking over the choices
+///
+/// With `rest`, elements left over are allowed (the pattern had a `...`);
+/// otherwise every element must be matched. `matches` extends `bindings` on
+/// success and may leave it changed on failure, so it is restored between
+/// attempts.
+pub fn match_unordered<P, T, B: Clone>(
+    patterns: &[&P],
+    terms: &[&T],
+    rest: bool,
+    bindings: &mut B,
+    matches: &mut impl FnMut(&P, &T, &mut B) -> bool,
+) -> bool {
+    if patterns.len() > terms.len() || (!rest && patterns.len() != terms.len()) {
+        return false;
+    }
+    let mut used = vec![false; terms.len()];
+    match_from(patterns, terms, &mut used, bindings, matches)
+}
+
+fn match_from<P, T, B: Clone>(
+    patterns: &[&P],
+    terms: &[&T],
+    used: &mut [bool],
+    bindings: &mut B,
+    matches: &mut impl FnMut(&P, &T, &mut B) -> bool,
+) -> bool {
+    let Some((first, others)) = patterns.split_first() else {
+        return true;
+    };
+    for i in 0..terms.len() {
+        if used[i] {
+            continue;
+        }
+        let saved = bindings.clone();
+        used[i] = true;
+        if matches(first, terms[i], bindings) && match_from(others, terms, used, bindings, matches)
+        {
+            return true;
+        }
+        used[i] = false;
+        *bindings = saved;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FreeVar, VarContext};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn free_variables_match_by_name() {
+        let a = VarContext::new().var("a");
+        let other_a = VarContext::new().var("a");
+        assert!(vars_match(&Var::Free(a.clone()), &Var::Free(other_a)));
+        assert!(!vars_match(&Var::Free(a), &Var::Free(FreeVar::fresh_named("b"))));
+    }
+
+    /// Patterns are `Some(name)` (binds a number consistently) or `None`
+    /// (matches anything)
+    fn bind(p: &Option<&str>, t: &u32, b: &mut BTreeMap<String, u32>) -> bool {
+        match p {
+            Some(name) => *b.entry(name.to_string()).or_insert(*t) == *t,
+            None => true,
+        }
+    }
+
+    #[test]
+    fn unordered_matching_backtracks() {
+        let mut b = BTreeMap::new();
+        // `x` first tries 1, then `x` again must be 2: backtrack to x = 2
+        let patterns = [&Some("x"), &Some("y"), &Some("x")];
+        assert!(!match_unordered(&patterns, &[&1, &2, &3], false, &mut b, &mut bind));
+        assert!(b.is_empty());
+
+        let patterns = [&Some("y"), &Some("x"), &Some("x")];
+        assert!(match_unordered(&patterns, &[&2, &1, &2], false, &mut b, &mut bind));
+        assert_eq!(b, BTreeMap::from([("x".into(), 2), ("y".into(), 1)]));
+    }
+
+    #[test]
+    fn rest_allows_leftover_elements() {
+        let mut b = BTreeMap::new();
+        assert!(!match_unordered(&[&None], &[&1, &2], false, &mut b, &mut bind));
+        assert!(match_unordered(&[&None], &[&1, &2], true, &mut b, &mut bind));
+        assert!(!match_unordered(&[&None, &None, &None], &[&1, &2], true, &mut b, &mut bind));
+    }
+}
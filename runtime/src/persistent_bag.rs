@@ -0,0 +1,583 @@
+//! PersistentBag - Multiset with structural sharing
+//!
+//! A `PersistentBag<T>` has the same interface and semantics as
+//! [`HashBag`](crate::HashBag), but is stored as a hash array mapped trie (HAMT)
+//! of reference-counted nodes. Cloning is O(1), and `insert`/`remove` copy only
+//! the O(log n) nodes on the path to the element, sharing the rest with other
+//! versions of the bag. Rewrites that rebuild a collection from `rest` plus a
+//! few elements therefore no longer copy the whole collection.
+//!
+//! Theories select it with `options { bag_backend: persistent }`.
+//!
+//! # Examples
+//!
+//! ```
+//! use mettail_runtime::PersistentBag;
+//!
+//! let mut bag = PersistentBag::new();
+//! bag.insert("a");
+//! bag.insert("a");
+//!
+//! let smaller = bag.without(&"a").unwrap();
+//! assert_eq!(bag.count(&"a"), 2);
+//! assert_eq!(smaller.count(&"a"), 1);
+//! ```
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use crate::hashbag::element_hash;
+use crate::{BoundTerm, Var};
+use moniker::{OnBoundFn, OnFreeFn, ScopeState};
+
+/// Bits of the hash consumed per trie level
+const BITS: u32 = 5;
+const MASK: u64 = (1 << BITS) - 1;
+
+/// All elements sharing one full hash, with their counts
+#[derive(Clone)]
+struct Leaf<T> {
+    hash: u64,
+    items: Vec<(T, usize)>,
+}
+
+#[derive(Clone)]
+enum Entry<T> {
+    Leaf(Arc<Leaf<T>>),
+    Node(Arc<Node<T>>),
+}
+
+/// A trie node: `entries` holds one entry per set bit of `bitmap`, in bit order
+#[derive(Clone)]
+struct Node<T> {
+    bitmap: u32,
+    entries: Vec<Entry<T>>,
+}
+
+impl<T> Node<T> {
+    fn empty() -> Self {
+        Node { bitmap: 0, entries: Vec::new() }
+    }
+
+    /// `(bit, position in entries)` for `hash` at this level
+    fn slot(&self, hash: u64, shift: u32) -> (u32, usize) {
+        let bit = 1u32 << ((hash >> shift) & MASK);
+        (bit, (self.bitmap & (bit - 1)).count_ones() as usize)
+    }
+}
+
+impl<T: Clone + Eq> Node<T> {
+    fn get(&self, hash: u64, shift: u32, item: &T) -> usize {
+        let (bit, pos) = self.slot(hash, shift);
+        if self.bitmap & bit == 0 {
+            return 0;
+        }
+        match &self.entries[pos] {
+            Entry::Node(child) => child.get(hash, shift + BITS, item),
+            Entry::Leaf(leaf) if leaf.hash == hash => leaf
+                .items
+                .iter()
+                .find(|(e, _)| e == item)
+                .map_or(0, |(_, c)| *c),
+            Entry::Leaf(_) => 0,
+        }
+    }
+
+    fn insert(&mut self, hash: u64, shift: u32, item: T, n: usize) {
+        let (bit, pos) = self.slot(hash, shift);
+        if self.bitmap & bit == 0 {
+            self.bitmap |= bit;
+            self.entries
+                .insert(pos, Entry::Leaf(Arc::new(Leaf { hash, items: vec![(item, n)] })));
+            return;
+        }
+
+        let split = match &mut self.entries[pos] {
+            Entry::Node(child) => {
+                Arc::make_mut(child).insert(hash, shift + BITS, item, n);
+                return;
+            },
+            Entry::Leaf(leaf) if leaf.hash == hash => {
+                let leaf = Arc::make_mut(leaf);
+                match leaf.items.iter_mut().find(|(e, _)| *e == item) {
+                    Some((_, count)) => *count += n,
+                    None => leaf.items.push((item, n)),
+                }
+                return;
+            },
+            // Different hash in the same slot: push both one level down
+            Entry::Leaf(leaf) => leaf.clone(),
+        };
+
+        let mut child = Node::empty();
+        let (child_bit, _) = child.slot(split.hash, shift + BITS);
+        child.bitmap = child_bit;
+        child.entries.push(Entry::Leaf(split));
+        child.insert(hash, shift + BITS, item, n);
+        self.entries[pos] = Entry::Node(Arc::new(child));
+    }
+
+    /// Remove one occurrence; the caller has checked that `item` is present
+    fn remove(&mut self, hash: u64, shift: u32, item: &T) {
+        let (bit, pos) = self.slot(hash, shift);
+        let now_empty = match &mut self.entries[pos] {
+            Entry::Node(child) => {
+                let child = Arc::make_mut(child);
+                child.remove(hash, shift + BITS, item);
+                // Pull a lone leaf back up so the trie stays shallow
+                if let [Entry::Leaf(leaf)] = child.entries.as_slice() {
+                    let leaf = leaf.clone();
+                    self.entries[pos] = Entry::Leaf(leaf);
+                    false
+                } else {
+                    child.entries.is_empty()
+                }
+            },
+            Entry::Leaf(leaf) => {
+                let leaf = Arc::make_mut(leaf);
+                if let Some(i) = leaf.items.iter().position(|(e, _)| e == item) {
+                    leaf.items[i].1 -= 1;
+                    if leaf.items[i].1 == 0 {
+                        leaf.items.swap_remove(i);
+                    }
+                }
+                leaf.items.is_empty()
+            },
+        };
+        if now_empty {
+            self.bitmap &= !bit;
+            self.entries.remove(pos);
+        }
+    }
+}
+
+/// A persistent multiset (bag) - unordered collection with duplicates.
+///
+/// Equality is based on element counts (order-independent). Like `HashBag`,
+/// the hash is a commutative sum maintained on `insert`/`remove`, so equal
+/// bags hash equally regardless of how they were built.
+///
+/// # Type Parameters
+///
+/// * `T` - Element type, must be `Clone + Hash + Eq`
+pub struct PersistentBag<T: Clone + Hash + Eq> {
+    root: Arc<Node<T>>,
+    /// Total number of elements (sum of all counts)
+    total_count: usize,
+    /// Sum of `element_hash` over all occurrences
+    hash: u64,
+}
+
+impl<T: Clone + Hash + Eq> Clone for PersistentBag<T> {
+    fn clone(&self) -> Self {
+        Self {
+            root: Arc::clone(&self.root),
+            total_count: self.total_count,
+            hash: self.hash,
+        }
+    }
+}
+
+impl<T: Clone + Hash + Eq> PersistentBag<T> {
+    /// Creates an empty `PersistentBag`.
+    pub fn new() -> Self {
+        Self {
+            root: Arc::new(Node::empty()),
+            total_count: 0,
+            hash: 0,
+        }
+    }
+
+    /// Creates a `PersistentBag` from an iterator of elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mettail_runtime::PersistentBag;
+    ///
+    /// let bag = PersistentBag::from_iter(vec!["a", "b", "a"]);
+    /// assert_eq!(bag.count(&"a"), 2);
+    /// assert_eq!(bag.len(), 3);
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut bag = Self::new();
+        for item in iter {
+            bag.insert(item);
+        }
+        bag
+    }
+
+    /// Inserts an element, incrementing its count.
+    ///
+    /// Nodes shared with other versions of the bag are copied, not modified.
+    pub fn insert(&mut self, item: T) {
+        self.insert_n(item, 1);
+    }
+
+    /// Inserts `n` occurrences of an element at once.
+    ///
+    /// # Panics
+    ///
+    /// If the bag would hold more than `usize::MAX` elements.
+    pub fn insert_n(&mut self, item: T, n: usize) {
+        if n == 0 {
+            return;
+        }
+        self.total_count = self
+            .total_count
+            .checked_add(n)
+            .expect("bag size overflows usize");
+        let hash = element_hash(&item);
+        Arc::make_mut(&mut self.root).insert(hash, 0, item, n);
+        self.hash = self.hash.wrapping_add(hash.wrapping_mul(n as u64));
+    }
+
+    /// Removes one occurrence of an element from the bag.
+    ///
+    /// Returns `true` if an element was removed, `false` if the element was not in the bag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mettail_runtime::PersistentBag;
+    ///
+    /// let mut bag = PersistentBag::from_iter(vec!["a"]);
+    /// assert!(bag.remove(&"a"));
+    /// assert!(!bag.remove(&"a"));
+    /// ```
+    pub fn remove(&mut self, item: &T) -> bool {
+        let hash = element_hash(item);
+        if self.root.get(hash, 0, item) == 0 {
+            return false;
+        }
+        Arc::make_mut(&mut self.root).remove(hash, 0, item);
+        self.total_count -= 1;
+        self.hash = self.hash.wrapping_sub(hash);
+        true
+    }
+
+    /// A new bag with one more occurrence of `item`, sharing structure with `self`.
+    pub fn with(&self, item: T) -> Self {
+        let mut bag = self.clone();
+        bag.insert(item);
+        bag
+    }
+
+    /// A new bag with one occurrence of `item` removed, or `None` if it is absent.
+    pub fn without(&self, item: &T) -> Option<Self> {
+        let mut bag = self.clone();
+        bag.remove(item).then_some(bag)
+    }
+
+    /// Returns `true` if the bag contains at least one occurrence of the element.
+    pub fn contains(&self, item: &T) -> bool {
+        self.count(item) > 0
+    }
+
+    /// Returns the count of an element in the bag (0 if absent).
+    pub fn count(&self, item: &T) -> usize {
+        self.root.get(element_hash(item), 0, item)
+    }
+
+    /// Returns the total number of elements in the bag (sum of all counts).
+    pub fn len(&self) -> usize {
+        self.total_count
+    }
+
+    /// Returns `true` if the bag contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.total_count == 0
+    }
+
+    /// Returns an iterator over `(element, count)` pairs.
+    ///
+    /// The order of iteration is arbitrary.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            stack: vec![self.root.entries.iter()],
+            leaf: [].iter(),
+        }
+    }
+
+    /// Returns an iterator that yields each element `count` times.
+    ///
+    /// The order of iteration is arbitrary.
+    pub fn iter_elements(&self) -> impl Iterator<Item = &T> {
+        self.iter()
+            .flat_map(|(k, count)| std::iter::repeat_n(k, count))
+    }
+
+    /// Rebuild the trie after elements may have changed in place
+    fn rebuild_with(&mut self, mut f: impl FnMut(&mut T)) {
+        let items: Vec<(T, usize)> = self.iter().map(|(e, c)| (e.clone(), c)).collect();
+        *self = Self::new();
+        for (mut elem, count) in items {
+            f(&mut elem);
+            self.insert_n(elem, count);
+        }
+    }
+}
+
+/// Iterator over the `(element, count)` pairs of a [`PersistentBag`]
+pub struct Iter<'a, T> {
+    stack: Vec<std::slice::Iter<'a, Entry<T>>>,
+    leaf: std::slice::Iter<'a, (T, usize)>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (&'a T, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((elem, count)) = self.leaf.next() {
+                return Some((elem, *count));
+            }
+            match self.stack.last_mut()?.next() {
+                Some(Entry::Leaf(leaf)) => self.leaf = leaf.items.iter(),
+                Some(Entry::Node(node)) => self.stack.push(node.entries.iter()),
+                None => {
+                    self.stack.pop();
+                },
+            }
+        }
+    }
+}
+
+// PartialEq: compare by element counts (order-independent)
+impl<T: Clone + Hash + Eq> PartialEq for PersistentBag<T> {
+    fn eq(&self, other: &Self) -> bool {
+        if Arc::ptr_eq(&self.root, &other.root) {
+            return true;
+        }
+        self.total_count == other.total_count
+            && self.hash == other.hash
+            && self.iter().all(|(elem, count)| other.count(elem) == count)
+    }
+}
+
+impl<T: Clone + Hash + Eq> Eq for PersistentBag<T> {}
+
+// Hash: the incrementally maintained commutative hash
+impl<T: Clone + Hash + Eq> Hash for PersistentBag<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.total_count.hash(state);
+        self.hash.hash(state);
+    }
+}
+
+// Ord: lexicographic ordering by sorted elements
+impl<T: Clone + Hash + Eq + Ord> PartialOrd for PersistentBag<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Clone + Hash + Eq + Ord> Ord for PersistentBag<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.total_count.cmp(&other.total_count) {
+            Ordering::Equal => {
+                let mut v1: Vec<_> = self.iter_elements().collect();
+                let mut v2: Vec<_> = other.iter_elements().collect();
+                v1.sort();
+                v2.sort();
+                v1.cmp(&v2)
+            },
+            ord => ord,
+        }
+    }
+}
+
+impl<T: Clone + Hash + Eq> Default for PersistentBag<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone + Hash + Eq> FromIterator<T> for PersistentBag<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from_iter(iter)
+    }
+}
+
+impl<T: Clone + Hash + Eq + fmt::Debug> fmt::Debug for PersistentBag<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+// BoundTerm: Integration with moniker for substitution and variable binding
+impl<N, T> BoundTerm<N> for PersistentBag<T>
+where
+    N: Clone + PartialEq,
+    T: Clone + Hash + Eq + BoundTerm<N>,
+{
+    fn term_eq(&self, other: &Self) -> bool {
+        if self.total_count != other.total_count {
+            return false;
+        }
+        // Check term equality for each element (alpha-equivalence aware)
+        for (elem1, count1) in self.iter() {
+            let count2 = other
+                .iter()
+                .filter(|(elem2, _)| elem1.term_eq(elem2))
+                .map(|(_, c)| c)
+                .sum::<usize>();
+            if count1 != count2 {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn close_term(&mut self, state: ScopeState, on_free: &impl OnFreeFn<N>) {
+        self.rebuild_with(|elem| elem.close_term(state, on_free));
+    }
+
+    fn open_term(&mut self, state: ScopeState, on_bound: &impl OnBoundFn<N>) {
+        self.rebuild_with(|elem| elem.open_term(state, on_bound));
+    }
+
+    fn visit_vars(&self, on_var: &mut impl FnMut(&Var<N>)) {
+        for (elem, _) in self.iter() {
+            elem.visit_vars(on_var);
+        }
+    }
+
+    fn visit_mut_vars(&mut self, on_var: &mut impl FnMut(&mut Var<N>)) {
+        self.rebuild_with(|elem| elem.visit_mut_vars(on_var));
+    }
+}
+
+/// Formats as `{elem1, elem1, elem2}`, sorted, like `HashBag`
+impl<T: Clone + Hash + Eq + Ord + fmt::Display> fmt::Display for PersistentBag<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut items: Vec<&T> = self.iter_elements().collect();
+        items.sort();
+
+        write!(f, "{{")?;
+        for (i, elem) in items.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", elem)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HashBag;
+
+    fn hash_of<T: Hash>(value: &T) -> u64 {
+        use std::collections::hash_map::DefaultHasher;
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Element whose hash is constant, to exercise full-hash collisions
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct Collide(u32);
+
+    impl Hash for Collide {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            0u8.hash(state);
+        }
+    }
+
+    #[test]
+    fn insert_remove_many() {
+        let mut bag = PersistentBag::new();
+        for i in 0..2000u32 {
+            bag.insert(i % 700);
+        }
+        assert_eq!(bag.len(), 2000);
+        assert_eq!(bag.count(&5), 3);
+        assert_eq!(bag.count(&699), 2);
+        assert_eq!(bag.iter().count(), 700);
+
+        for i in 0..2000u32 {
+            assert!(bag.remove(&(i % 700)));
+        }
+        assert!(bag.is_empty());
+        assert_eq!(bag, PersistentBag::new());
+        assert_eq!(hash_of(&bag), hash_of(&PersistentBag::<u32>::new()));
+    }
+
+    #[test]
+    fn versions_share_structure_without_interference() {
+        let base = PersistentBag::from_iter(0..100u32);
+        let more = base.with(7);
+        let less = base.without(&7).unwrap();
+
+        assert_eq!(base.count(&7), 1);
+        assert_eq!(more.count(&7), 2);
+        assert_eq!(less.count(&7), 0);
+        assert!(base.without(&1000).is_none());
+        assert_eq!(less.with(7), base);
+    }
+
+    #[test]
+    fn colliding_hashes() {
+        let mut bag = PersistentBag::from_iter(vec![Collide(1), Collide(2), Collide(1)]);
+        assert_eq!(bag.count(&Collide(1)), 2);
+        assert_eq!(bag.count(&Collide(2)), 1);
+        assert!(bag.remove(&Collide(2)));
+        assert!(!bag.contains(&Collide(2)));
+        assert_eq!(bag.len(), 2);
+    }
+
+    #[test]
+    fn display_and_ord_match_hashbag() {
+        let elems = vec![3, 1, 2, 1];
+        let bag = PersistentBag::from_iter(elems.clone());
+        let hash_bag = HashBag::from_iter(elems);
+        assert_eq!(bag.to_string(), hash_bag.to_string());
+        assert!(PersistentBag::from_iter(vec![0]) < PersistentBag::from_iter(vec![1]));
+    }
+
+    fn apply(ops: &[(bool, u16)]) -> (PersistentBag<u16>, HashBag<u16>) {
+        let mut bag = PersistentBag::new();
+        let mut model = HashBag::new();
+        for &(insert, x) in ops {
+            if insert {
+                bag.insert(x);
+                model.insert(x);
+            } else {
+                assert_eq!(bag.remove(&x), model.remove(&x));
+            }
+        }
+        (bag, model)
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_agrees_with_hashbag(
+            ops in proptest::collection::vec((proptest::bool::ANY, 0u16..300), 0..400),
+        ) {
+            let (bag, model) = apply(&ops);
+            proptest::prop_assert_eq!(bag.len(), model.len());
+            for (elem, count) in model.iter() {
+                proptest::prop_assert_eq!(bag.count(elem), count);
+            }
+            proptest::prop_assert_eq!(bag.iter().count(), model.iter().count());
+        }
+
+        #[test]
+        fn prop_equal_bags_hash_equal(
+            ops1 in proptest::collection::vec((proptest::bool::ANY, 0u16..8), 0..40),
+            ops2 in proptest::collection::vec((proptest::bool::ANY, 0u16..8), 0..40),
+        ) {
+            let ((bag1, model1), (bag2, model2)) = (apply(&ops1), apply(&ops2));
+            proptest::prop_assert_eq!(bag1 == bag2, model1 == model2);
+            if bag1 == bag2 {
+                proptest::prop_assert_eq!(hash_of(&bag1), hash_of(&bag2));
+            }
+        }
+    }
+}
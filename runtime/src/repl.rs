@@ -0,0 +1,296 @@
+//! The interface between theories and the REPL (the `repl` feature)
+//!
+//! With the feature on, `theory!` implements [`Theory`] for the theory's unit
+//! struct (the one with `explore`), with metadata counted from the definition,
+//! and wraps terms of its primary category in a `<Theory>ReplTerm` that
+//! implements [`Term`]:
+//!
+//! ```text
+//! let theory: Box<dyn Theory> = Box::new(RhoCalc);
+//! let term = theory.parse_term("{a!(0) | for(a->x){*(x)}}")?;
+//! let results = theory.run_ascent(term)?;
+//! ```
+//!
+//! With the `holes` option, it also parses patterns (terms with `?holes`),
+//! which the REPL's `query` matches against the explored terms.
+//!
+//! Theories with custom evaluation (such as an environment) can implement
+//! [`Theory`] by hand and delegate the metadata to the generated impl.
+//!
+//! With the `serde` feature, [`AscentResults`] can be saved and loaded, as the
+//! REPL's `save`/`restore` do.
+
+use crate::{ExploreStats, RewriteGraph};
+pub use anyhow;
+use anyhow::Result;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// A trait that all theories must implement to be usable in the REPL
+pub trait Theory: Send + Sync {
+    /// Get the name of this theory, as used by `load`
+    fn name(&self) -> &str;
+
+    /// Get the category names exported by this theory
+    fn categories(&self) -> Vec<String>;
+
+    /// Get the number of constructors, including generated variable constructors
+    fn constructor_count(&self) -> usize;
+
+    /// Get the number of equations
+    fn equation_count(&self) -> usize;
+
+    /// Get the number of rewrite rules, including congruences
+    fn rewrite_count(&self) -> usize;
+
+    /// Parse a term from a string
+    fn parse_term(&self, input: &str) -> Result<Box<dyn Term>>;
+
+    /// Run Ascent on a term and return results
+    fn run_ascent(&self, term: Box<dyn Term>) -> Result<AscentResults>;
+
+    /// Run Ascent on a term, with statistics of the run if the theory keeps them
+    ///
+    /// Generated theories with the `stats` option report their program's
+    /// `explore_with_stats`.
+    fn run_ascent_with_stats(
+        &self,
+        term: Box<dyn Term>,
+    ) -> Result<(AscentResults, Option<ExploreStats>)> {
+        Ok((self.run_ascent(term)?, None))
+    }
+
+    /// The terms a term rewrites to in one step
+    ///
+    /// Generated theories run their program without exploring the targets
+    /// (`<Theory>::step`); by default, the whole graph is explored.
+    fn step(&self, term: &dyn Term) -> Result<Vec<Box<dyn Term>>> {
+        let results = self.run_ascent(term.clone_box())?;
+        results
+            .rewrites_from(term.term_id())
+            .iter()
+            .filter_map(|rewrite| results.term(rewrite.to_id))
+            .map(|target| self.parse_term(&target.display))
+            .collect()
+    }
+
+    /// Format a term as a string
+    fn format_term(&self, term: &dyn Term) -> String;
+
+    /// Get the concrete syntax of terms, for completion and highlighting
+    fn syntax(&self) -> Syntax {
+        Syntax::default()
+    }
+
+    /// Parse a pattern: a term that may contain `?holes` and `...`
+    ///
+    /// Only theories with the `holes` option have patterns.
+    fn parse_pattern(&self, input: &str) -> Result<Box<dyn Pattern>> {
+        let _ = input;
+        anyhow::bail!("Theory '{}' has no patterns (it needs the `holes` option)", self.name())
+    }
+}
+
+/// A term with holes, as parsed by [`Theory::parse_pattern`]
+pub trait Pattern: Send + Sync {
+    /// Match `term` against this pattern, returning what each hole matched
+    fn match_term(&self, term: &dyn Term) -> Option<Bindings>;
+}
+
+/// The display of the term each hole of a pattern matched, by hole name
+pub type Bindings = BTreeMap<String, String>;
+
+/// The tokens of a theory's concrete syntax
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Syntax {
+    /// The grammar's terminals, such as `for`, `(` and `->`
+    pub terminals: Vec<String>,
+
+    /// Constructor labels, such as `PInput`
+    pub constructors: Vec<String>,
+
+    /// The terminals directly before each binder: an identifier after one of
+    /// these sequences is bound, like `x` after `->` in `for(a->x)`
+    pub binder_prefixes: Vec<Vec<String>>,
+
+    /// Matching opening and closing delimiters
+    pub delimiters: Vec<(String, String)>,
+}
+
+impl Syntax {
+    /// The syntax of a grammar with these terminals (in any order, possibly
+    /// repeated), constructors, binder prefixes and collection delimiters
+    ///
+    /// Brackets among the terminals are paired as delimiters, as are
+    /// terminals ending in an opening bracket, like `new(`.
+    pub fn new(
+        terminals: impl IntoIterator<Item = String>,
+        constructors: impl IntoIterator<Item = String>,
+        binder_prefixes: impl IntoIterator<Item = Vec<String>>,
+        collection_delimiters: impl IntoIterator<Item = (String, String)>,
+    ) -> Self {
+        let mut terminals: Vec<String> = terminals.into_iter().collect();
+        terminals.sort();
+        terminals.dedup();
+        let mut binder_prefixes: Vec<Vec<String>> = binder_prefixes.into_iter().collect();
+        binder_prefixes.sort();
+        binder_prefixes.dedup();
+
+        let mut delimiters: Vec<(String, String)> = collection_delimiters.into_iter().collect();
+        for open in &terminals {
+            let close = match open.chars().last() {
+                Some('(') => ")",
+                Some('[') => "]",
+                Some('{') => "}",
+                _ => continue,
+            };
+            if terminals.iter().any(|t| t == close) {
+                delimiters.push((open.clone(), close.to_string()));
+            }
+        }
+        delimiters.sort();
+        delimiters.dedup();
+
+        Self {
+            terminals,
+            constructors: constructors.into_iter().collect(),
+            binder_prefixes,
+            delimiters,
+        }
+    }
+}
+
+/// A trait for terms (AST nodes) that can be manipulated generically
+pub trait Term: fmt::Display + fmt::Debug + Send + Sync {
+    /// Clone this term into a Box
+    fn clone_box(&self) -> Box<dyn Term>;
+
+    /// Get a unique identifier for this term (for equality comparison)
+    fn term_id(&self) -> u64;
+
+    /// Check if this term is equal to another
+    fn term_eq(&self, other: &dyn Term) -> bool;
+
+    /// Get this as Any for downcasting
+    fn as_any(&self) -> &dyn std::any::Any;
+}
+
+/// Results from running Ascent
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AscentResults {
+    /// All reachable terms
+    pub all_terms: Vec<TermInfo>,
+
+    /// All rewrites (from -> to)
+    pub rewrites: Vec<Rewrite>,
+
+    /// Equivalence classes (terms related by equations)
+    pub equivalences: Vec<EquivClass>,
+}
+
+/// Information about a term in the rewrite graph
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TermInfo {
+    pub term_id: u64,
+    pub display: String,
+    pub is_normal_form: bool,
+}
+
+/// A rewrite from one term to another
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rewrite {
+    pub from_id: u64,
+    pub to_id: u64,
+    pub rule_name: Option<String>,
+}
+
+/// An equivalence class of terms
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EquivClass {
+    pub term_ids: Vec<u64>,
+}
+
+impl AscentResults {
+    /// Create empty results
+    pub fn empty() -> Self {
+        Self {
+            all_terms: Vec::new(),
+            rewrites: Vec::new(),
+            equivalences: Vec::new(),
+        }
+    }
+
+    /// Convert a theory's `explore` result, identifying terms by their hash
+    pub fn from_rewrite_graph<T: Hash + fmt::Display>(graph: &RewriteGraph<T>) -> Self {
+        let ids: Vec<u64> = graph.nodes().iter().map(term_id).collect();
+
+        let all_terms = graph
+            .nodes()
+            .iter()
+            .enumerate()
+            .map(|(idx, term)| TermInfo {
+                term_id: ids[idx],
+                display: term.to_string(),
+                is_normal_form: graph.is_normal_form(idx),
+            })
+            .collect();
+
+        let rewrites = graph
+            .edges()
+            .iter()
+            .map(|edge| Rewrite {
+                from_id: ids[edge.from],
+                to_id: ids[edge.to],
+                rule_name: Some(edge.label.clone()),
+            })
+            .collect();
+
+        let equivalences = graph
+            .equivalence_classes()
+            .into_iter()
+            .map(|class| EquivClass {
+                term_ids: class.into_iter().map(|idx| ids[idx]).collect(),
+            })
+            .collect();
+
+        Self { all_terms, rewrites, equivalences }
+    }
+
+    /// Get a term by its ID
+    pub fn term(&self, term_id: u64) -> Option<&TermInfo> {
+        self.all_terms.iter().find(|t| t.term_id == term_id)
+    }
+
+    /// Get normal forms (terms with no outgoing rewrites)
+    pub fn normal_forms(&self) -> Vec<&TermInfo> {
+        self.all_terms.iter().filter(|t| t.is_normal_form).collect()
+    }
+
+    /// Get rewrites from a specific term
+    pub fn rewrites_from(&self, term_id: u64) -> Vec<&Rewrite> {
+        self.rewrites
+            .iter()
+            .filter(|r| r.from_id == term_id)
+            .collect()
+    }
+
+    /// Get the equivalence class containing a term
+    pub fn equiv_class(&self, term_id: u64) -> Option<&EquivClass> {
+        self.equivalences
+            .iter()
+            .find(|ec| ec.term_ids.contains(&term_id))
+    }
+}
+
+/// The identifier of a term in [`AscentResults`]: its hash
+pub fn term_id<T: Hash>(term: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    term.hash(&mut hasher);
+    hasher.finish()
+}
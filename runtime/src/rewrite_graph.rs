@@ -0,0 +1,431 @@
+//! Rewrite graphs produced by exploring a term
+//!
+//! A [`RewriteGraph`] holds the terms reached from a root term, the rewrite
+//! steps between them (labeled by the rule that took them), the
+//! equivalence classes induced by the theory's equations, and its normal
+//! forms. Generated theories fill one in via `<Theory>::explore(term)`.
+//!
+//! Graphs export to Graphviz DOT, JSON and GraphML for external tooling.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display, Write};
+use std::hash::Hash;
+
+/// A labeled rewrite step between two nodes (indices into [`RewriteGraph::nodes`])
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RewriteEdge {
+    pub from: usize,
+    pub to: usize,
+    pub label: String,
+}
+
+/// Terms, rewrite steps and equivalence classes reachable from a root term
+#[derive(Debug, Clone)]
+pub struct RewriteGraph<T> {
+    nodes: Vec<T>,
+    index: HashMap<T, usize>,
+    edges: Vec<RewriteEdge>,
+    edge_set: HashSet<RewriteEdge>,
+    /// Indices into `edges` of each node's outgoing steps
+    out_edges: Vec<Vec<usize>>,
+    /// Union-find parents over node indices, for equivalence classes
+    parents: Vec<usize>,
+    root: Option<usize>,
+}
+
+impl<T: Clone + Eq + Hash> Default for RewriteGraph<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone + Eq + Hash> RewriteGraph<T> {
+    /// Create an empty graph
+    pub fn new() -> Self {
+        RewriteGraph {
+            nodes: Vec::new(),
+            index: HashMap::new(),
+            edges: Vec::new(),
+            edge_set: HashSet::new(),
+            out_edges: Vec::new(),
+            parents: Vec::new(),
+            root: None,
+        }
+    }
+
+    /// Add a term, returning its node index (existing terms are not duplicated)
+    pub fn add_node(&mut self, term: T) -> usize {
+        if let Some(&idx) = self.index.get(&term) {
+            return idx;
+        }
+        let idx = self.nodes.len();
+        self.index.insert(term.clone(), idx);
+        self.nodes.push(term);
+        self.out_edges.push(Vec::new());
+        self.parents.push(idx);
+        idx
+    }
+
+    /// Add a rewrite step `from => to`, adding either term if needed
+    pub fn add_edge(&mut self, from: T, to: T, label: impl Into<String>) {
+        let from = self.add_node(from);
+        let to = self.add_node(to);
+        let edge = RewriteEdge { from, to, label: label.into() };
+        if self.edge_set.insert(edge.clone()) {
+            self.out_edges[from].push(self.edges.len());
+            self.edges.push(edge);
+        }
+    }
+
+    /// Record that two terms are equal. Terms that are not nodes are ignored,
+    /// since equations also relate terms that were never reached.
+    pub fn add_equivalence(&mut self, a: &T, b: &T) {
+        if let (Some(&a), Some(&b)) = (self.index.get(a), self.index.get(b)) {
+            let (ra, rb) = (self.find(a), self.find(b));
+            if ra != rb {
+                // Smaller index is the root, so classes are listed in discovery order
+                self.parents[ra.max(rb)] = ra.min(rb);
+            }
+        }
+    }
+
+    /// Mark the term exploration started from, adding it if needed
+    pub fn set_root(&mut self, term: T) {
+        let idx = self.add_node(term);
+        self.root = Some(idx);
+    }
+
+    /// Node index of a term
+    pub fn index_of(&self, term: &T) -> Option<usize> {
+        self.index.get(term).copied()
+    }
+
+    fn find(&self, mut idx: usize) -> usize {
+        while self.parents[idx] != idx {
+            idx = self.parents[idx];
+        }
+        idx
+    }
+}
+
+impl<T> RewriteGraph<T> {
+    /// All terms, in insertion order
+    pub fn nodes(&self) -> &[T] {
+        &self.nodes
+    }
+
+    /// The term at a node index
+    pub fn node(&self, idx: usize) -> &T {
+        &self.nodes[idx]
+    }
+
+    /// All rewrite steps
+    pub fn edges(&self) -> &[RewriteEdge] {
+        &self.edges
+    }
+
+    /// Node index of the root term, if set
+    pub fn root(&self) -> Option<usize> {
+        self.root
+    }
+
+    /// Rewrite steps out of a node
+    pub fn edges_from(&self, idx: usize) -> impl Iterator<Item = &RewriteEdge> {
+        self.out_edges[idx].iter().map(|&edge| &self.edges[edge])
+    }
+
+    /// Whether a node has no outgoing rewrites
+    pub fn is_normal_form(&self, idx: usize) -> bool {
+        self.out_edges[idx].is_empty()
+    }
+
+    /// Nodes with no outgoing rewrites
+    pub fn normal_forms(&self) -> Vec<usize> {
+        (0..self.nodes.len())
+            .filter(|&i| self.is_normal_form(i))
+            .collect()
+    }
+
+    /// Nodes whose term satisfies `pred`, e.g. matches a pattern
+    pub fn nodes_where(&self, mut pred: impl FnMut(&T) -> bool) -> Vec<usize> {
+        (0..self.nodes.len())
+            .filter(|&i| pred(&self.nodes[i]))
+            .collect()
+    }
+
+    /// Equivalence classes with more than one member, each sorted by node index
+    pub fn equivalence_classes(&self) -> Vec<Vec<usize>> {
+        let mut classes: Vec<Vec<usize>> = Vec::new();
+        let mut class_of_root: HashMap<usize, usize> = HashMap::new();
+        for idx in 0..self.nodes.len() {
+            let mut root = idx;
+            while self.parents[root] != root {
+                root = self.parents[root];
+            }
+            let class = *class_of_root.entry(root).or_insert_with(|| {
+                classes.push(Vec::new());
+                classes.len() - 1
+            });
+            classes[class].push(idx);
+        }
+        classes.retain(|c| c.len() > 1);
+        classes
+    }
+
+    /// Number of nodes
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether the graph has no nodes
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+//=============================================================================
+// EXPORTERS
+//=============================================================================
+
+impl<T: Display> RewriteGraph<T> {
+    /// Graphviz DOT: the root is drawn bold, normal forms double-circled, and
+    /// equivalence classes as dashed undirected edges
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph rewrites {\n");
+        for (idx, term) in self.nodes.iter().enumerate() {
+            let mut attrs = format!("label=\"{}\"", escape_dot(&term.to_string()));
+            if self.is_normal_form(idx) {
+                attrs.push_str(", shape=doublecircle");
+            }
+            if self.root == Some(idx) {
+                attrs.push_str(", style=bold");
+            }
+            writeln!(out, "  n{} [{}];", idx, attrs).unwrap();
+        }
+        for edge in &self.edges {
+            writeln!(
+                out,
+                "  n{} -> n{} [label=\"{}\"];",
+                edge.from,
+                edge.to,
+                escape_dot(&edge.label)
+            )
+            .unwrap();
+        }
+        for class in self.equivalence_classes() {
+            for pair in class.windows(2) {
+                writeln!(out, "  n{} -> n{} [dir=none, style=dashed];", pair[0], pair[1]).unwrap();
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// JSON object with `root`, `nodes`, `edges`, `equivalences` and `normal_forms`
+    pub fn to_json(&self) -> String {
+        let nodes: Vec<String> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(idx, term)| {
+                format!(
+                    "{{\"id\":{},\"term\":\"{}\",\"normal_form\":{}}}",
+                    idx,
+                    escape_json(&term.to_string()),
+                    self.is_normal_form(idx)
+                )
+            })
+            .collect();
+        let edges: Vec<String> = self
+            .edges
+            .iter()
+            .map(|e| {
+                format!(
+                    "{{\"from\":{},\"to\":{},\"label\":\"{}\"}}",
+                    e.from,
+                    e.to,
+                    escape_json(&e.label)
+                )
+            })
+            .collect();
+        let classes: Vec<String> = self
+            .equivalence_classes()
+            .iter()
+            .map(|c| format!("[{}]", join(c)))
+            .collect();
+        let root = match self.root {
+            Some(idx) => idx.to_string(),
+            None => "null".to_string(),
+        };
+        format!(
+            "{{\"root\":{},\"nodes\":[{}],\"edges\":[{}],\"equivalences\":[{}],\"normal_forms\":[{}]}}",
+            root,
+            nodes.join(","),
+            edges.join(","),
+            classes.join(","),
+            join(&self.normal_forms())
+        )
+    }
+
+    /// GraphML with `term`, `normal_form`, `root` and `class` node data and a
+    /// `label` edge datum; `class` is the index of the node's equivalence class
+    pub fn to_graphml(&self) -> String {
+        let mut class_of: HashMap<usize, usize> = HashMap::new();
+        for (class_idx, class) in self.equivalence_classes().iter().enumerate() {
+            for &idx in class {
+                class_of.insert(idx, class_idx);
+            }
+        }
+
+        let mut out = String::new();
+        out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        out.push_str("  <key id=\"term\" for=\"node\" attr.name=\"term\" attr.type=\"string\"/>\n");
+        out.push_str("  <key id=\"normal_form\" for=\"node\" attr.name=\"normal_form\" attr.type=\"boolean\"/>\n");
+        out.push_str(
+            "  <key id=\"root\" for=\"node\" attr.name=\"root\" attr.type=\"boolean\"/>\n",
+        );
+        out.push_str("  <key id=\"class\" for=\"node\" attr.name=\"class\" attr.type=\"int\"/>\n");
+        out.push_str(
+            "  <key id=\"label\" for=\"edge\" attr.name=\"label\" attr.type=\"string\"/>\n",
+        );
+        out.push_str("  <graph id=\"rewrites\" edgedefault=\"directed\">\n");
+        for (idx, term) in self.nodes.iter().enumerate() {
+            writeln!(out, "    <node id=\"n{}\">", idx).unwrap();
+            writeln!(out, "      <data key=\"term\">{}</data>", escape_xml(&term.to_string()))
+                .unwrap();
+            writeln!(out, "      <data key=\"normal_form\">{}</data>", self.is_normal_form(idx))
+                .unwrap();
+            writeln!(out, "      <data key=\"root\">{}</data>", self.root == Some(idx)).unwrap();
+            if let Some(class) = class_of.get(&idx) {
+                writeln!(out, "      <data key=\"class\">{}</data>", class).unwrap();
+            }
+            out.push_str("    </node>\n");
+        }
+        for (idx, edge) in self.edges.iter().enumerate() {
+            writeln!(
+                out,
+                "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\">",
+                idx, edge.from, edge.to
+            )
+            .unwrap();
+            writeln!(out, "      <data key=\"label\">{}</data>", escape_xml(&edge.label)).unwrap();
+            out.push_str("    </edge>\n");
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+}
+
+impl<T: Display> fmt::Display for RewriteGraph<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for edge in &self.edges {
+            writeln!(f, "{} ~> {}", self.nodes[edge.from], self.nodes[edge.to])?;
+        }
+        Ok(())
+    }
+}
+
+fn join(ids: &[usize]) -> String {
+    ids.iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_json(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> RewriteGraph<&'static str> {
+        let mut g = RewriteGraph::new();
+        g.set_root("a");
+        g.add_edge("a", "b", "rw_proc");
+        g.add_edge("a", "c", "rw_proc");
+        g.add_edge("a", "b", "rw_proc");
+        g.add_equivalence(&"b", &"c");
+        g.add_equivalence(&"b", &"unreached");
+        g
+    }
+
+    #[test]
+    fn nodes_edges_and_normal_forms() {
+        let g = sample();
+        assert_eq!(g.nodes(), &["a", "b", "c"]);
+        assert_eq!(g.edges().len(), 2);
+        assert_eq!(g.root(), Some(0));
+        assert_eq!(g.normal_forms(), vec![1, 2]);
+        assert_eq!(g.equivalence_classes(), vec![vec![1, 2]]);
+    }
+
+    #[test]
+    fn edges_from_a_node() {
+        let mut g = sample();
+        // Another rule for the same step is another edge
+        g.add_edge("a", "b", "other");
+        g.add_edge("b", "c", "rw_proc");
+        let targets: Vec<_> = g.edges_from(0).map(|e| (e.to, e.label.as_str())).collect();
+        assert_eq!(targets, vec![(1, "rw_proc"), (2, "rw_proc"), (1, "other")]);
+        assert!(!g.is_normal_form(1));
+        assert_eq!(g.normal_forms(), vec![2]);
+    }
+
+    #[test]
+    fn dot_export() {
+        let dot = sample().to_dot();
+        assert!(dot.starts_with("digraph rewrites {"));
+        assert!(dot.contains("n0 [label=\"a\", style=bold];"));
+        assert!(dot.contains("n1 [label=\"b\", shape=doublecircle];"));
+        assert!(dot.contains("n0 -> n1 [label=\"rw_proc\"];"));
+        assert!(dot.contains("n1 -> n2 [dir=none, style=dashed];"));
+    }
+
+    #[test]
+    fn json_export() {
+        let mut g = RewriteGraph::new();
+        g.add_edge("say \"hi\"", "x", "rw");
+        assert_eq!(
+            g.to_json(),
+            "{\"root\":null,\"nodes\":[{\"id\":0,\"term\":\"say \\\"hi\\\"\",\"normal_form\":false},\
+             {\"id\":1,\"term\":\"x\",\"normal_form\":true}],\
+             \"edges\":[{\"from\":0,\"to\":1,\"label\":\"rw\"}],\"equivalences\":[],\"normal_forms\":[1]}"
+        );
+    }
+
+    #[test]
+    fn graphml_export() {
+        let mut g = RewriteGraph::new();
+        g.add_edge("{a | b}", "a<b", "rw");
+        let xml = g.to_graphml();
+        assert!(xml.contains("<data key=\"term\">a&lt;b</data>"));
+        assert!(xml.contains("<edge id=\"e0\" source=\"n0\" target=\"n1\">"));
+        assert!(xml.trim_end().ends_with("</graphml>"));
+    }
+}
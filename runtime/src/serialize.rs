@@ -0,0 +1,271 @@
+//! Serde support (the `serde` feature)
+//!
+//! Terms serialize in locally-nameless form: variables bound by a `Scope` are
+//! written as de Bruijn indices (`{"Bound":{"scope":0,"binder":0,"name":"x"}}`)
+//! and the scope itself records only its binders' names, for pretty-printing.
+//! Free variables are written by name (`{"Free":"x"}`).
+//!
+//! Deserializing interns free variable names the way a parser does, so every
+//! `x` in a document is the same variable. Names are interned in the context
+//! lent by [`deserialize_with_vars`], which the deserializer must run inside:
+//! pass the [`VarContext`] of an existing parse to get its variables back, or a
+//! fresh one per document.
+//!
+//! ```text
+//! let vars = VarContext::new();
+//! let term = rhocalc::ProcParser::new().parse(&vars, "a!(0)")?;
+//! let json = serde_json::to_string(&term)?;
+//! let back: Proc = deserialize_with_vars(&vars, || serde_json::from_str(&json))?;
+//! assert_eq!(term, back);
+//! ```
+//!
+//! Outside of it, deserializing a named free variable is an error.
+//!
+//! Two distinct free variables with the same name (e.g. a freshened binder and
+//! the variable it was renamed from) are merged on deserialization.
+
+use crate::{
+    Binder, BoundPattern, BoundVar, FreeVar, HashBag, OrdVar, PersistentBag, Scope, Var, VarContext,
+};
+use moniker::{BinderIndex, ScopeOffset};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::Cell;
+use std::hash::Hash;
+
+thread_local! {
+    /// The variables lent by the innermost `deserialize_with_vars`; empty
+    /// outside of one
+    static DESERIALIZE_VARS: VarContext = VarContext::new();
+
+    /// How many `deserialize_with_vars` calls are running
+    static LENDS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Run `f`, interning the free variables it deserializes in `vars`
+///
+/// The variables of `vars` are lent to the deserializer for the duration of
+/// `f`, so names already created in `vars` resolve to the same variables and
+/// new names are added to it.
+pub fn deserialize_with_vars<R>(vars: &VarContext, f: impl FnOnce() -> R) -> R {
+    /// Puts the thread's variables back, even if `f` panics
+    struct Lend<'a>(&'a VarContext);
+
+    impl Drop for Lend<'_> {
+        fn drop(&mut self) {
+            DESERIALIZE_VARS.with(|thread_vars| thread_vars.swap(self.0));
+            LENDS.with(|lends| lends.set(lends.get() - 1));
+        }
+    }
+
+    DESERIALIZE_VARS.with(|thread_vars| thread_vars.swap(vars));
+    LENDS.with(|lends| lends.set(lends.get() + 1));
+    let _lend = Lend(vars);
+    f()
+}
+
+//=============================================================================
+// VARIABLES
+//=============================================================================
+
+#[derive(Serialize, Deserialize)]
+enum VarRepr {
+    Free(Option<String>),
+    Bound {
+        scope: u32,
+        binder: u32,
+        name: Option<String>,
+    },
+}
+
+impl Serialize for OrdVar {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let repr = match &self.0 {
+            Var::Free(var) => VarRepr::Free(var.pretty_name.clone()),
+            Var::Bound(var) => VarRepr::Bound {
+                scope: var.scope.0,
+                binder: var.binder.0,
+                name: var.pretty_name.clone(),
+            },
+        };
+        repr.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for OrdVar {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let var = match VarRepr::deserialize(deserializer)? {
+            VarRepr::Free(Some(name)) => {
+                if LENDS.with(Cell::get) == 0 {
+                    return Err(D::Error::custom(format_args!(
+                        "free variable `{}` deserialized outside deserialize_with_vars",
+                        name
+                    )));
+                }
+                Var::Free(DESERIALIZE_VARS.with(|vars| vars.var(name)))
+            },
+            VarRepr::Free(None) => Var::Free(FreeVar::fresh_unnamed()),
+            VarRepr::Bound { scope, binder, name } => Var::Bound(BoundVar {
+                scope: ScopeOffset(scope),
+                binder: BinderIndex(binder),
+                pretty_name: name,
+            }),
+        };
+        Ok(OrdVar(var))
+    }
+}
+
+//=============================================================================
+// SCOPES
+//=============================================================================
+
+#[derive(Serialize)]
+struct ScopeRef<'a, T> {
+    binders: Vec<Option<String>>,
+    body: &'a T,
+}
+
+#[derive(Deserialize)]
+struct ScopeRepr<T> {
+    binders: Vec<Option<String>>,
+    body: T,
+}
+
+impl<P: BoundPattern<String>, T: Serialize> Serialize for Scope<P, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // The body is already closed, so it is written as is
+        let mut binders = Vec::new();
+        self.unsafe_pattern()
+            .visit_binders(&mut |binder| binders.push(binder.0.pretty_name.clone()));
+        ScopeRef { binders, body: self.unsafe_body() }.serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Scope<Binder<String>, T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let ScopeRepr { binders, body } = ScopeRepr::deserialize(deserializer)?;
+        let [name]: [Option<String>; 1] = binders
+            .try_into()
+            .map_err(|b: Vec<_>| D::Error::invalid_length(b.len(), &"a single binder"))?;
+        // Binder identities are irrelevant once the body is closed; a fresh one
+        // stands in until the scope is unbound
+        Ok(Scope::from_parts_unsafe(Binder(FreeVar::fresh(name)), body))
+    }
+}
+
+//=============================================================================
+// COLLECTIONS
+//=============================================================================
+
+// Bags are written as `[element, count]` pairs
+
+/// Reject bag entries whose counts are zero or sum past `usize::MAX`
+fn checked_counts<T, E: serde::de::Error>(counts: Vec<(T, usize)>) -> Result<Vec<(T, usize)>, E> {
+    let mut size = 0usize;
+    for (_, count) in &counts {
+        size = size
+            .checked_add(*count)
+            .filter(|_| *count > 0)
+            .ok_or_else(|| E::custom(format_args!("invalid bag count {}", count)))?;
+    }
+    Ok(counts)
+}
+
+impl<T: Serialize + Clone + Hash + Eq> Serialize for HashBag<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Clone + Hash + Eq> Deserialize<'de> for HashBag<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut bag = HashBag::new();
+        for (item, count) in checked_counts(Vec::deserialize(deserializer)?)? {
+            bag.insert_n(item, count);
+        }
+        Ok(bag)
+    }
+}
+
+impl<T: Serialize + Clone + Hash + Eq> Serialize for PersistentBag<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Clone + Hash + Eq> Deserialize<'de> for PersistentBag<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut bag = PersistentBag::new();
+        for (item, count) in checked_counts(Vec::deserialize(deserializer)?)? {
+            bag.insert_n(item, count);
+        }
+        Ok(bag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T: Serialize + for<'de> Deserialize<'de>>(vars: &VarContext, value: &T) -> T {
+        let json = serde_json::to_string(value).unwrap();
+        deserialize_with_vars(vars, || serde_json::from_str(&json)).unwrap()
+    }
+
+    #[test]
+    fn free_variables_keep_identity() {
+        let vars = VarContext::new();
+        let x = OrdVar(Var::Free(vars.var("x")));
+        assert_eq!(serde_json::to_string(&x).unwrap(), r#"{"Free":"x"}"#);
+        assert_eq!(round_trip(&vars, &x), x);
+        assert_eq!(vars.len(), 1);
+    }
+
+    #[test]
+    fn scopes_are_locally_nameless() {
+        let vars = VarContext::new();
+        let x = vars.var("x");
+        let scope = Scope::new(Binder(x.clone()), OrdVar(Var::Free(x)));
+        assert_eq!(
+            serde_json::to_string(&scope).unwrap(),
+            r#"{"binders":["x"],"body":{"Bound":{"scope":0,"binder":0,"name":"x"}}}"#
+        );
+        assert_eq!(round_trip(&vars, &scope), scope);
+    }
+
+    #[test]
+    fn free_variables_need_a_context() {
+        let json = r#"{"Free":"x"}"#;
+        assert!(serde_json::from_str::<OrdVar>(json).is_err());
+
+        // Each context interns its own variables, and none is kept afterwards
+        let first: OrdVar =
+            deserialize_with_vars(&VarContext::new(), || serde_json::from_str(json)).unwrap();
+        let second: OrdVar =
+            deserialize_with_vars(&VarContext::new(), || serde_json::from_str(json)).unwrap();
+        assert_ne!(first, second);
+        assert!(serde_json::from_str::<OrdVar>(json).is_err());
+        DESERIALIZE_VARS.with(|vars| assert_eq!(vars.len(), 0));
+    }
+
+    #[test]
+    fn bad_bag_counts_are_rejected() {
+        let huge = format!("[[1,{}],[2,{}]]", usize::MAX, usize::MAX);
+        assert!(serde_json::from_str::<HashBag<u32>>(&huge).is_err());
+        assert!(serde_json::from_str::<PersistentBag<u32>>("[[1,0]]").is_err());
+
+        // Counts are not expanded one by one
+        let bag: HashBag<u32> = serde_json::from_str(&format!("[[1,{}]]", usize::MAX)).unwrap();
+        assert_eq!(bag.count(&1), usize::MAX);
+    }
+
+    #[test]
+    fn bags_round_trip() {
+        let vars = VarContext::new();
+        let bag = HashBag::from_iter(["a", "b", "a"].map(|n| OrdVar(Var::Free(vars.var(n)))));
+        assert_eq!(round_trip(&vars, &bag), bag);
+
+        let bag: PersistentBag<u32> = PersistentBag::from_iter([1, 2, 2]);
+        assert_eq!(round_trip(&vars, &bag), bag);
+    }
+}
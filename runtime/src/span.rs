@@ -0,0 +1,272 @@
+//! Source spans of parsed terms
+//!
+//! Parsing with a context from [`VarContext::with_spans`] records the byte
+//! range of every constructor the parser builds. Generated categories turn
+//! the recording into a [`SpanTable`] keyed by [`TermPath`], so positions in
+//! rewrite traces or errors can point back at the source text:
+//!
+//! ```text
+//! let vars = VarContext::with_spans();
+//! let term = rhocalc::ProcParser::new().parse(&vars, src)?;
+//! let spans = term.spans(&vars);
+//! let span = spans.get(&path).unwrap();
+//! println!("line {}: {}", span.line_col(src).0, span.text(src));
+//! ```
+//!
+//! Constructors are recorded bottom-up, and each one claims the spans nested
+//! in it as its children. Collections store their elements sorted, so
+//! elements are matched to their spans by hash, falling back to source order
+//! for the few whose hash changed when an enclosing binder closed over them.
+
+use crate::{TermPath, VarContext};
+use rustc_hash::FxHasher;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// A byte range `start..end` of the source text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    /// The spanned source text
+    pub fn text<'s>(&self, source: &'s str) -> &'s str {
+        &source[self.start..self.end]
+    }
+
+    /// 1-based line and column (in characters) where the span starts
+    pub fn line_col(&self, source: &str) -> (usize, usize) {
+        let before = &source[..self.start];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        (line, before[line_start..].chars().count() + 1)
+    }
+
+    /// Whether `offset` lies within the span
+    pub fn contains(&self, offset: usize) -> bool {
+        self.start <= offset && offset < self.end
+    }
+
+    fn encloses(&self, other: &Span) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+/// The source spans of a parsed term's subterms, by position
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpanTable {
+    spans: BTreeMap<TermPath, Span>,
+}
+
+impl SpanTable {
+    /// The span of the subterm at `path`
+    pub fn get(&self, path: &TermPath) -> Option<Span> {
+        self.spans.get(path).copied()
+    }
+
+    /// The span of the whole term
+    pub fn root(&self) -> Option<Span> {
+        self.get(&TermPath::root())
+    }
+
+    /// The innermost subterm whose span contains `offset`
+    pub fn path_at(&self, offset: usize) -> Option<&TermPath> {
+        self.spans
+            .iter()
+            .filter(|(_, span)| span.contains(offset))
+            .max_by_key(|(path, _)| path.len())
+            .map(|(path, _)| path)
+    }
+
+    /// Spans in path order
+    pub fn iter(&self) -> impl Iterator<Item = (&TermPath, Span)> {
+        self.spans.iter().map(|(path, span)| (path, *span))
+    }
+
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+}
+
+/// A recorded constructor: its span, the hash of the term built, and the
+/// constructors nested in it in source order
+#[derive(Debug)]
+pub(crate) struct SpanTree {
+    span: Span,
+    key: u64,
+    children: Vec<SpanTree>,
+}
+
+/// Hash used to match recorded spans to subterms
+#[doc(hidden)]
+pub fn span_key<T: Hash + ?Sized>(term: &T) -> u64 {
+    let mut hasher = FxHasher::default();
+    term.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl VarContext {
+    /// A context that also records the source spans of the terms parsed with it
+    ///
+    /// Take the spans of each parsed term with its generated `spans` method
+    /// before parsing the next one.
+    pub fn with_spans() -> Self {
+        let vars = Self::default();
+        *vars.spans.borrow_mut() = Some(Vec::new());
+        vars
+    }
+
+    /// Whether this context records spans
+    pub fn records_spans(&self) -> bool {
+        self.spans.borrow().is_some()
+    }
+
+    /// Record that `term` was parsed from `start..end`; called by generated parsers
+    #[doc(hidden)]
+    pub fn spanned<T: Hash>(&self, start: usize, end: usize, term: T) -> T {
+        if let Some(stack) = self.spans.borrow_mut().as_mut() {
+            let span = Span::new(start, end);
+            let first_child = stack
+                .iter()
+                .rposition(|tree| !span.encloses(&tree.span))
+                .map_or(0, |i| i + 1);
+            let children = stack.split_off(first_child);
+            stack.push(SpanTree { span, key: span_key(&term), children });
+        }
+        term
+    }
+
+    /// Take the spans recorded for the last parsed term, positioned like the
+    /// subterms of `root`; called by generated `spans` methods
+    #[doc(hidden)]
+    pub fn take_spans<T: Copy>(
+        &self,
+        root: T,
+        children: impl Fn(T) -> Vec<T>,
+        key: impl Fn(T) -> u64,
+    ) -> SpanTable {
+        let mut table = SpanTable::default();
+        let tree = self.spans.borrow_mut().as_mut().and_then(|stack| {
+            let tree = stack.pop();
+            stack.clear();
+            tree
+        });
+        if let Some(tree) = tree {
+            align(tree, root, TermPath::root(), &children, &key, &mut table);
+        }
+        table
+    }
+}
+
+fn align<T: Copy>(
+    tree: SpanTree,
+    term: T,
+    path: TermPath,
+    children: &impl Fn(T) -> Vec<T>,
+    key: &impl Fn(T) -> u64,
+    table: &mut SpanTable,
+) {
+    table.spans.insert(path.clone(), tree.span);
+
+    let subterms = children(term);
+    let mut unclaimed: Vec<Option<SpanTree>> = tree.children.into_iter().map(Some).collect();
+    let mut claimed: Vec<Option<SpanTree>> = subterms
+        .iter()
+        .map(|&sub| {
+            let k = key(sub);
+            unclaimed
+                .iter_mut()
+                .find(|tree| tree.as_ref().is_some_and(|tree| tree.key == k))
+                .and_then(Option::take)
+        })
+        .collect();
+    let mut rest = unclaimed.into_iter().flatten();
+
+    for (i, sub) in subterms.into_iter().enumerate() {
+        if let Some(tree) = claimed[i].take().or_else(|| rest.next()) {
+            align(tree, sub, path.child(i), children, key, table);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_col_counts_characters() {
+        let source = "a\n  é b";
+        let b = source.find('b').unwrap();
+        assert_eq!(Span::new(b, b + 1).line_col(source), (2, 5));
+        assert_eq!(Span::new(0, 1).line_col(source), (1, 1));
+        assert_eq!(Span::new(b, b + 1).text(source), "b");
+    }
+
+    /// `(a b) c` as nested tuples: a node is its children
+    #[test]
+    fn nested_spans_become_children() {
+        let vars = VarContext::with_spans();
+        vars.spanned(1, 2, 'a');
+        vars.spanned(3, 4, 'b');
+        vars.spanned(0, 5, "ab");
+        vars.spanned(6, 7, 'c');
+        vars.spanned(0, 7, "abc");
+
+        let children = |t: &'static str| match t {
+            "abc" => vec!["ab", "c"],
+            "ab" => vec!["a", "b"],
+            _ => vec![],
+        };
+        let key = |t: &'static str| match t.len() {
+            1 => span_key(&t.chars().next().unwrap()),
+            _ => span_key(&t),
+        };
+        let table = vars.take_spans("abc", children, key);
+        assert_eq!(table.len(), 5);
+        assert_eq!(table.root(), Some(Span::new(0, 7)));
+        assert_eq!(table.get(&"/0/1".parse().unwrap()), Some(Span::new(3, 4)));
+        assert_eq!(table.path_at(6), Some(&"/1".parse().unwrap()));
+        assert!(vars.take_spans("abc", children, key).is_empty());
+    }
+
+    #[test]
+    fn collection_elements_match_by_hash() {
+        let vars = VarContext::with_spans();
+        // Parsed as `{z, y}`, stored sorted as `{y, z}`
+        vars.spanned(1, 2, 'z');
+        vars.spanned(4, 5, 'y');
+        vars.spanned(0, 6, 's');
+
+        let children = |t: char| if t == 's' { vec!['y', 'z'] } else { vec![] };
+        let table = vars.take_spans('s', children, |t| span_key(&t));
+        assert_eq!(table.get(&"/0".parse().unwrap()), Some(Span::new(4, 5)));
+        assert_eq!(table.get(&"/1".parse().unwrap()), Some(Span::new(1, 2)));
+    }
+
+    #[test]
+    fn contexts_record_spans_only_on_request() {
+        let vars = VarContext::new();
+        assert!(!vars.records_spans());
+        vars.spanned(0, 1, 'a');
+        assert!(vars
+            .take_spans('a', |_| vec![], |t| span_key(&t))
+            .is_empty());
+    }
+}
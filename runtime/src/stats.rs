@@ -0,0 +1,150 @@
+//! Statistics of a run of a theory's Ascent program
+//!
+//! With the theory's `stats` option, `<Theory>::explore_with_stats(term)`
+//! returns an [`ExploreStats`] with the graph: the number of facts of each
+//! relation, how often each rule fired, and Ascent's iterations and time per
+//! SCC (strongly connected component of rules, evaluated to a fixpoint in turn).
+//!
+//! Ascent does not count rule firings, so `theory!` emits a second copy of the
+//! program, `<theory>_stats_source`, whose rules end with
+//! `if mettail_runtime::fire(i)`. That counts only inside [`count_firings`];
+//! elsewhere it does nothing.
+
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::time::Duration;
+
+thread_local! {
+    /// Firings per rule, while counting
+    static FIRINGS: RefCell<Option<Vec<usize>>> = const { RefCell::new(None) };
+}
+
+/// Count a firing of rule `rule`, if counting; always holds
+#[doc(hidden)]
+pub fn fire(rule: usize) -> bool {
+    FIRINGS.with(|firings| {
+        if let Some(counts) = firings.borrow_mut().as_mut() {
+            if let Some(count) = counts.get_mut(rule) {
+                *count += 1;
+            }
+        }
+    });
+    true
+}
+
+/// Run `f`, counting the firings of rules `0..rules` on this thread
+pub fn count_firings<R>(rules: usize, f: impl FnOnce() -> R) -> (R, Vec<usize>) {
+    /// Puts the outer counts back, even if `f` panics
+    struct Restore(Option<Vec<usize>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let outer = self.0.take();
+            FIRINGS.with(|firings| firings.replace(outer));
+        }
+    }
+
+    let restore = Restore(FIRINGS.with(|firings| firings.replace(Some(vec![0; rules]))));
+    let result = f();
+    let counts = FIRINGS.with(|firings| firings.take());
+    drop(restore);
+    (result, counts.unwrap_or_default())
+}
+
+/// Statistics of a run of a theory's Ascent program
+#[derive(Debug, Clone, Default)]
+pub struct ExploreStats {
+    /// Facts per relation, by relation name
+    pub relations: Vec<(String, usize)>,
+
+    /// Firings per rule, in program order
+    pub rules: Vec<RuleStats>,
+
+    /// Iterations and time per SCC, in evaluation order
+    pub sccs: Vec<SccStats>,
+
+    /// Time running the Ascent program
+    pub ascent_time: Duration,
+
+    /// Time collecting its result into a graph
+    pub graph_time: Duration,
+}
+
+/// How often a rule fired
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleStats {
+    /// The rule's section and relations, as `rewrite: rw_proc <-- proc, ppar_contains`
+    pub label: String,
+
+    /// The number of times its body matched (including re-derived facts)
+    pub firings: usize,
+}
+
+/// Ascent's evaluation of one SCC
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SccStats {
+    pub iterations: usize,
+    pub time: Duration,
+}
+
+impl ExploreStats {
+    /// The total number of facts
+    pub fn total_facts(&self) -> usize {
+        self.relations.iter().map(|(_, count)| count).sum()
+    }
+
+    /// The total number of rule firings
+    pub fn total_firings(&self) -> usize {
+        self.rules.iter().map(|rule| rule.firings).sum()
+    }
+
+    /// The total number of iterations, over all SCCs
+    pub fn total_iterations(&self) -> usize {
+        self.sccs.iter().map(|scc| scc.iterations).sum()
+    }
+
+    /// The rules that fired, most often first
+    pub fn busiest_rules(&self) -> Vec<&RuleStats> {
+        let mut rules: Vec<_> = self.rules.iter().filter(|rule| rule.firings > 0).collect();
+        rules.sort_by_key(|rule| Reverse(rule.firings));
+        rules
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_firings() {
+        // Outside counting, firing does nothing
+        assert!(fire(0));
+
+        let ((), counts) = count_firings(2, || {
+            fire(0);
+            fire(1);
+            fire(1);
+            // Nested runs count separately
+            let ((), inner) = count_firings(1, || {
+                fire(0);
+            });
+            assert_eq!(inner, vec![1]);
+            fire(7);
+        });
+        assert_eq!(counts, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_count_firings_after_panic() {
+        let panicked = std::panic::catch_unwind(|| {
+            count_firings(1, || {
+                fire(0);
+                panic!("rule panicked");
+            })
+        });
+        assert!(panicked.is_err());
+
+        // Counting stopped with the panic
+        FIRINGS.with(|firings| assert!(firings.borrow().is_none()));
+    }
+}
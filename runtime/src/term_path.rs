@@ -0,0 +1,136 @@
+//! Positions of subterms
+//!
+//! A [`TermPath`] addresses a subterm by the child indices leading to it from
+//! the root. Generated theories number the children of a constructor in field
+//! order, with collection elements in sorted order (repeated once per
+//! occurrence), and provide `subterm`, `positions` and `replace_at` to navigate
+//! and edit terms by path. Paths display as `/0/2`; the root is `/`.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// The position of a subterm: child indices from the root
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TermPath {
+    indices: Vec<usize>,
+}
+
+impl TermPath {
+    /// The path to the root itself
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// Whether this is the root path
+    pub fn is_root(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Child indices from the root
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Number of steps from the root
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Whether this is the root path (same as [`TermPath::is_root`])
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// The path to this subterm's `index`th child
+    pub fn child(&self, index: usize) -> Self {
+        let mut indices = self.indices.clone();
+        indices.push(index);
+        TermPath { indices }
+    }
+
+    /// The path to this subterm's parent, or `None` at the root
+    pub fn parent(&self) -> Option<Self> {
+        let (_, parent) = self.indices.split_last()?;
+        Some(TermPath {
+            indices: parent.to_vec(),
+        })
+    }
+
+    /// Step down to the `index`th child in place
+    pub fn push(&mut self, index: usize) {
+        self.indices.push(index);
+    }
+
+    /// Step up to the parent in place, returning the child index left
+    pub fn pop(&mut self) -> Option<usize> {
+        self.indices.pop()
+    }
+
+    /// Whether this path addresses `other` or one of its ancestors
+    pub fn is_prefix_of(&self, other: &TermPath) -> bool {
+        other.indices.starts_with(&self.indices)
+    }
+}
+
+impl From<Vec<usize>> for TermPath {
+    fn from(indices: Vec<usize>) -> Self {
+        TermPath { indices }
+    }
+}
+
+impl FromIterator<usize> for TermPath {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        TermPath {
+            indices: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl fmt::Display for TermPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.indices.is_empty() {
+            return write!(f, "/");
+        }
+        for index in &self.indices {
+            write!(f, "/{}", index)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TermPath {
+    type Err = std::num::ParseIntError;
+
+    /// Parse `/0/2` (or `0/2`); `/` and the empty string are the root
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split('/')
+            .filter(|part| !part.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn navigation() {
+        let path = TermPath::root().child(0).child(2);
+        assert_eq!(path.indices(), &[0, 2]);
+        assert_eq!(path.parent(), Some(TermPath::from(vec![0])));
+        assert_eq!(TermPath::root().parent(), None);
+        assert!(TermPath::from(vec![0]).is_prefix_of(&path));
+        assert!(!path.is_prefix_of(&TermPath::from(vec![0])));
+    }
+
+    #[test]
+    fn display_and_parse() {
+        let path = TermPath::from(vec![0, 2, 1]);
+        assert_eq!(path.to_string(), "/0/2/1");
+        assert_eq!("/0/2/1".parse::<TermPath>().unwrap(), path);
+        assert_eq!(TermPath::root().to_string(), "/");
+        assert_eq!("/".parse::<TermPath>().unwrap(), TermPath::root());
+        assert!("/a".parse::<TermPath>().is_err());
+    }
+}
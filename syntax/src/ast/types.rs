@@ -14,6 +14,43 @@ pub struct TheoryDef {
     pub equations: Vec<Equation>,
     pub rewrites: Vec<RewriteRule>,
     pub semantics: Vec<SemanticRule>,
+    pub options: TheoryOptions,
+}
+
+/// Theory-level settings
+/// options { equation_backend: egraph, rewrite_modulo_equations: true, bag_backend: persistent, holes: true, stats: true }
+#[derive(Debug, Clone, Default)]
+pub struct TheoryOptions {
+    pub equation_backend: EquationBackend,
+    /// Match rewrite LHS constructors up to `eq_<cat>` instead of syntactically
+    pub rewrite_modulo_equations: bool,
+    pub bag_backend: BagBackend,
+    /// Give each category a `<X>Hole(name)` metavariable variant, parsed as `?name`
+    pub holes: bool,
+    /// Generate `<Theory>::explore_with_stats`, which runs a copy of the program
+    /// that counts rule firings
+    pub stats: bool,
+}
+
+/// Runtime type backing `HashBag(...)` collection fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BagBackend {
+    /// `mettail_runtime::HashBag`
+    #[default]
+    HashMap,
+    /// `mettail_runtime::PersistentBag`: O(1) clone, structurally shared updates
+    Persistent,
+}
+
+/// How equations are closed under congruence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EquationBackend {
+    /// Per-constructor congruence clauses over `#[ds(eqrel)] eq_<cat>`
+    #[default]
+    EqRel,
+    /// Congruence closure in an e-graph (`<Theory>EGraph`) instead of
+    /// generated congruence clauses
+    EGraph,
 }
 
 /// Theory parameter (for generic theories)
@@ -217,6 +254,18 @@ fn parse(input: ParseStream) -> SynResult<Self> {
             Vec::new()
         };
 
+        // Parse: options { ... } (optional)
+        let options = if input.peek(Ident) {
+            let lookahead = input.fork().parse::<Ident>()?;
+            if lookahead == "options" {
+                parse_options(input)?
+            } else {
+                TheoryOptions::default()
+            }
+        } else {
+            TheoryOptions::default()
+        };
+
         // Parse: exports { ... }
         let exports = if input.peek(Ident) {
             let lookahead = input.fork().parse::<Ident>()?;
@@ -285,6 +334,7 @@ fn parse(input: ParseStream) -> SynResult<Self> {
             equations,
             rewrites,
             semantics,
+            options,
         })
     }
 }
@@ -321,6 +371,67 @@ fn parse_params(input: ParseStream) -> SynResult<Vec<TheoryParam>> {
     Ok(params)
 }
 
+fn parse_options(input: ParseStream) -> SynResult<TheoryOptions> {
+    let options_ident = input.parse::<Ident>()?;
+    if options_ident != "options" {
+        return Err(syn::Error::new(options_ident.span(), "expected 'options'"));
+    }
+
+    let content;
+    syn::braced!(content in input);
+
+    let mut options = TheoryOptions::default();
+    while !content.is_empty() {
+        let key = content.parse::<Ident>()?;
+        let _ = content.parse::<Token![:]>()?;
+
+        if key == "equation_backend" {
+            let value = content.parse::<Ident>()?;
+            options.equation_backend = if value == "eqrel" {
+                EquationBackend::EqRel
+            } else if value == "egraph" {
+                EquationBackend::EGraph
+            } else {
+                return Err(syn::Error::new(
+                    value.span(),
+                    "expected equation backend 'eqrel' or 'egraph'",
+                ));
+            };
+        } else if key == "bag_backend" {
+            let value = content.parse::<Ident>()?;
+            options.bag_backend = if value == "hashmap" {
+                BagBackend::HashMap
+            } else if value == "persistent" {
+                BagBackend::Persistent
+            } else {
+                return Err(syn::Error::new(
+                    value.span(),
+                    "expected bag backend 'hashmap' or 'persistent'",
+                ));
+            };
+        } else if key == "rewrite_modulo_equations" {
+            options.rewrite_modulo_equations = content.parse::<syn::LitBool>()?.value;
+        } else if key == "holes" {
+            options.holes = content.parse::<syn::LitBool>()?.value;
+        } else if key == "stats" {
+            options.stats = content.parse::<syn::LitBool>()?.value;
+        } else {
+            return Err(syn::Error::new(key.span(), format!("unknown theory option '{}'", key)));
+        }
+
+        if content.peek(Token![,]) {
+            let _ = content.parse::<Token![,]>()?;
+        }
+    }
+
+    // Optional comma after closing brace
+    if input.peek(Token![,]) {
+        let _ = input.parse::<Token![,]>()?;
+    }
+
+    Ok(options)
+}
+
 fn parse_exports(input: ParseStream) -> SynResult<Vec<Export>> {
     let exports_ident = input.parse::<Ident>()?;
     if exports_ident != "exports" {
@@ -1132,4 +1243,101 @@ fn parse_collection_error_missing_sep() {
         // The error will be about unexpected token, not specifically about 'sep'
         // Just verify it fails to parse
     }
+
+    #[test]
+    fn parse_options_equation_backend() {
+        let input = quote! {
+            name: TestOpts,
+            options { equation_backend: egraph },
+            exports { Elem }
+            terms {
+                EZero . Elem ::= "0" ;
+            }
+        };
+
+        let theory = parse2::<TheoryDef>(input).expect("options should parse");
+        assert_eq!(theory.options.equation_backend, EquationBackend::EGraph);
+        assert!(!theory.options.rewrite_modulo_equations);
+    }
+
+    #[test]
+    fn parse_options_rewrite_modulo_equations() {
+        let input = quote! {
+            name: TestOpts,
+            options {
+                equation_backend: eqrel,
+                rewrite_modulo_equations: true,
+            },
+            exports { Elem }
+        };
+
+        let theory = parse2::<TheoryDef>(input).expect("options should parse");
+        assert_eq!(theory.options.equation_backend, EquationBackend::EqRel);
+        assert!(theory.options.rewrite_modulo_equations);
+    }
+
+    #[test]
+    fn parse_options_bag_backend() {
+        let input = quote! {
+            name: TestOpts,
+            options { bag_backend: persistent },
+            exports { Elem }
+        };
+        let theory = parse2::<TheoryDef>(input).expect("options should parse");
+        assert_eq!(theory.options.bag_backend, BagBackend::Persistent);
+
+        let input = quote! {
+            name: TestOpts,
+            options { bag_backend: btree },
+            exports { Elem }
+        };
+        assert!(parse2::<TheoryDef>(input).is_err());
+    }
+
+    #[test]
+    fn parse_options_holes() {
+        let input = quote! {
+            name: TestOpts,
+            options { holes: true },
+            exports { Elem }
+        };
+        let theory = parse2::<TheoryDef>(input).expect("options should parse");
+        assert!(theory.options.holes);
+    }
+
+    #[test]
+    fn parse_options_stats() {
+        let input = quote! {
+            name: TestOpts,
+            options { stats: true },
+            exports { Elem }
+        };
+        let theory = parse2::<TheoryDef>(input).expect("options should parse");
+        assert!(theory.options.stats);
+        assert!(!theory.options.holes);
+    }
+
+    #[test]
+    fn parse_options_default_and_unknown() {
+        let input = quote! {
+            name: TestOpts,
+            exports { Elem }
+            terms {
+                EZero . Elem ::= "0" ;
+            }
+        };
+        let theory = parse2::<TheoryDef>(input).unwrap();
+        assert_eq!(theory.options.equation_backend, EquationBackend::EqRel);
+        assert_eq!(theory.options.bag_backend, BagBackend::HashMap);
+
+        let input = quote! {
+            name: TestOpts,
+            options { no_such_option: true },
+            exports { Elem }
+        };
+        let err = parse2::<TheoryDef>(input)
+            .err()
+            .expect("unknown option should fail");
+        assert!(err.to_string().contains("unknown theory option"));
+    }
 }
@@ -0,0 +1,43 @@
+//! The theory definition language of MeTTaIL
+//!
+//! Shared by the `theory!` macro, which parses its input into a
+//! [`ast::TheoryDef`], and by tools that read theory definitions at runtime
+//! (such as the REPL's `load-file`), which parse the same syntax from text
+//! with [`parse_theory`].
+
+pub mod ast;
+pub mod validation;
+
+use proc_macro2::{Delimiter, Span, TokenStream, TokenTree};
+
+/// Parse a theory definition from source text
+///
+/// The text is either the body of a `theory! { ... }` invocation or the whole
+/// invocation, so a theory can be copied between Rust and `.mettail` files.
+pub fn parse_theory(source: &str) -> syn::Result<ast::TheoryDef> {
+    let tokens: TokenStream = source
+        .parse()
+        .map_err(|e: proc_macro2::LexError| syn::Error::new(e.span(), e))?;
+    syn::parse2(strip_invocation(tokens))
+}
+
+/// The line (1-based) and column (0-based) where `span` starts in the text
+/// given to [`parse_theory`]
+pub fn line_column(span: Span) -> (usize, usize) {
+    let start = span.start();
+    (start.line, start.column)
+}
+
+/// The body of `theory! { ... }`, or `tokens` unchanged
+fn strip_invocation(tokens: TokenStream) -> TokenStream {
+    let trees: Vec<TokenTree> = tokens.clone().into_iter().collect();
+    match trees.as_slice() {
+        [TokenTree::Ident(name), TokenTree::Punct(bang), TokenTree::Group(body)]
+        | [TokenTree::Ident(name), TokenTree::Punct(bang), TokenTree::Group(body), TokenTree::Punct(_)]
+            if name == "theory" && bang.as_char() == '!' && body.delimiter() != Delimiter::None =>
+        {
+            body.stream()
+        },
+        _ => tokens,
+    }
+}
@@ -393,6 +393,7 @@ fn make_simple_theory() -> TheoryDef {
             equations: vec![],
             rewrites: vec![],
             semantics: vec![],
+            options: TheoryOptions::default(),
         }
     }
 
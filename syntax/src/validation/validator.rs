@@ -366,6 +366,7 @@ fn test_valid_theory() {
             equations: vec![],
             rewrites: vec![],
             semantics: vec![],
+            options: TheoryOptions::default(),
         };
 
         assert!(validate_theory(&theory).is_ok());
@@ -392,6 +393,7 @@ fn test_invalid_category() {
             equations: vec![],
             rewrites: vec![],
             semantics: vec![],
+            options: TheoryOptions::default(),
         };
 
         assert!(validate_theory(&theory).is_err());
@@ -418,6 +420,7 @@ fn test_invalid_reference() {
             equations: vec![],
             rewrites: vec![],
             semantics: vec![],
+            options: TheoryOptions::default(),
         };
 
         let result = validate_theory(&theory);
@@ -494,6 +497,7 @@ fn test_freshness_valid() {
             }],
             rewrites: vec![],
             semantics: vec![],
+            options: TheoryOptions::default(),
         };
 
         // Should pass - x and P both appear in equation, types match
@@ -537,6 +541,7 @@ fn test_freshness_variable_not_in_equation() {
             }],
             rewrites: vec![],
             semantics: vec![],
+            options: TheoryOptions::default(),
         };
 
         let result = validate_theory(&theory);
@@ -575,6 +580,7 @@ fn test_freshness_self_reference() {
             }],
             rewrites: vec![],
             semantics: vec![],
+            options: TheoryOptions::default(),
         };
 
         let result = validate_theory(&theory);
@@ -9,6 +9,10 @@
 // Ambient Calculus Theory Definition
 theory! {
     name: Ambient,
+    options {
+        holes: true,
+        stats: true,
+    },
     exports {
         Proc
         Name
@@ -1,6 +1,8 @@
 // Generated Ascent Datalog for ambient theory
 // This file is generated by the theory! macro and is for inspection only.
 // Do not edit manually - changes will be overwritten.
+// ambient_stats_source (for explore_with_stats) is this program with each rule ending in
+// `if mettail_runtime::fire(<rule index>)`, which counts its firings.
 
 ascent_source! {
     ambient_source:
@@ -16,15 +18,25 @@
 
 relation rw_proc(Proc, Proc);
 
+relation rw_proc_rule(Proc, Proc, & 'static str);
+
 relation rw_name(Name, Name);
 
+relation rw_name_rule(Name, Name, & 'static str);
+
+relation one_step(bool);
+
 relation ppar_contains(Proc, Proc);
 
 
     // Category rules
+rw_proc(s, t) <--
+    rw_proc_rule(s, t, _);
+
 proc(c1) <--
     proc(c0),
-    rw_proc(c0, c1);
+    rw_proc(c0, c1),
+    ! one_step(true);
 
 name(field_0.as_ref().clone()),
 proc(field_1.as_ref().clone()) <--
@@ -59,9 +71,13 @@
 proc(elem) <--
     ppar_contains(_parent, elem);
 
+rw_name(s, t) <--
+    rw_name_rule(s, t, _);
+
 name(c1) <--
     name(c0),
-    rw_name(c0, c1);
+    rw_name(c0, c1),
+    ! one_step(true);
 
 
     // Equation rules
@@ -127,7 +143,7 @@
 
 bag.remove(& p0_elem_0);
 
-bag }, let rest = p0_rest.clone(), let p = body_0.clone(), let x = binder_0.clone(), if is_fresh(& x, & rest), let p1 = (Proc :: PNew(mettail_runtime :: Scope :: from_parts_unsafe(x.clone(), Box :: new(Proc :: PPar({ let mut bag = mettail_runtime :: HashBag :: new();
+bag }, let p = body_0.clone(), let rest = p0_rest.clone(), let x = binder_0.clone(), if is_fresh(& x, & rest), let p1 = (Proc :: PNew(mettail_runtime :: Scope :: from_parts_unsafe(x.clone(), Box :: new(Proc :: PPar({ let mut bag = mettail_runtime::HashBag :: new();
 
 bag.insert(p.clone());
 
@@ -143,9 +159,9 @@
     if let Proc :: PNew(p0_f1_inner_f0) = p0_f1_inner,
     let binder_1 = p0_f1_inner_f0.inner().unsafe_pattern.clone(),
     let body_1 = p0_f1_inner_f0.inner().unsafe_body.as_ref().clone(),
-    let x = binder_1.clone(),
-    let p = body_1.clone(),
     let n = p0_f0_val.clone(),
+    let p = body_1.clone(),
+    let x = binder_1.clone(),
     if is_fresh(& x, & p),
     let p1 = (Proc :: PNew(mettail_runtime :: Scope :: from_parts_unsafe(x.clone(), Box :: new(Proc :: PIn(Box :: new(n.clone()), Box :: new(p.clone())))))).normalize();
 
@@ -157,9 +173,9 @@
     if let Proc :: PNew(p0_f1_inner_f0) = p0_f1_inner,
     let binder_1 = p0_f1_inner_f0.inner().unsafe_pattern.clone(),
     let body_1 = p0_f1_inner_f0.inner().unsafe_body.as_ref().clone(),
-    let x = binder_1.clone(),
     let n = p0_f0_val.clone(),
     let p = body_1.clone(),
+    let x = binder_1.clone(),
     if is_fresh(& x, & p),
     let p1 = (Proc :: PNew(mettail_runtime :: Scope :: from_parts_unsafe(x.clone(), Box :: new(Proc :: POut(Box :: new(n.clone()), Box :: new(p.clone())))))).normalize();
 
@@ -172,8 +188,8 @@
     let binder_1 = p0_f1_inner_f0.inner().unsafe_pattern.clone(),
     let body_1 = p0_f1_inner_f0.inner().unsafe_body.as_ref().clone(),
     let n = p0_f0_val.clone(),
-    let x = binder_1.clone(),
     let p = body_1.clone(),
+    let x = binder_1.clone(),
     if is_fresh(& x, & p),
     let p1 = (Proc :: PNew(mettail_runtime :: Scope :: from_parts_unsafe(x.clone(), Box :: new(Proc :: POpen(Box :: new(n.clone()), Box :: new(p.clone())))))).normalize();
 
@@ -185,64 +201,95 @@
     if let Proc :: PNew(p0_f1_inner_f0) = p0_f1_inner,
     let binder_1 = p0_f1_inner_f0.inner().unsafe_pattern.clone(),
     let body_1 = p0_f1_inner_f0.inner().unsafe_body.as_ref().clone(),
-    let p = body_1.clone(),
     let n = p0_f0_val.clone(),
+    let p = body_1.clone(),
     let x = binder_1.clone(),
     if is_fresh(& x, & p),
     let p1 = (Proc :: PNew(mettail_runtime :: Scope :: from_parts_unsafe(x.clone(), Box :: new(Proc :: PAmb(Box :: new(n.clone()), Box :: new(p.clone())))))).normalize();
 
 
     // Rewrite rules
-rw_proc(s, t) <--
+relation pamb_rw1_d0_d0(Proc, Name, Proc, mettail_runtime::HashBag < Proc >);
+
+pamb_rw1_d0_d0(t.clone(), m.clone(), p.clone(), rest.clone()) <--
+    proc(t),
+    if let Proc :: PPar(t_f0) = t,
+    for (t_f0_elem_0, _count_t_f0_0) in t_f0.iter(),
+    if let Proc :: POut(t_f0_elem_0_f0, t_f0_elem_0_f1) = t_f0_elem_0,
+    let t_f0_elem_0_f0_val = t_f0_elem_0_f0.as_ref(),
+    let t_f0_elem_0_f1_val = t_f0_elem_0_f1.as_ref(),
+    let t_f0_rest = { let mut bag = t_f0.clone();
+
+bag.remove(& t_f0_elem_0);
+
+bag }, let m = t_f0_elem_0_f0_val.clone(), let p = t_f0_elem_0_f1_val.clone(), let rest = t_f0_rest.clone();
+
+relation pamb_rw1_d0(Proc, Name, Name, Proc, Proc, mettail_runtime::HashBag < Proc >);
+
+pamb_rw1_d0(t.clone(), m.clone(), n.clone(), p.clone(), r.clone(), rest.clone()) <--
+    proc(t),
+    if let Proc :: PPar(t_f0) = t,
+    for (t_f0_elem_0, _count_t_f0_0) in t_f0.iter(),
+    if let Proc :: PAmb(t_f0_elem_0_f0, t_f0_elem_0_f1) = t_f0_elem_0,
+    let t_f0_elem_0_f0_val = t_f0_elem_0_f0.as_ref(),
+    let t_f0_elem_0_f1_val = t_f0_elem_0_f1.as_ref(),
+    for (t_f0_elem_1, _count_t_f0_1) in t_f0.iter(),
+    if & t_f0_elem_1 != & t_f0_elem_0,
+    pamb_rw1_d0_d0(t_f0_elem_0_f1_val.clone(), deep0_m, deep0_p, deep0_rest),
+    let m = deep0_m.clone(),
+    let n = t_f0_elem_0_f0_val.clone(),
+    let p = deep0_p.clone(),
+    let r = t_f0_elem_1.clone(),
+    let rest = deep0_rest.clone();
+
+rw_proc_rule(s, t, "PAmb(PPar{PAmb(PPar{POut})})") <--
     proc(s),
     if let Proc :: PAmb(s_f0, s_f1) = s,
     let s_f0_val = s_f0.as_ref(),
-    let s_f1_inner = s_f1.as_ref(),
-    if let Proc :: PPar(s_f1_inner_f0) = s_f1_inner,
-    for (s_f1_inner_f0_elem_0, _count_s_f1_inner_f0_0) in s_f1_inner_f0.iter(),
-    if let Proc :: PAmb(s_f1_inner_f0_elem_0_f0, s_f1_inner_f0_elem_0_f1) = s_f1_inner_f0_elem_0,
-    let s_f1_inner_f0_elem_0_f0_val = s_f1_inner_f0_elem_0_f0.as_ref(),
-    let s_f1_inner_f0_elem_0_f1_inner = s_f1_inner_f0_elem_0_f1.as_ref(),
-    if let Proc :: PPar(s_f1_inner_f0_elem_0_f1_inner_f0) = s_f1_inner_f0_elem_0_f1_inner,
-    for (s_f1_inner_f0_elem_0_f1_inner_f0_elem_0, _count_s_f1_inner_f0_elem_0_f1_inner_f0_0) in s_f1_inner_f0_elem_0_f1_inner_f0.iter(),
-    if let Proc :: POut(s_f1_inner_f0_elem_0_f1_inner_f0_elem_0_f0, s_f1_inner_f0_elem_0_f1_inner_f0_elem_0_f1) = s_f1_inner_f0_elem_0_f1_inner_f0_elem_0,
-    let s_f1_inner_f0_elem_0_f1_inner_f0_elem_0_f0_val = s_f1_inner_f0_elem_0_f1_inner_f0_elem_0_f0.as_ref(),
-    let s_f1_inner_f0_elem_0_f1_inner_f0_elem_0_f1_val = s_f1_inner_f0_elem_0_f1_inner_f0_elem_0_f1.as_ref(),
-    let s_f1_inner_f0_elem_0_f1_inner_f0_rest = { let mut bag = s_f1_inner_f0_elem_0_f1_inner_f0.clone();
-
-bag.remove(& s_f1_inner_f0_elem_0_f1_inner_f0_elem_0);
+    let s_f1_val = s_f1.as_ref(),
+    pamb_rw1_d0(s_f1_val.clone(), deep0_m, deep0_n, deep0_p, deep0_r, deep0_rest),
+    eq_name(s_f0_val.clone(), deep0_m.clone()),
+    let t = (Proc :: PPar({ let mut bag = mettail_runtime::HashBag :: new();
 
-bag }, for (s_f1_inner_f0_elem_1, _count_s_f1_inner_f0_1) in s_f1_inner_f0.iter(), if & s_f1_inner_f0_elem_1 != & s_f1_inner_f0_elem_0, eq_name(s_f0_val.clone(), s_f1_inner_f0_elem_0_f1_inner_f0_elem_0_f0_val.clone()), let t = (Proc :: PPar({ let mut bag = mettail_runtime :: HashBag :: new();
+Proc :: insert_into_ppar(& mut bag, Proc :: PAmb(Box :: new(deep0_n.clone()), Box :: new(Proc :: PPar({ let mut bag = (deep0_rest.clone()).clone();
 
-Proc :: insert_into_ppar(& mut bag, Proc :: PAmb(Box :: new(s_f1_inner_f0_elem_0_f0_val.clone()), Box :: new(Proc :: PPar({ let mut bag = (s_f1_inner_f0_elem_0_f1_inner_f0_rest.clone()).clone();
-
-Proc :: insert_into_ppar(& mut bag, s_f1_inner_f0_elem_0_f1_inner_f0_elem_0_f1_val.clone());
+Proc :: insert_into_ppar(& mut bag, deep0_p.clone());
 
 bag }))));
 
-Proc :: insert_into_ppar(& mut bag, Proc :: PAmb(Box :: new(s_f0_val.clone()), Box :: new(s_f1_inner_f0_elem_1.clone())));
+Proc :: insert_into_ppar(& mut bag, Proc :: PAmb(Box :: new(s_f0_val.clone()), Box :: new(deep0_r.clone())));
 
 bag })).normalize();
 
-relation pamb_proj_c3_b0_p0(Proc, Name, Name, mettail_runtime :: HashBag < Proc > , Proc, Proc);
+relation pamb_proj_c3_b0_p0_d0(Proc, Name, Proc, mettail_runtime::HashBag < Proc >);
+
+pamb_proj_c3_b0_p0_d0(t.clone(), m.clone(), p.clone(), rest.clone()) <--
+    proc(t),
+    if let Proc :: PPar(t_f0) = t,
+    for (t_f0_elem_0, _count_t_f0_0) in t_f0.iter(),
+    if let Proc :: PIn(t_f0_elem_0_f0, t_f0_elem_0_f1) = t_f0_elem_0,
+    let t_f0_elem_0_f0_val = t_f0_elem_0_f0.as_ref(),
+    let t_f0_elem_0_f1_val = t_f0_elem_0_f1.as_ref(),
+    let t_f0_rest = { let mut bag = t_f0.clone();
+
+bag.remove(& t_f0_elem_0);
+
+bag }, let m = t_f0_elem_0_f0_val.clone(), let p = t_f0_elem_0_f1_val.clone(), let rest = t_f0_rest.clone();
+
+relation pamb_proj_c3_b0_p0(Proc, Name, Name, Proc, mettail_runtime::HashBag < Proc > , Proc);
 
-pamb_proj_c3_b0_p0(parent.clone(), m.clone(), n.clone(), rest.clone(), p.clone(), elem.clone()) <--
+pamb_proj_c3_b0_p0(parent.clone(), m.clone(), n.clone(), p.clone(), rest.clone(), elem.clone()) <--
     proc(parent),
     if let Proc :: PPar(ref bag_field) = parent,
     for (elem, _count) in bag_field.iter(),
     if let Proc :: PAmb(elem_f0, elem_f1) = elem,
     let elem_f0_val = elem_f0.as_ref(),
-    let elem_f1_inner = elem_f1.as_ref(),
-    if let Proc :: PPar(elem_f1_inner_f0) = elem_f1_inner,
-    for (elem_f1_inner_f0_elem_0, _count_elem_f1_inner_f0_0) in elem_f1_inner_f0.iter(),
-    if let Proc :: PIn(elem_f1_inner_f0_elem_0_f0, elem_f1_inner_f0_elem_0_f1) = elem_f1_inner_f0_elem_0,
-    let elem_f1_inner_f0_elem_0_f0_val = elem_f1_inner_f0_elem_0_f0.as_ref(),
-    let elem_f1_inner_f0_elem_0_f1_val = elem_f1_inner_f0_elem_0_f1.as_ref(),
-    let elem_f1_inner_f0_rest = { let mut bag = elem_f1_inner_f0.clone();
-
-bag.remove(& elem_f1_inner_f0_elem_0);
-
-bag }, let m = elem_f1_inner_f0_elem_0_f0_val.clone(), let n = elem_f0_val.clone(), let rest = elem_f1_inner_f0_rest.clone(), let p = elem_f1_inner_f0_elem_0_f1_val.clone();
+    let elem_f1_val = elem_f1.as_ref(),
+    pamb_proj_c3_b0_p0_d0(elem_f1_val.clone(), deep0_m, deep0_p, deep0_rest),
+    let m = deep0_m.clone(),
+    let n = elem_f0_val.clone(),
+    let p = deep0_p.clone(),
+    let rest = deep0_rest.clone();
 
 relation pamb_proj_c3_b0_p1(Proc, Name, Proc, Proc);
 
@@ -254,30 +301,55 @@
     let cap_m = (* * f0).clone(),
     let cap_r = (* * f1).clone();
 
-relation pamb_proj_c3_b1_p0(Proc, Name, Name, mettail_runtime :: HashBag < Proc > , Proc, Proc, Proc);
+relation pamb_proj_c3_b1_p0_d0_d0(Proc, Name, Proc, mettail_runtime::HashBag < Proc >);
+
+pamb_proj_c3_b1_p0_d0_d0(t.clone(), m.clone(), p.clone(), rest.clone()) <--
+    proc(t),
+    if let Proc :: PPar(t_f0) = t,
+    for (t_f0_elem_0, _count_t_f0_0) in t_f0.iter(),
+    if let Proc :: POut(t_f0_elem_0_f0, t_f0_elem_0_f1) = t_f0_elem_0,
+    let t_f0_elem_0_f0_val = t_f0_elem_0_f0.as_ref(),
+    let t_f0_elem_0_f1_val = t_f0_elem_0_f1.as_ref(),
+    let t_f0_rest = { let mut bag = t_f0.clone();
+
+bag.remove(& t_f0_elem_0);
+
+bag }, let m = t_f0_elem_0_f0_val.clone(), let p = t_f0_elem_0_f1_val.clone(), let rest = t_f0_rest.clone();
 
-pamb_proj_c3_b1_p0(parent.clone(), n.clone(), m.clone(), rest.clone(), r.clone(), p.clone(), elem.clone()) <--
+relation pamb_proj_c3_b1_p0_d0(Proc, Name, Name, Proc, Proc, mettail_runtime::HashBag < Proc >);
+
+pamb_proj_c3_b1_p0_d0(t.clone(), m.clone(), n.clone(), p.clone(), r.clone(), rest.clone()) <--
+    proc(t),
+    if let Proc :: PPar(t_f0) = t,
+    for (t_f0_elem_0, _count_t_f0_0) in t_f0.iter(),
+    if let Proc :: PAmb(t_f0_elem_0_f0, t_f0_elem_0_f1) = t_f0_elem_0,
+    let t_f0_elem_0_f0_val = t_f0_elem_0_f0.as_ref(),
+    let t_f0_elem_0_f1_val = t_f0_elem_0_f1.as_ref(),
+    for (t_f0_elem_1, _count_t_f0_1) in t_f0.iter(),
+    if & t_f0_elem_1 != & t_f0_elem_0,
+    pamb_proj_c3_b1_p0_d0_d0(t_f0_elem_0_f1_val.clone(), deep0_m, deep0_p, deep0_rest),
+    let m = deep0_m.clone(),
+    let n = t_f0_elem_0_f0_val.clone(),
+    let p = deep0_p.clone(),
+    let r = t_f0_elem_1.clone(),
+    let rest = deep0_rest.clone();
+
+relation pamb_proj_c3_b1_p0(Proc, Name, Name, Proc, Proc, mettail_runtime::HashBag < Proc > , Proc);
+
+pamb_proj_c3_b1_p0(parent.clone(), m.clone(), n.clone(), p.clone(), r.clone(), rest.clone(), elem.clone()) <--
     proc(parent),
     if let Proc :: PPar(ref bag_field) = parent,
     for (elem, _count) in bag_field.iter(),
     if let Proc :: PAmb(elem_f0, elem_f1) = elem,
     let elem_f0_val = elem_f0.as_ref(),
-    let elem_f1_inner = elem_f1.as_ref(),
-    if let Proc :: PPar(elem_f1_inner_f0) = elem_f1_inner,
-    for (elem_f1_inner_f0_elem_0, _count_elem_f1_inner_f0_0) in elem_f1_inner_f0.iter(),
-    if let Proc :: PAmb(elem_f1_inner_f0_elem_0_f0, elem_f1_inner_f0_elem_0_f1) = elem_f1_inner_f0_elem_0,
-    let elem_f1_inner_f0_elem_0_f0_val = elem_f1_inner_f0_elem_0_f0.as_ref(),
-    let elem_f1_inner_f0_elem_0_f1_inner = elem_f1_inner_f0_elem_0_f1.as_ref(),
-    if let Proc :: PPar(elem_f1_inner_f0_elem_0_f1_inner_f0) = elem_f1_inner_f0_elem_0_f1_inner,
-    for (elem_f1_inner_f0_elem_0_f1_inner_f0_elem_0, _count_elem_f1_inner_f0_elem_0_f1_inner_f0_0) in elem_f1_inner_f0_elem_0_f1_inner_f0.iter(),
-    if let Proc :: POut(elem_f1_inner_f0_elem_0_f1_inner_f0_elem_0_f0, elem_f1_inner_f0_elem_0_f1_inner_f0_elem_0_f1) = elem_f1_inner_f0_elem_0_f1_inner_f0_elem_0,
-    let elem_f1_inner_f0_elem_0_f1_inner_f0_elem_0_f0_val = elem_f1_inner_f0_elem_0_f1_inner_f0_elem_0_f0.as_ref(),
-    let elem_f1_inner_f0_elem_0_f1_inner_f0_elem_0_f1_val = elem_f1_inner_f0_elem_0_f1_inner_f0_elem_0_f1.as_ref(),
-    let elem_f1_inner_f0_elem_0_f1_inner_f0_rest = { let mut bag = elem_f1_inner_f0_elem_0_f1_inner_f0.clone();
-
-bag.remove(& elem_f1_inner_f0_elem_0_f1_inner_f0_elem_0);
-
-bag }, for (elem_f1_inner_f0_elem_1, _count_elem_f1_inner_f0_1) in elem_f1_inner_f0.iter(), if & elem_f1_inner_f0_elem_1 != & elem_f1_inner_f0_elem_0, let n = elem_f1_inner_f0_elem_0_f0_val.clone(), let m = elem_f1_inner_f0_elem_0_f1_inner_f0_elem_0_f0_val.clone(), let rest = elem_f1_inner_f0_elem_0_f1_inner_f0_rest.clone(), let r = elem_f1_inner_f0_elem_1.clone(), let p = elem_f1_inner_f0_elem_0_f1_inner_f0_elem_0_f1_val.clone();
+    let elem_f1_val = elem_f1.as_ref(),
+    pamb_proj_c3_b1_p0_d0(elem_f1_val.clone(), deep0_m, deep0_n, deep0_p, deep0_r, deep0_rest),
+    eq_name(elem_f0_val.clone(), deep0_m.clone()),
+    let m = elem_f0_val.clone(),
+    let n = deep0_n.clone(),
+    let p = deep0_p.clone(),
+    let r = deep0_r.clone(),
+    let rest = deep0_rest.clone();
 
 relation popen_proj_c3_b2_p0(Proc, Name, Proc, Proc);
 
@@ -318,11 +390,11 @@
     if let Proc :: PAmb(_field0, ref rewrite_field_box) = elem,
     let rewrite_field = (* * rewrite_field_box).clone();
 
-rw_proc(parent, result) <--
-    pamb_proj_c3_b0_p0(parent, cap_m_p0, cap_n_p0, cap_rest_p0, cap_p_p0, elem_0),
+rw_proc_rule(parent, result, "PPar{PAmb(PPar{PIn}), PAmb}") <--
+    pamb_proj_c3_b0_p0(parent, cap_m_p0, cap_n_p0, cap_p_p0, cap_rest_p0, elem_0),
+    eq_name(cap_m_p0.clone(), cap_m_p1),
     pamb_proj_c3_b0_p1(parent, cap_m_p1, cap_r_p1, elem_1),
-    eq_name(cap_m_p0.clone(), cap_m_p1.clone()),
-    let rhs_term = Proc :: PAmb(Box :: new(cap_m_p0.clone()), Box :: new(Proc :: PPar({ let mut bag = mettail_runtime :: HashBag :: new();
+    let rhs_term = Proc :: PAmb(Box :: new(cap_m_p0.clone()), Box :: new(Proc :: PPar({ let mut bag = mettail_runtime::HashBag :: new();
 
 Proc :: insert_into_ppar(& mut bag, Proc :: PAmb(Box :: new(cap_n_p0.clone()), Box :: new(Proc :: PPar({ let mut bag = (cap_rest_p0.clone()).clone();
 
@@ -332,21 +404,17 @@
 
 Proc :: insert_into_ppar(& mut bag, cap_r_p1.clone());
 
-bag }))), if let Proc :: PPar(ref bag) = parent, let remaining = { let mut b = bag.clone();
-
-b.remove(elem_0);
-
-b.remove(elem_1);
+bag }))), if let Proc :: PPar(ref bag) = parent, if let Some(remaining) = { let mut b = bag.clone();
 
-b }, let result = Proc :: PPar({ let mut bag_result = remaining;
+if b.remove(elem_0) && b.remove(elem_1) { Some(b) } else { None } }, let result = Proc :: PPar({ let mut bag_result = remaining;
 
 Proc :: insert_into_ppar(& mut bag_result, rhs_term);
 
 bag_result }).normalize();
 
-rw_proc(parent, result) <--
-    pamb_proj_c3_b1_p0(parent, cap_n_p0, cap_m_p0, cap_rest_p0, cap_r_p0, cap_p_p0, elem_0),
-    let rhs_term = Proc :: PPar({ let mut bag = mettail_runtime :: HashBag :: new();
+rw_proc_rule(parent, result, "PAmb(PPar{PAmb(PPar{POut})})") <--
+    pamb_proj_c3_b1_p0(parent, cap_m_p0, cap_n_p0, cap_p_p0, cap_r_p0, cap_rest_p0, elem_0),
+    let rhs_term = Proc :: PPar({ let mut bag = mettail_runtime::HashBag :: new();
 
 Proc :: insert_into_ppar(& mut bag, Proc :: PAmb(Box :: new(cap_n_p0.clone()), Box :: new(Proc :: PPar({ let mut bag = (cap_rest_p0.clone()).clone();
 
@@ -366,31 +434,27 @@
 
 bag_result }).normalize();
 
-rw_proc(parent, result) <--
+rw_proc_rule(parent, result, "PPar{POpen, PAmb}") <--
     popen_proj_c3_b2_p0(parent, cap_n_p0, cap_p_p0, elem_0),
+    eq_name(cap_n_p0.clone(), cap_n_p1),
     pamb_proj_c3_b2_p1(parent, cap_n_p1, cap_q_p1, elem_1),
-    eq_name(cap_n_p0.clone(), cap_n_p1.clone()),
-    let rhs_term = Proc :: PPar({ let mut bag = mettail_runtime :: HashBag :: new();
+    let rhs_term = Proc :: PPar({ let mut bag = mettail_runtime::HashBag :: new();
 
 Proc :: insert_into_ppar(& mut bag, cap_p_p0.clone());
 
 Proc :: insert_into_ppar(& mut bag, cap_q_p1.clone());
 
-bag }), if let Proc :: PPar(ref bag) = parent, let remaining = { let mut b = bag.clone();
-
-b.remove(elem_0);
+bag }), if let Proc :: PPar(ref bag) = parent, if let Some(remaining) = { let mut b = bag.clone();
 
-b.remove(elem_1);
-
-b }, let result = Proc :: PPar({ let mut bag_result = remaining;
+if b.remove(elem_0) && b.remove(elem_1) { Some(b) } else { None } }, let result = Proc :: PPar({ let mut bag_result = remaining;
 
 Proc :: insert_into_ppar(& mut bag_result, rhs_term);
 
 bag_result }).normalize();
 
-rw_proc(parent, result) <--
+rw_proc_rule(parent, result, rule) <--
     pnew_proj_c3_r0(parent, binder_var, body, elem),
-    rw_proc(body, body_rewritten),
+    rw_proc_rule(body, body_rewritten, rule),
     if let Proc :: PPar(ref bag) = parent,
     let remaining = { let mut b = bag.clone();
 
@@ -402,9 +466,9 @@
 
 bag }).normalize();
 
-rw_proc(parent, result) <--
+rw_proc_rule(parent, result, rule) <--
     pamb_proj_c3_r1(parent, body, elem),
-    rw_proc(body, body_rewritten),
+    rw_proc_rule(body, body_rewritten, rule),
     if let Proc :: PPar(ref bag) = parent,
     let remaining = { let mut b = bag.clone();
 
@@ -424,16 +488,16 @@
     let binder_var = scope.inner().unsafe_pattern.clone(),
     let body = scope.inner().unsafe_body.as_ref().clone();
 
-rw_proc(parent, result) <--
+rw_proc_rule(parent, result, rule) <--
     pnew_direct_congruence_proj(parent, binder_var, body),
-    rw_proc(body, body_rewritten),
+    rw_proc_rule(body, body_rewritten, rule),
     let scope_tmp = mettail_runtime :: Scope :: from_parts_unsafe(binder_var.clone(), Box :: new(body_rewritten.clone())),
     let result = Proc :: PNew(scope_tmp).normalize();
 
-rw_proc(s, t) <--
+rw_proc_rule(s, t, rule) <--
     proc(s),
     if let Proc :: PAmb(n, s0) = s,
-    rw_proc(* * s0, t0),
+    rw_proc_rule(* * s0, t0, rule),
     let t = Proc :: PAmb(n.clone(), Box :: new(t0.clone()));
 
 }
@@ -12,13 +12,21 @@
 
 relation rw_int(Int, Int);
 
+relation rw_int_rule(Int, Int, & 'static str);
+
+relation one_step(bool);
+
 relation env_var(String, i32);
 
 
     // Category rules
+rw_int(s, t) <--
+    rw_int_rule(s, t, _);
+
 int(c1) <--
     int(c0),
-    rw_int(c0, c1);
+    rw_int(c0, c1),
+    ! one_step(true);
 
 int(field_0.as_ref().clone()),
 int(field_1.as_ref().clone()) <--
@@ -57,55 +65,55 @@
 
 
     // Rewrite rules
-rw_int(s, t) <--
+rw_int_rule(s, t, "VarRef") <--
     int(s),
     if let Int :: VarRef(s_f0) = s,
     if let Some(var_name) = { let var_name_opt = match s_f0.clone() { mettail_runtime :: OrdVar(mettail_runtime :: Var :: Free(ref fv)) => { fv.pretty_name.clone() } _ => None };
 
 var_name_opt }, env_var(var_name, v), let t = Int :: NumLit(* v);
 
-rw_int(s, t) <--
+rw_int_rule(s, t, "Add") <--
     int(s),
     if let Int :: Add(left, right) = s,
     if let Int :: NumLit(a) = left.as_ref(),
     if let Int :: NumLit(b) = right.as_ref(),
     let t = Int :: NumLit(a + b);
 
-rw_int(s, t) <--
+rw_int_rule(s, t, "Sub") <--
     int(s),
     if let Int :: Sub(left, right) = s,
     if let Int :: NumLit(a) = left.as_ref(),
     if let Int :: NumLit(b) = right.as_ref(),
     let t = Int :: NumLit(a - b);
 
-rw_int(s, t) <--
+rw_int_rule(s, t, rule) <--
     int(s),
     if let Int :: Add(s0, r) = s,
-    rw_int(* * s0, t0),
+    rw_int_rule(* * s0, t0, rule),
     let t = Int :: Add(Box :: new(t0.clone()), r.clone());
 
-rw_int(s, t) <--
+rw_int_rule(s, t, rule) <--
     int(s),
     if let Int :: Add(l, s0) = s,
-    rw_int(* * s0, t0),
+    rw_int_rule(* * s0, t0, rule),
     let t = Int :: Add(l.clone(), Box :: new(t0.clone()));
 
-rw_int(s, t) <--
+rw_int_rule(s, t, rule) <--
     int(s),
     if let Int :: Sub(s0, r) = s,
-    rw_int(* * s0, t0),
+    rw_int_rule(* * s0, t0, rule),
     let t = Int :: Sub(Box :: new(t0.clone()), r.clone());
 
-rw_int(s, t) <--
+rw_int_rule(s, t, rule) <--
     int(s),
     if let Int :: Sub(l, s0) = s,
-    rw_int(* * s0, t0),
+    rw_int_rule(* * s0, t0, rule),
     let t = Int :: Sub(l.clone(), Box :: new(t0.clone()));
 
-rw_int(s, t) <--
+rw_int_rule(s, t, rule) <--
     int(s),
     if let Int :: Assign(x, s0) = s,
-    rw_int(* * s0, t0),
+    rw_int_rule(* * s0, t0, rule),
     let t = Int :: Assign(x.clone(), Box :: new(t0.clone()));
 
 }
@@ -0,0 +1,77 @@
+// Generated Blockly blocks for DeepAmb theory
+// DO NOT EDIT - generated by MeTTaIL theory! macro
+// Changes will be overwritten on next build
+
+import * as Blockly from "blockly/core";
+
+const definitions = [
+	// Zero: 0
+	{
+		type: "proc_zero",
+		tooltip: "Zero: 0",
+		message0: "0",
+		inputsInline: true,
+		output: "Proc",
+		colour: "208bfe",
+	},
+
+	// Tick: tick( ... )
+	{
+		type: "proc_tick",
+		tooltip: "Tick: tick( ... )",
+		message0: "tick( %1 )",
+		args0: [
+			{
+				type: "input_value",
+				name: "BODY",
+				check: "Proc",
+			},
+		],
+		inputsInline: true,
+		previousStatement: "Proc",
+		nextStatement: "Proc",
+		colour: "208bfe",
+	},
+
+	// Amb: ... [ ... ]
+	{
+		type: "proc_amb",
+		tooltip: "Amb: ... [ ... ]",
+		message0: "%1 [ %2 ]",
+		args0: [
+			{
+				type: "input_value",
+				name: "CHANNEL",
+				check: "Name",
+			},
+			{
+				type: "input_value",
+				name: "MESSAGE",
+				check: "Proc",
+			},
+		],
+		inputsInline: true,
+		previousStatement: "Proc",
+		nextStatement: "Proc",
+		colour: "208bfe",
+	},
+
+	// Par: { ... }
+	{
+		type: "proc_par",
+		tooltip: "Par: { ... }",
+		message0: "{ %1 }",
+		args0: [
+			{
+				type: "input_statement",
+				name: "PROCS",
+				check: "Proc",
+			},
+		],
+		previousStatement: "Proc",
+		nextStatement: "Proc",
+		colour: "208bfe",
+	}
+];
+
+export default Blockly.common.createBlockDefinitionsFromJsonArray(definitions);
@@ -0,0 +1,16 @@
+// Generated category metadata for DeepAmb theory
+// DO NOT EDIT - generated by MeTTaIL theory! macro
+
+export const categoryInfo = {
+	Proc: {
+		constructors: [
+			"PZero",
+			"PTick",
+			"PAmb",
+			"PPar"
+		],
+		colour: "208bfe",
+	}
+};
+
+export const theoryName = "DeepAmb";
@@ -0,0 +1,132 @@
+// Generated Ascent Datalog for deepamb theory
+// This file is generated by the theory! macro and is for inspection only.
+// Do not edit manually - changes will be overwritten.
+
+ascent_source! {
+    deepamb_source:
+
+    // Relations
+relation proc(Proc);
+
+relation name(Name);
+
+#[ds(crate :: eqrel)] relation eq_proc(Proc, Proc);
+
+#[ds(crate :: eqrel)] relation eq_name(Name, Name);
+
+relation rw_proc(Proc, Proc);
+
+relation rw_proc_rule(Proc, Proc, & 'static str);
+
+relation rw_name(Name, Name);
+
+relation rw_name_rule(Name, Name, & 'static str);
+
+relation one_step(bool);
+
+relation ppar_contains(Proc, Proc);
+
+
+    // Category rules
+rw_proc(s, t) <--
+    rw_proc_rule(s, t, _);
+
+proc(c1) <--
+    proc(c0),
+    rw_proc(c0, c1),
+    ! one_step(true);
+
+proc(field_0.as_ref().clone()) <--
+    proc(t),
+    if let Proc :: PTick(field_0) = t;
+
+name(field_0.as_ref().clone()),
+proc(field_1.as_ref().clone()) <--
+    proc(t),
+    if let Proc :: PAmb(field_0, field_1) = t;
+
+ppar_contains(parent.clone(), elem.clone()) <--
+    proc(parent),
+    if let Proc :: PPar(ref bag_field) = parent,
+    for (elem, _count) in bag_field.iter();
+
+proc(elem) <--
+    ppar_contains(_parent, elem);
+
+rw_name(s, t) <--
+    rw_name_rule(s, t, _);
+
+name(c1) <--
+    name(c0),
+    rw_name(c0, c1),
+    ! one_step(true);
+
+
+    // Equation rules
+eq_proc(t.clone(), t.clone()) <--
+    proc(t);
+
+eq_name(t.clone(), t.clone()) <--
+    name(t);
+
+eq_proc(Proc :: PTick(Box :: new(x0.clone())), Proc :: PTick(Box :: new(y0.clone()))) <--
+    proc(x0),
+    proc(y0),
+    eq_proc(x0.clone(), y0.clone());
+
+eq_proc(Proc :: PAmb(Box :: new(x0.clone()), Box :: new(x1.clone())), Proc :: PAmb(Box :: new(y0.clone()), Box :: new(y1.clone()))) <--
+    name(x0),
+    name(y0),
+    eq_name(x0.clone(), y0.clone()),
+    proc(x1),
+    proc(y1),
+    eq_proc(x1.clone(), y1.clone());
+
+
+    // Rewrite rules
+relation pamb_rw0_d0_d0(Proc, Proc, mettail_runtime::HashBag < Proc >);
+
+pamb_rw0_d0_d0(t.clone(), p.clone(), inner.clone()) <--
+    proc(t),
+    if let Proc :: PPar(t_f0) = t,
+    for (t_f0_elem_0, _count_t_f0_0) in t_f0.iter(),
+    if let Proc :: PTick(t_f0_elem_0_f0) = t_f0_elem_0,
+    let t_f0_elem_0_f0_val = t_f0_elem_0_f0.as_ref(),
+    let t_f0_rest = { let mut bag = t_f0.clone();
+
+bag.remove(& t_f0_elem_0);
+
+bag }, let p = t_f0_elem_0_f0_val.clone(), let inner = t_f0_rest.clone();
+
+relation pamb_rw0_d0(Proc, Name, Proc, mettail_runtime::HashBag < Proc > , mettail_runtime::HashBag < Proc >);
+
+pamb_rw0_d0(t.clone(), m.clone(), p.clone(), inner.clone(), rest.clone()) <--
+    proc(t),
+    if let Proc :: PPar(t_f0) = t,
+    for (t_f0_elem_0, _count_t_f0_0) in t_f0.iter(),
+    if let Proc :: PAmb(t_f0_elem_0_f0, t_f0_elem_0_f1) = t_f0_elem_0,
+    let t_f0_elem_0_f0_val = t_f0_elem_0_f0.as_ref(),
+    let t_f0_elem_0_f1_val = t_f0_elem_0_f1.as_ref(),
+    let t_f0_rest = { let mut bag = t_f0.clone();
+
+bag.remove(& t_f0_elem_0);
+
+bag }, pamb_rw0_d0_d0(t_f0_elem_0_f1_val.clone(), deep0_p, deep0_inner), let m = t_f0_elem_0_f0_val.clone(), let p = deep0_p.clone(), let inner = deep0_inner.clone(), let rest = t_f0_rest.clone();
+
+rw_proc_rule(s, t, "PAmb(PPar{PAmb(PPar{PTick})})") <--
+    proc(s),
+    if let Proc :: PAmb(s_f0, s_f1) = s,
+    let s_f0_val = s_f0.as_ref(),
+    let s_f1_val = s_f1.as_ref(),
+    pamb_rw0_d0(s_f1_val.clone(), deep0_m, deep0_p, deep0_inner, deep0_rest),
+    let t = (Proc :: PAmb(Box :: new(s_f0_val.clone()), Box :: new(Proc :: PPar({ let mut bag = (deep0_rest.clone()).clone();
+
+Proc :: insert_into_ppar(& mut bag, Proc :: PAmb(Box :: new(deep0_m.clone()), Box :: new(Proc :: PPar({ let mut bag = (deep0_inner.clone()).clone();
+
+Proc :: insert_into_ppar(& mut bag, deep0_p.clone());
+
+bag }))));
+
+bag })))).normalize();
+
+}
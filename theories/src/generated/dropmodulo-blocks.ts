@@ -0,0 +1,77 @@
+// Generated Blockly blocks for DropModulo theory
+// DO NOT EDIT - generated by MeTTaIL theory! macro
+// Changes will be overwritten on next build
+
+import * as Blockly from "blockly/core";
+
+const definitions = [
+	// Zero: 0
+	{
+		type: "proc_zero",
+		tooltip: "Zero: 0",
+		message0: "0",
+		inputsInline: true,
+		output: "Proc",
+		colour: "208bfe",
+	},
+
+	// Drop: * ( ... )
+	{
+		type: "proc_drop",
+		tooltip: "Drop: * ( ... )",
+		message0: "* ( %1 )",
+		args0: [
+			{
+				type: "input_value",
+				name: "CHANNEL",
+				check: "Name",
+			},
+		],
+		inputsInline: true,
+		previousStatement: "Proc",
+		nextStatement: "Proc",
+		colour: "208bfe",
+	},
+
+	// Output: ... ! ( ... )
+	{
+		type: "proc_output",
+		tooltip: "Output: ... ! ( ... )",
+		message0: "%1 ! ( %2 )",
+		args0: [
+			{
+				type: "input_value",
+				name: "CHANNEL",
+				check: "Name",
+			},
+			{
+				type: "input_value",
+				name: "MESSAGE",
+				check: "Proc",
+			},
+		],
+		inputsInline: true,
+		previousStatement: "Proc",
+		nextStatement: "Proc",
+		colour: "208bfe",
+	},
+
+	// Quote: @ ( ... )
+	{
+		type: "name_quote",
+		tooltip: "Quote: @ ( ... )",
+		message0: "@ ( %1 )",
+		args0: [
+			{
+				type: "input_value",
+				name: "BODY",
+				check: "Proc",
+			},
+		],
+		inputsInline: true,
+		output: "Name",
+		colour: "65cda8",
+	}
+];
+
+export default Blockly.common.createBlockDefinitionsFromJsonArray(definitions);
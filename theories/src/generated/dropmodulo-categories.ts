@@ -0,0 +1,21 @@
+// Generated category metadata for DropModulo theory
+// DO NOT EDIT - generated by MeTTaIL theory! macro
+
+export const categoryInfo = {
+	Name: {
+		constructors: [
+			"NQuote"
+		],
+		colour: "65cda8",
+	},
+	Proc: {
+		constructors: [
+			"PZero",
+			"PDrop",
+			"POutput"
+		],
+		colour: "208bfe",
+	}
+};
+
+export const theoryName = "DropModulo";
@@ -0,0 +1,104 @@
+// Generated Ascent Datalog for dropmodulo theory
+// This file is generated by the theory! macro and is for inspection only.
+// Do not edit manually - changes will be overwritten.
+
+ascent_source! {
+    dropmodulo_source:
+
+    // Relations
+relation proc(Proc);
+
+relation name(Name);
+
+#[ds(crate :: eqrel)] relation eq_proc(Proc, Proc);
+
+#[ds(crate :: eqrel)] relation eq_name(Name, Name);
+
+relation rw_proc(Proc, Proc);
+
+relation rw_proc_rule(Proc, Proc, & 'static str);
+
+relation rw_name(Name, Name);
+
+relation rw_name_rule(Name, Name, & 'static str);
+
+relation one_step(bool);
+
+
+    // Category rules
+rw_proc(s, t) <--
+    rw_proc_rule(s, t, _);
+
+proc(c1) <--
+    proc(c0),
+    rw_proc(c0, c1),
+    ! one_step(true);
+
+name(field_0.as_ref().clone()) <--
+    proc(t),
+    if let Proc :: PDrop(field_0) = t;
+
+name(field_0.as_ref().clone()),
+proc(field_1.as_ref().clone()) <--
+    proc(t),
+    if let Proc :: POutput(field_0, field_1) = t;
+
+rw_name(s, t) <--
+    rw_name_rule(s, t, _);
+
+name(c1) <--
+    name(c0),
+    rw_name(c0, c1),
+    ! one_step(true);
+
+proc(field_0.as_ref().clone()) <--
+    name(t),
+    if let Name :: NQuote(field_0) = t;
+
+
+    // Equation rules
+eq_proc(t.clone(), t.clone()) <--
+    proc(t);
+
+eq_name(t.clone(), t.clone()) <--
+    name(t);
+
+eq_proc(Proc :: PDrop(Box :: new(x0.clone())), Proc :: PDrop(Box :: new(y0.clone()))) <--
+    name(x0),
+    name(y0),
+    eq_name(x0.clone(), y0.clone());
+
+eq_proc(Proc :: POutput(Box :: new(x0.clone()), Box :: new(x1.clone())), Proc :: POutput(Box :: new(y0.clone()), Box :: new(y1.clone()))) <--
+    name(x0),
+    name(y0),
+    eq_name(x0.clone(), y0.clone()),
+    proc(x1),
+    proc(y1),
+    eq_proc(x1.clone(), y1.clone());
+
+eq_name(Name :: NQuote(Box :: new(x0.clone())), Name :: NQuote(Box :: new(y0.clone()))) <--
+    proc(x0),
+    proc(y0),
+    eq_proc(x0.clone(), y0.clone());
+
+eq_proc(p0, p1) <--
+    proc(p0),
+    if let Proc :: PDrop(p0_f0) = p0,
+    let p0_f0_inner = p0_f0.as_ref(),
+    if let Name :: NQuote(p0_f0_inner_f0) = p0_f0_inner,
+    let p0_f0_inner_f0_val = p0_f0_inner_f0.as_ref(),
+    let p = p0_f0_inner_f0_val.clone(),
+    let p1 = p.clone();
+
+
+    // Rewrite rules
+rw_proc_rule(s, t, "POutput") <--
+    proc(s),
+    eq_proc(s.clone(), s_eq),
+    proc(s_eq),
+    if let Proc :: POutput(s_eq_f0, s_eq_f1) = s_eq,
+    let s_eq_f0_val = s_eq_f0.as_ref(),
+    let s_eq_f1_val = s_eq_f1.as_ref(),
+    let t = s_eq_f1_val.clone();
+
+}
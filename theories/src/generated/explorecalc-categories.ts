@@ -0,0 +1,23 @@
+// Generated category metadata for ExploreCalc theory
+// DO NOT EDIT - generated by MeTTaIL theory! macro
+
+export const categoryInfo = {
+	Name: {
+		constructors: [
+			"NQuote"
+		],
+		colour: "65cda8",
+	},
+	Proc: {
+		constructors: [
+			"PZero",
+			"PDrop",
+			"POutput",
+			"PInput",
+			"PPar"
+		],
+		colour: "208bfe",
+	}
+};
+
+export const theoryName = "ExploreCalc";
@@ -0,0 +1,172 @@
+// Generated Ascent Datalog for explorecalc theory
+// This file is generated by the theory! macro and is for inspection only.
+// Do not edit manually - changes will be overwritten.
+
+ascent_source! {
+    explorecalc_source:
+
+    // Relations
+relation proc(Proc);
+
+relation name(Name);
+
+#[ds(crate :: eqrel)] relation eq_proc(Proc, Proc);
+
+#[ds(crate :: eqrel)] relation eq_name(Name, Name);
+
+relation rw_proc(Proc, Proc);
+
+relation rw_proc_rule(Proc, Proc, & 'static str);
+
+relation rw_name(Name, Name);
+
+relation rw_name_rule(Name, Name, & 'static str);
+
+relation one_step(bool);
+
+relation ppar_contains(Proc, Proc);
+
+
+    // Category rules
+rw_proc(s, t) <--
+    rw_proc_rule(s, t, _);
+
+proc(c1) <--
+    proc(c0),
+    rw_proc(c0, c1),
+    ! one_step(true);
+
+name(field_0.as_ref().clone()) <--
+    proc(t),
+    if let Proc :: PDrop(field_0) = t;
+
+name(field_0.as_ref().clone()),
+proc(field_1.as_ref().clone()) <--
+    proc(t),
+    if let Proc :: POutput(field_0, field_1) = t;
+
+name(field_0.as_ref().clone()),
+proc(body.clone()) <--
+    proc(t),
+    if let Proc :: PInput(field_0, scope_field) = t,
+    let body = (* scope_field.inner().unsafe_body).clone();
+
+ppar_contains(parent.clone(), elem.clone()) <--
+    proc(parent),
+    if let Proc :: PPar(ref bag_field) = parent,
+    for (elem, _count) in bag_field.iter();
+
+proc(elem) <--
+    ppar_contains(_parent, elem);
+
+rw_name(s, t) <--
+    rw_name_rule(s, t, _);
+
+name(c1) <--
+    name(c0),
+    rw_name(c0, c1),
+    ! one_step(true);
+
+proc(field_0.as_ref().clone()) <--
+    name(t),
+    if let Name :: NQuote(field_0) = t;
+
+
+    // Equation rules
+eq_proc(t.clone(), t.clone()) <--
+    proc(t);
+
+eq_name(t.clone(), t.clone()) <--
+    name(t);
+
+eq_proc(Proc :: PDrop(Box :: new(x0.clone())), Proc :: PDrop(Box :: new(y0.clone()))) <--
+    name(x0),
+    name(y0),
+    eq_name(x0.clone(), y0.clone());
+
+eq_proc(Proc :: POutput(Box :: new(x0.clone()), Box :: new(x1.clone())), Proc :: POutput(Box :: new(y0.clone()), Box :: new(y1.clone()))) <--
+    name(x0),
+    name(y0),
+    eq_name(x0.clone(), y0.clone()),
+    proc(x1),
+    proc(y1),
+    eq_proc(x1.clone(), y1.clone());
+
+eq_name(Name :: NQuote(Box :: new(x0.clone())), Name :: NQuote(Box :: new(y0.clone()))) <--
+    proc(x0),
+    proc(y0),
+    eq_proc(x0.clone(), y0.clone());
+
+
+    // Rewrite rules
+rw_proc_rule(s, t, "PDrop(NQuote)") <--
+    proc(s),
+    if let Proc :: PDrop(s_f0) = s,
+    let s_f0_inner = s_f0.as_ref(),
+    if let Name :: NQuote(s_f0_inner_f0) = s_f0_inner,
+    let s_f0_inner_f0_val = s_f0_inner_f0.as_ref(),
+    let t = (s_f0_inner_f0_val.clone()).normalize();
+
+relation pinput_proj_c2_b0_p0(Proc, Name, mettail_runtime :: Binder < String > , Proc, Proc);
+
+pinput_proj_c2_b0_p0(parent.clone(), cap_n.clone(), cap_x.clone(), cap_p.clone(), elem.clone()) <--
+    proc(parent),
+    if let Proc :: PPar(ref bag_field) = parent,
+    for (elem, _count) in bag_field.iter(),
+    if let Proc :: PInput(ref f0, ref f1) = elem,
+    let cap_n = (* * f0).clone(),
+    let (binder_tmp, body_tmp) = (* f1).clone().unbind(),
+    let cap_x = binder_tmp,
+    let cap_p = * body_tmp;
+
+relation poutput_proj_c2_b0_p1(Proc, Name, Proc, Proc);
+
+poutput_proj_c2_b0_p1(parent.clone(), cap_n.clone(), cap_q.clone(), elem.clone()) <--
+    proc(parent),
+    if let Proc :: PPar(ref bag_field) = parent,
+    for (elem, _count) in bag_field.iter(),
+    if let Proc :: POutput(ref f0, ref f1) = elem,
+    let cap_n = (* * f0).clone(),
+    let cap_q = (* * f1).clone();
+
+relation pdrop_proj_c2_b1_p0(Proc, Proc, Proc);
+
+pdrop_proj_c2_b1_p0(parent.clone(), p.clone(), elem.clone()) <--
+    proc(parent),
+    if let Proc :: PPar(ref bag_field) = parent,
+    for (elem, _count) in bag_field.iter(),
+    if let Proc :: PDrop(elem_f0) = elem,
+    let elem_f0_inner = elem_f0.as_ref(),
+    if let Name :: NQuote(elem_f0_inner_f0) = elem_f0_inner,
+    let elem_f0_inner_f0_val = elem_f0_inner_f0.as_ref(),
+    let p = elem_f0_inner_f0_val.clone();
+
+rw_proc_rule(parent, result, "PPar{PInput, POutput}") <--
+    pinput_proj_c2_b0_p0(parent, cap_n_p0, cap_x_p0, cap_p_p0, elem_0),
+    eq_name(cap_n_p0.clone(), cap_n_p1),
+    poutput_proj_c2_b0_p1(parent, cap_n_p1, cap_q_p1, elem_1),
+    let rhs_term = (cap_p_p0.clone()).substitute_name(& cap_x_p0.clone().0, & Name :: NQuote(Box :: new(cap_q_p1.clone()))),
+    if let Proc :: PPar(ref bag) = parent,
+    if let Some(remaining) = { let mut b = bag.clone();
+
+if b.remove(elem_0) && b.remove(elem_1) { Some(b) } else { None } }, let result = Proc :: PPar({ let mut bag_result = remaining;
+
+Proc :: insert_into_ppar(& mut bag_result, rhs_term);
+
+bag_result }).normalize();
+
+rw_proc_rule(parent, result, "PDrop(NQuote)") <--
+    pdrop_proj_c2_b1_p0(parent, cap_p_p0, elem_0),
+    let rhs_term = cap_p_p0.clone(),
+    if let Proc :: PPar(ref bag) = parent,
+    let remaining = { let mut b = bag.clone();
+
+b.remove(elem_0);
+
+b }, let result = Proc :: PPar({ let mut bag_result = remaining;
+
+Proc :: insert_into_ppar(& mut bag_result, rhs_term);
+
+bag_result }).normalize();
+
+}
@@ -0,0 +1,104 @@
+// Generated Blockly blocks for JoinCalc theory
+// DO NOT EDIT - generated by MeTTaIL theory! macro
+// Changes will be overwritten on next build
+
+import * as Blockly from "blockly/core";
+
+const definitions = [
+	// Zero: 0
+	{
+		type: "proc_zero",
+		tooltip: "Zero: 0",
+		message0: "0",
+		inputsInline: true,
+		output: "Proc",
+		colour: "208bfe",
+	},
+
+	// Output: ... ! ( ... )
+	{
+		type: "proc_output",
+		tooltip: "Output: ... ! ( ... )",
+		message0: "%1 ! ( %2 )",
+		args0: [
+			{
+				type: "input_value",
+				name: "CHANNEL",
+				check: "Name",
+			},
+			{
+				type: "input_value",
+				name: "MESSAGE",
+				check: "Proc",
+			},
+		],
+		inputsInline: true,
+		previousStatement: "Proc",
+		nextStatement: "Proc",
+		colour: "208bfe",
+	},
+
+	// Join: join ( ... , ... ) { ... }
+	{
+		type: "proc_join",
+		tooltip: "Join: join ( ... , ... ) { ... }",
+		message0: "join ( %1 , %2 ) { %3 }",
+		args0: [
+			{
+				type: "input_value",
+				name: "CHANNEL",
+				check: "Name",
+			},
+			{
+				type: "input_value",
+				name: "ARG2",
+				check: "Name",
+			},
+			{
+				type: "input_value",
+				name: "ARG3",
+				check: "Proc",
+			},
+		],
+		inputsInline: true,
+		previousStatement: "Proc",
+		nextStatement: "Proc",
+		colour: "208bfe",
+	},
+
+	// Par: { ... }
+	{
+		type: "proc_par",
+		tooltip: "Par: { ... }",
+		message0: "{ %1 }",
+		args0: [
+			{
+				type: "input_statement",
+				name: "PROCS",
+				check: "Proc",
+			},
+		],
+		previousStatement: "Proc",
+		nextStatement: "Proc",
+		colour: "208bfe",
+	},
+
+	// Quote: @ ( ... )
+	{
+		type: "name_quote",
+		tooltip: "Quote: @ ( ... )",
+		message0: "@ ( %1 )",
+		args0: [
+			{
+				type: "input_value",
+				name: "BODY",
+				check: "Proc",
+			},
+		],
+		inputsInline: true,
+		output: "Name",
+		colour: "65cda8",
+	}
+];
+
+export default Blockly.common.createBlockDefinitionsFromJsonArray(definitions);
@@ -0,0 +1,22 @@
+// Generated category metadata for JoinCalc theory
+// DO NOT EDIT - generated by MeTTaIL theory! macro
+
+export const categoryInfo = {
+	Name: {
+		constructors: [
+			"NQuote"
+		],
+		colour: "65cda8",
+	},
+	Proc: {
+		constructors: [
+			"PZero",
+			"POutput",
+			"PJoin",
+			"PPar"
+		],
+		colour: "208bfe",
+	}
+};
+
+export const theoryName = "JoinCalc";
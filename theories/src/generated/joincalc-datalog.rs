@@ -0,0 +1,157 @@
+// Generated Ascent Datalog for joincalc theory
+// This file is generated by the theory! macro and is for inspection only.
+// Do not edit manually - changes will be overwritten.
+
+ascent_source! {
+    joincalc_source:
+
+    // Relations
+relation proc(Proc);
+
+relation name(Name);
+
+#[ds(crate :: eqrel)] relation eq_proc(Proc, Proc);
+
+#[ds(crate :: eqrel)] relation eq_name(Name, Name);
+
+relation rw_proc(Proc, Proc);
+
+relation rw_proc_rule(Proc, Proc, & 'static str);
+
+relation rw_name(Name, Name);
+
+relation rw_name_rule(Name, Name, & 'static str);
+
+relation one_step(bool);
+
+relation ppar_contains(Proc, Proc);
+
+
+    // Category rules
+rw_proc(s, t) <--
+    rw_proc_rule(s, t, _);
+
+proc(c1) <--
+    proc(c0),
+    rw_proc(c0, c1),
+    ! one_step(true);
+
+name(field_0.as_ref().clone()),
+proc(field_1.as_ref().clone()) <--
+    proc(t),
+    if let Proc :: POutput(field_0, field_1) = t;
+
+name(field_0.as_ref().clone()),
+name(field_1.as_ref().clone()),
+proc(field_2.as_ref().clone()) <--
+    proc(t),
+    if let Proc :: PJoin(field_0, field_1, field_2) = t;
+
+ppar_contains(parent.clone(), elem.clone()) <--
+    proc(parent),
+    if let Proc :: PPar(ref bag_field) = parent,
+    for (elem, _count) in bag_field.iter();
+
+proc(elem) <--
+    ppar_contains(_parent, elem);
+
+rw_name(s, t) <--
+    rw_name_rule(s, t, _);
+
+name(c1) <--
+    name(c0),
+    rw_name(c0, c1),
+    ! one_step(true);
+
+proc(field_0.as_ref().clone()) <--
+    name(t),
+    if let Name :: NQuote(field_0) = t;
+
+
+    // Equation rules
+eq_proc(t.clone(), t.clone()) <--
+    proc(t);
+
+eq_name(t.clone(), t.clone()) <--
+    name(t);
+
+eq_proc(Proc :: POutput(Box :: new(x0.clone()), Box :: new(x1.clone())), Proc :: POutput(Box :: new(y0.clone()), Box :: new(y1.clone()))) <--
+    name(x0),
+    name(y0),
+    eq_name(x0.clone(), y0.clone()),
+    proc(x1),
+    proc(y1),
+    eq_proc(x1.clone(), y1.clone());
+
+eq_proc(Proc :: PJoin(Box :: new(x0.clone()), Box :: new(x1.clone()), Box :: new(x2.clone())), Proc :: PJoin(Box :: new(y0.clone()), Box :: new(y1.clone()), Box :: new(y2.clone()))) <--
+    name(x0),
+    name(y0),
+    eq_name(x0.clone(), y0.clone()),
+    name(x1),
+    name(y1),
+    eq_name(x1.clone(), y1.clone()),
+    proc(x2),
+    proc(y2),
+    eq_proc(x2.clone(), y2.clone());
+
+eq_name(Name :: NQuote(Box :: new(x0.clone())), Name :: NQuote(Box :: new(y0.clone()))) <--
+    proc(x0),
+    proc(y0),
+    eq_proc(x0.clone(), y0.clone());
+
+
+    // Rewrite rules
+relation pjoin_proj_c1_b0_p0(Proc, Name, Name, Proc, Proc);
+
+pjoin_proj_c1_b0_p0(parent.clone(), cap_n.clone(), cap_m.clone(), cap_p.clone(), elem.clone()) <--
+    proc(parent),
+    if let Proc :: PPar(ref bag_field) = parent,
+    for (elem, _count) in bag_field.iter(),
+    if let Proc :: PJoin(ref f0, ref f1, ref f2) = elem,
+    let cap_n = (* * f0).clone(),
+    let cap_m = (* * f1).clone(),
+    let cap_p = (* * f2).clone();
+
+relation poutput_proj_c1_b0_p1(Proc, Name, Proc, Proc);
+
+poutput_proj_c1_b0_p1(parent.clone(), cap_n.clone(), cap_q.clone(), elem.clone()) <--
+    proc(parent),
+    if let Proc :: PPar(ref bag_field) = parent,
+    for (elem, _count) in bag_field.iter(),
+    if let Proc :: POutput(ref f0, ref f1) = elem,
+    let cap_n = (* * f0).clone(),
+    let cap_q = (* * f1).clone();
+
+relation poutput_proj_c1_b0_p2(Proc, Name, Proc, Proc);
+
+poutput_proj_c1_b0_p2(parent.clone(), cap_m.clone(), cap_r.clone(), elem.clone()) <--
+    proc(parent),
+    if let Proc :: PPar(ref bag_field) = parent,
+    for (elem, _count) in bag_field.iter(),
+    if let Proc :: POutput(ref f0, ref f1) = elem,
+    let cap_m = (* * f0).clone(),
+    let cap_r = (* * f1).clone();
+
+rw_proc_rule(parent, result, "PPar{PJoin, POutput, POutput}") <--
+    pjoin_proj_c1_b0_p0(parent, cap_n_p0, cap_m_p0, cap_p_p0, elem_0),
+    eq_name(cap_n_p0.clone(), cap_n_p1),
+    poutput_proj_c1_b0_p1(parent, cap_n_p1, cap_q_p1, elem_1),
+    eq_name(cap_m_p0.clone(), cap_m_p2),
+    poutput_proj_c1_b0_p2(parent, cap_m_p2, cap_r_p2, elem_2),
+    let rhs_term = Proc :: PPar({ let mut bag = mettail_runtime::HashBag :: new();
+
+Proc :: insert_into_ppar(& mut bag, cap_p_p0.clone());
+
+Proc :: insert_into_ppar(& mut bag, cap_q_p1.clone());
+
+Proc :: insert_into_ppar(& mut bag, cap_r_p2.clone());
+
+bag }), if let Proc :: PPar(ref bag) = parent, if let Some(remaining) = { let mut b = bag.clone();
+
+if b.remove(elem_0) && b.remove(elem_1) && b.remove(elem_2) { Some(b) } else { None } }, let result = Proc :: PPar({ let mut bag_result = remaining;
+
+Proc :: insert_into_ppar(& mut bag_result, rhs_term);
+
+bag_result }).normalize();
+
+}
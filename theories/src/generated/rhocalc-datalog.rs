@@ -1,6 +1,8 @@
 // Generated Ascent Datalog for rhocalc theory
 // This file is generated by the theory! macro and is for inspection only.
 // Do not edit manually - changes will be overwritten.
+// rhocalc_stats_source (for explore_with_stats) is this program with each rule ending in
+// `if mettail_runtime::fire(<rule index>)`, which counts its firings.
 
 ascent_source! {
     rhocalc_source:
@@ -16,15 +18,25 @@
 
 relation rw_proc(Proc, Proc);
 
+relation rw_proc_rule(Proc, Proc, & 'static str);
+
 relation rw_name(Name, Name);
 
+relation rw_name_rule(Name, Name, & 'static str);
+
+relation one_step(bool);
+
 relation ppar_contains(Proc, Proc);
 
 
     // Category rules
+rw_proc(s, t) <--
+    rw_proc_rule(s, t, _);
+
 proc(c1) <--
     proc(c0),
-    rw_proc(c0, c1);
+    rw_proc(c0, c1),
+    ! one_step(true);
 
 name(field_0.as_ref().clone()) <--
     proc(t),
@@ -49,9 +61,13 @@
 proc(elem) <--
     ppar_contains(_parent, elem);
 
+rw_name(s, t) <--
+    rw_name_rule(s, t, _);
+
 name(c1) <--
     name(c0),
-    rw_name(c0, c1);
+    rw_name(c0, c1),
+    ! one_step(true);
 
 proc(field_0.as_ref().clone()) <--
     name(t),
@@ -94,13 +110,15 @@
 
 
     // Rewrite rules
-rw_proc(s, t) <--
+rw_proc_rule(s, t, "PDrop(NQuote)") <--
     proc(s),
-    if let Proc :: PDrop(s_f0) = s,
-    let s_f0_inner = s_f0.as_ref(),
-    if let Name :: NQuote(s_f0_inner_f0) = s_f0_inner,
-    let s_f0_inner_f0_val = s_f0_inner_f0.as_ref(),
-    let t = (s_f0_inner_f0_val.clone()).normalize();
+    eq_proc(s.clone(), s_eq),
+    proc(s_eq),
+    if let Proc :: PDrop(s_eq_f0) = s_eq,
+    let s_eq_f0_inner = s_eq_f0.as_ref(),
+    if let Name :: NQuote(s_eq_f0_inner_f0) = s_eq_f0_inner,
+    let s_eq_f0_inner_f0_val = s_eq_f0_inner_f0.as_ref(),
+    let t = (s_eq_f0_inner_f0_val.clone()).normalize();
 
 relation pinput_proj_c2_b0_p0(Proc, Name, mettail_runtime :: Binder < String > , Proc, Proc);
 
@@ -108,7 +126,9 @@
     proc(parent),
     if let Proc :: PPar(ref bag_field) = parent,
     for (elem, _count) in bag_field.iter(),
-    if let Proc :: PInput(ref f0, ref f1) = elem,
+    eq_proc(elem.clone(), elem_eq),
+    proc(elem_eq),
+    if let Proc :: PInput(ref f0, ref f1) = elem_eq,
     let cap_n = (* * f0).clone(),
     let (binder_tmp, body_tmp) = (* f1).clone().unbind(),
     let cap_x = binder_tmp,
@@ -120,7 +140,9 @@
     proc(parent),
     if let Proc :: PPar(ref bag_field) = parent,
     for (elem, _count) in bag_field.iter(),
-    if let Proc :: POutput(ref f0, ref f1) = elem,
+    eq_proc(elem.clone(), elem_eq),
+    proc(elem_eq),
+    if let Proc :: POutput(ref f0, ref f1) = elem_eq,
     let cap_n = (* * f0).clone(),
     let cap_q = (* * f1).clone();
 
@@ -130,31 +152,29 @@
     proc(parent),
     if let Proc :: PPar(ref bag_field) = parent,
     for (elem, _count) in bag_field.iter(),
-    if let Proc :: PDrop(elem_f0) = elem,
-    let elem_f0_inner = elem_f0.as_ref(),
-    if let Name :: NQuote(elem_f0_inner_f0) = elem_f0_inner,
-    let elem_f0_inner_f0_val = elem_f0_inner_f0.as_ref(),
-    let p = elem_f0_inner_f0_val.clone();
-
-rw_proc(parent, result) <--
+    eq_proc(elem.clone(), elem_eq),
+    proc(elem_eq),
+    if let Proc :: PDrop(elem_eq_f0) = elem_eq,
+    let elem_eq_f0_inner = elem_eq_f0.as_ref(),
+    if let Name :: NQuote(elem_eq_f0_inner_f0) = elem_eq_f0_inner,
+    let elem_eq_f0_inner_f0_val = elem_eq_f0_inner_f0.as_ref(),
+    let p = elem_eq_f0_inner_f0_val.clone();
+
+rw_proc_rule(parent, result, "PPar{PInput, POutput}") <--
     pinput_proj_c2_b0_p0(parent, cap_n_p0, cap_x_p0, cap_p_p0, elem_0),
+    eq_name(cap_n_p0.clone(), cap_n_p1),
     poutput_proj_c2_b0_p1(parent, cap_n_p1, cap_q_p1, elem_1),
-    eq_name(cap_n_p0.clone(), cap_n_p1.clone()),
     let rhs_term = (cap_p_p0.clone()).substitute_name(& cap_x_p0.clone().0, & Name :: NQuote(Box :: new(cap_q_p1.clone()))),
     if let Proc :: PPar(ref bag) = parent,
-    let remaining = { let mut b = bag.clone();
-
-b.remove(elem_0);
-
-b.remove(elem_1);
+    if let Some(remaining) = { let mut b = bag.clone();
 
-b }, let result = Proc :: PPar({ let mut bag_result = remaining;
+if b.remove(elem_0) && b.remove(elem_1) { Some(b) } else { None } }, let result = Proc :: PPar({ let mut bag_result = remaining;
 
 Proc :: insert_into_ppar(& mut bag_result, rhs_term);
 
 bag_result }).normalize();
 
-rw_proc(parent, result) <--
+rw_proc_rule(parent, result, "PDrop(NQuote)") <--
     pdrop_proj_c2_b1_p0(parent, cap_p_p0, elem_0),
     let rhs_term = cap_p_p0.clone(),
     if let Proc :: PPar(ref bag) = parent,
@@ -10,6 +10,12 @@
 theory! {
     name: RhoCalc,
 
+    options {
+        rewrite_modulo_equations: true,
+        holes: true,
+        stats: true,
+    },
+
     exports {
         Proc
         Name
@@ -0,0 +1,118 @@
+// Binary encoding of generated ASTs
+
+use mettail_runtime::{DecodeError, VarContext};
+use mettail_theories::calculator::{self, Int};
+use mettail_theories::rhocalc::{self, Proc};
+
+fn parse(vars: &VarContext, input: &str) -> Proc {
+    rhocalc::rhocalc::ProcParser::new()
+        .parse(vars, input)
+        .unwrap()
+}
+
+#[test]
+fn test_round_trip() {
+    let vars = VarContext::new();
+    for input in [
+        "0",
+        "{a!(0) | a!(0) | for(a->x){*(x)}}",
+        "for(@(0)->y){{y!(for(b->z){*(z)}) | *(y)}}",
+    ] {
+        let term = parse(&vars, input);
+        assert_eq!(Proc::from_bytes(&vars, &term.to_bytes()).unwrap(), term, "{}", input);
+    }
+}
+
+#[test]
+fn test_free_variables_are_interned() {
+    let vars = VarContext::new();
+    let bytes = parse(&vars, "{a!(0) | for(a->x){*(x)}}").to_bytes();
+
+    // In a fresh context, the two `a`s are still the same variable
+    let other = VarContext::new();
+    let term = Proc::from_bytes(&other, &bytes).unwrap();
+    assert_eq!(other.len(), 1);
+    assert_eq!(term, parse(&other, "{a!(0) | for(a->x){*(x)}}"));
+}
+
+#[test]
+fn test_repeated_subterms_are_shared() {
+    let vars = VarContext::new();
+    let big = "for(a->x){{x!(0) | x!(x!(0)) | *(x)}}";
+    let once = parse(&vars, big).to_bytes();
+    let twice = parse(&vars, &format!("{{{} | b!({})}}", big, big)).to_bytes();
+    assert!(twice.len() < once.len() + 16, "{} vs {}", twice.len(), once.len());
+}
+
+#[test]
+fn test_native_values() {
+    let vars = VarContext::new();
+    let term = calculator::calculator::IntParser::new()
+        .parse(&vars, "x = 1 + 300000 - 7")
+        .unwrap();
+    assert_eq!(Int::from_bytes(&vars, &term.to_bytes()).unwrap(), term);
+}
+
+#[test]
+fn test_rejects_bad_input() {
+    let vars = VarContext::new();
+    let bytes = parse(&vars, "a!(0)").to_bytes();
+
+    assert_eq!(Proc::from_bytes(&vars, b"garbage"), Err(DecodeError::BadMagic));
+    assert!(matches!(
+        Int::from_bytes(&vars, &bytes),
+        Err(DecodeError::TheoryMismatch { .. })
+    ));
+    assert_eq!(
+        Proc::from_bytes(&vars, &bytes[..bytes.len() - 1]),
+        Err(DecodeError::UnexpectedEof)
+    );
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert_eq!(Proc::from_bytes(&vars, &trailing), Err(DecodeError::TrailingBytes(1)));
+}
+
+#[test]
+fn test_rejects_bad_bag_counts() {
+    let vars = VarContext::new();
+    let bytes = parse(&vars, "{a!(0) | a!(0) | b!(0) | b!(0)}").to_bytes();
+    // The bag's entries end `a!(0) 2 b!(0) 2`, and `b!(0)` refers back to the first `0`
+    let n = bytes.len();
+    assert_eq!((bytes[n - 9], bytes[n - 1]), (2, 2));
+
+    let mut zero = bytes.clone();
+    zero[n - 1] = 0;
+    assert_eq!(Proc::from_bytes(&vars, &zero), Err(DecodeError::InvalidValue));
+
+    // Counts are not expanded one by one, and their sum must fit in a usize
+    let huge = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+    let mut overflow = bytes[..n - 9].to_vec();
+    overflow.extend(huge);
+    overflow.extend(&bytes[n - 8..n - 1]);
+    overflow.extend(huge);
+    assert_eq!(Proc::from_bytes(&vars, &overflow), Err(DecodeError::InvalidValue));
+}
+
+#[test]
+fn test_corrupted_input_does_not_panic() {
+    let vars = VarContext::new();
+    for input in ["{a!(0) | a!(0) | b!(0) | b!(0)}", "for(@(0)->y){{y!(for(b->z){*(z)}) | *(y)}}"] {
+        let bytes = parse(&vars, input).to_bytes();
+        for pos in 0..bytes.len() {
+            for value in [0, 1, 0x7f, 0x80, 0xff] {
+                let mut corrupted = bytes.clone();
+                corrupted[pos] = value;
+                let _ = Proc::from_bytes(&vars, &corrupted);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_bags_encode_canonically() {
+    let vars = VarContext::new();
+    let left = parse(&vars, "{a!(0) | b!(0) | c!(0) | d!(0)}");
+    let right = parse(&vars, "{d!(0) | c!(0) | b!(0) | a!(0)}");
+    assert_eq!(left, right);
+    assert_eq!(left.to_bytes(), right.to_bytes());
+}
@@ -0,0 +1,38 @@
+// Alpha-equivalence of generated terms: equality, hashing, ordering and
+// deduplication in Ascent relations
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use ascent::*;
+use ascent_byods_rels::*;
+use mettail_runtime::VarContext;
+use mettail_theories::rhocalc::*;
+
+fn parse(vars: &VarContext, input: &str) -> Proc {
+    rhocalc::ProcParser::new().parse(vars, input).unwrap()
+}
+
+fn hash_of(p: &Proc) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    p.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn test_alpha_equivalent_terms_deduplicate() {
+    let vars = VarContext::new();
+    let x = parse(&vars, "for(a->x){*(x)}");
+    let y = parse(&vars, "for(a->y){*(y)}");
+    assert_eq!(x, y);
+    assert_eq!(hash_of(&x), hash_of(&y));
+    assert_eq!(x.cmp(&y), std::cmp::Ordering::Equal);
+    assert_ne!(x, parse(&vars, "for(b->x){*(x)}"));
+
+    let prog = ascent_run! {
+        include_source!(rhocalc_source);
+        proc(x.clone());
+        proc(y.clone());
+    };
+    assert_eq!(prog.proc.iter().filter(|(p,)| p == &x).count(), 1);
+}
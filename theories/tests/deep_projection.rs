@@ -0,0 +1,88 @@
+#![allow(
+    non_local_definitions,
+    clippy::crate_in_macro_def,
+    clippy::empty_line_after_outer_attr
+)]
+
+// Collection patterns nested below a rewrite's root match through deep
+// projections, relations `<prefix>_d<k>` keyed by the inner collection term
+
+use ascent::*;
+use ascent_byods_rels::eqrel;
+use mettail_macros::theory;
+use mettail_runtime::VarContext;
+
+theory! {
+    name: DeepAmb,
+
+    exports {
+        Proc
+        Name
+    },
+
+    terms {
+        PZero . Proc ::= "0" ;
+        PTick . Proc ::= "tick(" Proc ")" ;
+        PAmb . Proc ::= Name "[" Proc "]" ;
+
+        PPar . Proc ::= HashBag(Proc) sep "|" delim "{" "}" ;
+    },
+
+    equations {
+    },
+
+    rewrites {
+        // A tick two ambients deep runs its process
+        (PAmb N (PPar {(PAmb M (PPar {(PTick P), ...inner})), ...rest}))
+            => (PAmb N (PPar {(PAmb M (PPar {P, ...inner})), ...rest}));
+    },
+}
+
+fn parse(vars: &VarContext, input: &str) -> Proc {
+    deepamb::ProcParser::new().parse(vars, input).unwrap()
+}
+
+#[test]
+fn test_nested_collections_match_through_deep_projections() {
+    let vars = VarContext::new();
+    let term = parse(&vars, "n[{m[{tick(0) | 0}] | 0}]");
+    let expected = parse(&vars, "n[{m[{0 | 0}] | 0}]");
+
+    let prog = ascent_run! {
+        include_source!(deepamb_source);
+        proc(term.clone());
+    };
+
+    // One relation per nesting level, keyed by the collection it matched in
+    let inner = parse(&vars, "{tick(0) | 0}");
+    assert!(prog
+        .pamb_rw0_d0_d0
+        .iter()
+        .any(|(t, p, _)| t == &inner && p == &parse(&vars, "0")));
+    let outer = parse(&vars, "{m[{tick(0) | 0}] | 0}");
+    assert!(prog.pamb_rw0_d0.iter().any(|fact| fact.0 == outer));
+
+    assert!(prog
+        .rw_proc
+        .iter()
+        .any(|(s, t)| s == &term && t == &expected));
+}
+
+#[test]
+fn test_deep_projection_needs_every_level() {
+    let vars = VarContext::new();
+    // The tick is only one ambient deep
+    let term = parse(&vars, "n[{tick(0) | 0}]");
+
+    let prog = ascent_run! {
+        include_source!(deepamb_source);
+        proc(term.clone());
+    };
+
+    assert!(prog
+        .pamb_rw0_d0_d0
+        .iter()
+        .any(|fact| fact.0 == parse(&vars, "{tick(0) | 0}")));
+    assert!(prog.pamb_rw0_d0.is_empty());
+    assert!(prog.rw_proc.is_empty());
+}
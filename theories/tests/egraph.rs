@@ -0,0 +1,138 @@
+#![allow(
+    non_local_definitions,
+    clippy::crate_in_macro_def,
+    clippy::empty_line_after_outer_attr
+)]
+
+// RhoCalc with the e-graph equation backend
+
+use ascent::*;
+use ascent_byods_rels::eqrel;
+use mettail_macros::theory;
+use mettail_runtime::VarContext;
+
+theory! {
+    name: RhoEGraph,
+
+    options {
+        equation_backend: egraph,
+    },
+
+    exports {
+        Proc
+        Name
+    },
+
+    terms {
+        PZero . Proc ::= "0" ;
+        PDrop . Proc ::= "*" "(" Name ")" ;
+        POutput . Proc ::= Name "!" "(" Proc ")" ;
+        PInput . Proc ::= "for" "(" Name "->" <Name> ")" "{" Proc "}" ;
+
+        PPar . Proc ::= HashBag(Proc) sep "|" delim "{" "}" ;
+
+        NQuote . Name ::= "@" "(" Proc ")" ;
+    },
+
+    equations {
+        (NQuote (PDrop N)) == N ;
+    },
+
+    rewrites {
+        (PPar {(PInput N x P), (POutput N Q)})
+            => (PPar {(subst P x (NQuote Q))});
+
+        (PDrop (NQuote P)) => P;
+
+        if S => T then (PPar {S, ...rest}) => (PPar {T, ...rest});
+    },
+}
+
+fn parse(vars: &VarContext, input: &str) -> Proc {
+    rhoegraph::ProcParser::new().parse(vars, input).unwrap()
+}
+
+#[test]
+fn test_equation_applies_under_congruence() {
+    let vars = VarContext::new();
+    let quoted = parse(&vars, "{@(*(n))!(0) | *(m)}");
+    let plain = parse(&vars, "{*(m) | n!(0)}");
+
+    let mut eg = RhoEGraphEGraph::new();
+    let a = eg.add_proc(&quoted);
+    let b = eg.add_proc(&plain);
+    assert!(!eg.equivalent(a, b));
+
+    eg.saturate(10);
+    assert!(eg.equivalent(a, b));
+    assert_eq!(eg.extract_proc(a), plain);
+}
+
+#[test]
+fn test_union_propagates_through_collections() {
+    let vars = VarContext::new();
+    let p = parse(&vars, "{a!(0) | *(b)}");
+    let q = parse(&vars, "{*(b) | c!(0)}");
+
+    let mut eg = RhoEGraphEGraph::new();
+    let id_p = eg.add_proc(&p);
+    let id_q = eg.add_proc(&q);
+    let a = eg.add_name(&parse_name(&vars, "a"));
+    let c = eg.add_name(&parse_name(&vars, "c"));
+
+    eg.union(a, c);
+    eg.rebuild();
+    assert!(eg.equivalent(id_p, id_q));
+}
+
+#[test]
+fn test_rewrites_join_on_egraph_representatives() {
+    let vars = VarContext::new();
+    let term = parse(&vars, "{for(@(*(n))->x){*(x)} | n!(0)}");
+
+    let prog = ascent_run! {
+        include_source!(rhoegraph_source);
+        proc(term.clone());
+    };
+
+    // The channels meet only through the e-graph representative of `@(*(n))`
+    assert_eq!(
+        RhoEGraphEGraph::canonical_name(&parse_name(&vars, "@(*(n))")),
+        parse_name(&vars, "n")
+    );
+    let reducts: Vec<&Proc> = prog
+        .rw_proc
+        .iter()
+        .filter(|(s, _)| s == &term)
+        .map(|(_, t)| t)
+        .collect();
+    assert_eq!(reducts, vec![&parse(&vars, "{*(@(0))}")]);
+
+    // No congruence or equation clauses: every equality shares a representative
+    for (l, r) in prog.__eq_name_ind_common.iter_all_added() {
+        assert_eq!(RhoEGraphEGraph::canonical_name(l), RhoEGraphEGraph::canonical_name(r));
+    }
+    for (l, r) in prog.__eq_proc_ind_common.iter_all_added() {
+        assert_eq!(RhoEGraphEGraph::canonical_proc(l), RhoEGraphEGraph::canonical_proc(r));
+    }
+}
+
+#[test]
+fn test_explore_uses_congruence_equalities() {
+    // The channels are equal only by congruence: `*(@(*(m))) == *(m)` under
+    // `a!(..)` under `@(..)`
+    let vars = VarContext::new();
+    let term = parse(&vars, "{for(@(a!(*(@(*(m)))))->x){*(x)} | @(a!(*(m)))!(0)}");
+
+    let graph = RhoEGraph::explore(term);
+    let normal_forms: Vec<String> = graph
+        .normal_forms()
+        .into_iter()
+        .map(|idx| graph.node(idx).to_string())
+        .collect();
+    assert_eq!(normal_forms, vec!["{0}"]);
+}
+
+fn parse_name(vars: &VarContext, input: &str) -> Name {
+    rhoegraph::NameParser::new().parse(vars, input).unwrap()
+}
@@ -0,0 +1,146 @@
+#![allow(
+    non_local_definitions,
+    clippy::crate_in_macro_def,
+    clippy::empty_line_after_outer_attr
+)]
+
+// Rewrite graphs built by the generated `explore`: reachable terms, edge
+// labels and exports
+
+use ascent_byods_rels::eqrel;
+use mettail_macros::theory;
+use mettail_runtime::{RewriteGraph, VarContext};
+
+theory! {
+    name: ExploreCalc,
+
+    exports {
+        Proc
+        Name
+    },
+
+    terms {
+        PZero . Proc ::= "0" ;
+        PDrop . Proc ::= "*" "(" Name ")" ;
+        POutput . Proc ::= Name "!" "(" Proc ")" ;
+        PInput . Proc ::= "for" "(" Name "->" <Name> ")" "{" Proc "}" ;
+
+        PPar . Proc ::= HashBag(Proc) sep "|" delim "{" "}" ;
+
+        NQuote . Name ::= "@" "(" Proc ")" ;
+    },
+
+    equations {
+    },
+
+    rewrites {
+        (PPar {(PInput N x P), (POutput N Q)})
+            => (PPar {(subst P x (NQuote Q))});
+
+        (PDrop (NQuote P)) => P;
+
+        if S => T then (PPar {S, ...rest}) => (PPar {T, ...rest});
+    },
+}
+
+fn parse(vars: &VarContext, input: &str) -> Proc {
+    explorecalc::ProcParser::new().parse(vars, input).unwrap()
+}
+
+/// `(from, to, label)` for every edge
+fn labelled_edges(graph: &RewriteGraph<Proc>) -> Vec<(Proc, Proc, String)> {
+    let mut edges: Vec<_> = graph
+        .edges()
+        .iter()
+        .map(|e| (graph.node(e.from).clone(), graph.node(e.to).clone(), e.label.clone()))
+        .collect();
+    edges.sort();
+    edges
+}
+
+#[test]
+fn test_explore_collects_reachable_terms() {
+    let vars = VarContext::new();
+    let term = parse(&vars, "{for(a->x){*(x)} | a!(0) | a!(b!(0))}");
+    let graph = ExploreCalc::explore(term.clone());
+
+    assert_eq!(graph.root().map(|i| graph.node(i)), Some(&term));
+    let mut normal_forms: Vec<Proc> = graph
+        .normal_forms()
+        .into_iter()
+        .map(|i| graph.node(i).clone())
+        .collect();
+    normal_forms.sort();
+    let mut expected = vec![parse(&vars, "{0 | a!(b!(0))}"), parse(&vars, "{b!(0) | a!(0)}")];
+    expected.sort();
+    assert_eq!(normal_forms, expected);
+    assert_eq!(graph.len(), 5);
+}
+
+#[test]
+fn test_explore_labels_edges_with_rules() {
+    let vars = VarContext::new();
+    let graph = ExploreCalc::explore(parse(&vars, "{for(a->x){*(x)} | a!(0) | a!(b!(0))}"));
+
+    // Communication is labelled by its left-hand side; the drop inside the
+    // bag is taken through the congruence rule but keeps the inner rule's label
+    let edge = |from: &str, to: &str, label: &str| {
+        (parse(&vars, from), parse(&vars, to), label.to_string())
+    };
+    let mut expected = vec![
+        edge(
+            "{for(a->x){*(x)} | a!(0) | a!(b!(0))}",
+            "{*(@(0)) | a!(b!(0))}",
+            "PPar{PInput, POutput}",
+        ),
+        edge(
+            "{for(a->x){*(x)} | a!(0) | a!(b!(0))}",
+            "{*(@(b!(0))) | a!(0)}",
+            "PPar{PInput, POutput}",
+        ),
+        edge("{*(@(0)) | a!(b!(0))}", "{0 | a!(b!(0))}", "PDrop(NQuote)"),
+        edge("{*(@(b!(0))) | a!(0)}", "{b!(0) | a!(0)}", "PDrop(NQuote)"),
+    ];
+    expected.sort();
+    assert_eq!(labelled_edges(&graph), expected);
+}
+
+#[test]
+fn test_explore_exports_labels() {
+    let vars = VarContext::new();
+    let graph = ExploreCalc::explore(parse(&vars, "{for(a->x){*(x)} | a!(0)}"));
+    let (root, mid, normal) = (
+        graph
+            .index_of(&parse(&vars, "{for(a->x){*(x)} | a!(0)}"))
+            .unwrap(),
+        graph.index_of(&parse(&vars, "{*(@(0))}")).unwrap(),
+        graph.index_of(&parse(&vars, "{0}")).unwrap(),
+    );
+
+    let dot = graph.to_dot();
+    assert!(dot.contains(&format!("n{} -> n{} [label=\"PPar{{PInput, POutput}}\"];", root, mid)));
+    assert!(dot.contains(&format!("n{} -> n{} [label=\"PDrop(NQuote)\"];", mid, normal)));
+    assert!(dot.contains(&format!("n{} [label=\"{{0}}\", shape=doublecircle];", normal)));
+
+    let json: serde_json::Value = serde_json::from_str(&graph.to_json()).unwrap();
+    let mut edges: Vec<(u64, u64, &str)> = json["edges"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| {
+            (
+                e["from"].as_u64().unwrap(),
+                e["to"].as_u64().unwrap(),
+                e["label"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    edges.sort();
+    let mut expected = vec![
+        (root as u64, mid as u64, "PPar{PInput, POutput}"),
+        (mid as u64, normal as u64, "PDrop(NQuote)"),
+    ];
+    expected.sort();
+    assert_eq!(edges, expected);
+    assert_eq!(json["nodes"][normal]["normal_form"], true);
+}
@@ -0,0 +1,65 @@
+// Free-variable and occurs queries on generated terms
+
+use mettail_runtime::{OrdVar, Var, VarContext};
+use mettail_theories::calculator::{self, Int};
+use mettail_theories::rhocalc::{self, Name, Proc};
+
+fn parse(vars: &VarContext, input: &str) -> Proc {
+    rhocalc::rhocalc::ProcParser::new()
+        .parse(vars, input)
+        .unwrap()
+}
+
+fn names(set: &std::collections::BTreeSet<OrdVar>) -> Vec<String> {
+    set.iter()
+        .map(|v| match &v.0 {
+            Var::Free(fv) => fv.pretty_name.clone().unwrap(),
+            Var::Bound(_) => panic!("bound variable reported free"),
+        })
+        .collect()
+}
+
+#[test]
+fn test_free_vars_skip_bound_variables() {
+    let vars = VarContext::new();
+    let term = parse(&vars, "for(a->x){x!(*(b))}");
+
+    assert_eq!(names(&term.free_vars()), vec!["a", "b"]);
+    assert!(term.occurs(&vars.var("a")));
+    assert!(!term.occurs(&vars.var("x")));
+    assert!(parse(&vars, "{0 | *(@(0))}").free_vars().is_empty());
+}
+
+#[test]
+fn test_free_vars_of_category() {
+    let vars = VarContext::new();
+    let term = parse(&vars, "{a!(0) | *(b)}");
+
+    assert_eq!(names(&term.free_vars_of_category::<Name>()), vec!["a", "b"]);
+    assert!(term.free_vars_of_category::<Proc>().is_empty());
+
+    // A variable stored in a constructor belongs to that constructor's category
+    let vars = VarContext::new();
+    let assign = calculator::calculator::IntParser::new()
+        .parse(&vars, "x = y + 1")
+        .unwrap();
+    assert_eq!(names(&assign.free_vars_of_category::<Int>()), vec!["x", "y"]);
+}
+
+#[test]
+fn test_rename_free() {
+    let vars = VarContext::new();
+    let term = parse(&vars, "{a!(0) | for(a->x){x!(*(a))}}");
+    let c = vars.var("c");
+
+    let renamed = term.rename_free(&vars.var("a"), &c);
+    assert_eq!(renamed, parse(&vars, "{c!(0) | for(c->x){x!(*(c))}}"));
+    assert!(!renamed.occurs(&vars.var("a")));
+    assert_eq!(renamed.free_vars(), [OrdVar(Var::Free(c))].into_iter().collect());
+
+    // Renaming to the name of a binder does not capture
+    let term = parse(&vars, "for(a->x){b!(*(x))}");
+    let captured = term.rename_free(&vars.var("b"), &vars.var("x"));
+    assert_eq!(captured.free_vars().len(), 2);
+    assert_ne!(captured, parse(&vars, "for(a->x){x!(*(x))}"));
+}
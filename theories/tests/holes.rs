@@ -0,0 +1,193 @@
+#![allow(
+    non_local_definitions,
+    clippy::crate_in_macro_def,
+    clippy::empty_line_after_outer_attr
+)]
+
+// Holes (`?name`) turn terms into patterns matched with `match_pattern`
+
+use ascent::*;
+use ascent_byods_rels::eqrel;
+use mettail_macros::theory;
+use mettail_runtime::{RewriteGraph, Var, VarContext};
+
+theory! {
+    name: HoleRho,
+
+    options {
+        holes: true,
+    },
+
+    exports {
+        Proc
+        Name
+    },
+
+    terms {
+        PZero . Proc ::= "0" ;
+        PDrop . Proc ::= "*" "(" Name ")" ;
+        POutput . Proc ::= Name "!" "(" Proc ")" ;
+        PInput . Proc ::= "for" "(" Name "->" <Name> ")" "{" Proc "}" ;
+
+        PPar . Proc ::= HashBag(Proc) sep "|" delim "{" "}" ;
+
+        NQuote . Name ::= "@" "(" Proc ")" ;
+    },
+
+    equations {
+        (NQuote (PDrop N)) == N ;
+    },
+
+    rewrites {
+        (PPar {(PInput N x P), (POutput N Q)})
+            => (PPar {(subst P x (NQuote Q))});
+
+        (PDrop (NQuote P)) => P;
+
+        if S => T then (PPar {S, ...rest}) => (PPar {T, ...rest});
+    },
+}
+
+fn parse(vars: &VarContext, input: &str) -> Proc {
+    holerho::ProcParser::new().parse(vars, input).unwrap()
+}
+
+#[test]
+fn test_holes_parse_and_display() {
+    let vars = VarContext::new();
+    let pattern = parse(&vars, "{?x!(?p) | ...}");
+    assert_eq!(pattern.to_string(), "{?x!(?p) | ...}");
+    assert_eq!(parse(&vars, &pattern.to_string()), pattern);
+    assert!(matches!(parse(&vars, "*(?n)"), Proc::PDrop(n) if *n == Name::NHole("n".into())));
+    assert_eq!(
+        pattern.holes().into_iter().collect::<Vec<_>>(),
+        vec!["p".to_string(), "x".to_string()]
+    );
+}
+
+#[test]
+fn test_match_pattern_binds_holes() {
+    let vars = VarContext::new();
+    let term = parse(&vars, "{a!(0) | for(b->y){*(y)} | c!(*(d))}");
+
+    let bindings = term
+        .match_pattern(&parse(&vars, "{?x!(*(?n)) | ...}"))
+        .unwrap();
+    assert_eq!(bindings["x"], HoleRhoTerm::Name(parse_name(&vars, "c")));
+    assert_eq!(bindings["n"], HoleRhoTerm::Name(parse_name(&vars, "d")));
+
+    // Without `...` every element must be matched
+    assert!(term
+        .match_pattern(&parse(&vars, "{?x!(?p) | ?q}"))
+        .is_none());
+    assert!(term
+        .match_pattern(&parse(&vars, "{?x!(?p) | ?q | ?r}"))
+        .is_some());
+    // Free variables in the pattern match by name
+    assert!(term
+        .match_pattern(&parse(&vars, "{a!(?p) | ...}"))
+        .is_some());
+    assert!(term
+        .match_pattern(&parse(&vars, "{e!(?p) | ...}"))
+        .is_none());
+    // Binders match up to renaming of the bound variable
+    assert!(term
+        .match_pattern(&parse(&vars, "{for(?c->z){*(z)} | ...}"))
+        .is_some());
+}
+
+#[test]
+fn test_holes_under_binders() {
+    let vars = VarContext::new();
+    let term = parse(&vars, "for(a->y){*(y)}");
+
+    // The body's bound variable is captured as a variable named after the
+    // term's binder, not as an index into the scope
+    let bindings = term.match_pattern(&parse(&vars, "for(a->z){?p}")).unwrap();
+    let HoleRhoTerm::Proc(body) = &bindings["p"] else {
+        panic!("expected a process");
+    };
+    assert_eq!(body.to_string(), "*(y)");
+    assert!(matches!(
+        body,
+        Proc::PDrop(n) if matches!(&**n, Name::NVar(v) if matches!(v.0, Var::Free(_)))
+    ));
+
+    // A hole for the bound variable binds it the same way
+    let bindings = term
+        .match_pattern(&parse(&vars, "for(a->z){*(?n)}"))
+        .unwrap();
+    assert!(matches!(&bindings["n"], HoleRhoTerm::Name(n) if n.to_string() == "y"));
+    // ...so it differs from a free variable of the same name outside the binder
+    assert!(parse(&vars, "{for(a->y){*(y)} | y!(0)}")
+        .match_pattern(&parse(&vars, "{for(a->z){*(?n)} | ?n!(0)}"))
+        .is_none());
+}
+
+#[test]
+fn test_generated_terms_have_no_holes() {
+    let vars = ["a".to_string()];
+    let terms = Proc::generate_terms(&vars, 2, 2);
+    assert!(!terms.is_empty());
+    assert!(terms.iter().all(|term| !term.is_pattern()));
+}
+
+fn parse_name(vars: &VarContext, input: &str) -> Name {
+    holerho::NameParser::new().parse(vars, input).unwrap()
+}
+
+#[test]
+fn test_repeated_holes_match_equal_terms() {
+    let vars = VarContext::new();
+    let pattern = parse(&vars, "{?x!(?p) | ?y!(?p)}");
+
+    assert!(parse(&vars, "{a!(0) | b!(0)}")
+        .match_pattern(&pattern)
+        .is_some());
+    assert!(parse(&vars, "{a!(0) | b!(*(c))}")
+        .match_pattern(&pattern)
+        .is_none());
+    // `?_` binds nothing, so repeating it constrains nothing
+    let wildcard = parse(&vars, "{?x!(?_) | ?y!(?_)}");
+    let bindings = parse(&vars, "{a!(0) | b!(*(c))}")
+        .match_pattern(&wildcard)
+        .unwrap();
+    assert_eq!(bindings.len(), 2);
+}
+
+#[test]
+fn test_fill_holes() {
+    let vars = VarContext::new();
+    let pattern = parse(&vars, "{?x!(?p) | ...}");
+    let term = parse(&vars, "{a!(0) | *(b)}");
+    let bindings = term.match_pattern(&pattern).unwrap();
+
+    assert_eq!(parse(&vars, "?x!(?p)").fill_holes(&bindings), parse(&vars, "a!(0)"));
+    // Unbound holes stay
+    assert_eq!(
+        parse(&vars, "{?x!(?q) | ...}").fill_holes(&bindings),
+        parse(&vars, "{a!(?q) | ...}")
+    );
+}
+
+#[test]
+fn test_query_reachable_terms() {
+    let vars = VarContext::new();
+    let term = parse(&vars, "{for(a->x){*(x)} | a!(b!(0)) | c!(0)}");
+    let prog = ascent_run! {
+        include_source!(holerho_source);
+        proc(term.clone());
+    };
+
+    let mut graph = RewriteGraph::new();
+    graph.set_root(term);
+    for (s, t) in prog.rw_proc.iter() {
+        graph.add_edge(s.clone(), t.clone(), "rw");
+    }
+
+    // The process sent on `a` runs once received
+    let pattern = parse(&vars, "{b!(?p) | ...}");
+    let found = graph.nodes_where(|p| p.match_pattern(&pattern).is_some());
+    assert_eq!(found.len(), 1);
+    assert_eq!(graph.node(found[0]), &parse(&vars, "{b!(0) | c!(0)}"));
+}
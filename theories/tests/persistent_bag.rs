@@ -0,0 +1,82 @@
+#![allow(
+    non_local_definitions,
+    clippy::crate_in_macro_def,
+    clippy::empty_line_after_outer_attr
+)]
+
+// Collection constructors backed by the persistent (structurally shared) bag
+
+use ascent::*;
+use ascent_byods_rels::eqrel;
+use mettail_macros::theory;
+use mettail_runtime::VarContext;
+
+theory! {
+    name: PersistentRho,
+
+    options {
+        bag_backend: persistent,
+    },
+
+    exports {
+        Proc
+        Name
+    },
+
+    terms {
+        PZero . Proc ::= "0" ;
+        PDrop . Proc ::= "*" "(" Name ")" ;
+        POutput . Proc ::= Name "!" "(" Proc ")" ;
+        PInput . Proc ::= "for" "(" Name "->" <Name> ")" "{" Proc "}" ;
+
+        PPar . Proc ::= HashBag(Proc) sep "|" delim "{" "}" ;
+
+        NQuote . Name ::= "@" "(" Proc ")" ;
+    },
+
+    equations {
+        (NQuote (PDrop N)) == N ;
+    },
+
+    rewrites {
+        (PPar {(PInput N x P), (POutput N Q)})
+            => (PPar {(subst P x (NQuote Q))});
+
+        (PDrop (NQuote P)) => P;
+
+        if S => T then (PPar {S, ...rest}) => (PPar {T, ...rest});
+    },
+}
+
+fn parse(vars: &VarContext, input: &str) -> Proc {
+    persistentrho::ProcParser::new().parse(vars, input).unwrap()
+}
+
+#[test]
+fn test_collection_field_is_persistent() {
+    let vars = VarContext::new();
+    let Proc::PPar(bag) = parse(&vars, "{a!(0) | b!(0) | a!(0)}") else {
+        panic!("expected a parallel composition");
+    };
+    let bag: mettail_runtime::PersistentBag<Proc> = bag;
+    assert_eq!(bag.len(), 3);
+    assert_eq!(bag.count(&parse(&vars, "a!(0)")), 2);
+}
+
+#[test]
+fn test_comm_and_congruence() {
+    let vars = VarContext::new();
+    let term = parse(&vars, "{for(a->x){*(x)} | a!(b!(0)) | c!(0)}");
+    let prog = ascent_run! {
+        include_source!(persistentrho_source);
+        proc(term.clone());
+    };
+
+    let normal_forms: Vec<Proc> = prog
+        .proc
+        .iter()
+        .map(|(p,)| p.clone())
+        .filter(|p| !prog.rw_proc.iter().any(|(s, _)| s == p))
+        .collect();
+    assert!(normal_forms.contains(&parse(&vars, "{b!(0) | c!(0)}")));
+}
@@ -0,0 +1,109 @@
+// The REPL's `Theory` impl generated by `theory!`
+
+use mettail_runtime::repl::Theory;
+use mettail_theories::ambient::Ambient;
+use mettail_theories::calculator::Calculator;
+use mettail_theories::rhocalc::{RhoCalc, RhoCalcReplTerm};
+
+#[test]
+fn test_metadata_is_counted_from_the_definition() {
+    assert_eq!(RhoCalc.name(), "rhocalc");
+    assert_eq!(RhoCalc.categories(), vec!["Proc", "Name"]);
+    // Six rules, plus the generated `PVar` and `NVar`
+    assert_eq!(RhoCalc.constructor_count(), 8);
+    assert_eq!(RhoCalc.equation_count(), 1);
+    assert_eq!(RhoCalc.rewrite_count(), 3);
+
+    assert_eq!(Ambient.name(), "ambient");
+    // `Int` is native, so it has no generated variable constructor
+    assert_eq!(Calculator.categories(), vec!["Int"]);
+    assert_eq!(Calculator.equation_count(), 0);
+}
+
+#[test]
+fn test_hole_variants_are_not_counted() {
+    // Seven rules, plus `PVar` and `NVar`; `PHole` and `NHole` only occur in patterns
+    assert_eq!(Ambient.constructor_count(), 9);
+    assert!(Ambient.parse_pattern("in(?n, ?p)").is_ok());
+    assert!(Ambient.parse_term("in(?n, ?p)").is_err());
+}
+
+#[test]
+fn test_parse_and_run() {
+    let theory: Box<dyn Theory> = Box::new(RhoCalc);
+    let term = theory.parse_term("{a!(0) | for(a->x){*(x)}}").unwrap();
+    assert!(term.as_any().downcast_ref::<RhoCalcReplTerm>().is_some());
+    assert!(term.term_eq(term.clone_box().as_ref()));
+
+    let results = theory.run_ascent(term.clone_box()).unwrap();
+    assert!(results
+        .all_terms
+        .iter()
+        .any(|t| t.term_id == term.term_id()));
+    let normal_forms: Vec<&str> = results
+        .normal_forms()
+        .iter()
+        .map(|t| t.display.as_str())
+        .collect();
+    assert_eq!(normal_forms, vec!["{0}"]);
+
+    assert!(theory.parse_term("a!(").is_err());
+}
+
+#[test]
+fn test_run_with_stats() {
+    let term = RhoCalc.parse_term("{a!(0) | for(a->x){*(x)}}").unwrap();
+    let (results, stats) = RhoCalc.run_ascent_with_stats(term).unwrap();
+    let stats = stats.unwrap();
+
+    let facts = |relation: &str| {
+        stats
+            .relations
+            .iter()
+            .find(|(name, _)| name == relation)
+            .map(|(_, count)| *count)
+    };
+    // The relations also hold facts about subterms, which the graph leaves out
+    assert!(facts("rw_proc").unwrap() >= results.rewrites.len());
+    assert!(facts("proc").unwrap() >= results.all_terms.len());
+    assert!(facts("ppar_contains").is_some());
+
+    // Each rewrite was derived at least once
+    let rewrite_firings: usize = stats
+        .rules
+        .iter()
+        .filter(|rule| rule.label.starts_with("rewrite: rw_proc_rule <-- "))
+        .map(|rule| rule.firings)
+        .sum();
+    assert!(rewrite_firings >= facts("rw_proc_rule").unwrap());
+    assert!(facts("rw_proc_rule").unwrap() >= facts("rw_proc").unwrap());
+    assert!(stats
+        .rules
+        .iter()
+        .any(|rule| rule.label == "equation: eq_proc <-- proc"));
+    assert!(stats.busiest_rules().iter().all(|rule| rule.firings > 0));
+    assert!(stats.total_iterations() >= stats.sccs.len());
+
+    // Calculator does not set the `stats` option
+    let term = Calculator.parse_term("1 + 2").unwrap();
+    let (results, stats) = Calculator.run_ascent_with_stats(term).unwrap();
+    assert!(stats.is_none());
+    assert!(!results.all_terms.is_empty());
+}
+
+#[test]
+fn test_step() {
+    let step = |input: &str| -> Vec<String> {
+        let term = RhoCalc.parse_term(input).unwrap();
+        RhoCalc
+            .step(term.as_ref())
+            .unwrap()
+            .iter()
+            .map(|t| t.to_string())
+            .collect()
+    };
+    // Only the first of the two rewrites to `{0}`
+    assert_eq!(step("{@(0)!(0) | for(@(0)->x){*(x)}}"), vec!["{*(@(0))}"]);
+    assert_eq!(step("{*(@(0))}"), vec!["{0}"]);
+    assert!(step("{0}").is_empty());
+}
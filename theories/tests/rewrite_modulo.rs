@@ -0,0 +1,77 @@
+#![allow(
+    non_local_definitions,
+    clippy::crate_in_macro_def,
+    clippy::empty_line_after_outer_attr
+)]
+
+// Rewriting modulo equations: a rewrite LHS matches any known equivalent term
+
+use ascent::*;
+use ascent_byods_rels::eqrel;
+use mettail_macros::theory;
+use mettail_runtime::VarContext;
+
+theory! {
+    name: DropModulo,
+
+    options {
+        rewrite_modulo_equations: true,
+    },
+
+    exports {
+        Proc
+        Name
+    },
+
+    terms {
+        PZero . Proc ::= "0" ;
+        PDrop . Proc ::= "*" "(" Name ")" ;
+        POutput . Proc ::= Name "!" "(" Proc ")" ;
+
+        NQuote . Name ::= "@" "(" Proc ")" ;
+    },
+
+    equations {
+        (PDrop (NQuote P)) == P ;
+    },
+
+    rewrites {
+        (POutput N P) => P;
+    },
+}
+
+#[test]
+fn test_rewrite_fires_on_equivalent_term() {
+    let vars = VarContext::new();
+    let term = dropmodulo::ProcParser::new()
+        .parse(&vars, "*(@(n!(0)))")
+        .unwrap();
+    let zero = dropmodulo::ProcParser::new().parse(&vars, "0").unwrap();
+
+    let prog = ascent_run! {
+        include_source!(dropmodulo_source);
+        proc(term.clone());
+    };
+
+    // `*(@(n!(0)))` is not an output, but it equals one
+    assert!(prog.rw_proc.iter().any(|(s, t)| s == &term && t == &zero));
+}
+
+#[test]
+fn test_rhocalc_communicates_on_equivalent_channels() {
+    use mettail_theories::rhocalc::{self, RhoCalc};
+
+    let vars = VarContext::new();
+    let parser = rhocalc::rhocalc::ProcParser::new();
+    // The input's channel `@(*(n))` equals the output's `n`
+    let term = parser
+        .parse(&vars, "{for(@(*(n))->x){x!(0)} | n!(data)}")
+        .unwrap();
+    let sent = parser.parse(&vars, "{@(data)!(0)}").unwrap();
+
+    let graph = RhoCalc::explore(term.clone());
+    let root = graph.index_of(&term).unwrap();
+    assert!(graph
+        .edges_from(root)
+        .any(|edge| graph.node(edge.to) == &sent && edge.label == "PPar{PInput, POutput}"));
+}
@@ -0,0 +1,87 @@
+#![allow(
+    non_local_definitions,
+    clippy::crate_in_macro_def,
+    clippy::empty_line_after_outer_attr
+)]
+
+// Serde round trips for generated ASTs (mettail-runtime's `serde` feature)
+
+use ascent::*;
+use ascent_byods_rels::eqrel;
+use mettail_macros::theory;
+use mettail_runtime::{deserialize_with_vars, VarContext};
+
+theory! {
+    name: SerdeRho,
+
+    exports {
+        Proc
+        Name
+    },
+
+    terms {
+        PZero . Proc ::= "0" ;
+        PDrop . Proc ::= "*" "(" Name ")" ;
+        POutput . Proc ::= Name "!" "(" Proc ")" ;
+        PInput . Proc ::= "for" "(" Name "->" <Name> ")" "{" Proc "}" ;
+
+        PPar . Proc ::= HashBag(Proc) sep "|" delim "{" "}" ;
+
+        NQuote . Name ::= "@" "(" Proc ")" ;
+    },
+
+    equations {
+    },
+
+    rewrites {
+        (PPar {(PInput N x P), (POutput N Q)})
+            => (PPar {(subst P x (NQuote Q))});
+
+        if S => T then (PPar {S, ...rest}) => (PPar {T, ...rest});
+    },
+}
+
+fn parse(vars: &VarContext, input: &str) -> Proc {
+    serderho::ProcParser::new().parse(vars, input).unwrap()
+}
+
+fn from_json(vars: &VarContext, json: &str) -> Proc {
+    deserialize_with_vars(vars, || serde_json::from_str(json)).unwrap()
+}
+
+#[test]
+fn test_round_trip_preserves_term() {
+    let vars = VarContext::new();
+    let term = parse(&vars, "{for(a->x){*(x)} | a!(0) | a!(0) | b!(for(c->y){0})}");
+    let json = serde_json::to_string(&term).unwrap();
+    assert_eq!(from_json(&vars, &json), term);
+}
+
+#[test]
+fn test_binders_are_locally_nameless() {
+    let vars = VarContext::new();
+    let x = serde_json::to_value(parse(&vars, "for(a->x){*(x)}")).unwrap();
+    let y = serde_json::to_value(parse(&vars, "for(a->y){*(y)}")).unwrap();
+    let bound = &x["PInput"][1]["body"]["PDrop"]["NVar"];
+    assert_eq!(bound["Bound"]["scope"], 0);
+    assert_eq!(bound["Bound"]["binder"], 0);
+
+    // Only the pretty names differ
+    assert_eq!(x["PInput"][1]["binders"], serde_json::json!(["x"]));
+    assert_eq!(y["PInput"][1]["binders"], serde_json::json!(["y"]));
+    assert_eq!(x["PInput"][0], y["PInput"][0]);
+}
+
+#[test]
+fn test_free_variables_are_shared_after_deserializing() {
+    let vars = VarContext::new();
+    let json = serde_json::to_string(&parse(&vars, "{for(a->x){*(x)} | a!(0)}")).unwrap();
+
+    // Deserialized in a fresh context, the two occurrences of `a` still meet
+    let term = from_json(&VarContext::new(), &json);
+    let prog = ascent_run! {
+        include_source!(serderho_source);
+        proc(term.clone());
+    };
+    assert_eq!(prog.rw_proc.iter().filter(|(s, _)| s == &term).count(), 1);
+}
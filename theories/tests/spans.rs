@@ -0,0 +1,71 @@
+// Source spans of parsed terms
+
+use mettail_runtime::{TermPath, VarContext};
+use mettail_theories::calculator;
+use mettail_theories::rhocalc;
+
+fn path(s: &str) -> TermPath {
+    s.parse().unwrap()
+}
+
+#[test]
+fn test_spans_follow_term_paths() {
+    let src = "for(a->x){x!(*(b))}";
+    let vars = VarContext::with_spans();
+    let term = rhocalc::rhocalc::ProcParser::new()
+        .parse(&vars, src)
+        .unwrap();
+    let spans = term.spans(&vars);
+
+    assert_eq!(spans.len(), term.positions().len());
+    for (p, sub) in term.positions() {
+        let span = spans.get(&p).unwrap();
+        assert_eq!(
+            span.text(src).replace(' ', ""),
+            sub.to_string().replace(' ', ""),
+            "at {}",
+            p
+        );
+    }
+    assert_eq!(spans.root().unwrap().text(src), src);
+    assert_eq!(spans.get(&path("/1/1/0")).unwrap().text(src), "b");
+    assert_eq!(spans.path_at(src.find('b').unwrap()), Some(&path("/1/1/0")));
+}
+
+#[test]
+fn test_collection_elements_keep_their_spans() {
+    // Elements are stored sorted, not in source order
+    let src = "{ z!(0) | a!(0) | z!(0) }";
+    let vars = VarContext::with_spans();
+    let term = rhocalc::rhocalc::ProcParser::new()
+        .parse(&vars, src)
+        .unwrap();
+    let spans = term.spans(&vars);
+
+    for (p, sub) in term.positions().into_iter().skip(1) {
+        assert_eq!(spans.get(&p).unwrap().text(src), sub.to_string(), "at {}", p);
+    }
+    assert_eq!(spans.get(&path("/0")).unwrap().text(src), "a!(0)");
+}
+
+#[test]
+fn test_spans_report_lines_and_columns() {
+    let src = "1 +\n  (x - 2)";
+    let vars = VarContext::with_spans();
+    let term = calculator::calculator::IntParser::new()
+        .parse(&vars, src)
+        .unwrap();
+    let spans = term.spans(&vars);
+
+    let sub = spans.get(&path("/1")).unwrap();
+    assert_eq!(sub.text(src), "x - 2");
+    assert_eq!(sub.line_col(src), (2, 4));
+
+    // The recording is consumed, and plain contexts record nothing
+    assert!(term.spans(&vars).is_empty());
+    let vars = VarContext::new();
+    let term = calculator::calculator::IntParser::new()
+        .parse(&vars, src)
+        .unwrap();
+    assert!(term.spans(&vars).is_empty());
+}
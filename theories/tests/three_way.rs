@@ -0,0 +1,96 @@
+#![allow(
+    non_local_definitions,
+    clippy::crate_in_macro_def,
+    clippy::empty_line_after_outer_attr
+)]
+
+// Three-party synchronization: a join consumes one output on each of two channels
+
+use ascent::*;
+use ascent_byods_rels::eqrel;
+use mettail_macros::theory;
+use mettail_runtime::VarContext;
+
+theory! {
+    name: JoinCalc,
+
+    exports {
+        Proc
+        Name
+    },
+
+    terms {
+        PZero . Proc ::= "0" ;
+        POutput . Proc ::= Name "!" "(" Proc ")" ;
+        PJoin . Proc ::= "join" "(" Name "," Name ")" "{" Proc "}" ;
+
+        PPar . Proc ::= HashBag(Proc) sep "|" delim "{" "}" ;
+
+        NQuote . Name ::= "@" "(" Proc ")" ;
+    },
+
+    equations {
+    },
+
+    rewrites {
+        (PPar {(PJoin N M P), (POutput N Q), (POutput M R)})
+            => (PPar {P, Q, R});
+
+        if S => T then (PPar {S, ...rest}) => (PPar {T, ...rest});
+    },
+}
+
+fn parse(vars: &VarContext, input: &str) -> Proc {
+    joincalc::ProcParser::new().parse(vars, input).unwrap()
+}
+
+fn step(vars: &VarContext, input: &str) -> Vec<Proc> {
+    let term = parse(vars, input);
+    let prog = ascent_run! {
+        include_source!(joincalc_source);
+        proc(term.clone());
+    };
+    let mut results: Vec<Proc> = prog
+        .rw_proc
+        .iter()
+        .filter(|(s, _)| s == &term)
+        .map(|(_, t)| t.clone())
+        .collect();
+    results.sort();
+    results
+}
+
+#[test]
+fn test_join_consumes_both_outputs() {
+    let vars = VarContext::new();
+    let results = step(&vars, "{join(a, b){0} | a!(@(0)!(0)) | b!(0) | c!(0)}");
+    assert_eq!(results, vec![parse(&vars, "{0 | @(0)!(0) | 0 | c!(0)}")]);
+}
+
+#[test]
+fn test_join_needs_both_channels() {
+    let vars = VarContext::new();
+    assert!(step(&vars, "{join(a, b){0} | a!(0) | c!(0)}").is_empty());
+}
+
+#[test]
+fn test_join_on_same_channel_needs_two_outputs() {
+    let vars = VarContext::new();
+    assert!(step(&vars, "{join(a, a){0} | a!(0)}").is_empty());
+    assert_eq!(
+        step(&vars, "{join(a, a){0} | a!(0) | a!(0)}"),
+        vec![parse(&vars, "{0 | 0 | 0}")]
+    );
+}
+
+#[test]
+fn test_join_picks_each_matching_pair() {
+    let vars = VarContext::new();
+    let results = step(&vars, "{join(a, b){0} | a!(0) | a!(@(0)!(0)) | b!(0)}");
+    let mut expected = vec![
+        parse(&vars, "{0 | 0 | 0 | a!(@(0)!(0))}"),
+        parse(&vars, "{0 | @(0)!(0) | 0 | a!(0)}"),
+    ];
+    expected.sort();
+    assert_eq!(results, expected);
+}
@@ -0,0 +1,129 @@
+// Visitors, folds and positional access for generated ASTs
+
+use mettail_runtime::{TermPath, Var, VarContext};
+use mettail_theories::rhocalc::{
+    self, Name, Proc, RhoCalcTerm, RhoCalcTermRef, RhoCalcVisitor, RhoCalcVisitorMut,
+};
+
+fn parse(vars: &VarContext, input: &str) -> Proc {
+    rhocalc::rhocalc::ProcParser::new()
+        .parse(vars, input)
+        .unwrap()
+}
+
+fn parse_name(vars: &VarContext, input: &str) -> Name {
+    rhocalc::rhocalc::NameParser::new()
+        .parse(vars, input)
+        .unwrap()
+}
+
+#[test]
+fn test_children_size_and_depth() {
+    let vars = VarContext::new();
+    let term = parse(&vars, "{b!(0) | a!(0) | a!(0)}");
+
+    let children = term.children();
+    assert_eq!(children.len(), 3);
+    assert!(children
+        .iter()
+        .all(|c| matches!(c, RhoCalcTermRef::Proc(_))));
+    // a!(0) | a!(0) | {b!(0)}, each output has a name and a body
+    assert_eq!(term.size(), 1 + 3 * 3);
+    assert_eq!(term.depth(), 3);
+    assert_eq!(parse(&vars, "0").depth(), 1);
+}
+
+#[test]
+fn test_fold_counts_categories() {
+    let vars = VarContext::new();
+    let term = parse(&vars, "for(a->x){x!(*(@(0)))}");
+    let names = term.fold(0, &mut |n, t| n + matches!(t, RhoCalcTermRef::Name(_)) as usize);
+    assert_eq!(names, 3);
+}
+
+#[test]
+fn test_visitor_collects_variables() {
+    #[derive(Default)]
+    struct Vars {
+        free: Vec<String>,
+        bound: usize,
+    }
+
+    impl RhoCalcVisitor for Vars {
+        fn visit_var(&mut self, var: &Var<String>) {
+            match var {
+                Var::Free(v) => self.free.push(v.pretty_name.clone().unwrap()),
+                Var::Bound(_) => self.bound += 1,
+            }
+        }
+    }
+
+    let vars = VarContext::new();
+    let mut visitor = Vars::default();
+    parse(&vars, "{for(a->x){*(x)} | b!(0)}").walk(&mut visitor);
+    visitor.free.sort();
+    assert_eq!(visitor.free, vec!["a", "b"]);
+    assert_eq!(visitor.bound, 1);
+}
+
+#[test]
+fn test_visitor_mut_rewrites_in_place() {
+    struct ZeroDrops;
+
+    impl RhoCalcVisitorMut for ZeroDrops {
+        fn visit_proc_mut(&mut self, term: &mut Proc) {
+            if matches!(term, Proc::PDrop(_)) {
+                *term = Proc::PZero;
+            } else {
+                term.walk_mut(self);
+            }
+        }
+    }
+
+    let vars = VarContext::new();
+    let mut term = parse(&vars, "{*(a) | b!(*(c)) | for(d->x){*(x)}}");
+    ZeroDrops.visit_proc_mut(&mut term);
+    assert_eq!(term, parse(&vars, "{0 | b!(0) | for(d->x){0}}"));
+}
+
+#[test]
+fn test_positions_and_replace() {
+    let vars = VarContext::new();
+    let term = parse(&vars, "a!(b!(0))");
+
+    let positions: Vec<String> = term
+        .positions()
+        .into_iter()
+        .map(|(path, t)| format!("{} {}", path, t))
+        .collect();
+    assert_eq!(positions, vec!["/ a!(b!(0))", "/0 a", "/1 b!(0)", "/1/0 b", "/1/1 0"]);
+
+    let path: TermPath = "/1/1".parse().unwrap();
+    assert_eq!(
+        term.subterm(&path).map(|t| t.cloned()),
+        Some(RhoCalcTerm::Proc(parse(&vars, "0")))
+    );
+    assert_eq!(
+        term.replace_at(&path, parse(&vars, "c!(0)")),
+        Some(parse(&vars, "a!(b!(c!(0)))"))
+    );
+    assert_eq!(
+        term.replace_at(&"/0".parse().unwrap(), parse_name(&vars, "@(0)")),
+        Some(parse(&vars, "@(0)!(b!(0))"))
+    );
+
+    // Wrong category, or no such position
+    assert_eq!(term.replace_at(&path, parse_name(&vars, "c")), None);
+    assert_eq!(term.replace_at(&"/2".parse().unwrap(), parse(&vars, "0")), None);
+}
+
+#[test]
+fn test_replace_in_collection_keeps_it_flat() {
+    let vars = VarContext::new();
+    let term = parse(&vars, "{a!(0) | b!(0)}");
+    let replaced = term
+        .replace_at(&TermPath::from(vec![1]), parse(&vars, "{c!(0) | d!(0)}"))
+        .unwrap();
+    assert_eq!(replaced, parse(&vars, "{a!(0) | c!(0) | d!(0)}"));
+    assert_eq!(replaced.children().len(), 3);
+}